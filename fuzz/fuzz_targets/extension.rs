@@ -0,0 +1,25 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+const HEADER: [u8; 18] = [0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 32, 8];
+const PIXEL: [u8; 4] = [0, 0, 0, 255];
+
+// Wraps the input between a 1x1 image and a footer pointing at it, so that it
+// is read as the extension area. The first four bytes of the input are the
+// developer area offset, which may point anywhere in the file.
+fuzz_target!(|data: &[u8]| {
+    let [a, b, c, d, area @ ..] = data else {
+        return;
+    };
+
+    let mut file = HEADER.to_vec();
+    file.extend_from_slice(&PIXEL);
+    let extension_offset = file.len() as u32;
+    file.extend_from_slice(area);
+    file.extend_from_slice(&extension_offset.to_le_bytes());
+    file.extend_from_slice(&[*a, *b, *c, *d]);
+    file.extend_from_slice(b"TRUEVISION-XFILE.\0");
+
+    let _ = tga::decode_untrusted(&file);
+});
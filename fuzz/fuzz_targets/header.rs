@@ -0,0 +1,13 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = tga::validate_header(&mut &data[..]);
+
+    if let Ok(mut decoder) = tga::Decoder::new(Cursor::new(data)) {
+        let _ = decoder.read_row(0);
+    }
+});
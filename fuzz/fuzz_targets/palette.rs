@@ -0,0 +1,25 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Prepends a header declaring a color map to the input, so that the color map
+// specification comes from the first bytes and the rest is read as color map
+// and pixel data.
+fuzz_target!(|data: &[u8]| {
+    let [image_type, first_entry, entry_count, color_depth, pixel_depth, rest @ ..] = data else {
+        return;
+    };
+
+    let mut file = vec![0, 1, *image_type];
+    file.extend_from_slice(&(*first_entry as u16).to_le_bytes());
+    file.extend_from_slice(&(*entry_count as u16).to_le_bytes());
+    file.push(*color_depth);
+    file.extend_from_slice(&[0, 0, 0, 0]);
+    file.extend_from_slice(&4u16.to_le_bytes());
+    file.extend_from_slice(&4u16.to_le_bytes());
+    file.extend_from_slice(&[*pixel_depth, 0]);
+    file.extend_from_slice(rest);
+
+    let _ = tga::validate_header(&mut &file[..]);
+    let _ = tga::decode_untrusted(&file);
+});
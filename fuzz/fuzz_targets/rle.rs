@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Prepends a run-length encoded true-color header to the input, so that
+// everything after the first three bytes is decoded as packets.
+fuzz_target!(|data: &[u8]| {
+    let [flags, width, height, packets @ ..] = data else {
+        return;
+    };
+
+    let pixel_depth = if flags & 1 == 0 { 24 } else { 32 };
+    let descriptor = flags & 0b00110000;
+    let mut file = vec![0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    file.extend_from_slice(&(*width as u16 + 1).to_le_bytes());
+    file.extend_from_slice(&(*height as u16 + 1).to_le_bytes());
+    file.extend_from_slice(&[pixel_depth, descriptor]);
+    file.extend_from_slice(packets);
+
+    let _ = tga::decode_untrusted(&file);
+});
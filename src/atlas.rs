@@ -0,0 +1,191 @@
+//! Packing of several small images into a single sprite sheet.
+//!
+//! Images are placed with a simple shelf packer: they are sorted by height and
+//! laid out left to right in rows, starting a new row whenever the next image
+//! would exceed the requested sheet width.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::io::Write;
+use crate::{Error, Image, Result};
+
+const BYTES_PER_PIXEL: usize = 4;
+
+/// The position of a packed image within the sheet.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// A sprite sheet and the placement of every image packed into it.
+#[derive(Clone, Debug)]
+pub struct Atlas {
+    /// The combined image. Unused areas are fully transparent.
+    pub image: Image,
+    /// Placement of each input image, in the same order as the input.
+    pub placements: Vec<Rect>,
+}
+
+impl Atlas {
+    /// Packs `images` into a sheet at most `max_width` pixels wide.
+    ///
+    /// The sheet is as narrow and as short as the packing allows. Fails if any
+    /// image is wider than `max_width` or if the packed sheet would be taller
+    /// than 65535 pixels.
+    pub fn pack(images: &[Image], max_width: u16) -> Result<Self> {
+        let mut order: Vec<usize> = (0..images.len()).collect();
+        order.sort_by(|&a, &b| images[b].height.cmp(&images[a].height));
+
+        let mut placements = vec![Rect::default(); images.len()];
+        let (mut x, mut y, mut shelf_height) = (0u32, 0u32, 0u32);
+        let mut sheet_width = 0u32;
+
+        for index in order {
+            let image = &images[index];
+            if image.width > max_width {
+                return Err(Error::InvalidDimensions("image is wider than the atlas"));
+            }
+
+            if x + image.width as u32 > max_width as u32 {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+
+            if y + image.height as u32 > u16::MAX as u32 {
+                return Err(Error::InvalidDimensions(
+                    "images do not fit into a single atlas",
+                ));
+            }
+
+            placements[index] = Rect {
+                x: x as u16,
+                y: y as u16,
+                width: image.width,
+                height: image.height,
+            };
+
+            x += image.width as u32;
+            shelf_height = shelf_height.max(image.height as u32);
+            sheet_width = sheet_width.max(x);
+        }
+
+        let sheet_width = sheet_width as u16;
+        let sheet_height = (y + shelf_height) as u16;
+        let mut data = vec![0; Image::effective_size(sheet_width, sheet_height)];
+
+        let sheet_row_size = sheet_width as usize * BYTES_PER_PIXEL;
+        for (image, rect) in images.iter().zip(&placements) {
+            let row_size = image.width as usize * BYTES_PER_PIXEL;
+            for row in 0..image.height as usize {
+                let start =
+                    (rect.y as usize + row) * sheet_row_size + rect.x as usize * BYTES_PER_PIXEL;
+                data[start..start + row_size].copy_from_slice(image.row(row));
+            }
+        }
+
+        Ok(Atlas {
+            image: Image::from_parts(sheet_width, sheet_height, data),
+            placements,
+        })
+    }
+
+    /// Writes the combined sheet as a TGA file.
+    pub fn write_to<T: Write>(&self, w: &mut T) -> Result<()> {
+        self.image.write_to(w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u16, height: u16, value: u8) -> Image {
+        Image::new(
+            width,
+            height,
+            vec![value; Image::effective_size(width, height)],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn packs_images_onto_shelves_by_height() {
+        let images = [
+            solid(3, 2, 1),
+            solid(2, 4, 2),
+            solid(4, 1, 3),
+            solid(1, 3, 4),
+        ];
+        let atlas = Atlas::pack(&images, 6).unwrap();
+
+        assert_eq!(
+            atlas.placements,
+            [
+                Rect {
+                    x: 3,
+                    y: 0,
+                    width: 3,
+                    height: 2
+                },
+                Rect {
+                    x: 0,
+                    y: 0,
+                    width: 2,
+                    height: 4
+                },
+                Rect {
+                    x: 0,
+                    y: 4,
+                    width: 4,
+                    height: 1
+                },
+                Rect {
+                    x: 2,
+                    y: 0,
+                    width: 1,
+                    height: 3
+                },
+            ]
+        );
+        assert_eq!((atlas.image.width(), atlas.image.height()), (6, 5));
+
+        for (image, rect) in images.iter().zip(&atlas.placements) {
+            for row in 0..rect.height as usize {
+                let sheet_row = atlas.image.row(rect.y as usize + row);
+                let start = rect.x as usize * BYTES_PER_PIXEL;
+                assert_eq!(
+                    &sheet_row[start..start + rect.width as usize * BYTES_PER_PIXEL],
+                    image.row(row)
+                );
+            }
+        }
+        // The corner no image was placed in stays transparent.
+        assert_eq!(atlas.image.row(4)[4 * BYTES_PER_PIXEL..], [0; 8]);
+
+        let mut file = Vec::new();
+        atlas.write_to(&mut file).unwrap();
+        let decoded = Image::read_from(&mut crate::io::Cursor::new(&file[..])).unwrap();
+        assert_eq!(decoded.data(), atlas.image.data());
+    }
+
+    #[test]
+    fn rejects_images_that_do_not_fit() {
+        assert!(matches!(
+            Atlas::pack(&[solid(5, 1, 0)], 4),
+            Err(Error::InvalidDimensions(_))
+        ));
+
+        let tall = [solid(2, 40000, 0), solid(2, 40000, 0)];
+        assert!(matches!(
+            Atlas::pack(&tall, 3),
+            Err(Error::InvalidDimensions(_))
+        ));
+        assert_eq!(Atlas::pack(&tall, 4).unwrap().image.height(), 40000);
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+
+use image::{DynamicImage, RgbaImage};
+use rayon::prelude::*;
+use tga::{EncodeOptions, Image, PixelFormat, Result, Seekable};
+
+/// Converts files between TGA and PNG, as told by the file extensions.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// The file to read followed by the file to write, or with `--out-dir` any
+    /// number of files or glob patterns such as 'frames/*.tga' to read.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Writes the converted files into this directory, named after the files
+    /// read.
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+    /// The extension, and so the format, of the files written to `--out-dir`,
+    /// by default png for TGA files and tga for anything else.
+    #[arg(long, requires = "out_dir")]
+    extension: Option<String>,
+    /// The number of files converted at once with `--out-dir`, by default the
+    /// number of CPUs.
+    #[arg(short, long, requires = "out_dir")]
+    jobs: Option<usize>,
+    /// The bits per pixel written, discarding the alpha channel for 24 and
+    /// writing grayscale for 8.
+    #[arg(long, value_enum, default_value_t = Depth::B32)]
+    depth: Depth,
+    /// Compresses the pixels of a TGA file with run-length encoding.
+    #[arg(long)]
+    rle: bool,
+    /// Flips the image upside down.
+    #[arg(long)]
+    flip_vertical: bool,
+    /// Mirrors the image from left to right.
+    #[arg(long)]
+    flip_horizontal: bool,
+}
+
+/// The bits per pixel to write.
+#[derive(Copy, Clone, clap::ValueEnum)]
+pub(crate) enum Depth {
+    #[value(name = "8")]
+    B8,
+    #[value(name = "24")]
+    B24,
+    #[value(name = "32")]
+    B32,
+}
+
+impl Depth {
+    pub(crate) fn pixel_format(self) -> PixelFormat {
+        match self {
+            Depth::B8 => PixelFormat::Gray8,
+            Depth::B24 => PixelFormat::Bgr24,
+            Depth::B32 => PixelFormat::Bgra32,
+        }
+    }
+}
+
+pub(crate) fn run(args: &Args) -> ExitCode {
+    let Some(out_dir) = &args.out_dir else {
+        let [input, output] = &args.files[..] else {
+            eprintln!("tga: convert takes a file to read and a file to write, or --out-dir");
+            return ExitCode::from(2);
+        };
+
+        return match convert(input, output, args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                crate::report(input, &error);
+                ExitCode::FAILURE
+            }
+        };
+    };
+
+    run_batch(args, out_dir)
+}
+
+/// Converts every file matched by the patterns into `out_dir` on a thread
+/// pool, printing a summary once all are done.
+///
+/// Nothing is converted if two files would be written to the same path, as
+/// happens for files of the same name in different directories.
+fn run_batch(args: &Args, out_dir: &Path) -> ExitCode {
+    let mut outputs = HashSet::new();
+    let mut files = Vec::new();
+    for input in expand(&args.files) {
+        let extension = match &args.extension {
+            Some(extension) => extension.as_str(),
+            None if is_tga(&input) => "png",
+            None => "tga",
+        };
+        let Some(stem) = input.file_stem() else {
+            eprintln!("tga: {}: not a file", input.display());
+            return ExitCode::from(2);
+        };
+        let mut name = stem.to_owned();
+        name.push(".");
+        name.push(extension);
+        let output = out_dir.join(name);
+        if !outputs.insert(output.clone()) {
+            eprintln!(
+                "tga: {}: more than one file would be written to {}",
+                input.display(),
+                output.display()
+            );
+            return ExitCode::from(2);
+        }
+        files.push((input, output));
+    }
+
+    if let Err(error) = fs::create_dir_all(out_dir) {
+        crate::report(out_dir, &error);
+        return ExitCode::FAILURE;
+    }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.jobs.unwrap_or(0))
+        .build();
+    let pool = match pool {
+        Ok(pool) => pool,
+        Err(error) => {
+            eprintln!("tga: {}", crate::describe(&error));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let start = Instant::now();
+    let failed = pool.install(|| {
+        files
+            .par_iter()
+            .filter(|(input, output)| match convert(input, output, args) {
+                Ok(()) => false,
+                Err(error) => {
+                    crate::report(input, &error);
+                    true
+                }
+            })
+            .count()
+    });
+    println!(
+        "converted {} of {} files in {:.2} s",
+        files.len() - failed,
+        files.len(),
+        start.elapsed().as_secs_f64()
+    );
+
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn convert(input: &Path, output: &Path, args: &Args) -> Result<()> {
+    let mut image = read_image(input)?;
+    if args.flip_vertical {
+        image.flip_vertical();
+    }
+    if args.flip_horizontal {
+        image.flip_horizontal();
+    }
+
+    let options = EncodeOptions {
+        pixel_format: args.depth.pixel_format(),
+        rle: args.rle,
+        ..EncodeOptions::default()
+    };
+
+    write_image(image, output, &options)
+}
+
+/// Expands the glob patterns among `patterns`. Patterns matching no files are
+/// kept as paths, so that reading them reports what is wrong.
+fn expand(patterns: &[PathBuf]) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for pattern in patterns {
+        let count = paths.len();
+        if let Some(matches) = pattern
+            .to_str()
+            .and_then(|pattern| glob::glob(pattern).ok())
+        {
+            paths.extend(matches.filter_map(|entry| entry.ok()));
+        }
+        if paths.len() == count {
+            paths.push(pattern.clone());
+        }
+    }
+
+    paths
+}
+
+/// Reads a TGA file with this crate and anything else with the `image` crate.
+pub(crate) fn read_image(path: &Path) -> Result<Image> {
+    if is_tga(path) {
+        return tga::decode(Seekable(BufReader::new(File::open(path)?)));
+    }
+
+    let image = image::open(path).map_err(io::Error::other)?.into_rgba8();
+    Image::try_from(image).map_err(|error| io::Error::other(error).into())
+}
+
+/// Writes a TGA file as described by `options`, and anything else with the
+/// `image` crate in the pixel format of `options`.
+pub(crate) fn write_image(image: Image, path: &Path, options: &EncodeOptions) -> Result<()> {
+    if is_tga(path) {
+        let mut w = BufWriter::new(File::create(path)?);
+        image.write_with_options(&mut w, options)?;
+        w.flush()?;
+
+        return Ok(());
+    }
+
+    let image = DynamicImage::ImageRgba8(RgbaImage::from(image));
+    let image = match options.pixel_format {
+        PixelFormat::Bgr24 => DynamicImage::ImageRgb8(image.into_rgb8()),
+        PixelFormat::Bgra32 => image,
+        PixelFormat::Gray8 => DynamicImage::ImageLuma8(image.into_luma8()),
+    };
+    image
+        .save(path)
+        .map_err(|error| io::Error::other(error).into())
+}
+
+pub(crate) fn is_tga(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("tga"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{parse, pattern, write, TempDir};
+    use crate::Command;
+
+    fn run_convert(args: &[&str]) -> ExitCode {
+        let Command::Convert(args) = parse(&[&["convert"], args].concat()) else {
+            unreachable!();
+        };
+
+        run(&args)
+    }
+
+    fn path(path: &Path) -> &str {
+        path.to_str().unwrap()
+    }
+
+    #[test]
+    fn files_convert_to_png_and_back() {
+        let dir = TempDir::new("convert");
+        let image = pattern(5, 3);
+        let (tga, png, back) = (dir.join("a.tga"), dir.join("a.png"), dir.join("b.tga"));
+        write(&tga, &image, &EncodeOptions::default());
+
+        assert_eq!(run_convert(&[path(&tga), path(&png)]), ExitCode::SUCCESS);
+        assert_eq!(
+            run_convert(&["--rle", path(&png), path(&back)]),
+            ExitCode::SUCCESS
+        );
+        let decoder = tga::Decoder::new(BufReader::new(File::open(&back).unwrap())).unwrap();
+        assert!(decoder.encode_options().rle);
+        assert_eq!(decoder.read_image().unwrap().data(), image.data());
+    }
+
+    #[test]
+    fn images_are_flipped_and_converted_to_the_depth() {
+        let dir = TempDir::new("convert-flip");
+        let image = pattern(4, 2);
+        let (input, output) = (dir.join("in.tga"), dir.join("out.tga"));
+        write(&input, &image, &EncodeOptions::default());
+
+        let args = [
+            "--flip-vertical",
+            "--flip-horizontal",
+            "--depth",
+            "24",
+            path(&input),
+            path(&output),
+        ];
+        assert_eq!(run_convert(&args), ExitCode::SUCCESS);
+        let mut expected = image.clone();
+        expected.flip_vertical();
+        expected.flip_horizontal();
+        let converted = read_image(&output).unwrap();
+        for (converted, expected) in converted.data().chunks(4).zip(expected.data().chunks(4)) {
+            assert_eq!(converted, [expected[0], expected[1], expected[2], 255]);
+        }
+    }
+
+    #[test]
+    fn batches_convert_every_matched_file() {
+        let dir = TempDir::new("convert-batch");
+        for name in ["a.tga", "b.tga"] {
+            write(&dir.join(name), &pattern(2, 2), &EncodeOptions::default());
+        }
+        let out_dir = dir.join("out");
+        let pattern = dir.join("*.tga");
+
+        let args = ["--out-dir", path(&out_dir), "--jobs", "2", path(&pattern)];
+        assert_eq!(run_convert(&args), ExitCode::SUCCESS);
+        for name in ["a.png", "b.png"] {
+            assert!(out_dir.join(name).is_file(), "{name}");
+        }
+
+        // Both files are named `a`, so they would be written to the same path.
+        let (png, tga) = (dir.join("out/a.png"), dir.join("a.tga"));
+        let args = [
+            "--out-dir",
+            path(&out_dir),
+            "--extension",
+            "tga",
+            path(&png),
+            path(&tga),
+        ];
+        assert_eq!(run_convert(&args), ExitCode::from(2));
+        assert!(!out_dir.join("a.tga").exists());
+
+        let missing = dir.join("missing.tga");
+        let args = ["--out-dir", path(&out_dir), path(&missing), path(&pattern)];
+        assert_eq!(run_convert(&args), ExitCode::FAILURE);
+    }
+
+    #[test]
+    fn bad_arguments_and_files_fail() {
+        let dir = TempDir::new("convert-failed");
+        let (input, output) = (dir.join("in.tga"), dir.join("out.png"));
+        assert_eq!(run_convert(&[path(&input)]), ExitCode::from(2));
+        assert_eq!(
+            run_convert(&[path(&input), path(&output)]),
+            ExitCode::FAILURE
+        );
+        assert!(!output.exists());
+    }
+}
@@ -0,0 +1,214 @@
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use image::RgbaImage;
+use tga::{EncodeOptions, Image, PixelFormat, Result};
+
+/// Compares the pixels of two files, exiting with 1 if they differ and 2 if
+/// they cannot be compared.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// The file to compare against, such as a golden image.
+    expected: PathBuf,
+    /// The file to compare.
+    actual: PathBuf,
+    /// The difference in any channel up to which pixels count as equal.
+    #[arg(long, default_value_t = 0)]
+    tolerance: u8,
+    /// Writes an image of where the pixels differ, from black for equal
+    /// pixels through red and yellow to white for the largest difference.
+    #[arg(long)]
+    heatmap: Option<PathBuf>,
+}
+
+/// Statistics of the differences between the channels of two images.
+#[derive(Default)]
+struct Statistics {
+    /// The number of pixels differing by more than the tolerance.
+    differing_pixels: u64,
+    /// The largest difference in any channel.
+    max_difference: u8,
+    /// The sum of the absolute differences of every channel.
+    sum: u64,
+    /// The sum of the squared differences of every channel.
+    sum_of_squares: u64,
+}
+
+pub(crate) fn run(args: &Args) -> ExitCode {
+    match diff(args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            crate::report(&args.actual, &error);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the statistics of the differences, returning whether the images
+/// count as equal.
+fn diff(args: &Args) -> Result<bool> {
+    let expected = RgbaImage::from(crate::convert::read_image(&args.expected)?);
+    let actual = RgbaImage::from(crate::convert::read_image(&args.actual)?);
+    if expected.dimensions() != actual.dimensions() {
+        let (expected_width, expected_height) = expected.dimensions();
+        let (actual_width, actual_height) = actual.dimensions();
+        return Err(io::Error::other(format!(
+            "image is {actual_width} x {actual_height} pixels, expected \
+             {expected_width} x {expected_height}"
+        ))
+        .into());
+    }
+
+    let mut statistics = Statistics::default();
+    let mut differences = Vec::with_capacity(expected.len() / 4);
+    for (expected, actual) in expected.chunks_exact(4).zip(actual.chunks_exact(4)) {
+        let mut difference = 0;
+        for (&expected, &actual) in expected.iter().zip(actual) {
+            let channel = expected.abs_diff(actual);
+            difference = difference.max(channel);
+            statistics.sum += channel as u64;
+            statistics.sum_of_squares += channel as u64 * channel as u64;
+        }
+        if difference > args.tolerance {
+            statistics.differing_pixels += 1;
+        }
+        statistics.max_difference = statistics.max_difference.max(difference);
+        differences.push(difference);
+    }
+
+    let pixels = differences.len() as u64;
+    let channels = (pixels * 4).max(1) as f64;
+    let mean_squared_error = statistics.sum_of_squares as f64 / channels;
+    println!(
+        "{} of {pixels} pixels differ ({:.2}%)",
+        statistics.differing_pixels,
+        100.0 * statistics.differing_pixels as f64 / pixels.max(1) as f64
+    );
+    println!("largest difference:  {}", statistics.max_difference);
+    println!(
+        "mean difference:     {:.4}",
+        statistics.sum as f64 / channels
+    );
+    println!("root mean square:    {:.4}", mean_squared_error.sqrt());
+    if mean_squared_error == 0.0 {
+        println!("PSNR:                infinite");
+    } else {
+        let psnr = 10.0 * (255.0 * 255.0 / mean_squared_error).log10();
+        println!("PSNR:                {psnr:.2} dB");
+    }
+
+    if let Some(path) = &args.heatmap {
+        let (width, height) = expected.dimensions();
+        let heatmap = heatmap(&differences, statistics.max_difference);
+        let heatmap = Image::new(width as u16, height as u16, heatmap)?;
+        let options = EncodeOptions {
+            pixel_format: PixelFormat::Bgr24,
+            ..EncodeOptions::default()
+        };
+        crate::convert::write_image(heatmap, path, &options)?;
+    }
+
+    Ok(statistics.differing_pixels == 0)
+}
+
+/// The 32-bit pixels of a heatmap of `differences`, scaled so that
+/// `max_difference` is white.
+fn heatmap(differences: &[u8], max_difference: u8) -> Vec<u8> {
+    let scale = 3.0 / max_difference.max(1) as f32;
+    differences
+        .iter()
+        .flat_map(|&difference| {
+            let t = difference as f32 * scale;
+            let channel = |offset: f32| ((t - offset).clamp(0.0, 1.0) * 255.0).round() as u8;
+            [channel(2.0), channel(1.0), channel(0.0), u8::MAX]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{parse, pattern, write, TempDir};
+    use crate::Command;
+
+    fn run_diff(args: &[&str]) -> ExitCode {
+        let Command::Diff(args) = parse(&[&["diff"], args].concat()) else {
+            unreachable!();
+        };
+
+        run(&args)
+    }
+
+    /// Writes `image` and a copy whose first pixel is brighter by
+    /// `difference`, returning their paths.
+    fn pair(dir: &TempDir, difference: u8) -> (String, String) {
+        let image = pattern(3, 2);
+        let mut data = image.data().to_vec();
+        data[0] = data[0].saturating_add(difference);
+        let changed = Image::new(3, 2, data).unwrap();
+        let (expected, actual) = (dir.join("expected.tga"), dir.join("actual.tga"));
+        write(&expected, &image, &EncodeOptions::default());
+        write(&actual, &changed, &EncodeOptions::default());
+
+        let path = |path: PathBuf| path.to_str().unwrap().to_owned();
+        (path(expected), path(actual))
+    }
+
+    #[test]
+    fn differences_beyond_the_tolerance_fail() {
+        let dir = TempDir::new("diff");
+        let (expected, actual) = pair(&dir, 5);
+        assert_eq!(run_diff(&[&expected, &expected]), ExitCode::SUCCESS);
+        assert_eq!(run_diff(&[&expected, &actual]), ExitCode::FAILURE);
+        assert_eq!(
+            run_diff(&["--tolerance", "5", &expected, &actual]),
+            ExitCode::SUCCESS
+        );
+    }
+
+    #[test]
+    fn heatmaps_show_where_pixels_differ() {
+        let dir = TempDir::new("diff-heatmap");
+        let (expected, actual) = pair(&dir, 5);
+        let heatmap = dir.join("heatmap.tga");
+        let args = ["--heatmap", heatmap.to_str().unwrap(), &expected, &actual];
+        assert_eq!(run_diff(&args), ExitCode::FAILURE);
+
+        let heatmap = crate::convert::read_image(&heatmap).unwrap();
+        assert_eq!((heatmap.width(), heatmap.height()), (3, 2));
+        assert_eq!(heatmap.data()[..4], [255; 4]);
+        assert!(heatmap.data()[4..]
+            .chunks(4)
+            .all(|pixel| pixel == [0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn heatmaps_run_from_black_through_red_and_yellow_to_white() {
+        assert_eq!(
+            heatmap(&[0, 1, 2, 3], 3),
+            [
+                [0, 0, 0, 255],
+                [0, 0, 255, 255],
+                [0, 255, 255, 255],
+                [255, 255, 255, 255]
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn images_that_cannot_be_compared_fail() {
+        let dir = TempDir::new("diff-failed");
+        let (expected, _) = pair(&dir, 0);
+        let smaller = dir.join("smaller.tga");
+        write(&smaller, &pattern(2, 2), &EncodeOptions::default());
+        let missing = dir.join("missing.tga");
+
+        for actual in [&smaller, &missing] {
+            let code = run_diff(&[&expected, actual.to_str().unwrap()]);
+            assert_eq!(code, ExitCode::from(2));
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// The size of the header.
+const HEADER_SIZE: usize = 18;
+/// The size of the footer, which ends the file.
+const FOOTER_SIZE: usize = 26;
+const SIGNATURE: &[u8] = b"TRUEVISION-XFILE.\0";
+/// The size of the fields of the extension area defined by the spec.
+const EXTENSION_SIZE: usize = 495;
+const DIRECTORY_ENTRY_SIZE: usize = 10;
+/// The most bytes of a field shown.
+const MAX_SHOWN_BYTES: usize = 8;
+
+/// Prints every field of the header, footer, extension area and developer
+/// directory with its offset, raw bytes and decoded value.
+///
+/// The file is read as raw bytes rather than decoded, so that broken files
+/// can be dumped too.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// The file to dump.
+    file: PathBuf,
+}
+
+pub(crate) fn run(args: &Args) -> ExitCode {
+    let data = match fs::read(&args.file) {
+        Ok(data) => data,
+        Err(error) => {
+            crate::report(&args.file, &error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let dump = Dump { data: &data };
+    println!("{}: {} bytes", args.file.display(), data.len());
+    dump.header();
+    let Some(footer) = dump.footer() else {
+        println!();
+        println!("no TGA 2.0 footer");
+        return ExitCode::SUCCESS;
+    };
+    if footer.extension_offset != 0 {
+        dump.extension_area(footer.extension_offset as usize);
+    }
+    if footer.developer_offset != 0 {
+        dump.developer_directory(footer.developer_offset as usize);
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// The offsets the footer holds.
+struct Footer {
+    extension_offset: u32,
+    developer_offset: u32,
+}
+
+struct Dump<'a> {
+    data: &'a [u8],
+}
+
+impl Dump<'_> {
+    fn header(&self) {
+        section("header", 0);
+        self.u8(0, "ID length");
+        self.u8(1, "color map type");
+        self.field(2, 1, "image type", |bytes| {
+            let name = match bytes[0] {
+                0 => "no image data",
+                1 => "uncompressed color-mapped",
+                2 => "uncompressed true-color",
+                3 => "uncompressed grayscale",
+                9 => "run-length encoded color-mapped",
+                10 => "run-length encoded true-color",
+                11 => "run-length encoded grayscale",
+                _ => "unknown",
+            };
+            format!("{} ({name})", bytes[0])
+        });
+        self.u16(3, "first entry index");
+        self.u16(5, "color map length");
+        self.u8(7, "color map entry size");
+        self.u16(8, "x-origin");
+        self.u16(10, "y-origin");
+        self.u16(12, "image width");
+        self.u16(14, "image height");
+        self.u8(16, "pixel depth");
+        self.field(17, 1, "image descriptor", |bytes| {
+            let descriptor = bytes[0];
+            let mut value = format!(
+                "{} alpha bits, {}, {}",
+                descriptor & 0x0f,
+                if descriptor & 0x20 == 0 {
+                    "bottom to top"
+                } else {
+                    "top to bottom"
+                },
+                if descriptor & 0x10 == 0 {
+                    "left to right"
+                } else {
+                    "right to left"
+                },
+            );
+            if descriptor & 0xc0 != 0 {
+                let _ = write!(value, ", reserved bits {:#04x}", descriptor & 0xc0);
+            }
+            value
+        });
+
+        let id_length = self.data.first().copied().unwrap_or(0) as usize;
+        if id_length > 0 {
+            self.field(HEADER_SIZE, id_length, "image ID", |bytes| {
+                format!("{:?}", String::from_utf8_lossy(bytes))
+            });
+        }
+    }
+
+    /// Prints the footer if the file ends in one with a valid signature.
+    fn footer(&self) -> Option<Footer> {
+        let start = self.data.len().checked_sub(FOOTER_SIZE)?;
+        if self.data.len() < HEADER_SIZE + FOOTER_SIZE || !self.data.ends_with(SIGNATURE) {
+            return None;
+        }
+
+        section("footer", start);
+        self.u32(start, "extension area offset");
+        self.u32(start + 4, "developer directory offset");
+        self.field(start + 8, SIGNATURE.len(), "signature", |bytes| {
+            format!("{:?}", String::from_utf8_lossy(bytes))
+        });
+
+        Some(Footer {
+            extension_offset: read_u32(&self.data[start..]),
+            developer_offset: read_u32(&self.data[start + 4..]),
+        })
+    }
+
+    fn extension_area(&self, start: usize) {
+        section("extension area", start);
+        self.u16(start, "extension size");
+        self.string(start + 2, "author name");
+        for line in 0..4 {
+            let offset = start + 43 + line * 81;
+            self.field(offset, 81, &format!("comment line {}", line + 1), text);
+        }
+        self.field(start + 367, 12, "date/time stamp", |bytes| {
+            let [month, day, year, hour, minute, second] = read_u16s(bytes);
+            format!("{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}:{second:02}")
+        });
+        self.string(start + 379, "job name/ID");
+        self.field(start + 420, 6, "job time", |bytes| {
+            let [hours, minutes, seconds] = read_u16s(bytes);
+            format!("{hours}:{minutes:02}:{seconds:02}")
+        });
+        self.string(start + 426, "software ID");
+        self.field(start + 467, 3, "software version", |bytes| {
+            let version = read_u16(bytes);
+            let letter = match bytes[2] {
+                b' ' | 0 => String::new(),
+                letter => char::from(letter).to_string(),
+            };
+            format!("{}.{:02}{letter}", version / 100, version % 100)
+        });
+        self.field(start + 470, 4, "key color", |bytes| {
+            let [b, g, r, a] = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+        });
+        self.field(start + 474, 4, "pixel aspect ratio", |bytes| {
+            let [numerator, denominator] = read_u16s(bytes);
+            format!("{numerator}:{denominator}")
+        });
+        self.field(start + 478, 4, "gamma value", |bytes| {
+            let [numerator, denominator] = read_u16s(bytes);
+            format!("{numerator}/{denominator}")
+        });
+        self.u32(start + 482, "color correction offset");
+        self.u32(start + 486, "postage stamp offset");
+        self.u32(start + 490, "scan line offset");
+        self.field(start + 494, 1, "attributes type", |bytes| {
+            let name = match bytes[0] {
+                0 => "no alpha",
+                1 => "undefined alpha, can be ignored",
+                2 => "undefined alpha, to be kept",
+                3 => "straight alpha",
+                4 => "premultiplied alpha",
+                _ => "unknown",
+            };
+            format!("{} ({name})", bytes[0])
+        });
+
+        let size = self
+            .data
+            .get(start..start + 2)
+            .map_or(0, |bytes| read_u16(bytes) as usize);
+        if size > EXTENSION_SIZE {
+            self.field(
+                start + EXTENSION_SIZE,
+                size - EXTENSION_SIZE,
+                "extra data",
+                |bytes| format!("{} bytes", bytes.len()),
+            );
+        }
+    }
+
+    fn developer_directory(&self, start: usize) {
+        section("developer directory", start);
+        self.u16(start, "tag count");
+        let Some(count) = self.data.get(start..start + 2).map(read_u16) else {
+            return;
+        };
+        for index in 0..count as usize {
+            let offset = start + 2 + index * DIRECTORY_ENTRY_SIZE;
+            self.field(offset, DIRECTORY_ENTRY_SIZE, "tag", |bytes| {
+                format!(
+                    "{} at byte {}, {} bytes",
+                    read_u16(bytes),
+                    read_u32(&bytes[2..]),
+                    read_u32(&bytes[6..])
+                )
+            });
+        }
+    }
+
+    fn u8(&self, offset: usize, name: &str) {
+        self.field(offset, 1, name, |bytes| bytes[0].to_string());
+    }
+
+    fn u16(&self, offset: usize, name: &str) {
+        self.field(offset, 2, name, |bytes| read_u16(bytes).to_string());
+    }
+
+    fn u32(&self, offset: usize, name: &str) {
+        self.field(offset, 4, name, |bytes| read_u32(bytes).to_string());
+    }
+
+    /// Prints a 41-byte string field.
+    fn string(&self, offset: usize, name: &str) {
+        self.field(offset, 41, name, text);
+    }
+
+    /// Prints the `size` bytes at `offset` with the value `decode` decodes
+    /// from them, or that they lie past the end of the file.
+    fn field(&self, offset: usize, size: usize, name: &str, decode: impl FnOnce(&[u8]) -> String) {
+        let Some(bytes) = self.data.get(offset..offset.saturating_add(size)) else {
+            println!(
+                "  {offset:>8}  {:<27}  {name:<28}past the end of the file",
+                ""
+            );
+            return;
+        };
+
+        let mut hex = String::new();
+        for (index, byte) in bytes.iter().take(MAX_SHOWN_BYTES).enumerate() {
+            if index > 0 {
+                hex.push(' ');
+            }
+            let _ = write!(hex, "{byte:02x}");
+        }
+        if bytes.len() > MAX_SHOWN_BYTES {
+            hex.push_str(" ...");
+        }
+        println!("  {offset:>8}  {hex:<27}  {name:<28}{}", decode(bytes));
+    }
+}
+
+fn section(name: &str, offset: usize) {
+    println!();
+    println!("{name} at byte {offset}");
+}
+
+/// Decodes a NUL-terminated string field.
+fn text(bytes: &[u8]) -> String {
+    let length = bytes
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(bytes.len());
+    format!("{:?}", String::from_utf8_lossy(&bytes[..length]))
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn read_u16s<const N: usize>(bytes: &[u8]) -> [u16; N] {
+    std::array::from_fn(|index| read_u16(&bytes[index * 2..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{parse, pattern, TempDir};
+    use crate::Command;
+    use tga::{DeveloperTag, EncodeOptions, ExtensionArea};
+
+    fn run_dump(path: &std::path::Path) -> ExitCode {
+        let Command::Dump(args) = parse(&["dump", path.to_str().unwrap()]) else {
+            unreachable!();
+        };
+
+        run(&args)
+    }
+
+    /// A file with an image ID, an extension area and a developer tag.
+    fn full_file() -> Vec<u8> {
+        let mut image = pattern(4, 2);
+        image.set_id(b"dump".to_vec()).unwrap();
+        image.set_extension_area(Some(ExtensionArea {
+            author_name: "A. Author".to_owned(),
+            extra: vec![1; 5],
+            ..ExtensionArea::default()
+        }));
+        image.set_developer_tags(vec![DeveloperTag {
+            tag: 3,
+            data: vec![1, 2],
+        }]);
+        let mut data = Vec::new();
+        image
+            .write_with_options(&mut data, &EncodeOptions::default())
+            .unwrap();
+
+        data
+    }
+
+    #[test]
+    fn footers_lead_to_the_areas_they_point_at() {
+        let data = full_file();
+        let footer = Dump { data: &data }.footer().unwrap();
+        assert_ne!(footer.extension_offset, 0);
+        assert_ne!(footer.developer_offset, 0);
+
+        let start = footer.extension_offset as usize;
+        assert_eq!(read_u16(&data[start..]), (EXTENSION_SIZE + 5) as u16);
+        assert_eq!(text(&data[start + 2..start + 43]), "\"A. Author\"");
+        let start = footer.developer_offset as usize;
+        assert_eq!(read_u16s::<2>(&data[start..]), [1, 3]);
+
+        assert!(Dump {
+            data: &data[..data.len() - 1]
+        }
+        .footer()
+        .is_none());
+        assert!(Dump { data: SIGNATURE }.footer().is_none());
+    }
+
+    #[test]
+    fn broken_files_are_dumped() {
+        let dir = TempDir::new("dump");
+        let path = dir.join("file.tga");
+        let data = full_file();
+        let footer_start = data.len() - FOOTER_SIZE;
+        let mut past_the_end = data.clone();
+        past_the_end[footer_start..footer_start + 8].fill(0xff);
+        let mut many_tags = data.clone();
+        let developer_offset = read_u32(&data[footer_start + 4..]) as usize;
+        many_tags[developer_offset..developer_offset + 2].fill(0xff);
+
+        for data in [
+            data.clone(),
+            past_the_end,
+            many_tags,
+            data[..10].to_vec(),
+            Vec::new(),
+        ] {
+            std::fs::write(&path, &data).unwrap();
+            assert_eq!(run_dump(&path), ExitCode::SUCCESS);
+        }
+    }
+
+    #[test]
+    fn files_that_cannot_be_read_fail() {
+        let dir = TempDir::new("dump-failed");
+        assert_eq!(run_dump(&dir.join("missing.tga")), ExitCode::FAILURE);
+    }
+
+    #[test]
+    fn text_fields_end_at_a_nul() {
+        assert_eq!(text(b"abc\0def"), "\"abc\"");
+        assert_eq!(text(b"abc"), "\"abc\"");
+    }
+}
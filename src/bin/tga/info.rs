@@ -0,0 +1,277 @@
+use std::fmt::Display;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use tga::{AttributesType, Decoder, ExtensionArea, Ratio, Result};
+
+/// Prints the dimensions, pixel format and metadata of files.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// The files to describe.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+pub(crate) fn run(args: &Args) -> ExitCode {
+    let mut code = ExitCode::SUCCESS;
+    let mut is_first = true;
+    for path in &args.files {
+        match print_info(path, is_first) {
+            Ok(()) => is_first = false,
+            Err(error) => {
+                crate::report(path, &error);
+                code = ExitCode::FAILURE;
+            }
+        }
+    }
+
+    code
+}
+
+fn print_info(path: &Path, is_first: bool) -> Result<()> {
+    let decoder = Decoder::new(BufReader::new(File::open(path)?))?;
+
+    if !is_first {
+        println!();
+    }
+    println!("{}", path.display());
+    field(
+        "Dimensions",
+        format!("{} x {}", decoder.width(), decoder.height()),
+    );
+    let image_type = decoder.image_type();
+    let name = match (image_type.is_rle(), image_type.is_grayscale()) {
+        (false, false) => "uncompressed true-color",
+        (false, true) => "uncompressed grayscale",
+        (true, false) => "run-length encoded true-color",
+        (true, true) => "run-length encoded grayscale",
+    };
+    field("Image type", format!("{} ({name})", u8::from(image_type)));
+    field(
+        "Pixel depth",
+        format!(
+            "{} bits, {} alpha bits",
+            decoder.pixel_depth().bits(),
+            decoder.alpha_depth()
+        ),
+    );
+    field(
+        "Orientation",
+        format!(
+            "{}, {}",
+            if decoder.is_bottom_to_top() {
+                "bottom to top"
+            } else {
+                "top to bottom"
+            },
+            if decoder.is_right_to_left() {
+                "right to left"
+            } else {
+                "left to right"
+            },
+        ),
+    );
+    let options = decoder.encode_options();
+    field(
+        "Compression",
+        match (options.rle, options.scan_line_table) {
+            (false, _) => "none",
+            (true, false) => "run-length encoding",
+            (true, true) => "run-length encoding with a scan line table",
+        },
+    );
+    if !decoder.id().is_empty() {
+        field(
+            "Image ID",
+            format!("{:?}", String::from_utf8_lossy(decoder.id())),
+        );
+    }
+
+    match decoder.extension_area() {
+        Some(extension_area) => print_extension_area(extension_area),
+        None => field("Extension area", "none"),
+    }
+    if let Some(postage_stamp) = decoder.postage_stamp() {
+        field(
+            "Postage stamp",
+            format!("{} x {}", postage_stamp.width(), postage_stamp.height()),
+        );
+    }
+    for tag in decoder.developer_tags() {
+        field(
+            "Developer tag",
+            format!("{} ({} bytes)", tag.tag, tag.data.len()),
+        );
+    }
+    for diagnostic in decoder.diagnostics() {
+        field("Diagnostic", diagnostic);
+    }
+
+    Ok(())
+}
+
+/// Prints the fields of the extension area that are set.
+pub(crate) fn print_extension_area(extension_area: &ExtensionArea) {
+    let text_fields = [
+        ("Author", &extension_area.author_name),
+        ("Job name", &extension_area.job_name),
+        ("Software", &extension_area.software_id),
+    ];
+    for (name, value) in text_fields {
+        if !value.is_empty() {
+            field(name, value);
+        }
+    }
+    for (index, line) in extension_area.comments.lines().iter().enumerate() {
+        if !line.is_empty() {
+            field(&format!("Comment {}", index + 1), line);
+        }
+    }
+
+    let timestamp = extension_area.timestamp;
+    if timestamp != Default::default() {
+        field(
+            "Timestamp",
+            format!(
+                "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                timestamp.year,
+                timestamp.month,
+                timestamp.day,
+                timestamp.hour,
+                timestamp.minute,
+                timestamp.second
+            ),
+        );
+    }
+    let job_time = extension_area.job_time;
+    if job_time != Default::default() {
+        field(
+            "Job time",
+            format!(
+                "{}:{:02}:{:02}",
+                job_time.hours, job_time.minutes, job_time.seconds
+            ),
+        );
+    }
+    if extension_area.software_version != Default::default() {
+        field("Software version", extension_area.software_version);
+    }
+
+    let key_color = extension_area.key_color;
+    if key_color != Default::default() {
+        field(
+            "Key color",
+            format!(
+                "#{:02x}{:02x}{:02x}{:02x}",
+                key_color.r, key_color.g, key_color.b, key_color.a
+            ),
+        );
+    }
+    if let Some(ratio) = ratio(extension_area.pixel_aspect_ratio, ":") {
+        field("Pixel aspect ratio", ratio);
+    }
+    if let Some(ratio) = ratio(extension_area.gamma, "/") {
+        field("Gamma", ratio);
+    }
+    let attributes_type = match extension_area.attributes_type {
+        AttributesType::NoAlpha => "no alpha".to_owned(),
+        AttributesType::Undefined => "undefined alpha, can be ignored".to_owned(),
+        AttributesType::UndefinedKeep => "undefined alpha, to be kept".to_owned(),
+        AttributesType::Useful => "straight alpha".to_owned(),
+        AttributesType::Premultiplied => "premultiplied alpha".to_owned(),
+        AttributesType::Unknown(value) => format!("unknown ({value})"),
+    };
+    field("Attributes type", attributes_type);
+    if extension_area.color_correction_table.is_some() {
+        field("Color correction", "table present");
+    }
+}
+
+/// Formats `ratio` with `separator` if it is specified.
+fn ratio(ratio: Ratio, separator: &str) -> Option<String> {
+    (ratio.denominator != 0).then(|| format!("{}{separator}{}", ratio.numerator, ratio.denominator))
+}
+
+fn field(name: &str, value: impl Display) {
+    println!("  {:<20}{value}", format!("{name}:"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{parse, pattern, write, TempDir};
+    use crate::Command;
+    use tga::EncodeOptions;
+
+    fn run_info(args: &[&str]) -> ExitCode {
+        let Command::Info(args) = parse(&[&["info"], args].concat()) else {
+            unreachable!();
+        };
+
+        run(&args)
+    }
+
+    #[test]
+    fn files_with_metadata_are_described() {
+        let dir = TempDir::new("info");
+        let mut image = pattern(8, 4);
+        image.set_id(b"info".to_vec()).unwrap();
+        image.set_extension_area(Some(ExtensionArea {
+            author_name: "A. Author".to_owned(),
+            pixel_aspect_ratio: Ratio {
+                numerator: 4,
+                denominator: 3,
+            },
+            attributes_type: AttributesType::Unknown(9),
+            ..ExtensionArea::default()
+        }));
+        image.set_postage_stamp(Some(pattern(2, 1))).unwrap();
+        image.set_developer_tags(vec![tga::DeveloperTag {
+            tag: 7,
+            data: vec![1, 2, 3],
+        }]);
+        let options = EncodeOptions {
+            rle: true,
+            scan_line_table: true,
+            ..EncodeOptions::default()
+        };
+        let path = dir.join("full.tga");
+        write(&path, &image, &options);
+        let plain = dir.join("plain.tga");
+        write(&plain, &pattern(3, 3), &EncodeOptions::default());
+
+        let code = run_info(&[path.to_str().unwrap(), plain.to_str().unwrap()]);
+        assert_eq!(code, ExitCode::SUCCESS);
+    }
+
+    #[test]
+    fn files_that_cannot_be_read_fail() {
+        let dir = TempDir::new("info-failed");
+        let plain = dir.join("plain.tga");
+        write(&plain, &pattern(3, 3), &EncodeOptions::default());
+        let broken = dir.join("broken.tga");
+        std::fs::write(&broken, b"short").unwrap();
+
+        for path in [&broken, &dir.join("missing.tga")] {
+            let code = run_info(&[plain.to_str().unwrap(), path.to_str().unwrap()]);
+            assert_eq!(code, ExitCode::FAILURE);
+        }
+    }
+
+    #[test]
+    fn unset_ratios_are_not_shown() {
+        let ratio = |numerator, denominator| {
+            super::ratio(
+                Ratio {
+                    numerator,
+                    denominator,
+                },
+                ":",
+            )
+        };
+        assert_eq!(ratio(16, 9).as_deref(), Some("16:9"));
+        assert_eq!(ratio(1, 0), None);
+    }
+}
@@ -0,0 +1,204 @@
+//! The `tga` command line tool, behind the `cli` feature.
+
+mod convert;
+mod diff;
+mod dump;
+mod info;
+mod meta;
+mod recompress;
+mod strip;
+mod thumb;
+mod validate;
+
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(version, about = "Inspects and converts Truevision TGA files")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    Convert(convert::Args),
+    Diff(diff::Args),
+    Dump(dump::Args),
+    Info(info::Args),
+    Meta(meta::Args),
+    Recompress(recompress::Args),
+    Strip(strip::Args),
+    Thumb(thumb::Args),
+    Validate(validate::Args),
+}
+
+fn main() -> ExitCode {
+    match Cli::parse().command {
+        Command::Convert(args) => convert::run(&args),
+        Command::Diff(args) => diff::run(&args),
+        Command::Dump(args) => dump::run(&args),
+        Command::Info(args) => info::run(&args),
+        Command::Meta(args) => meta::run(&args),
+        Command::Recompress(args) => recompress::run(&args),
+        Command::Strip(args) => strip::run(&args),
+        Command::Thumb(args) => thumb::run(&args),
+        Command::Validate(args) => validate::run(&args),
+    }
+}
+
+/// Reports that handling the file at `path` failed.
+fn report(path: &Path, error: &dyn Error) {
+    eprintln!("tga: {}: {}", path.display(), describe(error));
+}
+
+/// Describes `error` followed by the errors that caused it.
+fn describe(error: &dyn Error) -> String {
+    let mut description = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        description.push_str(": ");
+        description.push_str(&error.to_string());
+        source = error.source();
+    }
+
+    description
+}
+
+/// Replaces the file at `path` with what `write` writes, returning the sizes
+/// of the file before and after.
+///
+/// The new file is written next to the old one and then moved over it, so
+/// that the file is left as it was if anything fails. The old file can still
+/// be read while `write` runs.
+fn rewrite(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> tga::Result<()>,
+) -> tga::Result<(u64, u64)> {
+    let before = fs::metadata(path)?.len();
+    let mut temporary = OsString::from(path);
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+
+    let result = File::create(&temporary)
+        .map_err(tga::Error::from)
+        .and_then(|file| {
+            let mut w = BufWriter::new(file);
+            write(&mut w)?;
+            w.flush()?;
+            let after = w.get_ref().metadata()?.len();
+            fs::rename(&temporary, path)?;
+
+            Ok(after)
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+
+    Ok((before, result?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory removed with everything in it when dropped, named after
+    /// the test using it.
+    pub(crate) struct TempDir(PathBuf);
+
+    impl TempDir {
+        pub(crate) fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("tga-cli-{name}-{}", std::process::id()));
+            fs::create_dir_all(&path).unwrap();
+
+            TempDir(path)
+        }
+
+        pub(crate) fn join(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Parses the arguments following `tga` on the command line.
+    pub(crate) fn parse(args: &[&str]) -> Command {
+        let args = ["tga"].iter().chain(args);
+
+        Cli::try_parse_from(args).unwrap().command
+    }
+
+    /// A `width` by `height` image of varied, partly transparent pixels.
+    pub(crate) fn pattern(width: u16, height: u16) -> tga::Image {
+        let data = (0..width as usize * height as usize * 4)
+            .map(|index| (index * 37 % 251) as u8)
+            .collect();
+
+        tga::Image::new(width, height, data).unwrap()
+    }
+
+    /// Writes `image` to `path` as described by `options`.
+    pub(crate) fn write(path: &Path, image: &tga::Image, options: &tga::EncodeOptions) {
+        let mut data = Vec::new();
+        image.write_with_options(&mut data, options).unwrap();
+        fs::write(path, data).unwrap();
+    }
+
+    #[test]
+    fn subcommands_require_their_arguments() {
+        for args in [
+            &[][..],
+            &["info"],
+            &["convert"],
+            &["thumb", "a.tga"],
+            &["bogus"],
+        ] {
+            let args = ["tga"].iter().chain(args);
+            assert!(Cli::try_parse_from(args).is_err());
+        }
+        assert!(matches!(parse(&["info", "a.tga"]), Command::Info(_)));
+    }
+
+    #[test]
+    fn errors_are_described_with_their_causes() {
+        let error = tga::Error::from(std::io::Error::other("disk on fire"));
+        assert_eq!(describe(&error), format!("{error}: disk on fire"));
+    }
+
+    #[test]
+    fn rewriting_replaces_the_file() {
+        let dir = TempDir::new("rewrite");
+        let path = dir.join("file.bin");
+        fs::write(&path, b"before").unwrap();
+
+        let sizes = rewrite(&path, |w| Ok(w.write_all(b"after!!")?)).unwrap();
+        assert_eq!(sizes, (6, 7));
+        assert_eq!(fs::read(&path).unwrap(), b"after!!");
+    }
+
+    #[test]
+    fn failed_rewrites_leave_the_file_as_it_was() {
+        let dir = TempDir::new("rewrite-failed");
+        let path = dir.join("file.bin");
+        fs::write(&path, b"before").unwrap();
+
+        let result = rewrite(&path, |w| {
+            w.write_all(b"partial")?;
+            Err(tga::Error::TooLarge("image"))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"before");
+        assert_eq!(fs::read_dir(&dir.0).unwrap().count(), 1);
+        assert!(rewrite(&dir.join("missing.bin"), |_| Ok(())).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use std::fs::{File, OpenOptions};
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use tga::{Comments, Decoder, MetadataEditor, Result, Timestamp};
+
+/// Prints the extension area of a file, or overwrites some of its fields in
+/// place without touching the rest of the file.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// The file to read or edit.
+    file: PathBuf,
+    /// Sets the author name, truncated to 40 bytes.
+    #[arg(long)]
+    author: Option<String>,
+    /// Sets the first comment line, truncated to 80 bytes.
+    #[arg(long)]
+    comment1: Option<String>,
+    /// Sets the second comment line.
+    #[arg(long)]
+    comment2: Option<String>,
+    /// Sets the third comment line.
+    #[arg(long)]
+    comment3: Option<String>,
+    /// Sets the fourth comment line.
+    #[arg(long)]
+    comment4: Option<String>,
+    /// Sets the timestamp, given as YYYY-MM-DD HH:MM:SS.
+    #[arg(long, value_parser = parse_timestamp)]
+    timestamp: Option<Timestamp>,
+}
+
+impl Args {
+    fn comment_lines(&self) -> [Option<&str>; Comments::LINE_COUNT] {
+        [
+            &self.comment1,
+            &self.comment2,
+            &self.comment3,
+            &self.comment4,
+        ]
+        .map(|line| line.as_deref())
+    }
+
+    fn is_editing(&self) -> bool {
+        self.author.is_some()
+            || self.timestamp.is_some()
+            || self.comment_lines().iter().any(Option::is_some)
+    }
+}
+
+pub(crate) fn run(args: &Args) -> ExitCode {
+    let result = if args.is_editing() {
+        edit(args)
+    } else {
+        print(args)
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            crate::report(&args.file, &error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints the fields of the extension area that are set. This reads the file
+/// with a [`Decoder`], since opening a [`MetadataEditor`] would append an
+/// extension area to files without one.
+fn print(args: &Args) -> Result<()> {
+    let decoder = Decoder::new(BufReader::new(File::open(&args.file)?))?;
+    match decoder.extension_area() {
+        Some(extension_area) => crate::info::print_extension_area(extension_area),
+        None => println!("{}: no extension area", args.file.display()),
+    }
+
+    Ok(())
+}
+
+/// Overwrites the fields given, adding an extension area if there is none.
+fn edit(args: &Args) -> Result<()> {
+    let file = OpenOptions::new().read(true).write(true).open(&args.file)?;
+    let mut editor = MetadataEditor::new(file)?;
+    if let Some(author) = &args.author {
+        editor.set_author_name(author)?;
+    }
+
+    let lines = args.comment_lines();
+    if lines.iter().any(Option::is_some) {
+        let mut comments = editor.extension_area()?.comments;
+        for (index, line) in lines.into_iter().enumerate() {
+            let Some(line) = line else {
+                continue;
+            };
+            if !comments.set_line(index, line) {
+                eprintln!(
+                    "tga: {}: comment line {} truncated to {} bytes",
+                    args.file.display(),
+                    index + 1,
+                    Comments::MAX_LINE_LENGTH
+                );
+            }
+        }
+        editor.set_comments(&comments)?;
+    }
+
+    if let Some(timestamp) = args.timestamp {
+        editor.set_timestamp(timestamp)?;
+    }
+    editor.into_inner()?;
+
+    Ok(())
+}
+
+/// Parses a timestamp such as `2024-05-06 07:08:09`, also accepting a `T`
+/// between the date and the time.
+fn parse_timestamp(text: &str) -> std::result::Result<Timestamp, String> {
+    let invalid = || format!("`{text}` is not a timestamp of the form YYYY-MM-DD HH:MM:SS");
+    let (date, time) = text.split_once([' ', 'T']).ok_or_else(invalid)?;
+    let fields = |text: &str, separator: char| -> Option<[u16; 3]> {
+        let mut parts = text.split(separator).map(|part| part.parse().ok());
+        let fields = [parts.next()??, parts.next()??, parts.next()??];
+        parts.next().is_none().then_some(fields)
+    };
+    let [year, month, day] = fields(date, '-').ok_or_else(invalid)?;
+    let [hour, minute, second] = fields(time, ':').ok_or_else(invalid)?;
+
+    let is_valid = (1..=12).contains(&month)
+        && (1..=31).contains(&day)
+        && hour < 24
+        && minute < 60
+        && second < 60;
+    if !is_valid {
+        return Err(invalid());
+    }
+
+    Ok(Timestamp {
+        month,
+        day,
+        year,
+        hour,
+        minute,
+        second,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{parse, pattern, write, TempDir};
+    use crate::Command;
+    use tga::{EncodeOptions, ExtensionArea};
+
+    fn run_meta(args: &[&str]) -> ExitCode {
+        let Command::Meta(args) = parse(&[&["meta"], args].concat()) else {
+            unreachable!();
+        };
+
+        run(&args)
+    }
+
+    fn extension_area(path: &std::path::Path) -> Option<ExtensionArea> {
+        let decoder = Decoder::new(BufReader::new(File::open(path).unwrap())).unwrap();
+
+        decoder.extension_area().cloned()
+    }
+
+    #[test]
+    fn printing_leaves_the_file_untouched() {
+        let dir = TempDir::new("meta");
+        let path = dir.join("plain.tga");
+        write(&path, &pattern(3, 3), &EncodeOptions::default());
+        let before = std::fs::read(&path).unwrap();
+
+        assert_eq!(run_meta(&[path.to_str().unwrap()]), ExitCode::SUCCESS);
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+    }
+
+    #[test]
+    fn fields_are_set_in_place() {
+        let dir = TempDir::new("meta-edit");
+        let path = dir.join("edited.tga");
+        let image = pattern(3, 3);
+        write(&path, &image, &EncodeOptions::default());
+
+        let long = "x".repeat(100);
+        let args = [
+            "--author",
+            "A. Author",
+            "--comment2",
+            "second",
+            "--comment4",
+            &long,
+            "--timestamp",
+            "2024-05-06T07:08:09",
+            path.to_str().unwrap(),
+        ];
+        assert_eq!(run_meta(&args), ExitCode::SUCCESS);
+        let args = ["--comment1", "first", path.to_str().unwrap()];
+        assert_eq!(run_meta(&args), ExitCode::SUCCESS);
+
+        let extension_area = extension_area(&path).unwrap();
+        assert_eq!(extension_area.author_name, "A. Author");
+        assert_eq!(
+            extension_area.comments.lines(),
+            &[
+                "first".to_owned(),
+                "second".to_owned(),
+                String::new(),
+                "x".repeat(Comments::MAX_LINE_LENGTH)
+            ]
+        );
+        assert_eq!(
+            extension_area.timestamp,
+            parse_timestamp("2024-05-06 07:08:09").unwrap()
+        );
+        assert_eq!(
+            crate::convert::read_image(&path).unwrap().data(),
+            image.data()
+        );
+    }
+
+    #[test]
+    fn timestamps_are_parsed() {
+        assert_eq!(
+            parse_timestamp("1999-12-31 23:59:58"),
+            Ok(Timestamp {
+                month: 12,
+                day: 31,
+                year: 1999,
+                hour: 23,
+                minute: 59,
+                second: 58,
+            })
+        );
+        for text in [
+            "",
+            "1999-12-31",
+            "1999-12-31 23:59",
+            "1999-12-31 23:59:58:00",
+            "1999-13-31 23:59:58",
+            "1999-12-00 23:59:58",
+            "1999-12-31 24:00:00",
+            "1999-12-31 23:60:00",
+            "year-12-31 23:59:58",
+        ] {
+            assert!(parse_timestamp(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn files_that_cannot_be_read_fail() {
+        let dir = TempDir::new("meta-failed");
+        let path = dir.join("missing.tga");
+        assert_eq!(run_meta(&[path.to_str().unwrap()]), ExitCode::FAILURE);
+        let args = ["--author", "A. Author", path.to_str().unwrap()];
+        assert_eq!(run_meta(&args), ExitCode::FAILURE);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,126 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use tga::{Result, TranscodeOptions};
+
+use crate::convert::Depth;
+
+/// Rewrites files in place with a different compression or bit depth, keeping
+/// their metadata.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// The files to rewrite.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Compresses the pixels with run-length encoding.
+    #[arg(long, conflicts_with = "raw")]
+    rle: bool,
+    /// Stores the pixels uncompressed.
+    #[arg(long)]
+    raw: bool,
+    /// The bits per pixel written, discarding the alpha channel for 24 and
+    /// writing grayscale for 8. Files keep their depth by default.
+    #[arg(long, value_enum)]
+    depth: Option<Depth>,
+}
+
+pub(crate) fn run(args: &Args) -> ExitCode {
+    let options = TranscodeOptions {
+        pixel_format: args.depth.map(Depth::pixel_format),
+        rle: match (args.rle, args.raw) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        },
+    };
+
+    let mut code = ExitCode::SUCCESS;
+    for path in &args.files {
+        match recompress(path, options) {
+            Ok((before, after)) => println!("{}: {before} -> {after} bytes", path.display()),
+            Err(error) => {
+                crate::report(path, &error);
+                code = ExitCode::FAILURE;
+            }
+        }
+    }
+
+    code
+}
+
+/// Transcodes the file at `path` in place, returning its sizes before and
+/// after.
+fn recompress(path: &Path, options: TranscodeOptions) -> Result<(u64, u64)> {
+    let reader = BufReader::new(File::open(path)?);
+
+    crate::rewrite(path, |w| tga::transcode_pipelined(reader, w, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{parse, pattern, write, TempDir};
+    use crate::Command;
+    use clap::Parser;
+    use tga::{Decoder, EncodeOptions, ExtensionArea, PixelDepth};
+
+    fn run_recompress(args: &[&str]) -> ExitCode {
+        let Command::Recompress(args) = parse(&[&["recompress"], args].concat()) else {
+            unreachable!();
+        };
+
+        run(&args)
+    }
+
+    fn open(path: &Path) -> Decoder<BufReader<File>> {
+        Decoder::new(BufReader::new(File::open(path).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn files_are_rewritten_keeping_their_metadata() {
+        let dir = TempDir::new("recompress");
+        let path = dir.join("image.tga");
+        let mut image = pattern(6, 4);
+        image.set_extension_area(Some(ExtensionArea {
+            author_name: "A. Author".to_owned(),
+            ..ExtensionArea::default()
+        }));
+        write(&path, &image, &EncodeOptions::default());
+
+        assert_eq!(
+            run_recompress(&["--rle", path.to_str().unwrap()]),
+            ExitCode::SUCCESS
+        );
+        let decoder = open(&path);
+        assert!(decoder.encode_options().rle);
+        assert_eq!(decoder.extension_area().unwrap().author_name, "A. Author");
+        assert_eq!(decoder.read_image().unwrap().data(), image.data());
+
+        let args = ["--raw", "--depth", "24", path.to_str().unwrap()];
+        assert_eq!(run_recompress(&args), ExitCode::SUCCESS);
+        let decoder = open(&path);
+        assert!(!decoder.encode_options().rle);
+        assert_eq!(decoder.pixel_depth(), PixelDepth::B24);
+    }
+
+    #[test]
+    fn broken_files_are_left_as_they_were() {
+        let dir = TempDir::new("recompress-failed");
+        let valid = dir.join("valid.tga");
+        write(&valid, &pattern(2, 2), &EncodeOptions::default());
+        let broken = dir.join("broken.tga");
+        let mut data = std::fs::read(&valid).unwrap();
+        data.truncate(30);
+        std::fs::write(&broken, &data).unwrap();
+
+        let args = ["--rle", broken.to_str().unwrap(), valid.to_str().unwrap()];
+        assert_eq!(run_recompress(&args), ExitCode::FAILURE);
+        assert_eq!(std::fs::read(&broken).unwrap(), data);
+        assert!(open(&valid).encode_options().rle);
+
+        let args = ["tga", "recompress", "--rle", "--raw", "a.tga"];
+        assert!(crate::Cli::try_parse_from(args).is_err());
+    }
+}
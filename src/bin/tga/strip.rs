@@ -0,0 +1,157 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use tga::{Decoder, Result};
+
+/// Rewrites files in place without their image ID, extension area and
+/// developer area, such as the author name and timestamps.
+///
+/// The extension area also holds the postage stamp, the scan line table and
+/// the color correction table, which go with it. The pixels are encoded as
+/// they were, except that 15- and 16-bit pixels become 32-bit ones.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// The files to strip.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Keeps the image ID.
+    #[arg(long)]
+    keep_id: bool,
+    /// Keeps the extension area.
+    #[arg(long)]
+    keep_extension: bool,
+    /// Keeps the developer area.
+    #[arg(long)]
+    keep_developer: bool,
+}
+
+pub(crate) fn run(args: &Args) -> ExitCode {
+    let mut code = ExitCode::SUCCESS;
+    for path in &args.files {
+        match strip(path, args) {
+            Ok((before, after)) => println!("{}: {before} -> {after} bytes", path.display()),
+            Err(error) => {
+                crate::report(path, &error);
+                code = ExitCode::FAILURE;
+            }
+        }
+    }
+
+    code
+}
+
+fn strip(path: &Path, args: &Args) -> Result<(u64, u64)> {
+    let decoder = Decoder::new(BufReader::new(File::open(path)?))?;
+    let mut options = decoder.encode_options();
+    let mut image = decoder.read_image()?;
+    if !args.keep_id {
+        image.set_id(Vec::new())?;
+    }
+    if !args.keep_extension {
+        image.set_extension_area(None);
+        image.set_postage_stamp(None)?;
+        options.scan_line_table = false;
+    }
+    if !args.keep_developer {
+        image.set_developer_tags(Vec::new());
+    }
+
+    crate::rewrite(path, |w| image.write_with_options(w, &options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{parse, pattern, write, TempDir};
+    use crate::Command;
+    use tga::{DeveloperTag, EncodeOptions, ExtensionArea, Image};
+
+    fn run_strip(args: &[&str]) -> ExitCode {
+        let Command::Strip(args) = parse(&[&["strip"], args].concat()) else {
+            unreachable!();
+        };
+
+        run(&args)
+    }
+
+    fn open(path: &Path) -> Decoder<BufReader<File>> {
+        Decoder::new(BufReader::new(File::open(path).unwrap())).unwrap()
+    }
+
+    /// An image with every kind of metadata strip removes.
+    fn image() -> Image {
+        let mut image = pattern(4, 4);
+        image.set_id(b"strip".to_vec()).unwrap();
+        image.set_extension_area(Some(ExtensionArea {
+            author_name: "A. Author".to_owned(),
+            ..ExtensionArea::default()
+        }));
+        image.set_postage_stamp(Some(pattern(1, 1))).unwrap();
+        image.set_developer_tags(vec![DeveloperTag {
+            tag: 1,
+            data: vec![1; 3],
+        }]);
+
+        image
+    }
+
+    fn options() -> EncodeOptions {
+        EncodeOptions {
+            rle: true,
+            scan_line_table: true,
+            ..EncodeOptions::default()
+        }
+    }
+
+    #[test]
+    fn metadata_is_removed() {
+        let dir = TempDir::new("strip");
+        let path = dir.join("image.tga");
+        write(&path, &image(), &options());
+
+        assert_eq!(run_strip(&[path.to_str().unwrap()]), ExitCode::SUCCESS);
+        let decoder = open(&path);
+        assert!(decoder.id().is_empty());
+        assert!(decoder.extension_area().is_none());
+        assert!(decoder.postage_stamp().is_none());
+        assert!(decoder.developer_tags().is_empty());
+        assert!(decoder.encode_options().rle);
+        assert_eq!(decoder.read_image().unwrap().data(), image().data());
+    }
+
+    #[test]
+    fn metadata_is_kept_when_asked() {
+        let dir = TempDir::new("strip-keep");
+        let path = dir.join("image.tga");
+        write(&path, &image(), &options());
+
+        let args = [
+            "--keep-id",
+            "--keep-extension",
+            "--keep-developer",
+            path.to_str().unwrap(),
+        ];
+        assert_eq!(run_strip(&args), ExitCode::SUCCESS);
+        let decoder = open(&path);
+        assert_eq!(decoder.id(), b"strip");
+        assert_eq!(decoder.extension_area().unwrap().author_name, "A. Author");
+        assert!(decoder.postage_stamp().is_some());
+        assert!(decoder.scan_line_table().is_some());
+        assert_eq!(decoder.developer_tags().len(), 1);
+    }
+
+    #[test]
+    fn broken_files_are_left_as_they_were() {
+        let dir = TempDir::new("strip-failed");
+        let path = dir.join("broken.tga");
+        std::fs::write(&path, b"broken").unwrap();
+        let missing = dir.join("missing.tga");
+
+        let args = [path.to_str().unwrap(), missing.to_str().unwrap()];
+        assert_eq!(run_strip(&args), ExitCode::FAILURE);
+        assert_eq!(std::fs::read(&path).unwrap(), b"broken");
+        assert!(!missing.exists());
+    }
+}
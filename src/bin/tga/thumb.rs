@@ -0,0 +1,110 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use fast_image_resize::FilterType;
+use tga::{Decoder, EncodeOptions, Result};
+
+/// Writes a thumbnail of a file, using its postage stamp if it has one.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// The file to read.
+    input: PathBuf,
+    /// The file to write, as a TGA or PNG file depending on its extension.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// The number of pixels the thumbnail fits within on either side.
+    #[arg(long, default_value_t = 64, value_parser = clap::value_parser!(u16).range(1..))]
+    size: u16,
+}
+
+pub(crate) fn run(args: &Args) -> ExitCode {
+    match thumb(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            crate::report(&args.input, &error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Shrinks the postage stamp if the file has one, which saves decoding the
+/// image, and the image otherwise. A postage stamp smaller than the size is
+/// used as it is.
+fn thumb(args: &Args) -> Result<()> {
+    let decoder = Decoder::new(BufReader::new(File::open(&args.input)?))?;
+    let thumbnail = match decoder.postage_stamp() {
+        Some(postage_stamp) => {
+            postage_stamp.thumbnail(args.size, args.size, FilterType::Bilinear)?
+        }
+        None => decoder
+            .read_image()?
+            .thumbnail(args.size, args.size, FilterType::Bilinear)?,
+    };
+
+    crate::convert::write_image(thumbnail, &args.output, &EncodeOptions::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{parse, pattern, write, TempDir};
+    use crate::Command;
+    use clap::Parser;
+
+    fn run_thumb(args: &[&str]) -> ExitCode {
+        let Command::Thumb(args) = parse(&[&["thumb"], args].concat()) else {
+            unreachable!();
+        };
+
+        run(&args)
+    }
+
+    #[test]
+    fn images_are_shrunk_to_fit() {
+        let dir = TempDir::new("thumb");
+        let (input, output) = (dir.join("in.tga"), dir.join("out.png"));
+        write(&input, &pattern(16, 8), &EncodeOptions::default());
+
+        let args = [
+            "--size",
+            "4",
+            input.to_str().unwrap(),
+            "-o",
+            output.to_str().unwrap(),
+        ];
+        assert_eq!(run_thumb(&args), ExitCode::SUCCESS);
+        let thumbnail = crate::convert::read_image(&output).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (4, 2));
+    }
+
+    #[test]
+    fn postage_stamps_are_used_when_present() {
+        let dir = TempDir::new("thumb-stamp");
+        let (input, output) = (dir.join("in.tga"), dir.join("out.tga"));
+        let stamp = tga::Image::new(2, 2, vec![9; 16]).unwrap();
+        let mut image = pattern(16, 16);
+        image.set_postage_stamp(Some(stamp.clone())).unwrap();
+        write(&input, &image, &EncodeOptions::default());
+
+        let args = [input.to_str().unwrap(), "-o", output.to_str().unwrap()];
+        assert_eq!(run_thumb(&args), ExitCode::SUCCESS);
+        assert_eq!(
+            crate::convert::read_image(&output).unwrap().data(),
+            stamp.data()
+        );
+    }
+
+    #[test]
+    fn bad_sizes_and_files_fail() {
+        let dir = TempDir::new("thumb-failed");
+        let (input, output) = (dir.join("missing.tga"), dir.join("out.tga"));
+        let args = [input.to_str().unwrap(), "-o", output.to_str().unwrap()];
+        assert_eq!(run_thumb(&args), ExitCode::FAILURE);
+        assert!(!output.exists());
+
+        let args = ["tga", "thumb", "--size", "0", "in.tga", "-o", "out.tga"];
+        assert!(crate::Cli::try_parse_from(args).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use std::fs::File;
+use std::io::{BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use tga::{DecodeOptions, Decoder, Result, Severity, ViolationPolicy};
+
+/// Checks files against the spec, reporting every violation with the offset
+/// it was found at and failing if there are any.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// The files to check.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+pub(crate) fn run(args: &Args) -> ExitCode {
+    let mut failed = 0;
+    for path in &args.files {
+        match validate(path) {
+            Ok(true) => println!("{}: ok", path.display()),
+            Ok(false) => failed += 1,
+            Err(error) => {
+                println!("{}: error: {}", path.display(), crate::describe(&error));
+                failed += 1;
+            }
+        }
+    }
+
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        eprintln!("tga: {failed} of {} files are invalid", args.files.len());
+        ExitCode::FAILURE
+    }
+}
+
+/// Prints the violations found in the file at `path`, returning whether there
+/// are none.
+///
+/// The header is cross-checked first. The file is then decoded fixing up
+/// every violation the decoder can work around rather than stopping at the
+/// first, with the alpha depth and color map left to the header check.
+/// Diagnostics that are only informational are printed without failing the
+/// file, while anything the decoder cannot work around fails it.
+fn validate(path: &Path) -> Result<bool> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut is_valid = true;
+    for violation in tga::validate_header(&mut reader)? {
+        println!(
+            "{}: error at byte {}: {violation}",
+            path.display(),
+            violation.offset()
+        );
+        is_valid = false;
+    }
+
+    reader.seek(SeekFrom::Start(0))?;
+    let options = DecodeOptions {
+        alpha_depth: ViolationPolicy::Ignore,
+        color_map: ViolationPolicy::Ignore,
+        ..DecodeOptions::default()
+    };
+    let decoded = Decoder::with_options(reader, options)?.read_image_with_diagnostics()?;
+    for diagnostic in &decoded.diagnostics {
+        let severity = match diagnostic.severity {
+            Severity::Info => "info",
+            Severity::Warning => {
+                is_valid = false;
+                "error"
+            }
+        };
+        println!(
+            "{}: {severity} at byte {} [{}]: {} (reading {})",
+            path.display(),
+            diagnostic.offset,
+            diagnostic.code,
+            diagnostic.message,
+            diagnostic.context
+        );
+    }
+
+    Ok(is_valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{parse, pattern, write, TempDir};
+    use crate::Command;
+    use tga::{EncodeOptions, PixelFormat};
+
+    #[test]
+    fn valid_files_pass() {
+        let dir = TempDir::new("validate");
+        let path = dir.join("valid.tga");
+        write(&path, &pattern(4, 4), &EncodeOptions::default());
+        assert!(validate(&path).unwrap());
+
+        let Command::Validate(args) = parse(&["validate", path.to_str().unwrap()]) else {
+            unreachable!();
+        };
+        assert_eq!(run(&args), ExitCode::SUCCESS);
+    }
+
+    #[test]
+    fn violations_fail_the_file() {
+        let dir = TempDir::new("validate-failed");
+        let path = dir.join("alpha.tga");
+        let options = EncodeOptions {
+            pixel_format: PixelFormat::Bgr24,
+            ..EncodeOptions::default()
+        };
+        write(&path, &pattern(4, 4), &options);
+        let mut data = std::fs::read(&path).unwrap();
+        // 8 alpha bits do not fit 24-bit pixels.
+        data[17] |= 8;
+        std::fs::write(&path, &data).unwrap();
+        assert!(!validate(&path).unwrap());
+
+        let truncated = dir.join("truncated.tga");
+        std::fs::write(&truncated, &data[..10]).unwrap();
+        assert!(validate(&truncated).is_err());
+
+        let valid = dir.join("valid.tga");
+        write(&valid, &pattern(4, 4), &EncodeOptions::default());
+        let files = [&valid, &path, &truncated].map(|path| path.to_str().unwrap());
+        let Command::Validate(args) = parse(&[&["validate"], &files[..]].concat()) else {
+            unreachable!();
+        };
+        assert_eq!(run(&args), ExitCode::FAILURE);
+    }
+}
@@ -0,0 +1,346 @@
+//! Reading and writing uncompressed BMP files, which store their pixels in the
+//! same blue, green, red, alpha order as TGA files, usually from bottom to top.
+
+use alloc::vec;
+
+use byteorder::LittleEndian;
+
+use crate::decoder::{expand_16_bit_row, expand_row};
+use crate::io::{Read, ReadBytesExt, Write, WriteBytesExt};
+use crate::{Error, Image, Result};
+
+const SIGNATURE: [u8; 2] = *b"BM";
+
+const FILE_HEADER_SIZE: u32 = 14;
+/// The size of the `BITMAPCOREHEADER` of OS/2 files.
+const CORE_HEADER_SIZE: u32 = 12;
+/// The size of the `BITMAPINFOHEADER`, which later headers extend.
+const INFO_HEADER_SIZE: u32 = 40;
+/// The size of the smallest header that holds the red, green and blue masks.
+const V2_HEADER_SIZE: u32 = 52;
+/// The size of the smallest header that holds the alpha mask.
+const V3_HEADER_SIZE: u32 = 56;
+/// The size of the `BITMAPV4HEADER`, which is written since it is the
+/// smallest header with an alpha mask that readers commonly support.
+const V4_HEADER_SIZE: u32 = 108;
+
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+
+const RED_MASK: u32 = 0x00ff_0000;
+const GREEN_MASK: u32 = 0x0000_ff00;
+const BLUE_MASK: u32 = 0x0000_00ff;
+const ALPHA_MASK: u32 = 0xff00_0000;
+
+/// The color space type of sRGB colors, `'sRGB'` read as a big-endian word.
+const LCS_SRGB: u32 = 0x7352_4742;
+/// 72 pixels per inch, in pixels per meter.
+const PIXELS_PER_METER: i32 = 2835;
+
+impl Image {
+    /// Writes the image as an uncompressed 32-bit BMP file that keeps the
+    /// alpha channel.
+    ///
+    /// The rows are written from bottom to top as they are, without
+    /// converting any pixels. Fails for images too large for the size fields
+    /// of the file.
+    pub fn write_bmp<T: Write>(&self, w: &mut T) -> Result<()> {
+        let row_size = Image::effective_size(self.width, 1);
+        let pixel_size =
+            u32::try_from(row_size * self.height as usize).map_err(|_| Error::TooLarge("image"))?;
+        let offset = FILE_HEADER_SIZE + V4_HEADER_SIZE;
+        let file_size = offset
+            .checked_add(pixel_size)
+            .ok_or(Error::TooLarge("image"))?;
+
+        w.write_all(&SIGNATURE)?;
+        w.write_u32::<LittleEndian>(file_size)?;
+        w.write_u32::<LittleEndian>(0)?;
+        w.write_u32::<LittleEndian>(offset)?;
+
+        w.write_u32::<LittleEndian>(V4_HEADER_SIZE)?;
+        w.write_i32::<LittleEndian>(self.width as i32)?;
+        w.write_i32::<LittleEndian>(self.height as i32)?;
+        w.write_u16::<LittleEndian>(1)?;
+        w.write_u16::<LittleEndian>(32)?;
+        w.write_u32::<LittleEndian>(BI_BITFIELDS)?;
+        w.write_u32::<LittleEndian>(pixel_size)?;
+        w.write_i32::<LittleEndian>(PIXELS_PER_METER)?;
+        w.write_i32::<LittleEndian>(PIXELS_PER_METER)?;
+        w.write_u32::<LittleEndian>(0)?;
+        w.write_u32::<LittleEndian>(0)?;
+        for mask in [RED_MASK, GREEN_MASK, BLUE_MASK, ALPHA_MASK] {
+            w.write_u32::<LittleEndian>(mask)?;
+        }
+        w.write_u32::<LittleEndian>(LCS_SRGB)?;
+        // The endpoints and gammas, which sRGB colors do not use.
+        w.write_all(&[0; 48])?;
+
+        for y in (0..self.height as usize).rev() {
+            w.write_all(self.row(y))?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads an uncompressed BMP file with 16-, 24- or 32-bit pixels.
+    ///
+    /// 16-bit pixels must have five bits for each channel. 32-bit pixels must
+    /// be stored in blue, green, red, alpha order, and are read straight into
+    /// the image. Alpha is only read from files with an alpha mask, and
+    /// pixels are opaque otherwise.
+    ///
+    /// Palettes, compressed pixels and images wider or taller than 65535
+    /// pixels are not supported.
+    pub fn read_bmp<T: Read>(r: &mut T) -> Result<Image> {
+        let mut signature = [0; 2];
+        r.read_exact(&mut signature)?;
+        if signature != SIGNATURE {
+            return Err(Error::InvalidBmp("file does not start with \"BM\""));
+        }
+        let _file_size = r.read_u32::<LittleEndian>()?;
+        let _reserved = r.read_u32::<LittleEndian>()?;
+        let offset = r.read_u32::<LittleEndian>()?;
+
+        let header_size = r.read_u32::<LittleEndian>()?;
+        let (width, height, bit_count, compression) = match header_size {
+            CORE_HEADER_SIZE => {
+                let width = r.read_u16::<LittleEndian>()? as i32;
+                let height = r.read_u16::<LittleEndian>()? as i32;
+                let _planes = r.read_u16::<LittleEndian>()?;
+                let bit_count = r.read_u16::<LittleEndian>()?;
+                (width, height, bit_count, BI_RGB)
+            }
+            size if size >= INFO_HEADER_SIZE => {
+                let width = r.read_i32::<LittleEndian>()?;
+                let height = r.read_i32::<LittleEndian>()?;
+                let _planes = r.read_u16::<LittleEndian>()?;
+                let bit_count = r.read_u16::<LittleEndian>()?;
+                let compression = r.read_u32::<LittleEndian>()?;
+                skip(r, 20)?;
+                (width, height, bit_count, compression)
+            }
+            _ => return Err(Error::InvalidBmp("unknown header size")),
+        };
+
+        // Files with the original info header store the masks right after it.
+        let mut read = header_size.min(INFO_HEADER_SIZE);
+        let mut masks = [0; 4];
+        if header_size >= V2_HEADER_SIZE || compression == BI_BITFIELDS && read == INFO_HEADER_SIZE
+        {
+            let count = if header_size >= V3_HEADER_SIZE { 4 } else { 3 };
+            for mask in &mut masks[..count] {
+                *mask = r.read_u32::<LittleEndian>()?;
+            }
+            read += count as u32 * 4;
+        }
+        skip(r, header_size.saturating_sub(read))?;
+
+        let position = FILE_HEADER_SIZE + header_size.max(read);
+        let gap = offset
+            .checked_sub(position)
+            .ok_or(Error::InvalidBmp("pixel data overlaps the headers"))?;
+        skip(r, gap)?;
+
+        let has_alpha = match (bit_count, compression) {
+            (16 | 24 | 32, BI_RGB) => false,
+            (32, BI_BITFIELDS)
+                if masks[..3] == [RED_MASK, GREEN_MASK, BLUE_MASK]
+                    && matches!(masks[3], 0 | ALPHA_MASK) =>
+            {
+                masks[3] == ALPHA_MASK
+            }
+            _ => {
+                return Err(Error::UnsupportedBmp {
+                    compression,
+                    bit_count,
+                })
+            }
+        };
+
+        if width < 0 {
+            return Err(Error::InvalidBmp("width is negative"));
+        }
+        let is_top_to_bottom = height < 0;
+        let width = u16::try_from(width).map_err(|_| Error::TooLarge("image"))?;
+        let height = u16::try_from(height.unsigned_abs()).map_err(|_| Error::TooLarge("image"))?;
+
+        let row_size = Image::effective_size(width, 1);
+        let stored_size = (bit_count as usize * width as usize).div_ceil(32) * 4;
+        let mut data = vec![0; Image::effective_size(width, height)];
+        let mut source = vec![0; stored_size];
+        let source_size = width as usize * bit_count as usize / 8;
+        for index in 0..height as usize {
+            let y = if is_top_to_bottom {
+                index
+            } else {
+                height as usize - 1 - index
+            };
+            let row = &mut data[y * row_size..(y + 1) * row_size];
+            match bit_count {
+                32 => {
+                    r.read_exact(row)?;
+                    if !has_alpha {
+                        row.chunks_exact_mut(4).for_each(|pixel| pixel[3] = u8::MAX);
+                    }
+                }
+                24 => {
+                    r.read_exact(&mut source)?;
+                    expand_row(&source[..source_size], row);
+                }
+                _ => {
+                    r.read_exact(&mut source)?;
+                    expand_16_bit_row(&source[..source_size], row, false);
+                }
+            }
+        }
+
+        Image::new(width, height, data)
+    }
+}
+
+/// Reads past `length` bytes, failing if the file ends first.
+fn skip<T: Read>(r: &mut T, length: u32) -> Result<()> {
+    let mut buf = [0; 256];
+    let mut remaining = length as usize;
+    while remaining > 0 {
+        let length = remaining.min(buf.len());
+        r.read_exact(&mut buf[..length])?;
+        remaining -= length;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// A file with a `BITMAPINFOHEADER`, followed by `masks` and then, one
+    /// byte later, by `pixels`.
+    fn info_file(
+        width: i32,
+        height: i32,
+        bit_count: u16,
+        compression: u32,
+        masks: &[u32],
+        pixels: &[u8],
+    ) -> Vec<u8> {
+        let offset = FILE_HEADER_SIZE + INFO_HEADER_SIZE + masks.len() as u32 * 4 + 1;
+        let mut file = Vec::new();
+        file.extend_from_slice(&SIGNATURE);
+        file.write_u32::<LittleEndian>(offset + pixels.len() as u32)
+            .unwrap();
+        file.write_u32::<LittleEndian>(0).unwrap();
+        file.write_u32::<LittleEndian>(offset).unwrap();
+        file.write_u32::<LittleEndian>(INFO_HEADER_SIZE).unwrap();
+        file.write_i32::<LittleEndian>(width).unwrap();
+        file.write_i32::<LittleEndian>(height).unwrap();
+        file.write_u16::<LittleEndian>(1).unwrap();
+        file.write_u16::<LittleEndian>(bit_count).unwrap();
+        file.write_u32::<LittleEndian>(compression).unwrap();
+        file.extend_from_slice(&[0; 20]);
+        for &mask in masks {
+            file.write_u32::<LittleEndian>(mask).unwrap();
+        }
+        file.push(0);
+        file.extend_from_slice(pixels);
+
+        file
+    }
+
+    #[test]
+    fn written_files_read_back_with_alpha() {
+        let image = Image::new(3, 2, (0..24).collect()).unwrap();
+        let mut file = Vec::new();
+        image.to_aligned(16).write_bmp(&mut file).unwrap();
+
+        let offset = (FILE_HEADER_SIZE + V4_HEADER_SIZE) as usize;
+        assert_eq!(file.len(), offset + 24);
+        assert_eq!(file[2..6], (file.len() as u32).to_le_bytes());
+        // The bottom row comes first.
+        assert_eq!(file[offset..offset + 12], image.row(1)[..]);
+
+        let decoded = Image::read_bmp(&mut &file[..]).unwrap();
+        assert_eq!(decoded.data(), image.data());
+    }
+
+    #[test]
+    fn rows_are_read_in_either_order_and_without_padding() {
+        // Two rows of two 24-bit pixels, each padded to eight bytes.
+        let pixels = [1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11, 12, 0, 0];
+        let bottom_up = Image::read_bmp(&mut &info_file(2, 2, 24, BI_RGB, &[], &pixels)[..]);
+        let top_down = Image::read_bmp(&mut &info_file(2, -2, 24, BI_RGB, &[], &pixels)[..]);
+        let (bottom_up, top_down) = (bottom_up.unwrap(), top_down.unwrap());
+        assert_eq!(top_down.row(0), [1, 2, 3, 255, 4, 5, 6, 255]);
+        assert_eq!(top_down.row(1), [7, 8, 9, 255, 10, 11, 12, 255]);
+        assert_eq!(bottom_up.row(0), top_down.row(1));
+        assert_eq!(bottom_up.row(1), top_down.row(0));
+    }
+
+    #[test]
+    fn other_depths_and_headers_are_read() {
+        let white = 0x7fffu16.to_le_bytes();
+        let file = info_file(1, 1, 16, BI_RGB, &[], &[white[0], white[1], 0, 0]);
+        assert_eq!(Image::read_bmp(&mut &file[..]).unwrap().data(), [255; 4]);
+
+        let masks = [RED_MASK, GREEN_MASK, BLUE_MASK];
+        let file = info_file(1, 1, 32, BI_BITFIELDS, &masks, &[1, 2, 3, 4]);
+        assert_eq!(
+            Image::read_bmp(&mut &file[..]).unwrap().data(),
+            [1, 2, 3, 255]
+        );
+
+        let mut file = Vec::new();
+        file.extend_from_slice(&SIGNATURE);
+        file.extend_from_slice(&[0; 8]);
+        file.write_u32::<LittleEndian>(FILE_HEADER_SIZE + CORE_HEADER_SIZE)
+            .unwrap();
+        file.write_u32::<LittleEndian>(CORE_HEADER_SIZE).unwrap();
+        for value in [1, 1, 1, 24] {
+            file.write_u16::<LittleEndian>(value).unwrap();
+        }
+        file.extend_from_slice(&[9, 8, 7, 0]);
+        assert_eq!(
+            Image::read_bmp(&mut &file[..]).unwrap().data(),
+            [9, 8, 7, 255]
+        );
+    }
+
+    #[test]
+    fn files_that_cannot_be_read_are_rejected() {
+        let pixels = [0; 4];
+        let mut bad_signature = info_file(1, 1, 32, BI_RGB, &[], &pixels);
+        bad_signature[0] = b'X';
+        let mut bad_header = info_file(1, 1, 32, BI_RGB, &[], &pixels);
+        bad_header[14] = 20;
+        let mut overlapping = info_file(1, 1, 32, BI_RGB, &[], &pixels);
+        overlapping[10] = 20;
+        for file in [
+            bad_signature,
+            bad_header,
+            overlapping,
+            info_file(-1, 1, 32, BI_RGB, &[], &pixels),
+        ] {
+            let error = Image::read_bmp(&mut &file[..]).unwrap_err();
+            assert!(matches!(error, Error::InvalidBmp(_)), "{error:?}");
+        }
+
+        for (bit_count, compression) in [(8, BI_RGB), (32, 1), (16, BI_BITFIELDS)] {
+            let file = info_file(1, 1, bit_count, compression, &[0; 3], &pixels);
+            assert!(matches!(
+                Image::read_bmp(&mut &file[..]),
+                Err(Error::UnsupportedBmp { .. })
+            ));
+        }
+
+        let file = info_file(70000, 1, 32, BI_RGB, &[], &pixels);
+        assert!(matches!(
+            Image::read_bmp(&mut &file[..]),
+            Err(Error::TooLarge(_))
+        ));
+        let file = info_file(2, 1, 32, BI_RGB, &[], &pixels);
+        assert!(matches!(Image::read_bmp(&mut &file[..]), Err(Error::Io(_))));
+    }
+}
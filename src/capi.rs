@@ -0,0 +1,523 @@
+//! A C interface to decoding and encoding, behind the `capi` feature.
+//!
+//! The functions are laid out for cbindgen, which generates a C header from
+//! this module with the `cbindgen.toml` at the root of the crate:
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --output tga.h
+//! ```
+//!
+//! Build the crate as a static or dynamic library to link against, for
+//! example with `cargo rustc --release --features capi --crate-type cdylib`.
+//!
+//! Every function returns a [`TgaStatus`] and writes its result through an
+//! out pointer, which is left untouched unless [`TgaStatus::Ok`] is returned.
+//! Pixels are passed as 8-bit red, green, blue, alpha, from the top row to the
+//! bottom one, without row padding.
+//!
+//! Buffers returned by the library are owned by the caller and must be
+//! released with [`tga_image_free`] or [`tga_buffer_free`], never with
+//! `free`. Buffers passed to the library stay owned by the caller and are only
+//! read during the call. Panics are caught at the boundary and reported as
+//! [`TgaStatus::Panic`].
+
+use std::ffi::{c_char, CStr};
+use std::fs::File;
+use std::io::{self, BufReader, Cursor};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use crate::{Decoder, EncodeOptions, Error, Image};
+
+/// The outcome of a call.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum TgaStatus {
+    Ok = 0,
+    /// A pointer that must not be null is null.
+    NullPointer = 1,
+    /// An argument is invalid, such as a path that is not UTF-8 or dimensions
+    /// too large to encode.
+    InvalidArgument = 2,
+    /// Reading or writing a file failed.
+    Io = 3,
+    /// The file is malformed or truncated.
+    InvalidData = 4,
+    /// The file holds a kind of image the library cannot decode.
+    Unsupported = 5,
+    /// The library panicked, which is a bug.
+    Panic = 6,
+}
+
+/// A decoded image, owned by the caller until passed to [`tga_image_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct TgaImage {
+    /// `len` bytes of red, green, blue, alpha pixels.
+    pub pixels: *mut u8,
+    pub len: usize,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// Bytes of an encoded file, owned by the caller until passed to
+/// [`tga_buffer_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct TgaBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// What the header of a file describes, read without decoding the pixels.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct TgaInfo {
+    pub width: u16,
+    pub height: u16,
+    /// The number of bits each pixel takes up in the file.
+    pub pixel_depth: u8,
+    /// Whether the pixels have alpha, rather than being decoded as opaque.
+    pub has_alpha: bool,
+    /// Whether the pixels are run-length encoded.
+    pub rle: bool,
+}
+
+impl From<&Error> for TgaStatus {
+    /// Files that end too early count as malformed.
+    fn from(error: &Error) -> Self {
+        match error.root() {
+            Error::Io(error) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                TgaStatus::InvalidData
+            }
+            Error::Io(_) => TgaStatus::Io,
+            Error::UnsupportedImageType { .. } | Error::UnsupportedBmp { .. } => {
+                TgaStatus::Unsupported
+            }
+            Error::InvalidDimensions(_)
+            | Error::LengthMismatch { .. }
+            | Error::TooLarge(_)
+            | Error::RowOutOfBounds { .. } => TgaStatus::InvalidArgument,
+            _ => TgaStatus::InvalidData,
+        }
+    }
+}
+
+impl From<Error> for TgaStatus {
+    fn from(error: Error) -> Self {
+        TgaStatus::from(&error)
+    }
+}
+
+/// Decodes the file at the UTF-8 `path` into `out`.
+///
+/// # Safety
+///
+/// `path` must point to a null-terminated string, and `out` must be valid for
+/// writes of a [`TgaImage`].
+#[no_mangle]
+pub unsafe extern "C" fn tga_decode_file(path: *const c_char, out: *mut TgaImage) -> TgaStatus {
+    guard(|| {
+        if path.is_null() || out.is_null() {
+            return Err(TgaStatus::NullPointer);
+        }
+
+        // SAFETY: The caller guarantees that `path` is null-terminated.
+        let path = unsafe { CStr::from_ptr(path) };
+        let path = path.to_str().map_err(|_| TgaStatus::InvalidArgument)?;
+        let file = File::open(path).map_err(|_| TgaStatus::Io)?;
+        let image = Decoder::new(BufReader::new(file))?.read_image()?;
+
+        // SAFETY: The caller guarantees that `out` is valid for writes.
+        unsafe { out.write(TgaImage::from(image)) };
+        Ok(())
+    })
+}
+
+/// Decodes the file held in the `len` bytes at `data` into `out`.
+///
+/// Malformed files are reported as errors, and the memory allocated is
+/// bounded by `len`, as with [`decode_untrusted`](crate::decode_untrusted).
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `len` bytes, and `out` must be valid for
+/// writes of a [`TgaImage`].
+#[no_mangle]
+pub unsafe extern "C" fn tga_decode_memory(
+    data: *const u8,
+    len: usize,
+    out: *mut TgaImage,
+) -> TgaStatus {
+    guard(|| {
+        if data.is_null() || out.is_null() {
+            return Err(TgaStatus::NullPointer);
+        }
+
+        // SAFETY: The caller guarantees that `data` is valid for reads of
+        // `len` bytes.
+        let data = unsafe { slice::from_raw_parts(data, len) };
+        let image = crate::decode_untrusted(data)?;
+
+        // SAFETY: The caller guarantees that `out` is valid for writes.
+        unsafe { out.write(TgaImage::from(image)) };
+        Ok(())
+    })
+}
+
+/// Encodes `width` by `height` red, green, blue, alpha pixels as a 32-bit
+/// file into `out`, run-length encoding them if `rle` is set.
+///
+/// # Safety
+///
+/// `pixels` must be valid for reads of `width * height * 4` bytes, and `out`
+/// must be valid for writes of a [`TgaBuffer`].
+#[no_mangle]
+pub unsafe extern "C" fn tga_encode_rgba(
+    pixels: *const u8,
+    width: u16,
+    height: u16,
+    rle: bool,
+    out: *mut TgaBuffer,
+) -> TgaStatus {
+    guard(|| {
+        if pixels.is_null() || out.is_null() {
+            return Err(TgaStatus::NullPointer);
+        }
+
+        let size =
+            Image::checked_effective_size(width, height).ok_or(TgaStatus::InvalidArgument)?;
+        // SAFETY: The caller guarantees that `pixels` is valid for reads of
+        // `size` bytes.
+        let pixels = unsafe { slice::from_raw_parts(pixels, size) };
+        let image = Image::from_rgba(width, height, pixels.to_vec())?;
+        let options = EncodeOptions {
+            rle,
+            ..EncodeOptions::default()
+        };
+        let mut data = Vec::new();
+        image.write_with_options(&mut data, &options)?;
+
+        // SAFETY: The caller guarantees that `out` is valid for writes.
+        unsafe { out.write(TgaBuffer::from(data)) };
+        Ok(())
+    })
+}
+
+/// Reads the header of the file held in the `len` bytes at `data` into `out`,
+/// checking the areas the footer points at without decoding the pixels.
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `len` bytes, and `out` must be valid for
+/// writes of a [`TgaInfo`].
+#[no_mangle]
+pub unsafe extern "C" fn tga_probe(data: *const u8, len: usize, out: *mut TgaInfo) -> TgaStatus {
+    guard(|| {
+        if data.is_null() || out.is_null() {
+            return Err(TgaStatus::NullPointer);
+        }
+
+        // SAFETY: The caller guarantees that `data` is valid for reads of
+        // `len` bytes.
+        let data = unsafe { slice::from_raw_parts(data, len) };
+        let decoder = Decoder::new(Cursor::new(data))?;
+        let info = TgaInfo {
+            width: decoder.width(),
+            height: decoder.height(),
+            pixel_depth: decoder.pixel_depth().bits(),
+            has_alpha: decoder.has_alpha(),
+            rle: decoder.encode_options().rle,
+        };
+
+        // SAFETY: The caller guarantees that `out` is valid for writes.
+        unsafe { out.write(info) };
+        Ok(())
+    })
+}
+
+/// Releases the pixels of `image` and resets it. Does nothing if `image` or
+/// its pixels are null.
+///
+/// # Safety
+///
+/// `image` must be null or point to an image returned by this library whose
+/// pixels have not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn tga_image_free(image: *mut TgaImage) {
+    // SAFETY: The caller guarantees that `image` is null or valid.
+    let Some(image) = (unsafe { image.as_mut() }) else {
+        return;
+    };
+    // SAFETY: The caller guarantees that the pixels were allocated by this
+    // library and not released yet.
+    unsafe { release(image.pixels, image.len) };
+    *image = TgaImage {
+        pixels: ptr::null_mut(),
+        len: 0,
+        width: 0,
+        height: 0,
+    };
+}
+
+/// Releases the bytes of `buffer` and resets it. Does nothing if `buffer` or
+/// its bytes are null.
+///
+/// # Safety
+///
+/// `buffer` must be null or point to a buffer returned by this library whose
+/// bytes have not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn tga_buffer_free(buffer: *mut TgaBuffer) {
+    // SAFETY: The caller guarantees that `buffer` is null or valid.
+    let Some(buffer) = (unsafe { buffer.as_mut() }) else {
+        return;
+    };
+    // SAFETY: The caller guarantees that the bytes were allocated by this
+    // library and not released yet.
+    unsafe { release(buffer.data, buffer.len) };
+    *buffer = TgaBuffer {
+        data: ptr::null_mut(),
+        len: 0,
+    };
+}
+
+/// A null-terminated English description of `status`, which lives as long as
+/// the program and must not be released.
+#[no_mangle]
+pub extern "C" fn tga_status_message(status: TgaStatus) -> *const c_char {
+    let message: &CStr = match status {
+        TgaStatus::Ok => c"success",
+        TgaStatus::NullPointer => c"a required pointer is null",
+        TgaStatus::InvalidArgument => c"invalid argument",
+        TgaStatus::Io => c"reading or writing the file failed",
+        TgaStatus::InvalidData => c"the file is malformed or truncated",
+        TgaStatus::Unsupported => c"the file holds an unsupported kind of image",
+        TgaStatus::Panic => c"the library panicked",
+    };
+
+    message.as_ptr()
+}
+
+impl From<Image> for TgaImage {
+    fn from(image: Image) -> Self {
+        let (width, height) = (image.width, image.height);
+        let (pixels, len) = into_raw(image.to_rgba());
+
+        TgaImage {
+            pixels,
+            len,
+            width,
+            height,
+        }
+    }
+}
+
+impl From<Vec<u8>> for TgaBuffer {
+    fn from(data: Vec<u8>) -> Self {
+        let (data, len) = into_raw(data);
+
+        TgaBuffer { data, len }
+    }
+}
+
+/// Runs `f`, turning errors and panics into a status.
+fn guard(f: impl FnOnce() -> Result<(), TgaStatus>) -> TgaStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => TgaStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => TgaStatus::Panic,
+    }
+}
+
+/// Hands `data` over to the caller, to be given back to [`release`].
+fn into_raw(data: Vec<u8>) -> (*mut u8, usize) {
+    let data = Box::into_raw(data.into_boxed_slice());
+
+    (data.cast(), data.len())
+}
+
+/// Frees bytes handed over by [`into_raw`], doing nothing if `data` is null.
+///
+/// # Safety
+///
+/// `data` must be null or have been returned by [`into_raw`] along with `len`,
+/// and must not have been released yet.
+unsafe fn release(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        // SAFETY: The caller guarantees that `data` and `len` describe a boxed
+        // slice from `into_raw`.
+        drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::MaybeUninit;
+
+    fn pixels(width: u16, height: u16) -> Vec<u8> {
+        crate::tests::pseudo_random_bytes(width as usize * height as usize * 4)
+    }
+
+    fn encode(pixels: &[u8], width: u16, height: u16, rle: bool) -> TgaBuffer {
+        let mut buffer = MaybeUninit::uninit();
+        // SAFETY: `pixels` holds every pixel and `buffer` is writable.
+        let status =
+            unsafe { tga_encode_rgba(pixels.as_ptr(), width, height, rle, buffer.as_mut_ptr()) };
+        assert_eq!(status, TgaStatus::Ok);
+        // SAFETY: The buffer was written as the call succeeded.
+        unsafe { buffer.assume_init() }
+    }
+
+    fn decode(data: &[u8]) -> Result<TgaImage, TgaStatus> {
+        let mut image = MaybeUninit::uninit();
+        // SAFETY: `data` is valid for reads of its length and `image` is
+        // writable.
+        match unsafe { tga_decode_memory(data.as_ptr(), data.len(), image.as_mut_ptr()) } {
+            // SAFETY: The image was written as the call succeeded.
+            TgaStatus::Ok => Ok(unsafe { image.assume_init() }),
+            status => Err(status),
+        }
+    }
+
+    /// The bytes of `buffer`, which stays owned by the library.
+    fn bytes(buffer: &TgaBuffer) -> &[u8] {
+        // SAFETY: Buffers returned by the library hold `len` bytes.
+        unsafe { slice::from_raw_parts(buffer.data, buffer.len) }
+    }
+
+    #[test]
+    fn encoded_pixels_decode_unchanged() {
+        let expected = pixels(5, 3);
+        for rle in [false, true] {
+            let mut buffer = encode(&expected, 5, 3, rle);
+            let mut image = decode(bytes(&buffer)).unwrap();
+            assert_eq!((image.width, image.height), (5, 3));
+            // SAFETY: Images returned by the library hold `len` bytes.
+            let decoded = unsafe { slice::from_raw_parts(image.pixels, image.len) };
+            assert_eq!(decoded, expected);
+
+            // SAFETY: Both were returned by the library and not released.
+            unsafe {
+                tga_image_free(&mut image);
+                tga_buffer_free(&mut buffer);
+            }
+            assert!(image.pixels.is_null() && image.len == 0);
+            assert!(buffer.data.is_null() && buffer.len == 0);
+            // SAFETY: Released values are reset, so releasing them again does
+            // nothing.
+            unsafe {
+                tga_image_free(&mut image);
+                tga_buffer_free(&mut buffer);
+                tga_image_free(ptr::null_mut());
+            }
+        }
+    }
+
+    #[test]
+    fn files_are_decoded_by_path() {
+        let expected = pixels(2, 4);
+        let mut buffer = encode(&expected, 2, 4, true);
+        let path = std::env::temp_dir().join(format!("tga-capi-{}.tga", std::process::id()));
+        std::fs::write(&path, bytes(&buffer)).unwrap();
+        let c_path = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+
+        let mut image = MaybeUninit::uninit();
+        // SAFETY: `c_path` is null-terminated and `image` is writable.
+        let status = unsafe { tga_decode_file(c_path.as_ptr(), image.as_mut_ptr()) };
+        assert_eq!(status, TgaStatus::Ok);
+        // SAFETY: The image was written as the call succeeded.
+        let mut image = unsafe { image.assume_init() };
+        // SAFETY: Images returned by the library hold `len` bytes.
+        assert_eq!(
+            unsafe { slice::from_raw_parts(image.pixels, image.len) },
+            expected
+        );
+
+        std::fs::remove_file(&path).unwrap();
+        let mut untouched = MaybeUninit::<TgaImage>::uninit();
+        // SAFETY: As above.
+        let status = unsafe { tga_decode_file(c_path.as_ptr(), untouched.as_mut_ptr()) };
+        assert_eq!(status, TgaStatus::Io);
+        // SAFETY: Both were returned by the library and not released.
+        unsafe {
+            tga_image_free(&mut image);
+            tga_buffer_free(&mut buffer);
+        }
+    }
+
+    #[test]
+    fn probing_reads_the_header() {
+        let mut buffer = encode(&pixels(6, 2), 6, 2, true);
+        let mut info = TgaInfo::default();
+        // SAFETY: The buffer holds `len` bytes and `info` is writable.
+        let status = unsafe { tga_probe(buffer.data, buffer.len, &mut info) };
+        assert_eq!(status, TgaStatus::Ok);
+        assert_eq!(
+            info,
+            TgaInfo {
+                width: 6,
+                height: 2,
+                pixel_depth: 32,
+                has_alpha: true,
+                rle: true,
+            }
+        );
+        // SAFETY: The buffer was returned by the library and not released.
+        unsafe { tga_buffer_free(&mut buffer) };
+    }
+
+    #[test]
+    fn errors_are_reported_as_statuses() {
+        let mut buffer = encode(&pixels(4, 4), 4, 4, true);
+        let mut truncated = bytes(&buffer).to_vec();
+        truncated[crate::HEADER_SIZE..].fill(0x7f);
+        assert_eq!(decode(&truncated).unwrap_err(), TgaStatus::InvalidData);
+        assert_eq!(
+            decode(&truncated[..10]).unwrap_err(),
+            TgaStatus::InvalidData
+        );
+
+        let mut unsupported = bytes(&buffer).to_vec();
+        unsupported[2] = 32;
+        assert_eq!(decode(&unsupported).unwrap_err(), TgaStatus::Unsupported);
+        // SAFETY: The buffer was returned by the library and not released.
+        unsafe { tga_buffer_free(&mut buffer) };
+
+        let mut info = TgaInfo::default();
+        // SAFETY: Null pointers are rejected before anything is read.
+        unsafe {
+            assert_eq!(tga_probe(ptr::null(), 0, &mut info), TgaStatus::NullPointer);
+            assert_eq!(
+                tga_decode_file(ptr::null(), ptr::null_mut()),
+                TgaStatus::NullPointer
+            );
+            assert_eq!(
+                tga_encode_rgba(ptr::null(), 1, 1, false, ptr::null_mut()),
+                TgaStatus::NullPointer
+            );
+        }
+        assert_eq!(info, TgaInfo::default());
+        assert_eq!(guard(|| panic!("boom")), TgaStatus::Panic);
+    }
+
+    #[test]
+    fn every_status_has_a_message() {
+        let statuses = [
+            TgaStatus::Ok,
+            TgaStatus::NullPointer,
+            TgaStatus::InvalidArgument,
+            TgaStatus::Io,
+            TgaStatus::InvalidData,
+            TgaStatus::Unsupported,
+            TgaStatus::Panic,
+        ];
+        for status in statuses {
+            // SAFETY: Messages are null-terminated and live forever.
+            let message = unsafe { CStr::from_ptr(tga_status_message(status)) };
+            assert!(!message.is_empty(), "{status:?}");
+        }
+    }
+}
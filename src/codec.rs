@@ -0,0 +1,102 @@
+//! Traits for decoding and encoding through trait objects, for asset pipelines
+//! that look codecs up at runtime.
+
+use crate::io::{Read, Seek, Write};
+use crate::{DecodeOptions, Decoded, Decoder, EncodeOptions, Image, Result};
+
+/// A reader that can seek, as trait objects can only name one trait besides
+/// auto traits.
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek + ?Sized> ReadSeek for T {}
+
+/// Decodes images, and can be used as `dyn TgaDecode`.
+pub trait TgaDecode {
+    /// Decodes the image read from `reader`, handling spec violations as set
+    /// by `options`.
+    fn decode(&self, reader: &mut dyn ReadSeek, options: &DecodeOptions) -> Result<Decoded>;
+}
+
+/// Encodes images, and can be used as `dyn TgaEncode`.
+pub trait TgaEncode {
+    /// Writes `image` to `writer`, encoding its pixels as described by
+    /// `options`.
+    fn encode(&self, image: &Image, writer: &mut dyn Write, options: &EncodeOptions) -> Result<()>;
+}
+
+/// The codec of this crate, which decodes with a [`Decoder`] and encodes with
+/// [`Image::write_with_options`]. It holds no state, so it can be shared
+/// between threads as `Box<dyn TgaDecode + Send + Sync>`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Codec;
+
+impl TgaDecode for Codec {
+    fn decode(&self, reader: &mut dyn ReadSeek, options: &DecodeOptions) -> Result<Decoded> {
+        Decoder::with_options(reader, *options)?.read_image_with_diagnostics()
+    }
+}
+
+impl TgaEncode for Codec {
+    fn encode(
+        &self,
+        image: &Image,
+        mut writer: &mut dyn Write,
+        options: &EncodeOptions,
+    ) -> Result<()> {
+        image.write_with_options(&mut writer, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::Cursor;
+    use crate::{DiagnosticCode, ViolationPolicy, HEADER_SIZE};
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn codecs_work_as_trait_objects() {
+        let decoder: Box<dyn TgaDecode + Send + Sync> = Box::new(Codec);
+        let encoder: Box<dyn TgaEncode + Send + Sync> = Box::new(Codec);
+        let image = Image::new(3, 2, (0..24).collect()).unwrap();
+        let options = EncodeOptions {
+            rle: true,
+            ..EncodeOptions::default()
+        };
+        let mut file = Vec::new();
+        encoder.encode(&image, &mut file, &options).unwrap();
+
+        let mut expected = Vec::new();
+        image.write_with_options(&mut expected, &options).unwrap();
+        assert_eq!(file, expected);
+
+        let decoded = decoder
+            .decode(&mut Cursor::new(&file[..]), &DecodeOptions::default())
+            .unwrap();
+        assert_eq!(decoded.image.data(), image.data());
+        assert!(decoded.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn decoding_follows_the_options() {
+        let mut file = Vec::new();
+        Image::new(1, 1, [1, 2, 3, 4].to_vec())
+            .unwrap()
+            .write_to(&mut file)
+            .unwrap();
+        file[HEADER_SIZE - 1] |= 0xc0;
+
+        let decoded = Codec
+            .decode(&mut Cursor::new(&file[..]), &DecodeOptions::default())
+            .unwrap();
+        assert_eq!(decoded.diagnostics.len(), 1);
+        assert_eq!(decoded.diagnostics[0].code, DiagnosticCode::ReservedBits);
+
+        let options = DecodeOptions {
+            reserved_bits: ViolationPolicy::Error,
+            ..DecodeOptions::default()
+        };
+        assert!(Codec.decode(&mut Cursor::new(&file[..]), &options).is_err());
+    }
+}
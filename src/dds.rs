@@ -0,0 +1,134 @@
+//! Exporting images as uncompressed DDS textures, which many game engines
+//! ingest directly.
+
+use std::io::{self, Write};
+
+use ddsfile::{D3DFormat, Dds, NewD3dParams};
+
+use crate::{Error, Image, Result};
+
+impl From<ddsfile::Error> for Error {
+    fn from(error: ddsfile::Error) -> Self {
+        match error {
+            ddsfile::Error::Io(error) => Error::Io(error),
+            error => Error::Io(io::Error::other(error)),
+        }
+    }
+}
+
+impl Image {
+    /// Wraps the pixels into an uncompressed 32-bit `A8R8G8B8` DDS texture.
+    ///
+    /// The texture stores the pixels in the same blue, green, red, alpha
+    /// order, from top to bottom and without row padding, so its pitch is
+    /// four bytes per pixel. The header marks the pixels as RGB with alpha.
+    /// Fails for images with zero width or height.
+    pub fn to_dds(&self) -> Result<Dds> {
+        self.to_dds_with_mipmaps(&[])
+    }
+
+    /// Like [`Image::to_dds`], but stores `mipmaps` after the image, from the
+    /// largest to the smallest.
+    ///
+    /// Each level must be half the size of the one before it, rounded down
+    /// and at least 1 pixel, as the levels generated by `Image::mipmaps` with
+    /// the `fast_image_resize` feature are.
+    pub fn to_dds_with_mipmaps(&self, mipmaps: &[Image]) -> Result<Dds> {
+        if self.width == 0 || self.height == 0 {
+            return Err(Error::InvalidDimensions("DDS textures cannot be empty"));
+        }
+
+        let mut previous = self;
+        for level in mipmaps {
+            if level.width != (previous.width / 2).max(1)
+                || level.height != (previous.height / 2).max(1)
+            {
+                return Err(Error::InvalidDimensions(
+                    "each mipmap must be half the size of the previous level",
+                ));
+            }
+            previous = level;
+        }
+
+        let mut dds = Dds::new_d3d(NewD3dParams {
+            height: self.height as u32,
+            width: self.width as u32,
+            depth: None,
+            format: D3DFormat::A8R8G8B8,
+            mipmap_levels: Some(mipmaps.len() as u32 + 1),
+            caps2: None,
+        })?;
+
+        let mut offset = 0;
+        for level in std::iter::once(self).chain(mipmaps) {
+            for y in 0..level.height as usize {
+                let row = level.row(y);
+                dds.data[offset..offset + row.len()].copy_from_slice(row);
+                offset += row.len();
+            }
+        }
+
+        Ok(dds)
+    }
+
+    /// Writes the image as an uncompressed DDS file, as described by
+    /// [`Image::to_dds`].
+    pub fn write_dds<T: Write>(&self, w: &mut T) -> Result<()> {
+        self.to_dds()?.write(w)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u16, height: u16, value: u8) -> Image {
+        Image::new(
+            width,
+            height,
+            vec![value; Image::effective_size(width, height)],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn textures_hold_the_pixels_without_padding() {
+        let image = Image::new(3, 2, (0..24).collect()).unwrap().to_aligned(16);
+        let mut file = Vec::new();
+        image.write_dds(&mut file).unwrap();
+
+        let dds = Dds::read(&file[..]).unwrap();
+        assert_eq!((dds.get_width(), dds.get_height()), (3, 2));
+        assert_eq!(dds.get_d3d_format(), Some(D3DFormat::A8R8G8B8));
+        assert_eq!(dds.get_pitch(), Some(12));
+        assert_eq!(dds.get_num_mipmap_levels(), 1);
+        assert_eq!(dds.get_data(0).unwrap(), (0..24).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn mipmaps_follow_the_image() {
+        let mipmaps = [image(2, 1, 2), image(1, 1, 3)];
+        let dds = image(5, 3, 1).to_dds_with_mipmaps(&mipmaps).unwrap();
+        assert_eq!(dds.get_num_mipmap_levels(), 3);
+        let mut expected = vec![1; 60];
+        expected.extend_from_slice(&[2; 8]);
+        expected.extend_from_slice(&[3; 4]);
+        assert_eq!(dds.get_data(0).unwrap(), expected);
+    }
+
+    #[test]
+    fn invalid_levels_are_rejected() {
+        for mipmaps in [&[image(3, 1, 0)][..], &[image(2, 1, 0), image(1, 0, 0)]] {
+            assert!(matches!(
+                image(5, 3, 0).to_dds_with_mipmaps(mipmaps),
+                Err(Error::InvalidDimensions(_))
+            ));
+        }
+        assert!(matches!(
+            image(0, 3, 0).to_dds(),
+            Err(Error::InvalidDimensions(_))
+        ));
+    }
+}
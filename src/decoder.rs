@@ -0,0 +1,1903 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::mem;
+use core::ops::Range;
+
+use byteorder::LittleEndian;
+
+use crate::error::CountingReader;
+use crate::extension::{
+    COLOR_CORRECTION_OFFSET_POSITION, COLOR_CORRECTION_TABLE_SIZE, COMMENTS_POSITION,
+    EXTENSION_SIZE,
+};
+use crate::io::{self, Cursor, Read, ReadBytesExt, Seek, SeekFrom};
+use crate::parallel;
+#[cfg(feature = "std")]
+use crate::pool::BufferPool;
+use crate::rle::{self, RleDecoder};
+use crate::trace;
+use crate::validate;
+use crate::{
+    developer, extension, Bgra8, ColorCorrectionTable, ColorMapType, Context, DeveloperTag,
+    Diagnostic, DiagnosticCode, EncodeOptions, Error, ExtensionArea, Footer, Header,
+    HorizontalOrdering, Image, ImageDescriptor, ImageType, PixelDepth, PixelFormat, Result,
+    Severity, VerticalOrdering, FOOTER_SIZE, HEADER_SIZE, MAX_POSTAGE_STAMP_SIZE,
+};
+
+/// The fields of the header and the offsets they start at.
+const HEADER_FIELDS: [(u64, &str); 12] = [
+    (0, "ID length"),
+    (1, "color map type"),
+    (2, "image type"),
+    (3, "first entry index"),
+    (5, "color map length"),
+    (7, "color map entry size"),
+    (8, "x-origin"),
+    (10, "y-origin"),
+    (12, "image width"),
+    (14, "image height"),
+    (16, "pixel depth"),
+    (17, "image descriptor"),
+];
+pub(crate) const COLOR_MAP_TYPE_POSITION: u64 = 1;
+pub(crate) const IMAGE_TYPE_POSITION: u64 = 2;
+pub(crate) const COLOR_MAP_SPECIFICATION_POSITION: u64 = 3;
+pub(crate) const COLOR_MAP_LENGTH_POSITION: u64 = 5;
+pub(crate) const COLOR_MAP_ENTRY_SIZE_POSITION: u64 = 7;
+const X_ORIGIN_POSITION: u64 = 8;
+const Y_ORIGIN_POSITION: u64 = 10;
+const WIDTH_POSITION: u64 = 12;
+const HEIGHT_POSITION: u64 = 14;
+pub(crate) const PIXEL_DEPTH_POSITION: u64 = 16;
+pub(crate) const DESCRIPTOR_POSITION: u64 = 17;
+
+/// How the decoder handles a violation of the spec it could work around.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum ViolationPolicy {
+    /// Reject the file.
+    Error,
+    /// Work around the violation as described for its category and report it
+    /// by [`Decoder::diagnostics`].
+    #[default]
+    FixUp,
+    /// Read the file as it is stored, without reporting anything.
+    Ignore,
+}
+
+/// Options controlling how strictly a file is checked while decoding it, with
+/// a policy for each category of spec violation.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct DecodeOptions {
+    /// Reserved bits of the image descriptor are set. They have no effect on
+    /// decoding, so fixing up only reports them.
+    pub reserved_bits: ViolationPolicy,
+    /// The alpha depth does not fit the pixel depth. The alpha depth has no
+    /// effect on decoding, so fixing up only reports it.
+    pub alpha_depth: ViolationPolicy,
+    /// A true-color or grayscale image declares a color map, or the color
+    /// map type is unknown. The declared color map is skipped either way.
+    pub color_map: ViolationPolicy,
+    /// The file has no TGA 2.0 footer, which is allowed for TGA 1.0 files and
+    /// ignored by default. The file is read as a TGA 1.0 file either way.
+    pub missing_footer: ViolationPolicy,
+    /// The file ends in what looks like a TGA 2.0 footer with a corrupted
+    /// signature. The footer is ignored and the file is read as a TGA 1.0
+    /// file either way.
+    pub malformed_footer: ViolationPolicy,
+    /// An offset in the footer or extension area points at data that does not
+    /// lie between the pixel data and the footer, where the spec places it.
+    /// Fixing up ignores the data, while ignoring follows the offset anyway.
+    pub area_offsets: ViolationPolicy,
+    /// The declared color map does not fill the space between the image ID
+    /// and uncompressed pixel data. Where the pixel data ends is told from the
+    /// areas following it in TGA 2.0 files, and from the end of TGA 1.0 files,
+    /// which are allowed to have data after the pixels. Fixing up assumes the
+    /// color map fills the space before the pixel data instead.
+    pub color_map_length: ViolationPolicy,
+    /// The postage stamp is larger than the 64x64 pixels the spec allows.
+    /// Fixing up discards the stamp.
+    pub oversized_postage_stamp: ViolationPolicy,
+    /// The largest number of color map entries accepted. Files declaring more
+    /// are rejected with [`Error::LimitExceeded`].
+    pub max_color_map_entries: u16,
+    /// The largest color map entry size accepted, in bits. Files declaring
+    /// larger entries are rejected with [`Error::LimitExceeded`].
+    pub max_color_map_entry_size: u8,
+}
+
+impl DecodeOptions {
+    /// Options rejecting every violation, except for a missing footer since
+    /// TGA 1.0 files have none.
+    pub fn strict() -> Self {
+        DecodeOptions {
+            reserved_bits: ViolationPolicy::Error,
+            alpha_depth: ViolationPolicy::Error,
+            color_map: ViolationPolicy::Error,
+            missing_footer: ViolationPolicy::Ignore,
+            malformed_footer: ViolationPolicy::Error,
+            area_offsets: ViolationPolicy::Error,
+            color_map_length: ViolationPolicy::Error,
+            oversized_postage_stamp: ViolationPolicy::Error,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions {
+            reserved_bits: ViolationPolicy::FixUp,
+            alpha_depth: ViolationPolicy::FixUp,
+            color_map: ViolationPolicy::FixUp,
+            missing_footer: ViolationPolicy::Ignore,
+            malformed_footer: ViolationPolicy::FixUp,
+            area_offsets: ViolationPolicy::FixUp,
+            color_map_length: ViolationPolicy::FixUp,
+            oversized_postage_stamp: ViolationPolicy::FixUp,
+            max_color_map_entries: u16::MAX,
+            max_color_map_entry_size: 32,
+        }
+    }
+}
+
+/// A decoded image together with the problems tolerated while decoding it.
+#[derive(Clone, Debug)]
+pub struct Decoded {
+    pub image: Image,
+    /// The tolerated problems, in the order they were found.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A decoder for 15-, 16-, 24- or 32-bit true-color and 8-bit grayscale
+/// images, either uncompressed or run-length encoded.
+///
+/// Creating a decoder reads the header and, if the file has a TGA 2.0 footer,
+/// everything the footer points at. The pixel data is only read on request,
+/// either all at once with [`Decoder::read_image`] or one row at a time with
+/// [`Decoder::read_row`].
+///
+/// Images with zero width or height are rejected, since they hold no pixels.
+/// An empty postage stamp is treated as if there were none. Other spec
+/// violations are handled as set by [`DecodeOptions`].
+#[derive(Debug)]
+pub struct Decoder<R> {
+    reader: R,
+    options: DecodeOptions,
+    diagnostics: Vec<Diagnostic>,
+    start: u64,
+    end: u64,
+    header: Header,
+    id: Vec<u8>,
+    pixel_offset: u64,
+    extension_offset: u32,
+    extension_area: Option<ExtensionArea>,
+    developer_tags: Vec<DeveloperTag>,
+    postage_stamp: Option<Image>,
+    scan_line_table: Option<Vec<u32>>,
+}
+
+impl<R: Read + Seek> Decoder<R> {
+    pub fn new(reader: R) -> Result<Self> {
+        Decoder::with_options(reader, DecodeOptions::default())
+    }
+
+    pub fn with_options(mut reader: R, options: DecodeOptions) -> Result<Self> {
+        let start = reader.stream_position()?;
+        let phase = trace::phase!("header");
+        let header = read_header(&mut reader, &options)?;
+        phase.bytes(HEADER_SIZE as u64);
+        phase.end();
+
+        let phase = trace::phase!("image_id");
+        let mut id = vec![0; header.id_length as usize];
+        let mut r = CountingReader::new(&mut reader);
+        r.read_exact(&mut id).map_err(|error| {
+            Error::from(error).at(HEADER_SIZE as u64 + r.count(), Context::ImageId)
+        })?;
+        phase.bytes(id.len() as u64);
+        phase.end();
+
+        let mut pixel_offset = start + HEADER_SIZE as u64 + header.id_length as u64;
+        if header.color_map_type != ColorMapType::ABSENT {
+            // The color map is skipped, so the phase only records its size.
+            let phase = trace::phase!("color_map");
+            phase.bytes(header.color_map_specification.data_size());
+            pixel_offset += header.color_map_specification.data_size();
+        }
+
+        let end = reader.seek(SeekFrom::End(0))?;
+        let mut decoder = Decoder {
+            reader,
+            options,
+            diagnostics: Vec::new(),
+            start,
+            end,
+            header,
+            id,
+            pixel_offset,
+            extension_offset: 0,
+            extension_area: None,
+            developer_tags: Vec::new(),
+            postage_stamp: None,
+            scan_line_table: None,
+        };
+        check_header(&decoder.header, &decoder.options, &mut decoder.diagnostics)?;
+        decoder.read_footer()?;
+
+        Ok(decoder)
+    }
+
+    pub fn width(&self) -> u16 {
+        self.header.image_specification.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.header.image_specification.height
+    }
+
+    pub fn id(&self) -> &[u8] {
+        &self.id
+    }
+
+    /// The header of the file, as it was read.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// The image type of the file, which is true-color or grayscale as those
+    /// are the only types decoded.
+    pub fn image_type(&self) -> ImageType {
+        self.header.image_type
+    }
+
+    /// The number of bits each pixel takes up in the file.
+    pub fn pixel_depth(&self) -> PixelDepth {
+        self.header.image_specification.pixel_depth
+    }
+
+    /// The number of alpha bits of each pixel as declared by the image
+    /// descriptor.
+    pub fn alpha_depth(&self) -> u8 {
+        self.header.image_specification.descriptor.alpha_depth()
+    }
+
+    /// Whether the rows are stored from bottom to top, as most files store
+    /// them.
+    pub fn is_bottom_to_top(&self) -> bool {
+        self.header
+            .image_specification
+            .descriptor
+            .vertical_ordering()
+            == VerticalOrdering::BottomToTop
+    }
+
+    /// Whether the pixels of each row are stored from right to left.
+    pub fn is_right_to_left(&self) -> bool {
+        self.header
+            .image_specification
+            .descriptor
+            .horizontal_ordering()
+            == HorizontalOrdering::RightToLeft
+    }
+
+    /// Options that encode pixel data the same way as this file. 15- and
+    /// 16-bit pixels are encoded as 32-bit ones, which hold them without loss.
+    pub fn encode_options(&self) -> EncodeOptions {
+        EncodeOptions {
+            pixel_format: match self.header.image_specification.pixel_depth {
+                PixelDepth::B8 => PixelFormat::Gray8,
+                PixelDepth::B24 => PixelFormat::Bgr24,
+                _ => PixelFormat::Bgra32,
+            },
+            rle: self.is_rle(),
+            scan_line_table: self.scan_line_table.is_some(),
+            ..Default::default()
+        }
+    }
+
+    pub fn extension_area(&self) -> Option<&ExtensionArea> {
+        self.extension_area.as_ref()
+    }
+
+    /// The spec violations tolerated and unusual data found so far, in the
+    /// order they were found.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn developer_tags(&self) -> &[DeveloperTag] {
+        &self.developer_tags
+    }
+
+    pub fn postage_stamp(&self) -> Option<&Image> {
+        self.postage_stamp.as_ref()
+    }
+
+    /// The offsets of each scan line from the start of the file, in the order
+    /// the lines are stored.
+    pub fn scan_line_table(&self) -> Option<&[u32]> {
+        self.scan_line_table.as_deref()
+    }
+
+    /// The offset of the pixel data from the start of the file if it is stored
+    /// the way [`Image`] holds it: uncompressed 32-bit pixels from top to
+    /// bottom and left to right.
+    #[cfg(feature = "mmap")]
+    pub(crate) fn raw_pixel_offset(&self) -> Option<u64> {
+        let specification = self.header.image_specification;
+        let descriptor = specification.descriptor;
+        let is_raw = !self.is_rle()
+            && specification.pixel_depth == PixelDepth::B32
+            && descriptor.vertical_ordering() == VerticalOrdering::TopToBottom
+            && descriptor.horizontal_ordering() == HorizontalOrdering::LeftToRight;
+
+        is_raw.then(|| self.pixel_offset - self.start)
+    }
+
+    /// Reads row `y`, counting from the top of the image, as 32-bit
+    /// left-to-right pixels.
+    ///
+    /// The scan line table is used to locate the row if the file has one.
+    /// Otherwise, compressed pixel data has to be decoded from the first row
+    /// on.
+    pub fn read_row(&mut self, y: u16) -> Result<Vec<u8>> {
+        if y >= self.height() {
+            return Err(Error::RowOutOfBounds {
+                row: y,
+                height: self.height(),
+            });
+        }
+
+        self.read_rows(y..y + 1)
+    }
+
+    /// Reads `rows`, counting from the top of the image, as 32-bit pixels
+    /// from top to bottom and left to right.
+    ///
+    /// Only the rows requested are read and decompressed if the file has a
+    /// scan line table, like with [`Decoder::read_row`].
+    pub fn read_rows(&mut self, rows: Range<u16>) -> Result<Vec<u8>> {
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
+        if rows.end > self.height() {
+            return Err(Error::RowOutOfBounds {
+                row: rows.end - 1,
+                height: self.height(),
+            });
+        }
+        self.check_pixel_data_size()?;
+
+        let specification = self.header.image_specification;
+        let descriptor = specification.descriptor;
+        let height = specification.height as usize;
+        let is_flipped = descriptor.vertical_ordering() == VerticalOrdering::BottomToTop;
+        let file_rows = if is_flipped {
+            height - rows.end as usize..height - rows.start as usize
+        } else {
+            rows.start as usize..rows.end as usize
+        };
+
+        let bytes_per_pixel = specification.pixel_depth.bytes_per_pixel();
+        let source_size = self.width() as usize * bytes_per_pixel;
+        let (offset, first_row) = match &self.scan_line_table {
+            Some(table) => (self.start + table[file_rows.start] as u64, file_rows.start),
+            None if self.is_rle() => (self.pixel_offset, 0),
+            None => (
+                self.pixel_offset + (file_rows.start * source_size) as u64,
+                file_rows.start,
+            ),
+        };
+
+        let row_size = Image::effective_size(self.width(), 1);
+        let mut data = vec![0; file_rows.len() * row_size];
+        let phase = trace::phase!("pixel_data");
+        let bytes = self.decode_rows(offset, first_row..file_rows.end, |block, source| {
+            for (index, file_row) in block.enumerate() {
+                if !file_rows.contains(&file_row) {
+                    continue;
+                }
+
+                let y = if is_flipped {
+                    file_rows.end - 1 - file_row
+                } else {
+                    file_row - file_rows.start
+                };
+                let source = &source[index * source_size..][..source_size];
+                convert_row(
+                    source,
+                    &mut data[y * row_size..][..row_size],
+                    bytes_per_pixel,
+                    descriptor,
+                );
+            }
+        })?;
+        phase.bytes(bytes);
+
+        Ok(data)
+    }
+
+    /// Reads the rectangle of `width` by `height` pixels whose top left
+    /// corner is at `(x, y)`, reading only the rows it covers as
+    /// [`Decoder::read_rows`] does.
+    ///
+    /// The image returned holds only the pixels of the rectangle.
+    pub fn read_region(&mut self, x: u16, y: u16, width: u16, height: u16) -> Result<Image> {
+        if x as u32 + width as u32 > self.width() as u32
+            || y as u32 + height as u32 > self.height() as u32
+        {
+            return Err(Error::InvalidDimensions("region lies outside the image"));
+        }
+
+        let rows = self.read_rows(y..y + height)?;
+        let row_size = Image::effective_size(self.width(), 1);
+        let start = Image::effective_size(x, 1);
+        let region_row_size = Image::effective_size(width, 1);
+        let mut data = Vec::with_capacity(region_row_size * height as usize);
+        for row in rows.chunks_exact(row_size) {
+            data.extend_from_slice(&row[start..start + region_row_size]);
+        }
+
+        Image::new(width, height, data)
+    }
+
+    /// Reads the pixel data, returning it together with everything read when
+    /// the decoder was created.
+    pub fn read_image(self) -> Result<Image> {
+        Ok(self.read_image_with_diagnostics()?.image)
+    }
+
+    /// Like [`Decoder::read_image`], but also returns the diagnostics about
+    /// problems that were tolerated.
+    pub fn read_image_with_diagnostics(mut self) -> Result<Decoded> {
+        let size = self.image_size()?;
+        self.check_pixel_data_size()?;
+        let mut data = vec![0; size];
+        let phase = trace::phase!("pixel_data");
+        let bytes = self.decode_image(&mut data)?;
+        phase.bytes(bytes);
+        phase.end();
+
+        Ok(self.into_decoded(data))
+    }
+
+    /// Like [`Decoder::read_image_with_diagnostics`], but takes the buffer
+    /// for the pixels from `pool` instead of allocating a new one. The buffer
+    /// is returned to the pool if decoding fails.
+    #[cfg(feature = "std")]
+    pub fn read_image_with_pool(mut self, pool: &BufferPool) -> Result<Decoded> {
+        let size = self.image_size()?;
+        self.check_pixel_data_size()?;
+        let mut data = pool.take(size);
+        let phase = trace::phase!("pixel_data");
+        match self.decode_image(&mut data) {
+            Ok(bytes) => phase.bytes(bytes),
+            Err(error) => {
+                pool.put(data);
+                return Err(error);
+            }
+        }
+        phase.end();
+
+        Ok(self.into_decoded(data))
+    }
+
+    /// Reads the pixel data into `data`, which holds as many bytes as the
+    /// decoded image.
+    #[cfg(any(feature = "image", feature = "zune-image"))]
+    pub(crate) fn read_image_into(&mut self, data: &mut [u8]) -> Result<()> {
+        self.check_pixel_data_size()?;
+        let phase = trace::phase!("pixel_data");
+        let bytes = self.decode_image(data)?;
+        phase.bytes(bytes);
+
+        Ok(())
+    }
+
+    /// Whether the stored pixels have alpha, rather than being made opaque.
+    #[cfg(any(feature = "capi", feature = "pyo3", feature = "zune-image"))]
+    pub(crate) fn has_alpha(&self) -> bool {
+        let specification = self.header.image_specification;
+        specification.pixel_depth == PixelDepth::B32 || specification.descriptor.alpha_depth() == 1
+    }
+
+    /// The size in bytes of the decoded pixels.
+    fn image_size(&self) -> Result<usize> {
+        Image::checked_effective_size(self.width(), self.height()).ok_or(Error::TooLarge("image"))
+    }
+
+    /// Decodes the pixel data into `data`, which holds the decoded image,
+    /// returning the number of bytes of pixel data read.
+    fn decode_image(&mut self, data: &mut [u8]) -> Result<u64> {
+        if self.is_rle() {
+            return self.decode_rle_image(data);
+        }
+
+        let specification = self.header.image_specification;
+        let row_size = Image::effective_size(specification.width, 1);
+        let height = specification.height as usize;
+        let descriptor = specification.descriptor;
+        let bytes_per_pixel = specification.pixel_depth.bytes_per_pixel();
+
+        let source_size = specification.width as usize * bytes_per_pixel;
+        self.decode_rows(self.pixel_offset, 0..height, |rows, source| {
+            let is_flipped = descriptor.vertical_ordering() == VerticalOrdering::BottomToTop;
+            let (start, end) = if is_flipped {
+                (height - rows.end, height - rows.start)
+            } else {
+                (rows.start, rows.end)
+            };
+            let destination = &mut data[start * row_size..end * row_size];
+            parallel::for_each_row(destination, row_size, |y, row| {
+                let index = if is_flipped { rows.len() - 1 - y } else { y };
+                let source = &source[index * source_size..][..source_size];
+                convert_row(source, row, bytes_per_pixel, descriptor);
+            });
+        })
+    }
+
+    /// Decodes run-length encoded pixel data straight into the rows of
+    /// `data`, which holds the decoded image, returning the number of bytes
+    /// of pixel data read.
+    fn decode_rle_image(&mut self, data: &mut [u8]) -> Result<u64> {
+        let specification = self.header.image_specification;
+        let row_size = Image::effective_size(specification.width, 1);
+        let height = specification.height as usize;
+        let descriptor = specification.descriptor;
+        let bytes_per_pixel = specification.pixel_depth.bytes_per_pixel();
+        let is_flipped = descriptor.vertical_ordering() == VerticalOrdering::BottomToTop;
+        let is_reversed = descriptor.horizontal_ordering() == HorizontalOrdering::RightToLeft;
+
+        let position = self.pixel_offset - self.start;
+        self.reader.seek(SeekFrom::Start(self.pixel_offset))?;
+        let mut r = CountingReader::new(&mut self.reader);
+        let mut rle = RleDecoder::default();
+        for file_row in 0..height {
+            let y = if is_flipped {
+                height - 1 - file_row
+            } else {
+                file_row
+            };
+            let row = &mut data[y * row_size..][..row_size];
+            let expand = |source: &[u8], row: &mut [u8]| {
+                expand_pixels(source, row, bytes_per_pixel, descriptor);
+            };
+            rle.read_row_expanded(&mut r, row, bytes_per_pixel, expand)
+                .map_err(|error| {
+                    error.at(position + r.count(), Context::Packet(rle.packet_index()))
+                })?;
+            if is_reversed {
+                bytemuck::cast_slice_mut::<u8, Bgra8>(row).reverse();
+            }
+        }
+
+        rle.finish()
+            .map_err(|error| error.at(position + r.count(), Context::Packet(rle.packet_index())))?;
+
+        Ok(r.count())
+    }
+
+    /// Puts the decoded pixels together with everything read when the decoder
+    /// was created.
+    fn into_decoded(self, data: Vec<u8>) -> Decoded {
+        let specification = self.header.image_specification;
+        let mut image = Image::from_parts(specification.width, specification.height, data);
+        image.id = self.id;
+        image.extension_area = self.extension_area;
+        image.developer_tags = self.developer_tags;
+        image.postage_stamp = self.postage_stamp.map(Box::new);
+
+        Decoded {
+            image,
+            diagnostics: self.diagnostics,
+        }
+    }
+
+    /// Rejects images whose dimensions call for more pixel data than the rest
+    /// of the file could hold, before a buffer of that size is allocated.
+    ///
+    /// Uncompressed pixels take up their full size. Run-length encoded pixels
+    /// take up at least one packet header and one pixel per 128 pixels.
+    fn check_pixel_data_size(&self) -> Result<()> {
+        if self.end.saturating_sub(self.pixel_offset) < self.min_pixel_data_size() {
+            let error = Error::InvalidHeader("image dimensions exceed the size of the file");
+            return Err(error.at(
+                WIDTH_POSITION,
+                Context::Header(header_field(WIDTH_POSITION)),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Records something unusual about a file that does not violate the spec.
+    fn note(&mut self, code: DiagnosticCode, offset: u64, context: Context, message: &'static str) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Info,
+            code,
+            offset,
+            context,
+            message,
+        });
+    }
+
+    /// Where the pixel data ends at the earliest, relative to the start of the
+    /// file.
+    pub(crate) fn pixel_data_end(&self) -> u64 {
+        self.pixel_offset - self.start + self.min_pixel_data_size()
+    }
+
+    /// The offset of the extension area that was read, or 0 if there is none.
+    pub(crate) fn extension_offset(&self) -> u32 {
+        self.extension_offset
+    }
+
+    /// The smallest number of bytes the pixel data can take up.
+    fn min_pixel_data_size(&self) -> u64 {
+        let specification = self.header.image_specification;
+        let bytes_per_pixel = specification.pixel_depth.bytes_per_pixel() as u64;
+        let pixels = specification.width as u64 * specification.height as u64;
+        if self.is_rle() {
+            pixels.div_ceil(rle::MAX_PACKET_LENGTH as u64) * (1 + bytes_per_pixel)
+        } else {
+            pixels * bytes_per_pixel
+        }
+    }
+
+    /// Checks that the `size` bytes at `offset` lie within `bounds`, which
+    /// spans from the end of the pixel data to the footer. Returns whether to
+    /// follow the offset.
+    fn check_area(
+        &mut self,
+        offset: u32,
+        size: u64,
+        bounds: &Range<u64>,
+        context: Context,
+    ) -> Result<bool> {
+        let offset = offset as u64;
+        if offset >= bounds.start && offset.saturating_add(size) <= bounds.end {
+            return Ok(true);
+        }
+
+        let follow = !self.violation(
+            self.options.area_offsets,
+            DiagnosticCode::AreaOffset,
+            Error::InvalidOffset,
+            offset,
+            context,
+            "offset points outside the space between the pixel data and the footer",
+        )?;
+
+        Ok(follow)
+    }
+
+    /// Handles a spec violation as [`violation`] does, recording it with the
+    /// other diagnostics of the decoder.
+    fn violation(
+        &mut self,
+        policy: ViolationPolicy,
+        code: DiagnosticCode,
+        error: Error,
+        offset: u64,
+        context: Context,
+        message: &'static str,
+    ) -> Result<bool> {
+        violation(
+            policy,
+            code,
+            error,
+            offset,
+            context,
+            message,
+            &mut self.diagnostics,
+        )
+    }
+
+    fn read_footer(&mut self) -> Result<()> {
+        let length = self.end - self.start;
+        let footer_offset = length.saturating_sub(FOOTER_SIZE as u64);
+        let mut footer = None;
+        if length >= (HEADER_SIZE + FOOTER_SIZE) as u64 {
+            let phase = trace::phase!("footer");
+            self.reader.seek(SeekFrom::End(-(FOOTER_SIZE as i64)))?;
+            footer = Some(self.read_at(footer_offset, Context::Footer, |r| {
+                Ok(Footer::read_from(r)?)
+            })?);
+            phase.bytes(FOOTER_SIZE as u64);
+        }
+
+        let footer = match footer {
+            Some(footer) if footer.is_valid() => footer,
+            Some(footer) if footer.is_malformed() => {
+                let message = "footer is malformed, reading the file as TGA 1.0";
+                self.violation(
+                    self.options.malformed_footer,
+                    DiagnosticCode::MalformedFooter,
+                    Error::InvalidFooter("footer signature is corrupted"),
+                    footer_offset,
+                    Context::Footer,
+                    message,
+                )?;
+
+                return self.check_color_map_length(None);
+            }
+            _ => {
+                self.violation(
+                    self.options.missing_footer,
+                    DiagnosticCode::MissingFooter,
+                    Error::MissingFooter,
+                    length,
+                    Context::Footer,
+                    "file has no TGA 2.0 footer",
+                )?;
+
+                return self.check_color_map_length(None);
+            }
+        };
+
+        let pixel_data_end = self
+            .first_area_offset(footer, footer_offset)
+            .unwrap_or(footer_offset);
+        self.check_color_map_length(Some(pixel_data_end))?;
+        self.check_unused_data(pixel_data_end);
+
+        let bounds = self.pixel_data_end()..footer_offset;
+        let offset = footer.extension_offset;
+        if offset != 0 {
+            let phase = trace::phase!("extension_area");
+            self.seek_to(offset)?;
+            let size = self.read_at(
+                offset as u64,
+                Context::ExtensionArea("extension size"),
+                |r| Ok(r.read_u16::<LittleEndian>()?),
+            )?;
+            let size = (size as u64).max(EXTENSION_SIZE as u64);
+            phase.bytes(size);
+            if self.check_area(offset, size, &bounds, Context::Footer)? {
+                self.read_extension_area(offset, &bounds)?;
+            }
+        }
+
+        let offset = footer.developer_offset;
+        if offset != 0 && self.check_area(offset, 2, &bounds, Context::Footer)? {
+            self.read_developer_area(offset, &bounds)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the declared color map fills the space between the image ID
+    /// and uncompressed pixel data ending at `pixel_data_end`, which is known
+    /// for TGA 2.0 files. Otherwise the pixel data may end anywhere before the
+    /// end of the file, since TGA 1.0 files may be followed by anything.
+    ///
+    /// To fix up a mismatch, the color map is assumed to fill the space before
+    /// the pixel data, which ends at the first area or the end of the file.
+    fn check_color_map_length(&mut self, pixel_data_end: Option<u64>) -> Result<()> {
+        if self.header.color_map_type == ColorMapType::ABSENT || self.is_rle() {
+            return Ok(());
+        }
+
+        let is_exact = pixel_data_end.is_some();
+        let pixel_data_end = pixel_data_end.unwrap_or(self.end - self.start);
+
+        let color_map_offset = (HEADER_SIZE + self.id.len()) as u64;
+        let declared = self.pixel_offset - self.start - color_map_offset;
+        let Some(actual) = pixel_data_end
+            .checked_sub(self.min_pixel_data_size())
+            .and_then(|pixel_offset| pixel_offset.checked_sub(color_map_offset))
+        else {
+            // The pixel data does not fit even without a color map, which is
+            // reported once it is read.
+            return Ok(());
+        };
+
+        if actual == declared || (!is_exact && actual > declared) {
+            return Ok(());
+        }
+
+        let position = COLOR_MAP_LENGTH_POSITION;
+        if self.violation(
+            self.options.color_map_length,
+            DiagnosticCode::ColorMapLength,
+            Error::ColorMapLength { declared, actual },
+            position,
+            Context::Header(header_field(position)),
+            "color map length does not match the data before the pixels",
+        )? {
+            self.pixel_offset = self.start + color_map_offset + actual;
+        }
+
+        Ok(())
+    }
+
+    /// Notes data between uncompressed pixel data and `pixel_data_end`, where
+    /// the first area of a TGA 2.0 file starts.
+    fn check_unused_data(&mut self, pixel_data_end: u64) {
+        let end = self.pixel_offset - self.start + self.min_pixel_data_size();
+        if !self.is_rle() && end < pixel_data_end {
+            self.note(
+                DiagnosticCode::UnusedData,
+                end,
+                Context::PixelData,
+                "file has unused data after the pixel data",
+            );
+        }
+    }
+
+    /// Finds the earliest offset the footer and extension and developer areas
+    /// point at, which is where the pixel data ends if the areas follow it as
+    /// they should. Offsets that cannot be read are left out.
+    fn first_area_offset(&mut self, footer: Footer, footer_offset: u64) -> Option<u64> {
+        let mut offsets = vec![footer.extension_offset, footer.developer_offset];
+        if footer.extension_offset != 0 {
+            let position = footer
+                .extension_offset
+                .checked_add(COLOR_CORRECTION_OFFSET_POSITION as u32);
+            if position.is_some_and(|position| self.seek_to(position).is_ok()) {
+                for _ in 0..3 {
+                    offsets.extend(self.reader.read_u32::<LittleEndian>().ok());
+                }
+            }
+        }
+        if footer.developer_offset != 0 && self.seek_to(footer.developer_offset).is_ok() {
+            let available = (self.end - self.start).saturating_sub(footer.developer_offset as u64);
+            let entries =
+                developer::read_directory_at(&mut self.reader, 0, available).unwrap_or_default();
+            offsets.extend(
+                entries
+                    .iter()
+                    .filter(|entry| entry.size != 0)
+                    .map(|entry| entry.offset),
+            );
+        }
+
+        let pixel_offset = (HEADER_SIZE + self.id.len()) as u64;
+        offsets
+            .into_iter()
+            .map(|offset| offset as u64)
+            .filter(|offset| (pixel_offset..footer_offset).contains(offset))
+            .min()
+    }
+
+    /// Reads the extension area at `offset`, along with everything it points
+    /// at that lies within `bounds`.
+    fn read_extension_area(&mut self, offset: u32, bounds: &Range<u64>) -> Result<()> {
+        self.extension_offset = offset;
+        self.seek_to(offset)?;
+        let mut extension_area = extension::read_extension_area(&mut self.reader, offset as u64)?;
+        let is_unusual = |c: char| c.is_control() || c == char::REPLACEMENT_CHARACTER;
+        if extension_area
+            .comments
+            .lines()
+            .iter()
+            .any(|line| line.contains(is_unusual))
+        {
+            self.note(
+                DiagnosticCode::UnusualComments,
+                offset as u64 + COMMENTS_POSITION,
+                Context::ExtensionArea("author comments"),
+                "comments contain control characters or invalid UTF-8",
+            );
+        }
+
+        let offset = extension_area.color_correction_offset;
+        let context = Context::ExtensionArea("color correction offset");
+        let size = COLOR_CORRECTION_TABLE_SIZE as u64;
+        if offset != 0 && self.check_area(offset, size, bounds, context)? {
+            self.seek_to(offset)?;
+            let table = self.read_at(offset as u64, Context::ColorCorrectionTable, |r| {
+                ColorCorrectionTable::read_from(r)
+            })?;
+            extension_area.color_correction_table = Some(table);
+        }
+
+        let offset = extension_area.postage_stamp_offset;
+        let context = Context::ExtensionArea("postage stamp offset");
+        if offset != 0 && self.check_area(offset, 2, bounds, context)? {
+            self.seek_to(offset)?;
+            let (width, height) = self.read_at(offset as u64, Context::PostageStamp, |r| {
+                Ok((r.read_u8()? as u16, r.read_u8()? as u16))
+            })?;
+
+            let specification = self.header.image_specification;
+            let bytes_per_pixel = specification.pixel_depth.bytes_per_pixel() as u64;
+            let size = 2 + width as u64 * height as u64 * bytes_per_pixel;
+            if width != 0 && height != 0 && self.check_area(offset, size, bounds, context)? {
+                let data = self.read_at(offset as u64 + 2, Context::PostageStamp, |r| {
+                    Ok(read_pixels(
+                        r,
+                        width,
+                        height,
+                        specification.pixel_depth,
+                        specification.descriptor,
+                    )?)
+                })?;
+
+                let mut stamp = Some(Image::from_parts(width, height, data));
+                let message = "postage stamp is larger than 64x64 pixels";
+                let is_oversized =
+                    width > MAX_POSTAGE_STAMP_SIZE || height > MAX_POSTAGE_STAMP_SIZE;
+                if is_oversized
+                    && self.violation(
+                        self.options.oversized_postage_stamp,
+                        DiagnosticCode::OversizedPostageStamp,
+                        Error::InvalidDimensions(message),
+                        offset as u64,
+                        Context::PostageStamp,
+                        message,
+                    )?
+                {
+                    stamp = None;
+                }
+                self.postage_stamp = stamp;
+            }
+        }
+
+        let offset = extension_area.scan_line_offset;
+        let context = Context::ExtensionArea("scan line offset");
+        let size = self.height() as u64 * 4;
+        if offset != 0 && self.check_area(offset, size, bounds, context)? {
+            self.seek_to(offset)?;
+            let height = self.height();
+            let table = self.read_at(offset as u64, Context::ScanLineTable, |r| {
+                let mut table = Vec::with_capacity(height as usize);
+                for _ in 0..height {
+                    table.push(r.read_u32::<LittleEndian>()?);
+                }
+
+                Ok(table)
+            })?;
+            self.scan_line_table = Some(table);
+        }
+
+        self.extension_area = Some(extension_area);
+
+        Ok(())
+    }
+
+    /// Reads the developer directory at `offset`, followed by the payload of
+    /// every tag it lists that lies within `bounds`.
+    ///
+    /// Payloads may not add up to more than the size of the file, so that a
+    /// directory repeatedly listing the same data cannot make reading it
+    /// arbitrarily expensive.
+    fn read_developer_area(&mut self, offset: u32, bounds: &Range<u64>) -> Result<()> {
+        let phase = trace::phase!("developer_area");
+        self.seek_to(offset)?;
+        let available = (self.end - self.start).saturating_sub(offset as u64);
+        let entries = developer::read_directory_at(&mut self.reader, offset as u64, available)?;
+        let size = 2 + (entries.len() * developer::DIRECTORY_ENTRY_SIZE) as u64;
+        if !self.check_area(offset, size, bounds, Context::Footer)? {
+            return Ok(());
+        }
+
+        let mut remaining = self.end - self.start;
+        let mut bytes = size;
+        self.developer_tags.reserve_exact(entries.len());
+        for entry in entries {
+            let context = Context::DeveloperTag(entry.tag);
+            remaining = remaining.checked_sub(entry.size as u64).ok_or_else(|| {
+                Error::InvalidDeveloperArea("tag data exceeds the size of the file")
+                    .at(entry.offset as u64, context)
+            })?;
+
+            if self.check_area(entry.offset, entry.size as u64, bounds, context)? {
+                let tag = developer::read_tag(&mut self.reader, self.start, entry)?;
+                self.developer_tags.push(tag);
+                bytes += entry.size as u64;
+            }
+        }
+        phase.bytes(bytes);
+
+        Ok(())
+    }
+
+    /// Reads the extension area exactly as it is stored in the file.
+    pub(crate) fn read_raw_extension_area(&mut self) -> Result<Option<Vec<u8>>> {
+        if self.extension_area.is_none() {
+            return Ok(None);
+        }
+
+        self.seek_to(self.extension_offset)?;
+        let size = self.reader.read_u16::<LittleEndian>()?;
+        let mut raw = size.to_le_bytes().to_vec();
+        raw.resize(size as usize, 0);
+        self.reader.read_exact(&mut raw[2..])?;
+
+        Ok(Some(raw))
+    }
+
+    /// Decodes the pixel data, passing it to `f` in blocks of consecutive rows
+    /// in the order they are stored, like [`Decoder::decode_rows`]. The pixels
+    /// are left as stored until converted with [`Decoder::row_converter`].
+    #[cfg(feature = "std")]
+    pub(crate) fn read_stored_rows(&mut self, f: impl FnMut(Range<usize>, &[u8])) -> Result<()> {
+        self.check_pixel_data_size()?;
+        let height = self.height() as usize;
+        let phase = trace::phase!("pixel_data");
+        let bytes = self.decode_rows(self.pixel_offset, 0..height, f)?;
+        phase.bytes(bytes);
+
+        Ok(())
+    }
+
+    /// A function converting a row as stored to 32-bit, left-to-right pixels.
+    #[cfg(feature = "std")]
+    pub(crate) fn row_converter(&self) -> impl Fn(&[u8], &mut [u8]) + Copy + Send {
+        let specification = self.header.image_specification;
+        let bytes_per_pixel = specification.pixel_depth.bytes_per_pixel();
+        let descriptor = specification.descriptor;
+
+        move |source, row| convert_row(source, row, bytes_per_pixel, descriptor)
+    }
+
+    /// Moves everything read when the decoder was created into an image
+    /// without pixel data, for writing it out again around pixels decoded
+    /// separately.
+    #[cfg(feature = "std")]
+    pub(crate) fn take_metadata(&mut self) -> Image {
+        let mut image = Image::without_pixels(self.width(), self.height());
+        image.id = mem::take(&mut self.id);
+        image.extension_area = self.extension_area.take();
+        image.developer_tags = mem::take(&mut self.developer_tags);
+        image.postage_stamp = self.postage_stamp.take().map(Box::new);
+
+        image
+    }
+
+    /// Decodes the rows in `rows`, counting in the order they are stored,
+    /// from pixel data starting at `position`. The rows are passed to `f` in
+    /// blocks of consecutive rows, together with the range of rows in each
+    /// block. Decoding the last row of the image checks that no packet runs
+    /// past its end. Returns the number of bytes of pixel data read.
+    fn decode_rows(
+        &mut self,
+        position: u64,
+        rows: Range<usize>,
+        mut f: impl FnMut(Range<usize>, &[u8]),
+    ) -> Result<u64> {
+        let specification = self.header.image_specification;
+        let bytes_per_pixel = specification.pixel_depth.bytes_per_pixel();
+        let source_size = specification.width as usize * bytes_per_pixel;
+        let rows_per_block = parallel::rows_per_block(source_size);
+        let is_rle = self.is_rle();
+
+        self.reader.seek(SeekFrom::Start(position))?;
+        let mut r = CountingReader::new(&mut self.reader);
+        let mut block = vec![0; rows.len().min(rows_per_block) * source_size];
+        let mut rle = RleDecoder::default();
+        let is_last_row = rows.end == specification.height as usize;
+        let mut first = rows.start;
+        while first < rows.end {
+            let last = rows.end.min(first + rows_per_block);
+            let block = &mut block[..(last - first) * source_size];
+            let block_start = r.count();
+            let result = if is_rle {
+                block
+                    .chunks_exact_mut(source_size)
+                    .try_for_each(|row| rle.read_row(&mut r, row, bytes_per_pixel))
+                    .map_err(|error| (error, Context::Packet(rle.packet_index())))
+            } else {
+                r.read_exact(block).map_err(|error| {
+                    let row = first + ((r.count() - block_start) / source_size as u64) as usize;
+                    (error.into(), Context::Row(row as u16))
+                })
+            };
+            if let Err((error, context)) = result {
+                return Err(error.at(position - self.start + r.count(), context));
+            }
+
+            f(first..last, block);
+            first = last;
+        }
+
+        if is_rle && is_last_row {
+            rle.finish().map_err(|error| {
+                let offset = position - self.start + r.count();
+                error.at(offset, Context::Packet(rle.packet_index()))
+            })?;
+        }
+
+        Ok(r.count())
+    }
+
+    /// Reads a structure starting `offset` bytes into the file with `read`,
+    /// reporting where reading it failed if it does.
+    fn read_at<T>(
+        &mut self,
+        offset: u64,
+        context: Context,
+        read: impl FnOnce(&mut CountingReader<R>) -> Result<T>,
+    ) -> Result<T> {
+        let mut r = CountingReader::new(&mut self.reader);
+        read(&mut r).map_err(|error| error.at(offset + r.count(), context))
+    }
+
+    fn is_rle(&self) -> bool {
+        self.header.image_type.is_rle()
+    }
+
+    /// Seeks to an offset relative to the start of the file.
+    fn seek_to(&mut self, offset: u32) -> io::Result<()> {
+        self.reader
+            .seek(SeekFrom::Start(self.start + offset as u64))
+            .map(|_| ())
+    }
+}
+
+impl Decoder<Cursor<&[u8]>> {
+    /// Like [`Decoder::read_image_with_diagnostics`], but converts uncompressed
+    /// pixels straight from the slice being read instead of copying each
+    /// block of rows out of it first.
+    pub(crate) fn read_slice_with_diagnostics(self) -> Result<Decoded> {
+        if self.is_rle() {
+            return self.read_image_with_diagnostics();
+        }
+
+        let size = self.image_size()?;
+        self.check_pixel_data_size()?;
+        let specification = self.header.image_specification;
+        let row_size = Image::effective_size(specification.width, 1);
+        let height = specification.height as usize;
+        let descriptor = specification.descriptor;
+        let bytes_per_pixel = specification.pixel_depth.bytes_per_pixel();
+        let is_flipped = descriptor.vertical_ordering() == VerticalOrdering::BottomToTop;
+
+        // The size of the file was checked to hold all of the pixel data.
+        let source_size = specification.width as usize * bytes_per_pixel;
+        let source = &self.reader.get_ref()[self.pixel_offset as usize..][..height * source_size];
+        let mut data = vec![0; size];
+        let phase = trace::phase!("pixel_data");
+        parallel::for_each_row(&mut data, row_size, |y, row| {
+            let index = if is_flipped { height - 1 - y } else { y };
+            let source = &source[index * source_size..][..source_size];
+            convert_row(source, row, bytes_per_pixel, descriptor);
+        });
+        phase.bytes(source.len() as u64);
+        phase.end();
+
+        Ok(self.into_decoded(data))
+    }
+}
+
+/// Decodes an image from a file held in memory that may come from an untrusted
+/// source.
+///
+/// Malformed input is reported as an error and never causes a panic. The
+/// memory allocated and the work done are bounded by the length of `data`:
+/// pixel buffers are only allocated once the file is known to be large enough
+/// to hold the pixel data, and no part of the file is read more than a fixed
+/// number of times. Run-length encoding lets each byte expand to at most 43
+/// pixels, as a repeat packet of 15- or 16-bit pixels takes up 3 bytes and
+/// holds 128 of them; with 24- and 32-bit pixels it is 32 and 26 pixels.
+pub fn decode_untrusted(data: &[u8]) -> Result<Image> {
+    Ok(Decoder::new(Cursor::new(data))?
+        .read_slice_with_diagnostics()?
+        .image)
+}
+
+/// Reads the header, rejecting images the decoder does not support and color
+/// maps larger than `options` allow.
+pub(crate) fn read_header<T: Read>(r: &mut T, options: &DecodeOptions) -> Result<Header> {
+    let mut r = CountingReader::new(r);
+    let header = Header::read_from(&mut r).map_err(|error| {
+        let position = r.count();
+        Error::from(error).at(position, Context::Header(header_field(position)))
+    })?;
+
+    if header.image_type.is_color_mapped() && header.color_map_type != ColorMapType::PRESENT {
+        let error = Error::ColorMapMismatch {
+            color_map_type: header.color_map_type.0,
+            image_type: header.image_type.into(),
+        };
+        return Err(error.at(
+            COLOR_MAP_TYPE_POSITION,
+            Context::Header(header_field(COLOR_MAP_TYPE_POSITION)),
+        ));
+    }
+
+    let color_map = header.color_map_specification;
+    if header.color_map_type != ColorMapType::ABSENT {
+        let exceeded = if color_map.entry_count > options.max_color_map_entries {
+            Some((COLOR_MAP_LENGTH_POSITION, "color map length"))
+        } else if color_map.color_depth > options.max_color_map_entry_size {
+            Some((COLOR_MAP_ENTRY_SIZE_POSITION, "color map entry size"))
+        } else {
+            None
+        };
+        if let Some((position, limit)) = exceeded {
+            let error = Error::LimitExceeded(limit);
+            return Err(error.at(position, Context::Header(header_field(position))));
+        }
+    }
+
+    let specification = header.image_specification;
+    let pixel_depth = specification.pixel_depth;
+    let is_supported = header.image_type.is_true_color() || header.image_type.is_grayscale();
+    let depths: &[PixelDepth] = if header.image_type.is_grayscale() {
+        &[PixelDepth::B8]
+    } else {
+        &[
+            PixelDepth::B15,
+            PixelDepth::B16,
+            PixelDepth::B24,
+            PixelDepth::B32,
+        ]
+    };
+    if !is_supported || !depths.contains(&pixel_depth) {
+        let position = if is_supported {
+            PIXEL_DEPTH_POSITION
+        } else {
+            IMAGE_TYPE_POSITION
+        };
+        let error = Error::UnsupportedImageType {
+            image_type: header.image_type.into(),
+            pixel_depth: pixel_depth.bits(),
+        };
+        return Err(error.at(position, Context::Header(header_field(position))));
+    }
+
+    if specification.width == 0 || specification.height == 0 {
+        let position = if specification.width == 0 {
+            WIDTH_POSITION
+        } else {
+            HEIGHT_POSITION
+        };
+        let error = Error::InvalidDimensions("image has zero width or height");
+        return Err(error.at(position, Context::Header(header_field(position))));
+    }
+
+    Ok(header)
+}
+
+/// Checks the header for the spec violations that can be told from it alone,
+/// handling them as set by `options` and recording the ones tolerated in
+/// `diagnostics`. Every way of decoding a file runs these checks.
+pub(crate) fn check_header(
+    header: &Header,
+    options: &DecodeOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<()> {
+    check_color_map(header, options, diagnostics)?;
+    check_descriptor(header, options, diagnostics)?;
+    check_origin(header, diagnostics);
+
+    Ok(())
+}
+
+/// Checks that a true-color or grayscale image declares no color map. A
+/// declared color map is skipped, since its size is known from the header.
+fn check_color_map(
+    header: &Header,
+    options: &DecodeOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<()> {
+    let context = Context::Header(header_field(COLOR_MAP_TYPE_POSITION));
+    let (error, message) = match header.color_map_type {
+        ColorMapType::ABSENT => return Ok(()),
+        ColorMapType::PRESENT => (
+            Error::ColorMapMismatch {
+                color_map_type: header.color_map_type.0,
+                image_type: header.image_type.into(),
+            },
+            "image without color-mapped pixels declares a color map",
+        ),
+        _ => (
+            Error::InvalidHeader("unknown color map type"),
+            "unknown color map type",
+        ),
+    };
+    violation(
+        options.color_map,
+        DiagnosticCode::ColorMap,
+        error,
+        COLOR_MAP_TYPE_POSITION,
+        context,
+        message,
+        diagnostics,
+    )?;
+
+    Ok(())
+}
+
+fn check_descriptor(
+    header: &Header,
+    options: &DecodeOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<()> {
+    let specification = header.image_specification;
+    let descriptor = specification.descriptor;
+    if descriptor.has_reserved_bits() {
+        let message = "reserved image descriptor bits are set";
+        violation(
+            options.reserved_bits,
+            DiagnosticCode::ReservedBits,
+            Error::InvalidHeader(message),
+            DESCRIPTOR_POSITION,
+            Context::Header("image descriptor"),
+            message,
+            diagnostics,
+        )?;
+    }
+
+    if !validate::alpha_depth_fits(
+        false,
+        specification.pixel_depth.bits(),
+        descriptor.alpha_depth(),
+    ) {
+        let message = "alpha depth does not match the pixel depth";
+        violation(
+            options.alpha_depth,
+            DiagnosticCode::AlphaDepth,
+            Error::InvalidHeader(message),
+            DESCRIPTOR_POSITION,
+            Context::Header("image descriptor"),
+            message,
+            diagnostics,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn check_origin(header: &Header, diagnostics: &mut Vec<Diagnostic>) {
+    let specification = header.image_specification;
+    if specification.x_origin != 0 || specification.y_origin != 0 {
+        let position = if specification.x_origin != 0 {
+            X_ORIGIN_POSITION
+        } else {
+            Y_ORIGIN_POSITION
+        };
+        diagnostics.push(Diagnostic {
+            severity: Severity::Info,
+            code: DiagnosticCode::NonzeroOrigin,
+            offset: position,
+            context: Context::Header(header_field(position)),
+            message: "image origin is not zero",
+        });
+    }
+}
+
+/// Handles a spec violation as set by `policy`, rejecting it with `error` or
+/// recording `message` as a warning in `diagnostics`. Returns whether the
+/// caller should fix up the violation.
+fn violation(
+    policy: ViolationPolicy,
+    code: DiagnosticCode,
+    error: Error,
+    offset: u64,
+    context: Context,
+    message: &'static str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<bool> {
+    match policy {
+        ViolationPolicy::Error => Err(error.at(offset, context)),
+        ViolationPolicy::FixUp => {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                code,
+                offset,
+                context,
+                message,
+            });
+
+            Ok(true)
+        }
+        ViolationPolicy::Ignore => Ok(false),
+    }
+}
+
+/// The name of the header field at `position`.
+fn header_field(position: u64) -> &'static str {
+    let (_, field) = HEADER_FIELDS
+        .iter()
+        .rev()
+        .find(|&&(start, _)| start <= position)
+        .copied()
+        .unwrap_or(HEADER_FIELDS[0]);
+
+    field
+}
+
+/// Reads uncompressed true-color or grayscale pixels, converting them to 32 bits in
+/// top-to-bottom, left-to-right order.
+fn read_pixels<T: Read>(
+    r: &mut T,
+    width: u16,
+    height: u16,
+    pixel_depth: PixelDepth,
+    descriptor: ImageDescriptor,
+) -> io::Result<Vec<u8>> {
+    let row_size = Image::effective_size(width, 1);
+    let height = height as usize;
+    let bytes_per_pixel = pixel_depth.bytes_per_pixel();
+
+    let mut data = vec![0; row_size * height];
+    let mut source = vec![0; width as usize * bytes_per_pixel];
+    for y in 0..height {
+        r.read_exact(&mut source)?;
+
+        let y = match descriptor.vertical_ordering() {
+            VerticalOrdering::BottomToTop => height - 1 - y,
+            VerticalOrdering::TopToBottom => y,
+        };
+        let row = &mut data[y * row_size..][..row_size];
+        convert_row(&source, row, bytes_per_pixel, descriptor);
+    }
+
+    Ok(data)
+}
+
+/// Converts a row of pixels as stored in the file to 32-bit, left-to-right
+/// pixels.
+pub(crate) fn convert_row(
+    source: &[u8],
+    row: &mut [u8],
+    bytes_per_pixel: usize,
+    descriptor: ImageDescriptor,
+) {
+    expand_pixels(source, row, bytes_per_pixel, descriptor);
+
+    if descriptor.horizontal_ordering() == HorizontalOrdering::RightToLeft {
+        bytemuck::cast_slice_mut::<u8, Bgra8>(row).reverse();
+    }
+}
+
+/// Expands pixels of `bytes_per_pixel` bytes each to 32 bits, keeping their
+/// order.
+pub(crate) fn expand_pixels(
+    source: &[u8],
+    row: &mut [u8],
+    bytes_per_pixel: usize,
+    descriptor: ImageDescriptor,
+) {
+    match bytes_per_pixel {
+        4 => row.copy_from_slice(source),
+        3 => expand_row(source, row),
+        1 => expand_gray_row(source, row),
+        _ => expand_16_bit_row(source, row, descriptor.alpha_depth() == 1),
+    }
+}
+
+/// Expands 8-bit grayscale pixels to 32 bits with opaque alpha.
+pub(crate) fn expand_gray_row(source: &[u8], row: &mut [u8]) {
+    for (&value, pixel) in source.iter().zip(row.chunks_exact_mut(4)) {
+        pixel.copy_from_slice(&[value, value, value, u8::MAX]);
+    }
+}
+
+/// Expands 24-bit pixels to 32 bits with opaque alpha.
+///
+/// Four pixels are handled at a time, splitting the three little-endian words
+/// they are stored in into a word for each pixel.
+pub(crate) fn expand_row(source: &[u8], row: &mut [u8]) {
+    let mut sources = source.chunks_exact(12);
+    let mut rows = row.chunks_exact_mut(16);
+    for (source, row) in (&mut sources).zip(&mut rows) {
+        let word = |index: usize| {
+            u32::from_le_bytes([
+                source[index],
+                source[index + 1],
+                source[index + 2],
+                source[index + 3],
+            ])
+        };
+        let (a, b, c) = (word(0), word(4), word(8));
+        let pixels = [
+            a | ALPHA,
+            a >> 24 | b << 8 | ALPHA,
+            b >> 16 | c << 16 | ALPHA,
+            c >> 8 | ALPHA,
+        ];
+        for (pixel, value) in row.chunks_exact_mut(4).zip(pixels) {
+            pixel.copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    let pixels: &mut [Bgra8] = bytemuck::cast_slice_mut(rows.into_remainder());
+    for (pixel, source) in pixels.iter_mut().zip(sources.remainder().chunks_exact(3)) {
+        *pixel = Bgra8::new(source[0], source[1], source[2], u8::MAX);
+    }
+}
+
+/// Expands 15- or 16-bit pixels to 32 bits. The top bit of each pixel is its
+/// alpha if `has_alpha` is set, and the pixel is opaque otherwise.
+///
+/// Each byte of a pixel is looked up in a table of the channel bits it
+/// contributes, which are combined into the 32-bit pixel.
+pub(crate) fn expand_16_bit_row(source: &[u8], row: &mut [u8], has_alpha: bool) {
+    let opaque = if has_alpha { 0 } else { ALPHA };
+    for (pixel, source) in row.chunks_exact_mut(4).zip(source.chunks_exact(2)) {
+        let value = LOW_BYTE[source[0] as usize] | HIGH_BYTE[source[1] as usize] | opaque;
+        pixel.copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// The alpha channel of a 32-bit pixel read as a little-endian word.
+const ALPHA: u32 = 0xff00_0000;
+
+/// The 8-bit values of 5-bit channels, which repeat their high bits in the
+/// low bits.
+const EXPAND_5_BIT: [u8; 32] = {
+    let mut table = [0; 32];
+    let mut value = 0;
+    while value < 32 {
+        table[value] = (value << 3 | value >> 2) as u8;
+        value += 1;
+    }
+
+    table
+};
+
+/// The bits of a 32-bit pixel given by the low byte of a 16-bit pixel: blue
+/// and the low bits of green.
+///
+/// Expanding a channel only shifts and combines its bits, so the two bytes
+/// a green channel is split across expand independently of each other.
+const LOW_BYTE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let blue = EXPAND_5_BIT[byte & 0x1f] as u32;
+        let green = EXPAND_5_BIT[byte >> 5] as u32;
+        table[byte] = blue | green << 8;
+        byte += 1;
+    }
+
+    table
+};
+
+/// The bits of a 32-bit pixel given by the high byte of a 16-bit pixel: the
+/// high bits of green, red and alpha.
+const HIGH_BYTE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let green = EXPAND_5_BIT[(byte & 0x3) << 3] as u32;
+        let red = EXPAND_5_BIT[byte >> 2 & 0x1f] as u32;
+        let alpha = if byte & 0x80 != 0 { ALPHA } else { 0 };
+        table[byte] = green << 8 | red << 16 | alpha;
+        byte += 1;
+    }
+
+    table
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::string::ToString;
+
+    /// A file declaring `width` by `height` 32-bit pixels that holds only
+    /// `pixel_bytes` bytes of pixel data.
+    fn truncated_file(width: u16, height: u16, rle: bool, pixel_bytes: usize) -> Vec<u8> {
+        let options = EncodeOptions {
+            rle,
+            ..EncodeOptions::default()
+        };
+        let mut file = Vec::new();
+        Header::new(width, height, &options)
+            .write_to(&mut file)
+            .unwrap();
+        file.resize(file.len() + pixel_bytes, 0);
+
+        file
+    }
+
+    /// A `width` by `height` image whose rows start with a run of repeated
+    /// pixels and end with distinct ones.
+    fn pattern(width: u16, height: u16) -> Image {
+        let data = (0..height as usize)
+            .flat_map(|y| {
+                (0..width as usize).flat_map(move |x| {
+                    let value = if x < 3 { y } else { y * 31 + x * 7 };
+                    [value as u8, (value >> 3) as u8, 0x40, 0xff]
+                })
+            })
+            .collect();
+
+        Image::new(width, height, data).unwrap()
+    }
+
+    fn encode(image: &Image, options: &EncodeOptions) -> Vec<u8> {
+        let mut file = Vec::new();
+        image.write_with_options(&mut file, options).unwrap();
+
+        file
+    }
+
+    #[test]
+    fn read_row_locates_rows_through_the_scan_line_table() {
+        let image = pattern(9, 7);
+        let options = EncodeOptions {
+            rle: true,
+            scan_line_table: true,
+            ..EncodeOptions::default()
+        };
+        let file = encode(&image, &options);
+        let mut decoder = Decoder::new(Cursor::new(&file[..])).unwrap();
+        let table = decoder.scan_line_table().unwrap().to_vec();
+        assert_eq!(table.len(), 7);
+        assert!(table.windows(2).all(|offsets| offsets[0] < offsets[1]));
+        for y in [6, 0, 3, 3, 5] {
+            assert_eq!(
+                decoder.read_row(y).unwrap(),
+                image.row(y as usize),
+                "row {y}"
+            );
+        }
+        assert!(matches!(
+            decoder.read_row(7),
+            Err(Error::RowOutOfBounds { row: 7, height: 7 })
+        ));
+
+        // Rows after the first are read without decoding it.
+        let mut corrupted = file.clone();
+        corrupted[table[0] as usize..table[1] as usize].fill(0xff);
+        let mut decoder = Decoder::new(Cursor::new(&corrupted[..])).unwrap();
+        for y in 1..7 {
+            assert_eq!(
+                decoder.read_row(y).unwrap(),
+                image.row(y as usize),
+                "row {y}"
+            );
+        }
+    }
+
+    #[test]
+    fn read_row_decodes_rows_without_a_scan_line_table() {
+        let image = pattern(9, 7);
+        for rle in [false, true] {
+            let options = EncodeOptions {
+                rle,
+                ..EncodeOptions::default()
+            };
+            let file = encode(&image, &options);
+            let mut decoder = Decoder::new(Cursor::new(&file[..])).unwrap();
+            assert!(decoder.scan_line_table().is_none());
+            for y in [4, 0, 6, 4] {
+                assert_eq!(
+                    decoder.read_row(y).unwrap(),
+                    image.row(y as usize),
+                    "row {y}"
+                );
+            }
+            assert!(matches!(
+                decoder.read_row(u16::MAX),
+                Err(Error::RowOutOfBounds { .. })
+            ));
+        }
+    }
+
+    /// `image` stored uncompressed from bottom to top.
+    fn bottom_to_top_file(image: &Image) -> Vec<u8> {
+        let mut header = Header::new(image.width(), image.height(), &EncodeOptions::default());
+        header.set_descriptor(header.descriptor() & !0x20);
+        assert!(header.is_bottom_to_top());
+        let mut file = Vec::new();
+        header.write_to(&mut file).unwrap();
+        for y in (0..image.height() as usize).rev() {
+            file.extend_from_slice(image.row(y));
+        }
+
+        file
+    }
+
+    #[test]
+    fn read_rows_and_read_region_match_the_whole_image() {
+        let image = pattern(9, 7);
+        let files = [
+            encode(&image, &EncodeOptions::default()),
+            encode(
+                &image,
+                &EncodeOptions {
+                    rle: true,
+                    ..EncodeOptions::default()
+                },
+            ),
+            encode(
+                &image,
+                &EncodeOptions {
+                    rle: true,
+                    scan_line_table: true,
+                    ..EncodeOptions::default()
+                },
+            ),
+            bottom_to_top_file(&image),
+        ];
+        let row_size = Image::effective_size(9, 1);
+        for (index, file) in files.iter().enumerate() {
+            let mut decoder = Decoder::new(Cursor::new(&file[..])).unwrap();
+            for rows in [0..7, 2..5, 6..7, 3..3] {
+                assert_eq!(
+                    decoder.read_rows(rows.clone()).unwrap(),
+                    image.data()[rows.start as usize * row_size..rows.end as usize * row_size],
+                    "file {index}, rows {rows:?}"
+                );
+            }
+
+            let region = decoder.read_region(2, 1, 5, 4).unwrap();
+            assert_eq!((region.width(), region.height()), (5, 4));
+            for y in 0..4 {
+                let start = Image::effective_size(2, 1);
+                assert_eq!(
+                    region.row(y),
+                    &image.row(y + 1)[start..start + Image::effective_size(5, 1)],
+                    "file {index}, row {y}"
+                );
+            }
+            let whole = decoder.read_region(0, 0, 9, 7).unwrap();
+            assert_eq!(whole.data(), image.data());
+        }
+    }
+
+    #[test]
+    fn rows_and_regions_outside_the_image_are_rejected() {
+        let file = encode(&pattern(9, 7), &EncodeOptions::default());
+        let mut decoder = Decoder::new(Cursor::new(&file[..])).unwrap();
+        assert!(matches!(
+            decoder.read_rows(5..8),
+            Err(Error::RowOutOfBounds { row: 7, height: 7 })
+        ));
+        for (x, y, width, height) in [
+            (0, 0, 10, 1),
+            (8, 0, 2, 1),
+            (0, 6, 1, 2),
+            (u16::MAX, 0, 1, 1),
+        ] {
+            assert!(matches!(
+                decoder.read_region(x, y, width, height),
+                Err(Error::InvalidDimensions(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn read_rows_rejects_files_too_short_for_their_dimensions() {
+        let file = truncated_file(4, 4, false, 8);
+        let mut decoder = Decoder::new(Cursor::new(&file[..])).unwrap();
+        assert!(decoder.read_rows(0..1).is_err());
+
+        // Without the check, this would allocate 16 GiB before reading.
+        let file = truncated_file(u16::MAX, u16::MAX, true, 5);
+        let mut decoder = Decoder::new(Cursor::new(&file[..])).unwrap();
+        assert!(decoder.read_rows(0..u16::MAX).is_err());
+    }
+
+    #[test]
+    fn expand_row_matches_a_pixel_at_a_time() {
+        for width in 0..=70 {
+            let source: Vec<u8> = (0..width * 3).map(|index| (index * 11 + 5) as u8).collect();
+            let mut row = vec![0; width * 4];
+            expand_row(&source, &mut row);
+
+            let expected: Vec<u8> = source
+                .chunks_exact(3)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], u8::MAX])
+                .collect();
+            assert_eq!(row, expected, "{width} pixels");
+        }
+    }
+
+    /// Expands a 16-bit pixel with shifts, as the tables replace.
+    fn reference_expand_16_bit(value: u16, has_alpha: bool) -> [u8; 4] {
+        let channel = |shift: u16| {
+            let bits = (value >> shift & 0x1f) as u8;
+            bits << 3 | bits >> 2
+        };
+        let alpha = if !has_alpha || value & 0x8000 != 0 {
+            u8::MAX
+        } else {
+            0
+        };
+
+        [channel(0), channel(5), channel(10), alpha]
+    }
+
+    #[test]
+    fn expand_16_bit_row_matches_shifts_for_every_pixel() {
+        let source: Vec<u8> = (0..=u16::MAX).flat_map(u16::to_le_bytes).collect();
+        for has_alpha in [false, true] {
+            let mut row = vec![0; source.len() * 2];
+            expand_16_bit_row(&source, &mut row, has_alpha);
+            for (value, pixel) in (0..=u16::MAX).zip(row.chunks_exact(4)) {
+                assert_eq!(
+                    pixel,
+                    reference_expand_16_bit(value, has_alpha),
+                    "pixel {value:#06x}"
+                );
+            }
+
+            for width in 0..=70 {
+                let source = &source[2 * 0x7bc0..][..width * 2];
+                let mut row = vec![0; width * 4];
+                expand_16_bit_row(source, &mut row, has_alpha);
+                let expected: Vec<u8> = source
+                    .chunks_exact(2)
+                    .flat_map(|pixel| {
+                        reference_expand_16_bit(u16::from_le_bytes([pixel[0], pixel[1]]), has_alpha)
+                    })
+                    .collect();
+                assert_eq!(row, expected, "{width} pixels");
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_up_violations_are_reported_as_warnings() {
+        let mut file = encode(&pattern(4, 2), &EncodeOptions::default());
+        file[DESCRIPTOR_POSITION as usize] |= 0xc0;
+
+        let decoded = Decoder::new(Cursor::new(&file))
+            .unwrap()
+            .read_image_with_diagnostics()
+            .unwrap();
+        assert_eq!(decoded.image.data(), pattern(4, 2).data());
+        let [diagnostic] = &decoded.diagnostics[..] else {
+            panic!("{:?}", decoded.diagnostics);
+        };
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(diagnostic.code, DiagnosticCode::ReservedBits);
+        assert_eq!(diagnostic.offset, DESCRIPTOR_POSITION);
+        assert_eq!(
+            diagnostic.to_string(),
+            format!(
+                "warning [reserved-bits]: reserved image descriptor bits are set (at byte 17, \
+                 reading {})",
+                diagnostic.context
+            )
+        );
+
+        assert!(Decoder::with_options(Cursor::new(&file), DecodeOptions::strict()).is_err());
+        let options = DecodeOptions {
+            reserved_bits: ViolationPolicy::Ignore,
+            ..DecodeOptions::default()
+        };
+        let decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+        assert!(decoder.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn unusual_files_are_reported_as_info() {
+        let mut file = encode(&pattern(4, 2), &EncodeOptions::default());
+        file.truncate(file.len() - FOOTER_SIZE);
+        file[Y_ORIGIN_POSITION as usize] = 2;
+
+        let options = DecodeOptions {
+            missing_footer: ViolationPolicy::FixUp,
+            ..DecodeOptions::default()
+        };
+        let decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+        let found: Vec<_> = decoder
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| (diagnostic.severity, diagnostic.code, diagnostic.offset))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    Severity::Info,
+                    DiagnosticCode::NonzeroOrigin,
+                    Y_ORIGIN_POSITION
+                ),
+                (
+                    Severity::Warning,
+                    DiagnosticCode::MissingFooter,
+                    file.len() as u64
+                ),
+            ]
+        );
+        assert!(Decoder::new(Cursor::new(&file))
+            .unwrap()
+            .diagnostics()
+            .iter()
+            .all(|diagnostic| diagnostic.code != DiagnosticCode::MissingFooter));
+    }
+}
@@ -0,0 +1,224 @@
+use alloc::vec::Vec;
+
+use byteorder::LittleEndian;
+
+use crate::error::CountingReader;
+use crate::io::{self, Read, ReadBytesExt, Seek, SeekFrom, Write, WriteBytesExt};
+use crate::{Context, Error, Result};
+
+/// An entry of the developer area, holding application-specific data.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeveloperTag {
+    pub tag: u16,
+    pub data: Vec<u8>,
+}
+
+pub(crate) const DIRECTORY_ENTRY_SIZE: usize = 10;
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct DirectoryEntry {
+    pub(crate) tag: u16,
+    pub(crate) offset: u32,
+    pub(crate) size: u32,
+}
+
+impl DirectoryEntry {
+    fn read_from<T: Read>(r: &mut T) -> io::Result<Self> {
+        Ok(DirectoryEntry {
+            tag: r.read_u16::<LittleEndian>()?,
+            offset: r.read_u32::<LittleEndian>()?,
+            size: r.read_u32::<LittleEndian>()?,
+        })
+    }
+
+    fn write_to<T: Write>(&self, w: &mut T) -> io::Result<()> {
+        w.write_u16::<LittleEndian>(self.tag)?;
+        w.write_u32::<LittleEndian>(self.offset)?;
+        w.write_u32::<LittleEndian>(self.size)?;
+
+        Ok(())
+    }
+}
+
+/// The combined size in bytes of the payloads of `tags`.
+pub(crate) fn payload_size(tags: &[DeveloperTag]) -> usize {
+    tags.iter().map(|tag| tag.data.len()).sum()
+}
+
+/// The size in bytes of the developer directory listing `tags`.
+pub(crate) fn directory_size(tags: &[DeveloperTag]) -> usize {
+    2 + tags.len() * DIRECTORY_ENTRY_SIZE
+}
+
+/// Reads the developer directory at the current position, `offset` bytes into
+/// the file, which has `available` bytes left from there on.
+///
+/// The entries are allocated at once, but never more of them than the rest
+/// of the file could hold.
+pub(crate) fn read_directory_at<T: Read>(
+    r: &mut T,
+    offset: u64,
+    available: u64,
+) -> Result<Vec<DirectoryEntry>> {
+    let mut counter = CountingReader::new(r);
+    read_directory(&mut counter, available).map_err(|error| {
+        Error::from(error).at(offset + counter.count(), Context::DeveloperDirectory)
+    })
+}
+
+fn read_directory<T: Read>(r: &mut T, available: u64) -> io::Result<Vec<DirectoryEntry>> {
+    let count = r.read_u16::<LittleEndian>()? as usize;
+    let capacity = count.min((available.saturating_sub(2) / DIRECTORY_ENTRY_SIZE as u64) as usize);
+    let mut entries = Vec::with_capacity(capacity);
+    for _ in 0..count {
+        entries.push(DirectoryEntry::read_from(r)?);
+    }
+
+    Ok(entries)
+}
+
+/// Reads the payload of the tag listed by `entry`.
+///
+/// Offsets in the directory are relative to `start`, the beginning of the file.
+/// The size listed is allocated up front, so callers must check it against
+/// the size of the file first.
+pub(crate) fn read_tag<T: Read + Seek>(
+    r: &mut T,
+    start: u64,
+    entry: DirectoryEntry,
+) -> Result<DeveloperTag> {
+    r.seek(SeekFrom::Start(start + entry.offset as u64))?;
+
+    let mut data = Vec::with_capacity(entry.size as usize);
+    r.take(entry.size as u64).read_to_end(&mut data)?;
+    if data.len() != entry.size as usize {
+        let offset = entry.offset as u64 + data.len() as u64;
+        return Err(Error::InvalidDeveloperArea("tag data is truncated")
+            .at(offset, Context::DeveloperTag(entry.tag)));
+    }
+
+    Ok(DeveloperTag {
+        tag: entry.tag,
+        data,
+    })
+}
+
+/// Writes the payload of every tag, followed by the developer directory.
+///
+/// `offset` is the position of the first payload from the beginning of the file.
+pub(crate) fn write_developer_area<T: Write>(
+    w: &mut T,
+    tags: &[DeveloperTag],
+    mut offset: u32,
+) -> Result<()> {
+    let count =
+        u16::try_from(tags.len()).map_err(|_| Error::TooLarge("number of developer tags"))?;
+
+    let mut entries = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let size = u32::try_from(tag.data.len()).map_err(|_| Error::TooLarge("developer tag"))?;
+        entries.push(DirectoryEntry {
+            tag: tag.tag,
+            offset,
+            size,
+        });
+
+        offset = offset
+            .checked_add(size)
+            .ok_or(Error::TooLarge("developer area"))?;
+        w.write_all(&tag.data)?;
+    }
+
+    w.write_u16::<LittleEndian>(count)?;
+    for entry in entries {
+        entry.write_to(w)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::Cursor;
+    use crate::Image;
+    use alloc::vec;
+
+    fn tags() -> Vec<DeveloperTag> {
+        vec![
+            DeveloperTag {
+                tag: 1,
+                data: vec![1, 2, 3],
+            },
+            DeveloperTag {
+                tag: 0xffff,
+                data: Vec::new(),
+            },
+            DeveloperTag {
+                tag: 7,
+                data: vec![9; 20],
+            },
+        ]
+    }
+
+    #[test]
+    fn developer_areas_round_trip() {
+        let tags = tags();
+        let mut file = vec![0; 10];
+        write_developer_area(&mut file, &tags, 10).unwrap();
+        assert_eq!(file.len(), 10 + payload_size(&tags) + directory_size(&tags));
+
+        let offset = 10 + payload_size(&tags) as u64;
+        let mut r = Cursor::new(&file[..]);
+        r.seek(SeekFrom::Start(offset)).unwrap();
+        let entries = read_directory_at(&mut r, offset, file.len() as u64 - offset).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!((entries[2].offset, entries[2].size), (13, 20));
+
+        let read: Vec<_> = entries
+            .into_iter()
+            .map(|entry| read_tag(&mut r, 0, entry).unwrap())
+            .collect();
+        assert_eq!(read, tags);
+    }
+
+    #[test]
+    fn short_directories_and_tags_are_rejected() {
+        let mut file = Vec::new();
+        write_developer_area(&mut file, &tags(), 0).unwrap();
+        let offset = payload_size(&tags()) as u64;
+
+        // A directory cut short in its second entry.
+        let directory = &file[offset as usize..][..2 + DIRECTORY_ENTRY_SIZE + 4];
+        let error = read_directory_at(&mut &directory[..], offset, 16).unwrap_err();
+        assert_eq!(error.context(), Some(Context::DeveloperDirectory));
+        assert_eq!(error.offset(), Some(offset + directory.len() as u64));
+
+        // A count far larger than the file could hold is not allocated for.
+        let count = u16::MAX.to_le_bytes();
+        assert!(read_directory_at(&mut &count[..], 0, 2).is_err());
+
+        let entry = DirectoryEntry {
+            tag: 7,
+            offset: 3,
+            size: 60,
+        };
+        let error = read_tag(&mut Cursor::new(&file[..]), 0, entry).unwrap_err();
+        assert!(matches!(error.root(), Error::InvalidDeveloperArea(_)));
+        assert_eq!(error.context(), Some(Context::DeveloperTag(7)));
+        assert_eq!(error.offset(), Some(file.len() as u64));
+    }
+
+    #[test]
+    fn images_keep_their_developer_tags() {
+        let mut image = Image::new(2, 1, vec![0; 8]).unwrap();
+        image.set_developer_tags(tags());
+        let mut file = Vec::new();
+        image.write_to(&mut file).unwrap();
+
+        let decoded = Image::read_from(&mut Cursor::new(&file[..])).unwrap();
+        assert_eq!(decoded.developer_tags(), tags());
+    }
+}
@@ -0,0 +1,103 @@
+use core::fmt;
+
+use crate::Context;
+
+/// A problem with a file that was tolerated while decoding it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// What kind of problem was found.
+    pub code: DiagnosticCode,
+    /// The offset from the start of the file at which the problem was found.
+    pub offset: u64,
+    /// The part of the file the problem was found in.
+    pub context: Context,
+    pub message: &'static str,
+}
+
+/// How serious a tolerated problem is.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    /// The file is valid, but uses the format in an unusual way.
+    Info,
+    /// The file violates the spec, and the violation was worked around.
+    Warning,
+}
+
+/// The kind of a tolerated problem, stable across releases so that tools can
+/// filter or count diagnostics.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum DiagnosticCode {
+    /// Reserved bits of the image descriptor are set.
+    ReservedBits,
+    /// The alpha depth does not fit the pixel depth.
+    AlphaDepth,
+    /// A true-color or grayscale image declares a color map, or the color
+    /// map type is unknown.
+    ColorMap,
+    /// The declared color map does not fill the space before the pixel data.
+    ColorMapLength,
+    /// The file has no TGA 2.0 footer.
+    MissingFooter,
+    /// The TGA 2.0 footer has a corrupted signature.
+    MalformedFooter,
+    /// An offset points at data outside the space between the pixel data and
+    /// the footer.
+    AreaOffset,
+    /// The postage stamp is larger than 64x64 pixels.
+    OversizedPostageStamp,
+    /// The image origin is not zero.
+    NonzeroOrigin,
+    /// There is unused data between the pixel data and the areas following it.
+    UnusedData,
+    /// The comments contain control characters or invalid UTF-8.
+    UnusualComments,
+}
+
+impl DiagnosticCode {
+    /// A short name for the code, suitable for logs.
+    pub fn name(self) -> &'static str {
+        match self {
+            DiagnosticCode::ReservedBits => "reserved-bits",
+            DiagnosticCode::AlphaDepth => "alpha-depth",
+            DiagnosticCode::ColorMap => "color-map",
+            DiagnosticCode::ColorMapLength => "color-map-length",
+            DiagnosticCode::MissingFooter => "missing-footer",
+            DiagnosticCode::MalformedFooter => "malformed-footer",
+            DiagnosticCode::AreaOffset => "area-offset",
+            DiagnosticCode::OversizedPostageStamp => "oversized-postage-stamp",
+            DiagnosticCode::NonzeroOrigin => "nonzero-origin",
+            DiagnosticCode::UnusedData => "unused-data",
+            DiagnosticCode::UnusualComments => "unusual-comments",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+impl fmt::Display for DiagnosticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} [{}]: {} (at byte {}, reading {})",
+            self.severity, self.code, self.message, self.offset, self.context
+        )
+    }
+}
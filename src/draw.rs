@@ -0,0 +1,166 @@
+//! Minimal drawing primitives for annotating images before they are saved.
+//!
+//! Coordinates are signed so that shapes may extend past the edges of the
+//! image; anything outside the image is clipped.
+
+use crate::{Bgra8, Image};
+
+const BYTES_PER_PIXEL: usize = 4;
+
+impl Image {
+    /// Sets a single pixel, ignoring coordinates outside the image.
+    pub fn draw_pixel(&mut self, x: i32, y: i32, color: Bgra8) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+
+        let offset = y as usize * self.stride + x as usize * BYTES_PER_PIXEL;
+        if let Some(pixel) = self.data.get_mut(offset..offset + BYTES_PER_PIXEL) {
+            pixel.copy_from_slice(&[color.b, color.g, color.r, color.a]);
+        }
+    }
+
+    /// Draws a one pixel wide line from `(x0, y0)` to `(x1, y1)`, inclusive.
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Bgra8) {
+        self.line(x0.into(), y0.into(), x1.into(), y1.into(), color);
+    }
+
+    /// Draws a line between points that may lie anywhere, stepping along the
+    /// longer axis and rounding the other coordinate to the nearest pixel.
+    ///
+    /// Only the steps whose coordinate along the longer axis lies within the
+    /// image are visited, so that the work is bounded by the size of the
+    /// image rather than by the length of the line.
+    fn line(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, color: Bgra8) {
+        let is_steep = (y1 - y0).abs() > (x1 - x0).abs();
+        let (major0, minor0, major1, minor1, major_size) = if is_steep {
+            (y0, x0, y1, x1, self.height)
+        } else {
+            (x0, y0, x1, y1, self.width)
+        };
+
+        let steps = (major1 - major0).abs();
+        let step = if major0 < major1 { 1 } else { -1 };
+        // The range of steps that land inside the image along the major axis.
+        let (first, last) = if step == 1 {
+            (-major0, major_size as i64 - 1 - major0)
+        } else {
+            (major0 - (major_size as i64 - 1), major0)
+        };
+        let minor_delta = (minor1 - minor0) as i128;
+        for index in first.max(0)..=last.min(steps) {
+            let major = major0 + index * step;
+            let minor = if steps == 0 {
+                minor0
+            } else {
+                // Rounds half away from zero, which is symmetric about the
+                // midpoint of the line.
+                let numerator = 2 * index as i128 * minor_delta;
+                let denominator = 2 * steps as i128;
+                let rounded = (numerator + numerator.signum() * steps as i128) / denominator;
+                minor0 + rounded as i64
+            };
+            let (x, y) = if is_steep {
+                (minor, major)
+            } else {
+                (major, minor)
+            };
+            if let (Ok(x), Ok(y)) = (i32::try_from(x), i32::try_from(y)) {
+                self.draw_pixel(x, y, color);
+            }
+        }
+    }
+
+    /// Draws the one pixel wide outline of a rectangle.
+    pub fn draw_rect(&mut self, x: i32, y: i32, width: u16, height: u16, color: Bgra8) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let (x, y) = (i64::from(x), i64::from(y));
+        let right = x + width as i64 - 1;
+        let bottom = y + height as i64 - 1;
+        self.line(x, y, right, y, color);
+        self.line(x, bottom, right, bottom, color);
+        self.line(x, y, x, bottom, color);
+        self.line(right, y, right, bottom, color);
+    }
+
+    /// Fills a rectangle with a solid color.
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: u16, height: u16, color: Bgra8) {
+        let left = x.max(0);
+        let top = y.max(0);
+        let right = x.saturating_add(width as i32).min(self.width as i32);
+        let bottom = y.saturating_add(height as i32).min(self.height as i32);
+
+        if left >= right {
+            return;
+        }
+
+        for row in top..bottom {
+            let start = row as usize * self.stride + left as usize * BYTES_PER_PIXEL;
+            let end = start + (right - left) as usize * BYTES_PER_PIXEL;
+            if let Some(pixels) = self.data.get_mut(start..end) {
+                bytemuck::cast_slice_mut::<u8, Bgra8>(pixels).fill(color);
+            }
+        }
+    }
+
+    /// Draws a cross centered on `(x, y)` whose arms extend `radius` pixels in
+    /// each direction.
+    pub fn draw_cross(&mut self, x: i32, y: i32, radius: u16, color: Bgra8) {
+        let (x, y, radius) = (i64::from(x), i64::from(y), i64::from(radius));
+        self.line(x - radius, y, x + radius, y, color);
+        self.line(x, y - radius, x, y + radius, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    const WHITE: Bgra8 = Bgra8 {
+        b: 255,
+        g: 255,
+        r: 255,
+        a: 255,
+    };
+
+    fn drawn(image: &Image) -> Vec<(usize, usize)> {
+        let width = image.width() as usize;
+        bytemuck::cast_slice::<u8, Bgra8>(image.data())
+            .iter()
+            .enumerate()
+            .filter(|(_, &pixel)| pixel == WHITE)
+            .map(|(index, _)| (index % width, index / width))
+            .collect()
+    }
+
+    #[test]
+    fn extreme_coordinates_do_not_overflow() {
+        let mut image = Image::new(4, 4, vec![0; 64]).unwrap();
+        image.draw_line(i32::MIN, i32::MIN, i32::MAX, i32::MAX, WHITE);
+        image.draw_rect(i32::MAX, i32::MAX, u16::MAX, u16::MAX, WHITE);
+        image.fill_rect(i32::MAX, i32::MIN, u16::MAX, u16::MAX, WHITE);
+        image.draw_cross(i32::MIN, i32::MAX, u16::MAX, WHITE);
+
+        assert_eq!(drawn(&image), [(0, 0), (1, 1), (2, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn clipped_line_matches_the_visible_part() {
+        let mut clipped = Image::new(8, 8, vec![0; 256]).unwrap();
+        clipped.draw_line(-100, -50, 100, 50, WHITE);
+        let mut reference = Image::new(8, 8, vec![0; 256]).unwrap();
+        for x in 0..8 {
+            // The line runs through the origin with a slope of one half.
+            let y = (x + 1) / 2;
+            reference.draw_pixel(x, y, WHITE);
+        }
+
+        assert_eq!(drawn(&clipped), drawn(&reference));
+    }
+}
@@ -0,0 +1,209 @@
+use crate::extension::{self, AUTHOR_NAME_POSITION, COMMENTS_POSITION, TIMESTAMP_POSITION};
+use crate::io::{self, Read, Seek, SeekFrom, Write};
+use crate::{
+    Comments, Context, DecodeOptions, Decoder, Error, ExtensionArea, Footer, Result, Timestamp,
+    ViolationPolicy, FOOTER_SIZE,
+};
+
+/// Edits the extension area of an existing file in place.
+///
+/// Every setter overwrites a single fixed-size field, so the pixel data and
+/// everything else in the file is left untouched. This makes stamping metadata
+/// onto large files cheap compared to decoding and re-encoding them.
+#[derive(Debug)]
+pub struct MetadataEditor<F> {
+    file: F,
+    start: u64,
+    extension_offset: u32,
+}
+
+impl<F: Read + Write + Seek> MetadataEditor<F> {
+    /// Opens the file starting at the current position of `file` for editing.
+    ///
+    /// The file is checked as a decoder would with [`ViolationPolicy::Error`]
+    /// for area offsets, so an extension area that overlaps the pixel data or
+    /// the footer is rejected rather than overwritten. A file whose header
+    /// does not decode, or that is too short for its pixel data, is rejected
+    /// before anything is written to it.
+    ///
+    /// If the file has no extension area, an empty one is appended together
+    /// with a new footer. The developer area, if any, is kept.
+    pub fn new(mut file: F) -> Result<Self> {
+        let start = file.stream_position()?;
+        let options = DecodeOptions {
+            area_offsets: ViolationPolicy::Error,
+            ..Default::default()
+        };
+        let decoder = Decoder::with_options(&mut file, options)?;
+        let pixel_data_end = decoder.pixel_data_end();
+        let extension_offset = decoder.extension_offset();
+
+        let extension_offset = match extension_offset {
+            0 => {
+                let end = file.seek(SeekFrom::End(0))?;
+                if end - start < pixel_data_end {
+                    return Err(Error::from(io::Error::from(io::ErrorKind::UnexpectedEof))
+                        .at(end - start, Context::PixelData));
+                }
+
+                let mut footer = None;
+                if end - start >= pixel_data_end + FOOTER_SIZE as u64 {
+                    file.seek(SeekFrom::End(-(FOOTER_SIZE as i64)))?;
+                    footer = Some(Footer::read_from(&mut file)?).filter(Footer::is_valid);
+                }
+
+                let position = match footer {
+                    Some(_) => end - FOOTER_SIZE as u64,
+                    None => end,
+                };
+                let extension_offset =
+                    u32::try_from(position - start).map_err(|_| Error::TooLarge("file"))?;
+
+                file.seek(SeekFrom::Start(position))?;
+                ExtensionArea::default().write_to(&mut file)?;
+                Footer {
+                    extension_offset,
+                    developer_offset: footer.map_or(0, |footer| footer.developer_offset),
+                    ..Default::default()
+                }
+                .write_to(&mut file)?;
+
+                extension_offset
+            }
+            extension_offset => extension_offset,
+        };
+
+        Ok(MetadataEditor {
+            file,
+            start,
+            extension_offset,
+        })
+    }
+
+    /// Reads the extension area as it currently is in the file.
+    pub fn extension_area(&mut self) -> Result<ExtensionArea> {
+        self.seek_to_field(0)?;
+        extension::read_extension_area(&mut self.file, self.extension_offset as u64)
+    }
+
+    /// Overwrites the author name, truncating it to 40 bytes.
+    pub fn set_author_name(&mut self, author_name: &str) -> Result<()> {
+        self.seek_to_field(AUTHOR_NAME_POSITION)?;
+        extension::write_string(&mut self.file, author_name, extension::SHORT_FIELD_SIZE)?;
+
+        Ok(())
+    }
+
+    pub fn set_comments(&mut self, comments: &Comments) -> Result<()> {
+        self.seek_to_field(COMMENTS_POSITION)?;
+        extension::write_comments(&mut self.file, comments)?;
+
+        Ok(())
+    }
+
+    pub fn set_timestamp(&mut self, timestamp: Timestamp) -> Result<()> {
+        self.seek_to_field(TIMESTAMP_POSITION)?;
+        extension::write_timestamp(&mut self.file, timestamp)?;
+
+        Ok(())
+    }
+
+    /// Flushes any pending writes and returns the underlying file.
+    pub fn into_inner(mut self) -> Result<F> {
+        self.file.flush()?;
+
+        Ok(self.file)
+    }
+
+    /// Seeks to a position relative to the start of the extension area.
+    fn seek_to_field(&mut self, position: u64) -> io::Result<()> {
+        let offset = self.start + self.extension_offset as u64 + position;
+        self.file.seek(SeekFrom::Start(offset)).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::Cursor;
+    use crate::{Image, HEADER_SIZE};
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn image() -> Image {
+        let data = (0..64).collect();
+
+        Image::new(4, 4, data).unwrap()
+    }
+
+    /// A file holding `image` with a default extension area at the end, but a
+    /// footer pointing at `extension_offset` instead.
+    fn file_with_extension_offset(extension_offset: u32) -> Vec<u8> {
+        let mut file = Vec::new();
+        image().write_to(&mut file).unwrap();
+        file.truncate(file.len() - FOOTER_SIZE);
+        ExtensionArea::default().write_to(&mut file).unwrap();
+        Footer::new(extension_offset, 0)
+            .write_to(&mut file)
+            .unwrap();
+
+        file
+    }
+
+    #[test]
+    fn appends_an_extension_area_and_round_trips_its_fields() {
+        let mut file = Vec::new();
+        image().write_to(&mut file).unwrap();
+
+        let mut editor = MetadataEditor::new(Cursor::new(file)).unwrap();
+        editor.set_author_name("Ada").unwrap();
+        assert_eq!(editor.extension_area().unwrap().author_name, "Ada");
+
+        let file = editor.into_inner().unwrap().into_inner();
+        let decoded = Image::read_from(&mut Cursor::new(&file[..])).unwrap();
+        assert_eq!(decoded.data, image().data);
+        assert_eq!(decoded.extension_area().unwrap().author_name, "Ada");
+
+        // Opening the file again edits the same extension area.
+        let mut editor = MetadataEditor::new(Cursor::new(file.clone())).unwrap();
+        editor.set_author_name("Grace").unwrap();
+        let edited = editor.into_inner().unwrap().into_inner();
+        assert_eq!(edited.len(), file.len());
+    }
+
+    #[test]
+    fn rejects_an_extension_offset_overlapping_the_pixel_data() {
+        for extension_offset in [1, HEADER_SIZE as u32, HEADER_SIZE as u32 + 63] {
+            let file = file_with_extension_offset(extension_offset);
+            let mut cursor = Cursor::new(file.clone());
+            let error = MetadataEditor::new(&mut cursor).unwrap_err();
+            assert!(matches!(error.root(), Error::InvalidOffset));
+            assert_eq!(cursor.into_inner(), file);
+        }
+
+        let file = file_with_extension_offset(HEADER_SIZE as u32 + 64);
+        assert!(MetadataEditor::new(Cursor::new(file)).is_ok());
+    }
+
+    #[test]
+    fn rejects_an_extension_area_overlapping_the_footer() {
+        let file = file_with_extension_offset(HEADER_SIZE as u32 + 64 + 1);
+        let mut cursor = Cursor::new(file.clone());
+        let error = MetadataEditor::new(&mut cursor).unwrap_err();
+        assert!(matches!(error.root(), Error::InvalidOffset));
+        assert_eq!(cursor.into_inner(), file);
+    }
+
+    #[test]
+    fn does_not_append_to_files_that_do_not_decode() {
+        let mut truncated = Vec::new();
+        image().write_to(&mut truncated).unwrap();
+        truncated.truncate(HEADER_SIZE + 8);
+
+        for file in [vec![0xff; 64], truncated] {
+            let mut cursor = Cursor::new(file.clone());
+            assert!(MetadataEditor::new(&mut cursor).is_err());
+            assert_eq!(cursor.into_inner(), file);
+        }
+    }
+}
@@ -0,0 +1,763 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::slice;
+#[cfg(feature = "std")]
+use std::io::IoSlice;
+
+use byteorder::LittleEndian;
+
+use crate::io::{self, Write, WriteBytesExt};
+use crate::{
+    developer, extension, parallel, rle, Bgra8, ColorCorrectionTable, Error, ExtensionArea, Footer,
+    Header, Image, ImageType, PixelDepth, Result, FOOTER_SIZE, HEADER_SIZE,
+};
+
+/// The pixel format written by the encoder.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PixelFormat {
+    /// 24-bit blue, green, red. The alpha channel is discarded.
+    Bgr24,
+    /// 32-bit blue, green, red, alpha.
+    #[default]
+    Bgra32,
+    /// 8-bit grayscale, weighing red, green and blue as Rec. 601 does. The
+    /// alpha channel is discarded.
+    Gray8,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            PixelFormat::Bgr24 => 3,
+            PixelFormat::Bgra32 => 4,
+            PixelFormat::Gray8 => 1,
+        }
+    }
+
+    pub(crate) fn pixel_depth(&self) -> PixelDepth {
+        match self {
+            PixelFormat::Bgr24 => PixelDepth::B24,
+            PixelFormat::Bgra32 => PixelDepth::B32,
+            PixelFormat::Gray8 => PixelDepth::B8,
+        }
+    }
+
+    pub(crate) fn image_type(&self, rle: bool) -> ImageType {
+        match (self, rle) {
+            (PixelFormat::Gray8, false) => ImageType::Grayscale,
+            (PixelFormat::Gray8, true) => ImageType::RleGrayscale,
+            (_, false) => ImageType::TrueColor,
+            (_, true) => ImageType::RleTrueColor,
+        }
+    }
+
+    pub(crate) fn alpha_depth(&self) -> u8 {
+        match self {
+            PixelFormat::Bgr24 | PixelFormat::Gray8 => 0,
+            PixelFormat::Bgra32 => 8,
+        }
+    }
+
+    /// Converts a row of 32-bit pixels into `out`, which holds as many pixels
+    /// in this format.
+    pub(crate) fn convert_row(&self, row: &[u8], out: &mut [u8]) {
+        match self {
+            PixelFormat::Bgr24 => {
+                let pixels: &[Bgra8] = bytemuck::cast_slice(row);
+                for (pixel, out) in pixels.iter().zip(out.chunks_exact_mut(3)) {
+                    out.copy_from_slice(&[pixel.b, pixel.g, pixel.r]);
+                }
+            }
+            PixelFormat::Bgra32 => out.copy_from_slice(row),
+            PixelFormat::Gray8 => {
+                let pixels: &[Bgra8] = bytemuck::cast_slice(row);
+                for (pixel, out) in pixels.iter().zip(out) {
+                    *out = luma(pixel);
+                }
+            }
+        }
+    }
+}
+
+/// The Rec. 601 luma of `pixel`, with weights scaled to sum to 256.
+fn luma(pixel: &Bgra8) -> u8 {
+    let sum = 77 * pixel.r as u32 + 150 * pixel.g as u32 + 29 * pixel.b as u32;
+
+    ((sum + 128) >> 8) as u8
+}
+
+/// The default for [`EncodeOptions::chunk_size`].
+pub(crate) const DEFAULT_CHUNK_SIZE: usize = 1 << 20;
+
+/// Options controlling how the pixel data is encoded.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct EncodeOptions {
+    pub pixel_format: PixelFormat,
+    /// Whether to compress the pixel data with run-length encoding.
+    pub rle: bool,
+    /// Whether to write a scan line table, which lets readers seek directly to
+    /// any row of compressed pixel data.
+    pub scan_line_table: bool,
+    /// The number of bytes of encoded pixel data collected before they are
+    /// written, 1 MiB by default.
+    ///
+    /// The pixel data is converted and compressed a chunk at a time, so the
+    /// memory used besides the image itself is proportional to the chunk
+    /// size, however large the image is. A chunk always holds at least one
+    /// row, and a scan line table takes another 8 bytes per row.
+    pub chunk_size: usize,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            pixel_format: PixelFormat::default(),
+            rle: false,
+            scan_line_table: false,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+}
+
+impl Image {
+    /// Writes the image, encoding the pixel data as described by `options`.
+    ///
+    /// The pixels are always written top-to-bottom and left-to-right. Images
+    /// with zero width or height cannot be stored in a TGA file and are
+    /// rejected, as are images whose data is too short for their dimensions
+    /// with [`Error::LengthMismatch`]. With run-length encoding, a file too
+    /// large for the 32-bit offsets of the format is only detected once the
+    /// pixel data has been written.
+    pub fn write_with_options<T: Write>(&self, w: &mut T, options: &EncodeOptions) -> Result<()> {
+        self.encode(w, options, None)
+    }
+
+    /// The size in bytes of the file [`Image::write_with_options`] writes with
+    /// `options`, or `None` if it does not fit into a `usize`.
+    ///
+    /// With run-length encoding the pixel data is assumed to compress as
+    /// badly as possible, so the actual file may be smaller.
+    pub fn encoded_size(&self, options: &EncodeOptions) -> Option<usize> {
+        let bytes_per_pixel = options.pixel_format.bytes_per_pixel();
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let row_size = if options.rle {
+            rle::max_encoded_row_size(width, bytes_per_pixel)?
+        } else {
+            width.checked_mul(bytes_per_pixel)?
+        };
+
+        let mut size = (HEADER_SIZE + self.id.len()).checked_add(row_size.checked_mul(height)?)?;
+        if !self.developer_tags.is_empty() {
+            size = size
+                .checked_add(developer::payload_size(&self.developer_tags))?
+                .checked_add(developer::directory_size(&self.developer_tags))?;
+        }
+        if self.has_extension_area(options) {
+            if self.color_correction_table().is_some() {
+                size = size.checked_add(extension::COLOR_CORRECTION_TABLE_SIZE)?;
+            }
+            if let Some(stamp) = &self.postage_stamp {
+                size = size.checked_add(
+                    2 + stamp.width as usize * bytes_per_pixel * stamp.height as usize,
+                )?;
+            }
+            if options.scan_line_table {
+                size = size.checked_add(height.checked_mul(4)?)?;
+            }
+            let extra = self
+                .extension_area
+                .as_ref()
+                .map_or(0, |extension_area| extension_area.extra.len());
+            size = size.checked_add(extension::EXTENSION_SIZE + extra)?;
+        }
+
+        size.checked_add(FOOTER_SIZE)
+    }
+
+    /// Writes the image as uncompressed 32-bit pixels like [`Image::write_to`],
+    /// but hands the header, ID, pixel data, trailing areas and footer to
+    /// [`Write::write_vectored`] as separate buffers.
+    ///
+    /// The pixel data is passed on as it is stored in the image, without
+    /// being copied, which saves a copy and several calls on unbuffered
+    /// writers such as a [`File`](std::fs::File). Rows are passed one by one
+    /// if the image has row padding.
+    ///
+    /// Only available with the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn write_vectored_to<T: Write>(&self, w: &mut T) -> Result<()> {
+        self.pixels().check_length()?;
+        let options = EncodeOptions::default();
+        let layout = self.layout(&options, None, None)?;
+        let mut areas = Vec::new();
+        self.write_areas(&mut areas, &options, &layout, None)?;
+
+        let header = layout.header.to_bytes();
+        let footer = layout.footer.to_bytes();
+        let row_size = Image::effective_size(self.width, 1);
+        let mut slices = vec![IoSlice::new(&header), IoSlice::new(&self.id)];
+        if self.stride == row_size {
+            slices.push(IoSlice::new(&self.data[..row_size * self.height as usize]));
+        } else {
+            slices.extend((0..self.height as usize).map(|y| IoSlice::new(self.row(y))));
+        }
+        slices.push(IoSlice::new(&areas));
+        slices.push(IoSlice::new(&footer));
+        write_all_vectored(w, &mut slices)?;
+
+        Ok(())
+    }
+
+    /// Writes `height` rows of `width` 32-bit pixels that start every `stride`
+    /// bytes at `data`, encoding them as described by `options`.
+    ///
+    /// This writes pixels held in memory the caller owns, such as a mapped
+    /// staging buffer, without copying them into an [`Image`] first. The file
+    /// has no ID, extension area or developer area, and is otherwise the same
+    /// as the one [`Image::write_with_options`] writes. Images with zero width
+    /// or height are rejected.
+    ///
+    /// # Safety
+    ///
+    /// Unless `width` or `height` is zero, `data` must be valid for reads of
+    /// `stride * (height - 1) + 4 * width` bytes, which must not be written
+    /// to until this returns.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stride` is smaller than a row of `width` pixels.
+    pub unsafe fn write_raw_parts<T: Write>(
+        w: &mut T,
+        data: *const u8,
+        width: u16,
+        height: u16,
+        stride: usize,
+        options: &EncodeOptions,
+    ) -> Result<()> {
+        let row_size = Image::effective_size(width, 1);
+        assert!(
+            stride >= row_size,
+            "stride must hold at least one row of pixels"
+        );
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidDimensions("image has zero width or height"));
+        }
+
+        let length = Image::strided_size(width, height, stride).ok_or(Error::TooLarge("image"))?;
+        let pixels = Pixels {
+            // SAFETY: The caller guarantees that `data` is valid for reads of
+            // `length` bytes that are not written to during the call.
+            data: unsafe { slice::from_raw_parts(data, length) },
+            width,
+            height,
+            stride,
+        };
+
+        // The image only describes what is written besides the pixels.
+        Image::without_pixels(width, height).encode_pixels(pixels, w, options, None)
+    }
+
+    /// Writes the image, copying the extension area from `raw_extension_area`
+    /// instead of serializing [`Image::extension_area`] if it is given.
+    ///
+    /// The offsets held by the copied extension area are updated to match the
+    /// new file.
+    pub(crate) fn encode<T: Write>(
+        &self,
+        w: &mut T,
+        options: &EncodeOptions,
+        raw_extension_area: Option<&[u8]>,
+    ) -> Result<()> {
+        self.encode_pixels(self.pixels(), w, options, raw_extension_area)
+    }
+
+    /// Like [`Image::encode`], but writes `pixels` instead of the pixels of
+    /// the image.
+    fn encode_pixels<T: Write>(
+        &self,
+        pixels: Pixels<'_>,
+        w: &mut T,
+        options: &EncodeOptions,
+        raw_extension_area: Option<&[u8]>,
+    ) -> Result<()> {
+        pixels.check_length()?;
+        if !options.rle {
+            // The layout is known up front, so a file too large for its
+            // offsets is rejected before anything is written.
+            let layout = self.layout(options, None, raw_extension_area)?;
+            layout.header.write_to(w)?;
+            w.write_all(&self.id)?;
+            pixels.write_raw(w, options.pixel_format, options.chunk_size)?;
+
+            return self.finish(w, options, &layout, raw_extension_area);
+        }
+
+        self.header(options)?.write_to(w)?;
+        w.write_all(&self.id)?;
+        let (length, scan_lines) = pixels.write_rle(w, options)?;
+        let layout = self.layout(options, Some((length, &scan_lines)), raw_extension_area)?;
+
+        self.finish(w, options, &layout, raw_extension_area)
+    }
+
+    /// Like [`Image::encode`], but takes the pixel data, already encoded as
+    /// described by `options`, from `blocks` of consecutive rows. Each block
+    /// comes with the length of each of its rows.
+    #[cfg(feature = "std")]
+    pub(crate) fn encode_blocks<T: Write>(
+        &self,
+        w: &mut T,
+        options: &EncodeOptions,
+        raw_extension_area: Option<&[u8]>,
+        blocks: impl IntoIterator<Item = (Vec<u8>, Vec<usize>)>,
+    ) -> Result<()> {
+        self.header(options)?.write_to(w)?;
+        w.write_all(&self.id)?;
+        let mut length = 0;
+        let mut scan_lines = Vec::new();
+        for (data, row_lengths) in blocks {
+            for row_length in row_lengths {
+                if options.scan_line_table {
+                    scan_lines.push(length);
+                }
+                length += row_length;
+            }
+            w.write_all(&data)?;
+        }
+
+        let compressed = options.rle.then_some((length, &scan_lines[..]));
+        let layout = self.layout(options, compressed, raw_extension_area)?;
+
+        self.finish(w, options, &layout, raw_extension_area)
+    }
+
+    /// Writes everything after the pixel data.
+    fn finish<T: Write>(
+        &self,
+        w: &mut T,
+        options: &EncodeOptions,
+        layout: &Layout,
+        raw_extension_area: Option<&[u8]>,
+    ) -> Result<()> {
+        self.write_areas(w, options, layout, raw_extension_area)?;
+        layout.footer.write_to(w)?;
+
+        Ok(())
+    }
+
+    /// The header of the file written with `options`.
+    fn header(&self, options: &EncodeOptions) -> Result<Header> {
+        if self.width == 0 || self.height == 0 {
+            return Err(Error::InvalidDimensions("image has zero width or height"));
+        }
+
+        let mut header = Header::new(self.width, self.height, options);
+        header.id_length = self.id.len() as u8;
+
+        Ok(header)
+    }
+
+    /// Works out where everything is written, given the length of the
+    /// run-length encoded pixel data and the offsets of its rows if it is
+    /// `compressed`. The offsets are only needed for a scan line table.
+    fn layout(
+        &self,
+        options: &EncodeOptions,
+        compressed: Option<(usize, &[usize])>,
+        raw_extension_area: Option<&[u8]>,
+    ) -> Result<Layout> {
+        let header = self.header(options)?;
+        let format = options.pixel_format;
+        let height = self.height as usize;
+        let row_size = self.width as usize * format.bytes_per_pixel();
+        let pixel_data_size = (row_size as u64) * height as u64;
+        let scan_lines = match compressed {
+            Some((_, scan_lines)) => scan_lines.to_vec(),
+            None => (0..height).map(|y| y * row_size).collect(),
+        };
+
+        // Offsets are computed in 64 bits so that they cannot overflow before
+        // being checked against the 32-bit offsets the format allows.
+        let pixels_start = (HEADER_SIZE + self.id.len()) as u64;
+        let pixels_end =
+            pixels_start + compressed.map_or(pixel_data_size, |(length, _)| length as u64);
+        let mut offset = pixels_end;
+        let mut footer = Footer::default();
+        if !self.developer_tags.is_empty() {
+            offset += developer::payload_size(&self.developer_tags) as u64;
+            footer.developer_offset = file_offset(offset)?;
+            offset += developer::directory_size(&self.developer_tags) as u64;
+        }
+
+        let has_extension_area = self.has_extension_area(options) || raw_extension_area.is_some();
+        let mut color_correction_offset = 0;
+        let mut postage_stamp_offset = 0;
+        let mut scan_line_offset = 0;
+        if has_extension_area {
+            if self.color_correction_table().is_some() {
+                color_correction_offset = file_offset(offset)?;
+                offset += extension::COLOR_CORRECTION_TABLE_SIZE as u64;
+            }
+            if let Some(stamp) = &self.postage_stamp {
+                postage_stamp_offset = file_offset(offset)?;
+                offset +=
+                    2 + stamp.width as u64 * format.bytes_per_pixel() as u64 * stamp.height as u64;
+            }
+            if options.scan_line_table {
+                scan_line_offset = file_offset(offset)?;
+                offset += 4 * height as u64;
+            }
+            footer.extension_offset = file_offset(offset)?;
+        }
+
+        Ok(Layout {
+            header,
+            footer,
+            pixels_start,
+            pixels_end,
+            scan_lines,
+            has_extension_area,
+            color_correction_offset,
+            postage_stamp_offset,
+            scan_line_offset,
+        })
+    }
+
+    /// Writes everything between the pixel data and the footer.
+    fn write_areas<T: Write>(
+        &self,
+        w: &mut T,
+        options: &EncodeOptions,
+        layout: &Layout,
+        raw_extension_area: Option<&[u8]>,
+    ) -> Result<()> {
+        if !self.developer_tags.is_empty() {
+            let directory_offset = file_offset(layout.pixels_end)?;
+            developer::write_developer_area(w, &self.developer_tags, directory_offset)?;
+        }
+        if !layout.has_extension_area {
+            return Ok(());
+        }
+
+        if let Some(table) = self.color_correction_table() {
+            table.write_to(w)?;
+        }
+        if let Some(stamp) = &self.postage_stamp {
+            w.write_u8(stamp.width as u8)?;
+            w.write_u8(stamp.height as u8)?;
+            stamp
+                .pixels()
+                .write_raw(w, options.pixel_format, options.chunk_size)?;
+        }
+        if options.scan_line_table {
+            for &line in &layout.scan_lines {
+                w.write_u32::<LittleEndian>(file_offset(layout.pixels_start + line as u64)?)?;
+            }
+        }
+
+        match raw_extension_area {
+            Some(raw) => {
+                let mut raw = raw.to_vec();
+                extension::patch_offsets(
+                    &mut raw,
+                    layout.color_correction_offset,
+                    layout.postage_stamp_offset,
+                    layout.scan_line_offset,
+                );
+                w.write_all(&raw)?;
+            }
+            None => ExtensionArea {
+                color_correction_offset: layout.color_correction_offset,
+                postage_stamp_offset: layout.postage_stamp_offset,
+                scan_line_offset: layout.scan_line_offset,
+                ..self.extension_area.clone().unwrap_or_default()
+            }
+            .write_to(w)?,
+        }
+
+        Ok(())
+    }
+
+    /// Whether an extension area is written, either because the image has one
+    /// or to hold the areas it points at.
+    fn has_extension_area(&self, options: &EncodeOptions) -> bool {
+        self.extension_area.is_some() || self.postage_stamp.is_some() || options.scan_line_table
+    }
+
+    fn color_correction_table(&self) -> Option<&ColorCorrectionTable> {
+        self.extension_area
+            .as_ref()
+            .and_then(|extension_area| extension_area.color_correction_table.as_ref())
+    }
+
+    fn pixels(&self) -> Pixels<'_> {
+        Pixels {
+            data: &self.data,
+            width: self.width,
+            height: self.height,
+            stride: self.stride,
+        }
+    }
+}
+
+/// The 32-bit pixels being encoded, borrowed from an image or from memory the
+/// caller owns.
+#[derive(Copy, Clone)]
+struct Pixels<'a> {
+    data: &'a [u8],
+    width: u16,
+    height: u16,
+    /// The distance in bytes between the starts of consecutive rows.
+    stride: usize,
+}
+
+impl Pixels<'_> {
+    /// Checks that the data holds every row of pixels.
+    fn check_length(&self) -> Result<()> {
+        let expected = Image::strided_size(self.width, self.height, self.stride)
+            .ok_or(Error::TooLarge("image"))?;
+        if self.data.len() < expected {
+            return Err(Error::LengthMismatch {
+                expected,
+                actual: self.data.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The pixels of row `y`, excluding padding.
+    fn row(&self, y: usize) -> &[u8] {
+        let start = y * self.stride;
+
+        &self.data[start..start + Image::effective_size(self.width, 1)]
+    }
+
+    /// Writes the pixel data uncompressed and without any row padding,
+    /// converting chunks of about `chunk_size` bytes at a time.
+    fn write_raw<T: Write>(
+        &self,
+        w: &mut T,
+        format: PixelFormat,
+        chunk_size: usize,
+    ) -> io::Result<()> {
+        if format == PixelFormat::Bgra32 && self.stride == Image::effective_size(self.width, 1) {
+            return w.write_all(&self.data[..self.stride * self.height as usize]);
+        }
+
+        let row_size = self.width as usize * format.bytes_per_pixel();
+        let height = self.height as usize;
+        let rows_per_chunk = (chunk_size / row_size.max(1)).max(1);
+        let mut chunk = vec![0; height.min(rows_per_chunk) * row_size];
+        for first in (0..height).step_by(rows_per_chunk) {
+            let last = height.min(first + rows_per_chunk);
+            let chunk = &mut chunk[..(last - first) * row_size];
+            parallel::for_each_row(chunk, row_size, |y, row| {
+                format.convert_row(self.row(first + y), row);
+            });
+            w.write_all(chunk)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the pixel data run-length encoded, a chunk of at least
+    /// `options.chunk_size` bytes at a time unless the data ends first.
+    /// Returns the length of the data and, for a scan line table, the offset
+    /// of each row.
+    fn write_rle<T: Write>(
+        &self,
+        w: &mut T,
+        options: &EncodeOptions,
+    ) -> io::Result<(usize, Vec<usize>)> {
+        let format = options.pixel_format;
+        let mut row = vec![0; self.width as usize * format.bytes_per_pixel()];
+        let mut chunk = Vec::new();
+        let mut length = 0;
+        let mut scan_lines = Vec::new();
+        for y in 0..self.height as usize {
+            if options.scan_line_table {
+                scan_lines.push(length + chunk.len());
+            }
+
+            format.convert_row(self.row(y), &mut row);
+            rle::encode_row(&row, format.bytes_per_pixel(), &mut chunk);
+            if chunk.len() >= options.chunk_size {
+                w.write_all(&chunk)?;
+                length += chunk.len();
+                chunk.clear();
+            }
+        }
+        w.write_all(&chunk)?;
+        length += chunk.len();
+
+        Ok((length, scan_lines))
+    }
+}
+
+/// Where the parts of an encoded file go.
+struct Layout {
+    header: Header,
+    footer: Footer,
+    pixels_start: u64,
+    pixels_end: u64,
+    /// The offsets of the rows from the start of the pixel data.
+    scan_lines: Vec<usize>,
+    has_extension_area: bool,
+    color_correction_offset: u32,
+    postage_stamp_offset: u32,
+    scan_line_offset: u32,
+}
+
+/// Writes all of `slices`, like the unstable `Write::write_all_vectored`.
+#[cfg(feature = "std")]
+fn write_all_vectored<T: Write>(w: &mut T, mut slices: &mut [IoSlice<'_>]) -> io::Result<()> {
+    IoSlice::advance_slices(&mut slices, 0);
+    while !slices.is_empty() {
+        match w.write_vectored(slices) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(length) => IoSlice::advance_slices(&mut slices, length),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts a position in the file to the 32-bit offsets used by the footer and
+/// the areas it points at.
+fn file_offset(offset: u64) -> Result<u32> {
+    u32::try_from(offset).map_err(|_| Error::TooLarge("file"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_data_is_rejected_before_writing() {
+        // The constructors reject such data, so it has to be set directly.
+        let image = Image {
+            data: vec![0; 8],
+            ..Image::without_pixels(4, 4)
+        };
+        for rle in [false, true] {
+            let options = EncodeOptions {
+                rle,
+                ..EncodeOptions::default()
+            };
+            let mut file = Vec::new();
+            let result = image.write_with_options(&mut file, &options);
+
+            assert!(matches!(
+                result,
+                Err(Error::LengthMismatch {
+                    expected: 64,
+                    actual: 8
+                })
+            ));
+            assert!(file.is_empty());
+        }
+    }
+
+    #[test]
+    fn only_the_pixels_of_longer_data_are_written() {
+        let image = Image::new(2, 2, vec![7; 20]).unwrap();
+        let mut file = Vec::new();
+        image.write_to(&mut file).unwrap();
+
+        assert_eq!(file.len(), HEADER_SIZE + 16 + FOOTER_SIZE);
+    }
+
+    #[test]
+    fn gray_8_writes_the_luma_as_a_grayscale_image() {
+        let row = [0, 0, 0, 9, 255, 255, 255, 0, 0, 255, 0, 255, 255, 0, 0, 255];
+        let mut out = [0; 4];
+        PixelFormat::Gray8.convert_row(&row, &mut out);
+        assert_eq!(out, [0, 255, 149, 29]);
+
+        let image = Image::new(2, 2, row.to_vec()).unwrap();
+        for (rle, image_type) in [(false, 3), (true, 11)] {
+            let options = EncodeOptions {
+                pixel_format: PixelFormat::Gray8,
+                rle,
+                ..EncodeOptions::default()
+            };
+            let mut file = Vec::new();
+            image.write_with_options(&mut file, &options).unwrap();
+
+            assert_eq!(file[2], image_type);
+            assert_eq!(file[16], 8);
+            assert_eq!(file[17] & 0x0f, 0);
+        }
+    }
+
+    /// A writer recording the length of every write.
+    #[cfg(feature = "std")]
+    #[derive(Default)]
+    struct Recorder {
+        data: Vec<u8>,
+        writes: Vec<usize>,
+    }
+
+    #[cfg(feature = "std")]
+    impl Write for Recorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.writes.push(buf.len());
+            self.data.extend_from_slice(buf);
+
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn pixel_data_is_written_a_chunk_at_a_time() {
+        let data = (0..16 * 64 * 4u32)
+            .map(|index| (index * 7 / 5) as u8)
+            .collect();
+        let image = Image::new(16, 64, data).unwrap();
+        for rle in [false, true] {
+            let options = EncodeOptions {
+                pixel_format: PixelFormat::Bgr24,
+                rle,
+                ..EncodeOptions::default()
+            };
+            let mut expected = Vec::new();
+            image.write_with_options(&mut expected, &options).unwrap();
+
+            let row_size = if rle {
+                rle::max_encoded_row_size(16, 3).unwrap()
+            } else {
+                16 * 3
+            };
+            for chunk_size in [0, 1, 100, 1000] {
+                let mut recorder = Recorder::default();
+                let options = EncodeOptions {
+                    chunk_size,
+                    ..options
+                };
+                image.write_with_options(&mut recorder, &options).unwrap();
+                assert_eq!(recorder.data, expected, "chunks of {chunk_size} bytes");
+
+                let largest = recorder.writes.iter().max().unwrap();
+                let limit = if rle {
+                    chunk_size + row_size
+                } else {
+                    chunk_size.max(row_size)
+                };
+                assert!(*largest <= limit, "chunks of {chunk_size} bytes");
+            }
+        }
+    }
+}
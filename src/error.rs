@@ -0,0 +1,234 @@
+use alloc::boxed::Box;
+use core::fmt;
+
+use thiserror::Error;
+
+use crate::io::{self, Read};
+
+/// The result of encoding or decoding an image.
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+/// An error raised while encoding or decoding an image.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading from or writing to the underlying stream failed.
+    #[error("I/O error")]
+    Io(#[from] io::Error),
+    /// The file holds a kind of image this crate cannot decode.
+    #[error("unsupported image type {image_type} with {pixel_depth}-bit pixels")]
+    UnsupportedImageType { image_type: u8, pixel_depth: u8 },
+    /// The image type needs a color map that is not declared, or does not use
+    /// the one that is.
+    #[error("color map type {color_map_type} does not match image type {image_type}")]
+    ColorMapMismatch { color_map_type: u8, image_type: u8 },
+    /// The color map declared in the header does not fill the space before the
+    /// pixel data.
+    #[error("color map declares {declared} bytes but {actual} are stored")]
+    ColorMapLength { declared: u64, actual: u64 },
+    /// A header field holds a value the format does not allow.
+    #[error("invalid header: {0}")]
+    InvalidHeader(&'static str),
+    /// Image or tile dimensions are invalid for the requested operation.
+    #[error("invalid dimensions: {0}")]
+    InvalidDimensions(&'static str),
+    /// A pixel buffer does not have the length its dimensions call for.
+    #[error("expected {expected} bytes of pixel data but got {actual}")]
+    LengthMismatch { expected: usize, actual: usize },
+    /// The run-length encoded pixel data is corrupted.
+    #[error("corrupt run-length encoded data: {0}")]
+    CorruptRle(&'static str),
+    /// The TGA 2.0 footer is malformed.
+    #[error("invalid footer: {0}")]
+    InvalidFooter(&'static str),
+    /// The file has no TGA 2.0 footer although one was required.
+    #[error("file has no TGA 2.0 footer")]
+    MissingFooter,
+    /// The extension area is malformed.
+    #[error("invalid extension area: {0}")]
+    InvalidExtensionArea(&'static str),
+    /// An offset points at data that does not lie between the pixel data and
+    /// the footer.
+    #[error("offset points outside the space between the pixel data and the footer")]
+    InvalidOffset,
+    /// The developer area is malformed.
+    #[error("invalid developer area: {0}")]
+    InvalidDeveloperArea(&'static str),
+    /// A value read from the file exceeds a limit set by the decode options.
+    #[error("{0} exceeds the configured limit")]
+    LimitExceeded(&'static str),
+    /// A value does not fit into the field the format stores it in.
+    #[error("{0} is too large")]
+    TooLarge(&'static str),
+    /// A BMP file is malformed.
+    #[error("invalid BMP file: {0}")]
+    InvalidBmp(&'static str),
+    /// A BMP file stores its pixels in a way this crate cannot read.
+    #[error("unsupported BMP file with {bit_count}-bit pixels and compression {compression}")]
+    UnsupportedBmp { compression: u32, bit_count: u16 },
+    /// A row outside the image was requested.
+    #[error("row {row} is outside an image {height} pixels tall")]
+    RowOutOfBounds { row: u16, height: u16 },
+    /// Decoding failed at `offset` bytes from the start of the file while
+    /// reading the part of the file described by `context`. The error that
+    /// occurred is available as the [`source`](core::error::Error::source).
+    #[error("decoding failed at byte {offset} while reading {context}")]
+    Parse {
+        offset: u64,
+        context: Context,
+        source: Box<Error>,
+    },
+}
+
+/// The part of a file being read when decoding failed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum Context {
+    /// A header field, named as in the spec.
+    Header(&'static str),
+    ImageId,
+    /// The pixel data as a whole.
+    PixelData,
+    /// Uncompressed pixel data of a row, counting rows in the order they are
+    /// stored.
+    Row(u16),
+    /// A run-length encoded packet, counting packets from where decoding
+    /// started.
+    Packet(u64),
+    Footer,
+    /// An extension area field, named as in the spec.
+    ExtensionArea(&'static str),
+    ColorCorrectionTable,
+    PostageStamp,
+    ScanLineTable,
+    DeveloperDirectory,
+    /// The data of the developer tag with the given ID.
+    DeveloperTag(u16),
+}
+
+impl Error {
+    /// The offset from the start of the file at which decoding failed, if
+    /// known.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Error::Parse { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// The part of the file being read when decoding failed, if known.
+    pub fn context(&self) -> Option<Context> {
+        match self {
+            Error::Parse { context, .. } => Some(*context),
+            _ => None,
+        }
+    }
+
+    /// The error stripped of where it happened.
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Parse { source, .. } => source.root(),
+            error => error,
+        }
+    }
+
+    /// Attaches the position of the error, unless a more precise one is
+    /// already known.
+    pub(crate) fn at(self, offset: u64, context: Context) -> Error {
+        match self {
+            Error::Parse { .. } => self,
+            error => Error::Parse {
+                offset,
+                context,
+                source: Box::new(error),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Context::Header(field) => write!(f, "header field \"{field}\""),
+            Context::ImageId => f.write_str("image ID"),
+            Context::PixelData => f.write_str("pixel data"),
+            Context::Row(row) => write!(f, "row {row}"),
+            Context::Packet(index) => write!(f, "RLE packet {index}"),
+            Context::Footer => f.write_str("footer"),
+            Context::ExtensionArea(field) => write!(f, "extension area field \"{field}\""),
+            Context::ColorCorrectionTable => f.write_str("color correction table"),
+            Context::PostageStamp => f.write_str("postage stamp"),
+            Context::ScanLineTable => f.write_str("scan line table"),
+            Context::DeveloperDirectory => f.write_str("developer directory"),
+            Context::DeveloperTag(tag) => write!(f, "developer tag {tag}"),
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        let kind = match error.root() {
+            Error::Io(error) => error.kind(),
+            Error::InvalidDimensions(_)
+            | Error::LengthMismatch { .. }
+            | Error::TooLarge(_)
+            | Error::RowOutOfBounds { .. } => io::ErrorKind::InvalidInput,
+            _ => io::ErrorKind::InvalidData,
+        };
+
+        match error {
+            Error::Io(error) => error,
+            error => io::Error::new(kind, error),
+        }
+    }
+}
+
+/// Passes reads through to another reader while counting the bytes read, so
+/// that errors can be reported at the offset they happened at.
+pub(crate) struct CountingReader<'a, R> {
+    inner: &'a mut R,
+    count: u64,
+}
+
+impl<'a, R: Read> CountingReader<'a, R> {
+    pub(crate) fn new(inner: &'a mut R) -> Self {
+        CountingReader { inner, count: 0 }
+    }
+
+    pub(crate) fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<R> io::Sealed for CountingReader<'_, R> {}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = self.inner.read(buf)?;
+        self.count += length as u64;
+
+        Ok(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use core::error::Error as _;
+
+    #[test]
+    fn io_errors_are_reported_as_the_source() {
+        let error = Error::from(io::Error::from(io::ErrorKind::UnexpectedEof));
+        assert_eq!(error.to_string(), "I/O error");
+
+        let source = error.source().unwrap();
+        let source = source.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::UnexpectedEof);
+
+        let error = error.at(4, Context::Header("width"));
+        assert_eq!(io::Error::from(error).kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,779 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use bytemuck::{Pod, Zeroable};
+use byteorder::LittleEndian;
+
+use crate::error::CountingReader;
+use crate::io::{self, Read, ReadBytesExt, Write, WriteBytesExt};
+use crate::{Bgra8, Context, Error, Result};
+
+pub(crate) const EXTENSION_SIZE: usize = 495;
+pub(crate) const AUTHOR_NAME_POSITION: u64 = 2;
+pub(crate) const COMMENTS_POSITION: u64 = 43;
+pub(crate) const TIMESTAMP_POSITION: u64 = 367;
+pub(crate) const COLOR_CORRECTION_OFFSET_POSITION: usize = 482;
+pub(crate) const SHORT_FIELD_SIZE: usize = 41;
+const COMMENT_LINE_SIZE: usize = 81;
+const COMMENT_LINE_COUNT: usize = 4;
+pub(crate) const COLOR_CORRECTION_ENTRY_COUNT: usize = 256;
+pub(crate) const COLOR_CORRECTION_TABLE_SIZE: usize = COLOR_CORRECTION_ENTRY_COUNT * 8;
+
+/// A date and time as stored in the extension area.
+///
+/// All fields are zero if the stamp was not set.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timestamp {
+    pub month: u16,
+    pub day: u16,
+    pub year: u16,
+    pub hour: u16,
+    pub minute: u16,
+    pub second: u16,
+}
+
+/// The amount of time spent on the job the image belongs to.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JobTime {
+    pub hours: u16,
+    pub minutes: u16,
+    pub seconds: u16,
+}
+
+/// A fraction used for the pixel aspect ratio and gamma fields.
+///
+/// A denominator of zero means the value was not specified.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ratio {
+    pub numerator: u16,
+    pub denominator: u16,
+}
+
+/// The four comment lines of the extension area.
+///
+/// Each line is stored in a fixed 81-byte field, so lines are limited to 80
+/// bytes followed by a terminating NUL. The spec calls for ASCII; other UTF-8
+/// text is kept as long as it fits.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Comments {
+    lines: [String; COMMENT_LINE_COUNT],
+}
+
+impl Comments {
+    pub const LINE_COUNT: usize = COMMENT_LINE_COUNT;
+    pub const MAX_LINE_LENGTH: usize = COMMENT_LINE_SIZE - 1;
+
+    pub fn new() -> Self {
+        Comments::default()
+    }
+
+    /// Splits `text` into lines and stores the first four.
+    ///
+    /// Returns the comments and whether all of `text` could be stored
+    /// unchanged. Lines past the fourth are dropped, and long lines are
+    /// truncated as with [`Comments::set_line`].
+    pub fn from_text(text: &str) -> (Self, bool) {
+        let mut comments = Comments::new();
+        let mut lossless = true;
+
+        let mut lines = text.lines();
+        for index in 0..COMMENT_LINE_COUNT {
+            if let Some(line) = lines.next() {
+                lossless &= comments.set_line(index, line);
+            }
+        }
+        lossless &= lines.next().is_none();
+
+        (comments, lossless)
+    }
+
+    pub fn line(&self, index: usize) -> &str {
+        &self.lines[index]
+    }
+
+    pub fn lines(&self) -> &[String; COMMENT_LINE_COUNT] {
+        &self.lines
+    }
+
+    /// Sets line `index`, returning whether it could be stored unchanged.
+    ///
+    /// Lines are cut at the first NUL, since it would end the line in the
+    /// file, and truncated to [`Comments::MAX_LINE_LENGTH`] bytes without
+    /// splitting a character.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than [`Comments::LINE_COUNT`].
+    #[must_use = "the line may have been truncated"]
+    pub fn set_line(&mut self, index: usize, line: &str) -> bool {
+        let stored = fit_field(line, COMMENT_LINE_SIZE);
+        self.lines[index] = stored.to_owned();
+
+        stored.len() == line.len()
+    }
+}
+
+impl fmt::Display for Comments {
+    /// Formats the lines separated by newlines, omitting trailing empty lines.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = self
+            .lines
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(0, |index| index + 1);
+
+        for (index, line) in self.lines[..count].iter().enumerate() {
+            if index > 0 {
+                f.write_str("\n")?;
+            }
+            f.write_str(line)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The version of the software that created the image, such as 4.17b.
+///
+/// The file stores the version multiplied by 100, so `minor` is limited to two
+/// decimal digits and `major` to 655.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SoftwareVersion {
+    pub major: u16,
+    pub minor: u8,
+    pub letter: Option<char>,
+}
+
+impl SoftwareVersion {
+    /// Creates a version from the number and letter stored in the file.
+    ///
+    /// A letter of space or NUL means there is none.
+    pub fn from_raw(number: u16, letter: u8) -> Self {
+        SoftwareVersion {
+            major: number / 100,
+            minor: (number % 100) as u8,
+            letter: match letter {
+                b' ' | b'\0' => None,
+                letter => Some(letter as char),
+            },
+        }
+    }
+
+    /// Converts the version to the number and letter stored in the file.
+    ///
+    /// Out-of-range components saturate, and letters outside ASCII are
+    /// written as no letter.
+    pub fn to_raw(&self) -> (u16, u8) {
+        let number = self
+            .major
+            .saturating_mul(100)
+            .saturating_add(self.minor.min(99) as u16);
+        let letter = match self.letter {
+            Some(letter) if letter.is_ascii() => letter as u8,
+            _ => b' ',
+        };
+
+        (number, letter)
+    }
+}
+
+impl fmt::Display for SoftwareVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:02}", self.major, self.minor)?;
+        if let Some(letter) = self.letter {
+            write!(f, "{}", letter)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The error returned when parsing a [`SoftwareVersion`] fails.
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[error("invalid software version")]
+pub struct ParseSoftwareVersionError;
+
+impl FromStr for SoftwareVersion {
+    type Err = ParseSoftwareVersionError;
+
+    /// Parses versions such as `4`, `4.1`, `4.17` and `4.17b`.
+    ///
+    /// A single minor digit is read as tenths, so `4.1` equals `4.10`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, letter) = match s.char_indices().last() {
+            Some((index, letter)) if letter.is_ascii_alphabetic() => (&s[..index], Some(letter)),
+            _ => (s, None),
+        };
+        let (major, minor) = number.split_once('.').unwrap_or((number, "0"));
+
+        let is_decimal =
+            |digits: &str| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit());
+        if !is_decimal(major) || !is_decimal(minor) || minor.len() > 2 {
+            return Err(ParseSoftwareVersionError);
+        }
+
+        let major = match major.parse() {
+            Ok(major) if major <= u16::MAX / 100 => major,
+            _ => return Err(ParseSoftwareVersionError),
+        };
+        let minor = match minor.parse::<u8>() {
+            Ok(tenths) if minor.len() == 1 => tenths * 10,
+            Ok(minor) => minor,
+            Err(_) => return Err(ParseSoftwareVersionError),
+        };
+
+        Ok(SoftwareVersion {
+            major,
+            minor,
+            letter,
+        })
+    }
+}
+
+/// How the alpha channel of the image should be interpreted.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AttributesType {
+    /// There is no alpha data.
+    #[default]
+    NoAlpha,
+    /// The alpha data is undefined and can be ignored.
+    Undefined,
+    /// The alpha data is undefined but should be retained.
+    UndefinedKeep,
+    /// The alpha data is straight, unassociated alpha.
+    Useful,
+    /// The color channels are premultiplied by the alpha data.
+    Premultiplied,
+    /// A reserved or unassigned value.
+    Unknown(u8),
+}
+
+impl From<u8> for AttributesType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => AttributesType::NoAlpha,
+            1 => AttributesType::Undefined,
+            2 => AttributesType::UndefinedKeep,
+            3 => AttributesType::Useful,
+            4 => AttributesType::Premultiplied,
+            value => AttributesType::Unknown(value),
+        }
+    }
+}
+
+impl From<AttributesType> for u8 {
+    fn from(value: AttributesType) -> Self {
+        match value {
+            AttributesType::NoAlpha => 0,
+            AttributesType::Undefined => 1,
+            AttributesType::UndefinedKeep => 2,
+            AttributesType::Useful => 3,
+            AttributesType::Premultiplied => 4,
+            AttributesType::Unknown(value) => value,
+        }
+    }
+}
+
+/// A single entry of a [`ColorCorrectionTable`].
+///
+/// Slices of entries can be cast to and from `u16`s with `bytemuck`, in the
+/// same order as the file stores them.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Pod, Zeroable)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct ColorCorrectionEntry {
+    pub alpha: u16,
+    pub red: u16,
+    pub green: u16,
+    pub blue: u16,
+}
+
+/// A table mapping each 8-bit channel value to a corrected 16-bit value.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColorCorrectionTable {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::interop::color_correction_entries")
+    )]
+    pub entries: [ColorCorrectionEntry; COLOR_CORRECTION_ENTRY_COUNT],
+}
+
+impl Default for ColorCorrectionTable {
+    /// Creates a table that leaves every color unchanged.
+    fn default() -> Self {
+        let mut entries = [ColorCorrectionEntry::default(); COLOR_CORRECTION_ENTRY_COUNT];
+        for (value, entry) in entries.iter_mut().enumerate() {
+            let value = value as u16 * 257;
+            *entry = ColorCorrectionEntry {
+                alpha: value,
+                red: value,
+                green: value,
+                blue: value,
+            };
+        }
+
+        ColorCorrectionTable { entries }
+    }
+}
+
+impl ColorCorrectionTable {
+    pub fn read_from<T: Read>(r: &mut T) -> Result<Self> {
+        let mut entries = [ColorCorrectionEntry::default(); COLOR_CORRECTION_ENTRY_COUNT];
+        for entry in &mut entries {
+            *entry = ColorCorrectionEntry {
+                alpha: r.read_u16::<LittleEndian>()?,
+                red: r.read_u16::<LittleEndian>()?,
+                green: r.read_u16::<LittleEndian>()?,
+                blue: r.read_u16::<LittleEndian>()?,
+            };
+        }
+
+        Ok(ColorCorrectionTable { entries })
+    }
+
+    pub fn write_to<T: Write>(&self, w: &mut T) -> Result<()> {
+        for entry in &self.entries {
+            w.write_u16::<LittleEndian>(entry.alpha)?;
+            w.write_u16::<LittleEndian>(entry.red)?;
+            w.write_u16::<LittleEndian>(entry.green)?;
+            w.write_u16::<LittleEndian>(entry.blue)?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies the table to a color, keeping the upper 8 bits of each
+    /// corrected channel.
+    pub fn correct(&self, color: Bgra8) -> Bgra8 {
+        Bgra8 {
+            b: (self.entries[color.b as usize].blue >> 8) as u8,
+            g: (self.entries[color.g as usize].green >> 8) as u8,
+            r: (self.entries[color.r as usize].red >> 8) as u8,
+            a: (self.entries[color.a as usize].alpha >> 8) as u8,
+        }
+    }
+}
+
+/// The TGA 2.0 extension area.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtensionArea {
+    pub author_name: String,
+    pub comments: Comments,
+    pub timestamp: Timestamp,
+    pub job_name: String,
+    pub job_time: JobTime,
+    pub software_id: String,
+    pub software_version: SoftwareVersion,
+    pub key_color: Bgra8,
+    pub pixel_aspect_ratio: Ratio,
+    pub gamma: Ratio,
+    /// Offset of the color correction table from the start of the file.
+    pub color_correction_offset: u32,
+    /// Offset of the postage stamp image from the start of the file.
+    pub postage_stamp_offset: u32,
+    /// Offset of the scan line table from the start of the file.
+    pub scan_line_offset: u32,
+    pub attributes_type: AttributesType,
+    /// The color correction table pointed at by `color_correction_offset`.
+    ///
+    /// The table is stored separately from the extension area, so it is read
+    /// and written by [`Image`](crate::Image) rather than by
+    /// [`ExtensionArea::read_from`] and [`ExtensionArea::write_to`].
+    pub color_correction_table: Option<ColorCorrectionTable>,
+    /// Data following the standard fields, as written by later revisions of
+    /// the format or by other software. It is written back unchanged.
+    pub extra: Vec<u8>,
+}
+
+impl ExtensionArea {
+    pub fn read_from<T: Read>(r: &mut T) -> Result<Self> {
+        let size = r.read_u16::<LittleEndian>()?;
+        if (size as usize) < EXTENSION_SIZE {
+            return Err(Error::InvalidExtensionArea(
+                "size is smaller than the fields defined by the spec",
+            ));
+        }
+
+        let author_name = read_string(r, SHORT_FIELD_SIZE)?;
+        let mut comments = Comments::new();
+        for line in &mut comments.lines {
+            *line = read_string(r, COMMENT_LINE_SIZE)?;
+        }
+        let timestamp = Timestamp {
+            month: r.read_u16::<LittleEndian>()?,
+            day: r.read_u16::<LittleEndian>()?,
+            year: r.read_u16::<LittleEndian>()?,
+            hour: r.read_u16::<LittleEndian>()?,
+            minute: r.read_u16::<LittleEndian>()?,
+            second: r.read_u16::<LittleEndian>()?,
+        };
+        let job_name = read_string(r, SHORT_FIELD_SIZE)?;
+        let job_time = JobTime {
+            hours: r.read_u16::<LittleEndian>()?,
+            minutes: r.read_u16::<LittleEndian>()?,
+            seconds: r.read_u16::<LittleEndian>()?,
+        };
+        let software_id = read_string(r, SHORT_FIELD_SIZE)?;
+        let software_version =
+            SoftwareVersion::from_raw(r.read_u16::<LittleEndian>()?, r.read_u8()?);
+        let mut key_color = [0; 4];
+        r.read_exact(&mut key_color)?;
+        let pixel_aspect_ratio = Ratio {
+            numerator: r.read_u16::<LittleEndian>()?,
+            denominator: r.read_u16::<LittleEndian>()?,
+        };
+        let gamma = Ratio {
+            numerator: r.read_u16::<LittleEndian>()?,
+            denominator: r.read_u16::<LittleEndian>()?,
+        };
+        let color_correction_offset = r.read_u32::<LittleEndian>()?;
+        let postage_stamp_offset = r.read_u32::<LittleEndian>()?;
+        let scan_line_offset = r.read_u32::<LittleEndian>()?;
+        let attributes_type = AttributesType::from(r.read_u8()?);
+
+        let extra_size = size as u64 - EXTENSION_SIZE as u64;
+        let mut extra = Vec::with_capacity(extra_size as usize);
+        r.take(extra_size).read_to_end(&mut extra)?;
+        if extra.len() as u64 != extra_size {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        Ok(ExtensionArea {
+            author_name,
+            comments,
+            timestamp,
+            job_name,
+            job_time,
+            software_id,
+            software_version,
+            key_color: Bgra8::new(key_color[0], key_color[1], key_color[2], key_color[3]),
+            pixel_aspect_ratio,
+            gamma,
+            color_correction_offset,
+            postage_stamp_offset,
+            scan_line_offset,
+            attributes_type,
+            color_correction_table: None,
+            extra,
+        })
+    }
+
+    /// Writes the extension area.
+    ///
+    /// Text fields longer than their fixed size in the file are truncated.
+    pub fn write_to<T: Write>(&self, w: &mut T) -> Result<()> {
+        let size = u16::try_from(EXTENSION_SIZE + self.extra.len())
+            .map_err(|_| Error::TooLarge("extension area"))?;
+
+        w.write_u16::<LittleEndian>(size)?;
+        write_string(w, &self.author_name, SHORT_FIELD_SIZE)?;
+        write_comments(w, &self.comments)?;
+        write_timestamp(w, self.timestamp)?;
+        write_string(w, &self.job_name, SHORT_FIELD_SIZE)?;
+        w.write_u16::<LittleEndian>(self.job_time.hours)?;
+        w.write_u16::<LittleEndian>(self.job_time.minutes)?;
+        w.write_u16::<LittleEndian>(self.job_time.seconds)?;
+        write_string(w, &self.software_id, SHORT_FIELD_SIZE)?;
+        let (version_number, version_letter) = self.software_version.to_raw();
+        w.write_u16::<LittleEndian>(version_number)?;
+        w.write_u8(version_letter)?;
+        let Bgra8 { b, g, r, a } = self.key_color;
+        w.write_all(&[b, g, r, a])?;
+        w.write_u16::<LittleEndian>(self.pixel_aspect_ratio.numerator)?;
+        w.write_u16::<LittleEndian>(self.pixel_aspect_ratio.denominator)?;
+        w.write_u16::<LittleEndian>(self.gamma.numerator)?;
+        w.write_u16::<LittleEndian>(self.gamma.denominator)?;
+        w.write_u32::<LittleEndian>(self.color_correction_offset)?;
+        w.write_u32::<LittleEndian>(self.postage_stamp_offset)?;
+        w.write_u32::<LittleEndian>(self.scan_line_offset)?;
+        w.write_u8(self.attributes_type.into())?;
+        w.write_all(&self.extra)?;
+
+        Ok(())
+    }
+}
+
+/// The fields of the extension area and the offsets they start at.
+const FIELDS: [(u64, &str); 16] = [
+    (0, "extension size"),
+    (AUTHOR_NAME_POSITION, "author name"),
+    (COMMENTS_POSITION, "author comments"),
+    (TIMESTAMP_POSITION, "date/time stamp"),
+    (379, "job name/ID"),
+    (420, "job time"),
+    (426, "software ID"),
+    (467, "software version"),
+    (470, "key color"),
+    (474, "pixel aspect ratio"),
+    (478, "gamma value"),
+    (
+        COLOR_CORRECTION_OFFSET_POSITION as u64,
+        "color correction offset",
+    ),
+    (486, "postage stamp offset"),
+    (490, "scan line offset"),
+    (494, "attributes type"),
+    (EXTENSION_SIZE as u64, "extra data"),
+];
+
+/// Reads the extension area starting `offset` bytes into the file, reporting
+/// the field being read if that fails.
+pub(crate) fn read_extension_area<T: Read>(r: &mut T, offset: u64) -> Result<ExtensionArea> {
+    let mut r = CountingReader::new(r);
+    ExtensionArea::read_from(&mut r).map_err(|error| {
+        // The size is the only field that is checked after being read.
+        let position = match error {
+            Error::InvalidExtensionArea(_) => 0,
+            _ => r.count(),
+        };
+        let (_, field) = FIELDS
+            .iter()
+            .rev()
+            .find(|&&(start, _)| start <= position)
+            .copied()
+            .unwrap_or(FIELDS[0]);
+
+        error.at(offset + position, Context::ExtensionArea(field))
+    })
+}
+
+/// Reads a NUL-terminated string from a fixed-size field.
+fn read_string<T: Read>(r: &mut T, size: usize) -> io::Result<String> {
+    let mut field = vec![0; size];
+    r.read_exact(&mut field)?;
+
+    let length = field.iter().position(|&b| b == 0).unwrap_or(size);
+    Ok(String::from_utf8_lossy(&field[..length]).into_owned())
+}
+
+/// Writes a string into a fixed-size field, padding it with NULs.
+pub(crate) fn write_string<T: Write>(w: &mut T, value: &str, size: usize) -> io::Result<()> {
+    let value = fit_field(value, size);
+    let mut field = vec![0; size];
+    field[..value.len()].copy_from_slice(value.as_bytes());
+
+    w.write_all(&field)
+}
+
+pub(crate) fn write_comments<T: Write>(w: &mut T, comments: &Comments) -> io::Result<()> {
+    for line in &comments.lines {
+        write_string(w, line, COMMENT_LINE_SIZE)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn write_timestamp<T: Write>(w: &mut T, timestamp: Timestamp) -> io::Result<()> {
+    w.write_u16::<LittleEndian>(timestamp.month)?;
+    w.write_u16::<LittleEndian>(timestamp.day)?;
+    w.write_u16::<LittleEndian>(timestamp.year)?;
+    w.write_u16::<LittleEndian>(timestamp.hour)?;
+    w.write_u16::<LittleEndian>(timestamp.minute)?;
+    w.write_u16::<LittleEndian>(timestamp.second)?;
+
+    Ok(())
+}
+
+/// Returns the longest prefix of `value` that fits into a fixed-size field
+/// along with a terminating NUL, without splitting a character or including
+/// an embedded NUL.
+pub(crate) fn fit_field(value: &str, size: usize) -> &str {
+    let value = value.split('\0').next().unwrap_or_default();
+
+    let mut length = value.len().min(size - 1);
+    while !value.is_char_boundary(length) {
+        length -= 1;
+    }
+
+    &value[..length]
+}
+
+/// Overwrites the table offsets of a serialized extension area.
+pub(crate) fn patch_offsets(
+    raw: &mut [u8],
+    color_correction_offset: u32,
+    postage_stamp_offset: u32,
+    scan_line_offset: u32,
+) {
+    let offsets = [
+        color_correction_offset,
+        postage_stamp_offset,
+        scan_line_offset,
+    ];
+    let fields = raw[COLOR_CORRECTION_OFFSET_POSITION..].chunks_exact_mut(4);
+    for (field, offset) in fields.zip(offsets) {
+        field.copy_from_slice(&offset.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn extension_area() -> ExtensionArea {
+        let (comments, _) = Comments::from_text("first\nsecond");
+
+        ExtensionArea {
+            author_name: "Ada".to_owned(),
+            comments,
+            timestamp: Timestamp {
+                month: 10,
+                day: 14,
+                year: 2026,
+                hour: 9,
+                minute: 30,
+                second: 5,
+            },
+            job_name: "job".to_owned(),
+            job_time: JobTime {
+                hours: 1,
+                minutes: 2,
+                seconds: 3,
+            },
+            software_id: "tga".to_owned(),
+            software_version: "4.17b".parse().unwrap(),
+            key_color: Bgra8::new(1, 2, 3, 4),
+            pixel_aspect_ratio: Ratio {
+                numerator: 4,
+                denominator: 3,
+            },
+            gamma: Ratio {
+                numerator: 22,
+                denominator: 10,
+            },
+            color_correction_offset: 1000,
+            postage_stamp_offset: 2000,
+            scan_line_offset: 3000,
+            attributes_type: AttributesType::Premultiplied,
+            color_correction_table: None,
+            extra: vec![5, 6, 7],
+        }
+    }
+
+    #[test]
+    fn extension_areas_round_trip() {
+        let area = extension_area();
+        let mut raw = Vec::new();
+        area.write_to(&mut raw).unwrap();
+        assert_eq!(raw.len(), EXTENSION_SIZE + 3);
+        assert_eq!(raw[..2], [(EXTENSION_SIZE + 3) as u8, 1]);
+        assert_eq!(&raw[AUTHOR_NAME_POSITION as usize..][..4], b"Ada\0");
+
+        assert_eq!(ExtensionArea::read_from(&mut &raw[..]).unwrap(), area);
+    }
+
+    #[test]
+    fn patched_offsets_read_back() {
+        let mut raw = Vec::new();
+        extension_area().write_to(&mut raw).unwrap();
+        patch_offsets(&mut raw, 7, 8, 9);
+
+        let area = ExtensionArea::read_from(&mut &raw[..]).unwrap();
+        assert_eq!(
+            (
+                area.color_correction_offset,
+                area.postage_stamp_offset,
+                area.scan_line_offset
+            ),
+            (7, 8, 9)
+        );
+        assert_eq!(area.extra, [5, 6, 7]);
+    }
+
+    #[test]
+    fn errors_name_the_field_being_read() {
+        let mut raw = Vec::new();
+        extension_area().write_to(&mut raw).unwrap();
+
+        // Too small a size is reported at the size field.
+        let mut small = raw.clone();
+        small[..2].copy_from_slice(&494u16.to_le_bytes());
+        let error = read_extension_area(&mut &small[..], 100).unwrap_err();
+        assert!(matches!(error.root(), Error::InvalidExtensionArea(_)));
+        assert_eq!(error.offset(), Some(100));
+        assert_eq!(
+            error.context(),
+            Some(Context::ExtensionArea("extension size"))
+        );
+
+        // Running out of data is reported at the field cut short.
+        for (length, field) in [
+            (30, "author name"),
+            (480, "gamma value"),
+            (496, "extra data"),
+        ] {
+            let error = read_extension_area(&mut &raw[..length], 100).unwrap_err();
+            assert!(matches!(error.root(), Error::Io(_)), "{field}");
+            assert_eq!(error.context(), Some(Context::ExtensionArea(field)));
+        }
+    }
+
+    #[test]
+    fn text_fields_are_cut_to_fit() {
+        assert_eq!(fit_field("abc\0def", 10), "abc");
+        assert_eq!(fit_field("abcdef", 4), "abc");
+        // The two-byte character would not fit whole.
+        assert_eq!(fit_field("abé", 4), "ab");
+
+        let mut comments = Comments::new();
+        assert!(comments.set_line(0, &"x".repeat(Comments::MAX_LINE_LENGTH)));
+        assert!(!comments.set_line(1, &"y".repeat(Comments::MAX_LINE_LENGTH + 1)));
+        assert_eq!(comments.line(1).len(), Comments::MAX_LINE_LENGTH);
+
+        let (comments, lossless) = Comments::from_text("1\n2\n3\n4\n5");
+        assert!(!lossless);
+        assert_eq!(comments.to_string(), "1\n2\n3\n4");
+        assert_eq!(Comments::from_text("one\n\n").0.to_string(), "one");
+    }
+
+    #[test]
+    fn software_versions_parse_and_store() {
+        let version: SoftwareVersion = "4.17b".parse().unwrap();
+        assert_eq!(version.to_raw(), (417, b'b'));
+        assert_eq!(version.to_string(), "4.17b");
+        assert_eq!(SoftwareVersion::from_raw(417, b'b'), version);
+        assert_eq!("4.1".parse::<SoftwareVersion>().unwrap().minor, 10);
+        assert_eq!(SoftwareVersion::from_raw(400, b' ').letter, None);
+
+        for invalid in ["", "4.", ".5", "4.123", "656", "4.1.2", "x"] {
+            assert_eq!(
+                invalid.parse::<SoftwareVersion>(),
+                Err(ParseSoftwareVersionError),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn color_correction_tables_round_trip() {
+        let mut table = ColorCorrectionTable::default();
+        let color = Bgra8::new(10, 20, 30, 40);
+        assert_eq!(table.correct(color), color);
+
+        table.entries[10].blue = 0xff00;
+        let mut raw = Vec::new();
+        table.write_to(&mut raw).unwrap();
+        assert_eq!(raw.len(), COLOR_CORRECTION_TABLE_SIZE);
+
+        let table = ColorCorrectionTable::read_from(&mut &raw[..]).unwrap();
+        assert_eq!(table.correct(color), Bgra8::new(255, 20, 30, 40));
+        assert!(ColorCorrectionTable::read_from(&mut &raw[..100]).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! Flipping images in place.
+//!
+//! Both flips work on the pixel data where it is, without allocating. The
+//! vertical flip swaps pairs of rows a tile of at most [`TILE_SIZE`] bytes at a
+//! time, so that each step only touches two short stretches of memory however
+//! wide the image is, and the horizontal flip reverses each row in place. With
+//! the `rayon` feature, the rows are spread over the thread pool.
+
+use crate::{parallel, Bgra8, Image};
+
+/// The largest number of bytes swapped at once, a multiple of the size of a
+/// 32-bit pixel that keeps both halves of a swap well within the L1 cache.
+const TILE_SIZE: usize = 4096;
+
+impl Image {
+    /// Flips the image upside down, swapping each row with its mirror image
+    /// across the horizontal center line.
+    pub fn flip_vertical(&mut self) {
+        let row_size = Image::effective_size(self.width, 1);
+        let half = self.height as usize / 2;
+        if row_size == 0 || half == 0 {
+            return;
+        }
+
+        // The middle row of an image with an odd height stays where it is.
+        let (top, rest) = self.data.split_at_mut(half * self.stride);
+        let bottom_start = (self.height as usize - 2 * half) * self.stride;
+        let bottom = &mut rest[bottom_start..][..(half - 1) * self.stride + row_size];
+        parallel::for_each_mirrored_row(top, bottom, self.stride, |top, bottom| {
+            let tiles = top[..row_size].chunks_mut(TILE_SIZE);
+            for (top, bottom) in tiles.zip(bottom[..row_size].chunks_mut(TILE_SIZE)) {
+                top.swap_with_slice(bottom);
+            }
+        });
+    }
+
+    /// Flips the image left to right, reversing the pixels of each row.
+    pub fn flip_horizontal(&mut self) {
+        let row_size = Image::effective_size(self.width, 1);
+        let height = self.height as usize;
+        if row_size == 0 || height == 0 {
+            return;
+        }
+
+        let reverse = |row: &mut [u8]| {
+            bytemuck::cast_slice_mut::<u8, Bgra8>(&mut row[..row_size]).reverse();
+        };
+        // The last row may end without the padding of the others.
+        let (rows, last) = self.data.split_at_mut((height - 1) * self.stride);
+        parallel::for_each_row(rows, self.stride, |_, row| reverse(row));
+        reverse(last);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An image whose pixels all differ, with rows padded to `stride` bytes
+    /// if it is given.
+    fn image(width: u16, height: u16, stride: Option<usize>) -> Image {
+        let data = (0..width as u32 * height as u32)
+            .flat_map(|index| index.to_le_bytes())
+            .collect();
+        let image = Image::new(width, height, data).unwrap();
+
+        match stride {
+            Some(stride) => image.to_aligned(stride),
+            None => image,
+        }
+    }
+
+    fn pixel(image: &Image, x: usize, y: usize) -> &[u8] {
+        &image.row(y)[x * 4..][..4]
+    }
+
+    #[test]
+    fn flips_mirror_every_pixel() {
+        // Widths on both sides of a tile, and odd and even heights.
+        let widths = [1, 3, (TILE_SIZE / 4) as u16, (TILE_SIZE / 4) as u16 + 5];
+        for width in widths {
+            for height in [1, 2, 5, 6] {
+                for stride in [None, Some(64)] {
+                    let original = image(width, height, stride);
+                    let (width, height) = (width as usize, height as usize);
+
+                    let mut flipped = original.clone();
+                    flipped.flip_vertical();
+                    for y in 0..height {
+                        assert_eq!(flipped.row(y), original.row(height - 1 - y));
+                    }
+
+                    let mut flipped = original.clone();
+                    flipped.flip_horizontal();
+                    for y in 0..height {
+                        for x in 0..width {
+                            assert_eq!(pixel(&flipped, x, y), pixel(&original, width - 1 - x, y));
+                        }
+                    }
+
+                    flipped.flip_horizontal();
+                    for y in 0..height {
+                        assert_eq!(flipped.row(y), original.row(y));
+                    }
+                }
+            }
+        }
+    }
+}
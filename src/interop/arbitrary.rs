@@ -0,0 +1,188 @@
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::encoder::DEFAULT_CHUNK_SIZE;
+use crate::extension::{fit_field, EXTENSION_SIZE, SHORT_FIELD_SIZE};
+use crate::{
+    AttributesType, Comments, DeveloperTag, EncodeOptions, ExtensionArea, Image, ImageType,
+    PixelDepth, SoftwareVersion, MAX_POSTAGE_STAMP_SIZE,
+};
+
+/// The largest width and height of the images generated, which keeps
+/// property tests fast while still covering runs, padding and multi-row
+/// chunks.
+const MAX_IMAGE_SIZE: u16 = 64;
+
+impl<'a> Arbitrary<'a> for AttributesType {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(AttributesType::from(u8::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for PixelDepth {
+    /// Generates depths as they are read from a byte, so that a depth the
+    /// spec defines is never given as [`PixelDepth::Unknown`].
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(PixelDepth::from(u8::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for ImageType {
+    /// Generates image types as they are read from a byte, like
+    /// [`PixelDepth`].
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(ImageType::from(u8::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for SoftwareVersion {
+    /// Generates versions the file can store, with at most two digits in
+    /// `minor` and an ASCII letter other than space.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let letter = if bool::arbitrary(u)? {
+            Some(u.int_in_range(b'!'..=b'~')? as char)
+        } else {
+            None
+        };
+
+        Ok(SoftwareVersion {
+            major: u.int_in_range(0..=u16::MAX / 100 - 1)?,
+            minor: u.int_in_range(0..=99)?,
+            letter,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Comments {
+    /// Generates lines short enough to be stored unchanged.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut comments = Comments::new();
+        for index in 0..Comments::LINE_COUNT {
+            let _ = comments.set_line(index, &String::arbitrary(u)?);
+        }
+
+        Ok(comments)
+    }
+}
+
+impl<'a> Arbitrary<'a> for ExtensionArea {
+    /// Generates extension areas that are written and read back unchanged,
+    /// apart from the offsets, which are left at zero since writing an image
+    /// fills them in.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let field = |u: &mut Unstructured<'a>| {
+            String::arbitrary(u).map(|value| fit_field(&value, SHORT_FIELD_SIZE).to_owned())
+        };
+        let mut extra = Vec::<u8>::arbitrary(u)?;
+        extra.truncate(u16::MAX as usize - EXTENSION_SIZE);
+
+        Ok(ExtensionArea {
+            author_name: field(u)?,
+            comments: Comments::arbitrary(u)?,
+            timestamp: Arbitrary::arbitrary(u)?,
+            job_name: field(u)?,
+            job_time: Arbitrary::arbitrary(u)?,
+            software_id: field(u)?,
+            software_version: Arbitrary::arbitrary(u)?,
+            key_color: Arbitrary::arbitrary(u)?,
+            pixel_aspect_ratio: Arbitrary::arbitrary(u)?,
+            gamma: Arbitrary::arbitrary(u)?,
+            color_correction_offset: 0,
+            postage_stamp_offset: 0,
+            scan_line_offset: 0,
+            attributes_type: Arbitrary::arbitrary(u)?,
+            color_correction_table: Arbitrary::arbitrary(u)?,
+            extra,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for EncodeOptions {
+    /// Generates chunk sizes of up to the default, so that encoding small
+    /// images exercises splitting their pixel data into several chunks.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(EncodeOptions {
+            pixel_format: Arbitrary::arbitrary(u)?,
+            rle: Arbitrary::arbitrary(u)?,
+            scan_line_table: Arbitrary::arbitrary(u)?,
+            chunk_size: u.int_in_range(1..=DEFAULT_CHUNK_SIZE)?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Image {
+    /// Generates images of up to 64 by 64 pixels that the encoder accepts,
+    /// with an arbitrary ID, extension area, developer tags and postage stamp.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut image = arbitrary_pixels(u, MAX_IMAGE_SIZE)?;
+
+        let mut id = Vec::<u8>::arbitrary(u)?;
+        id.truncate(u8::MAX as usize);
+        image.id = id;
+        image.extension_area = Arbitrary::arbitrary(u)?;
+        image.developer_tags = Vec::<DeveloperTag>::arbitrary(u)?;
+        if bool::arbitrary(u)? {
+            let stamp = arbitrary_pixels(u, MAX_POSTAGE_STAMP_SIZE)?;
+            image.postage_stamp = Some(Box::new(stamp));
+        }
+
+        Ok(image)
+    }
+}
+
+/// Generates an image of at least one and at most `max_size` pixels in each
+/// direction, filling in pixels with zeros once the data runs out.
+fn arbitrary_pixels(u: &mut Unstructured<'_>, max_size: u16) -> Result<Image> {
+    let width = u.int_in_range(1..=max_size)?;
+    let height = u.int_in_range(1..=max_size)?;
+    let mut data = vec![0; Image::effective_size(width, height)];
+    u.fill_buffer(&mut data)?;
+
+    Ok(Image::from_parts(width, height, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::Cursor;
+    use crate::tests::pseudo_random_bytes;
+    use crate::PixelFormat;
+
+    #[test]
+    fn arbitrary_images_round_trip() {
+        let bytes = pseudo_random_bytes(1 << 20);
+        let mut u = Unstructured::new(&bytes);
+        for _ in 0..50 {
+            let image = Image::arbitrary(&mut u).unwrap();
+            assert!((1..=MAX_IMAGE_SIZE).contains(&image.width()));
+            assert!((1..=MAX_IMAGE_SIZE).contains(&image.height()));
+            let options = EncodeOptions {
+                pixel_format: PixelFormat::Bgra32,
+                ..EncodeOptions::arbitrary(&mut u).unwrap()
+            };
+            assert!((1..=DEFAULT_CHUNK_SIZE).contains(&options.chunk_size));
+
+            let mut file = Vec::new();
+            image.write_with_options(&mut file, &options).unwrap();
+            let decoded = Image::read_from(&mut Cursor::new(&file[..])).unwrap();
+            assert_eq!(decoded.data(), image.data());
+            assert_eq!(decoded.id(), image.id());
+            assert_eq!(decoded.developer_tags(), image.developer_tags());
+            assert_eq!(
+                decoded.postage_stamp().map(Image::data),
+                image.postage_stamp().map(Image::data)
+            );
+            if let Some(expected) = image.extension_area() {
+                let extension_area = decoded.extension_area().unwrap();
+                assert_eq!(
+                    ExtensionArea {
+                        color_correction_offset: 0,
+                        postage_stamp_offset: 0,
+                        scan_line_offset: 0,
+                        ..extension_area.clone()
+                    },
+                    *expected
+                );
+            }
+        }
+    }
+}
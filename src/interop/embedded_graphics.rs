@@ -0,0 +1,178 @@
+use core::convert::Infallible;
+
+use ::embedded_graphics_core::draw_target::DrawTarget;
+use ::embedded_graphics_core::geometry::{Dimensions, OriginDimensions, Point, Size};
+use ::embedded_graphics_core::image::{GetPixel, ImageDrawable};
+use ::embedded_graphics_core::pixelcolor::{Rgb888, RgbColor};
+use ::embedded_graphics_core::primitives::Rectangle;
+use ::embedded_graphics_core::Pixel;
+
+use crate::{Bgra8, Image};
+
+impl From<Bgra8> for Rgb888 {
+    /// Drops the alpha channel, which embedded-graphics colors do not have.
+    fn from(value: Bgra8) -> Self {
+        Rgb888::new(value.r, value.g, value.b)
+    }
+}
+
+impl From<Rgb888> for Bgra8 {
+    /// Makes the color fully opaque.
+    fn from(value: Rgb888) -> Self {
+        Bgra8::new(value.b(), value.g(), value.r(), u8::MAX)
+    }
+}
+
+impl OriginDimensions for Image {
+    fn size(&self) -> Size {
+        Size::new(self.width as u32, self.height as u32)
+    }
+}
+
+impl ImageDrawable for Image {
+    type Color = Rgb888;
+
+    /// Draws every pixel of the image, ignoring its alpha channel.
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        self.draw_sub_image(target, &self.bounding_box())
+    }
+
+    /// Draws the pixels of `area` with its top left corner at the origin of
+    /// `target`, leaving out the parts of it outside the image.
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let visible = area.intersection(&self.bounding_box());
+        if visible.size.width == 0 || visible.size.height == 0 {
+            return Ok(());
+        }
+
+        let x = visible.top_left.x as usize;
+        let y = visible.top_left.y as usize;
+        let (width, height) = (visible.size.width as usize, visible.size.height as usize);
+        let colors = (y..y + height).flat_map(|y| {
+            let row = bytemuck::cast_slice::<u8, Bgra8>(self.row(y));
+            row[x..x + width].iter().map(|&pixel| Rgb888::from(pixel))
+        });
+
+        let destination = Rectangle::new(visible.top_left - area.top_left, visible.size);
+        target.fill_contiguous(&destination, colors)
+    }
+}
+
+impl GetPixel for Image {
+    type Color = Rgb888;
+
+    fn pixel(&self, p: Point) -> Option<Self::Color> {
+        let x = usize::try_from(p.x)
+            .ok()
+            .filter(|&x| x < self.width as usize)?;
+        let y = usize::try_from(p.y)
+            .ok()
+            .filter(|&y| y < self.height as usize)?;
+        let row = bytemuck::cast_slice::<u8, Bgra8>(self.row(y));
+
+        Some(Rgb888::from(row[x]))
+    }
+}
+
+impl DrawTarget for Image {
+    type Color = Rgb888;
+    type Error = Infallible;
+
+    /// Draws fully opaque pixels, leaving out those outside the image.
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            self.draw_pixel(point.x, point.y, Bgra8::from(color));
+        }
+
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+        let (x, y) = (area.top_left.x, area.top_left.y);
+        let (width, height) = (area.size.width as u16, area.size.height as u16);
+        self.fill_rect(x, y, width, height, Bgra8::from(color));
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill_solid(&self.bounding_box(), color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn image() -> Image {
+        let data = (0..12u8)
+            .flat_map(|index| [index, index * 2, index * 3, 0])
+            .collect();
+
+        Image::new(4, 3, data).unwrap()
+    }
+
+    #[test]
+    fn pixels_are_read_and_drawn_opaque() {
+        let source = image();
+        assert_eq!(source.size(), Size::new(4, 3));
+        assert_eq!(source.pixel(Point::new(1, 2)), Some(Rgb888::new(27, 18, 9)));
+        for point in [Point::new(4, 0), Point::new(0, 3), Point::new(-1, 0)] {
+            assert_eq!(source.pixel(point), None);
+        }
+
+        let mut target = Image::new(4, 3, vec![0; 48]).unwrap();
+        source.draw(&mut target).unwrap();
+        let opaque: Vec<u8> = source
+            .data()
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], u8::MAX])
+            .collect();
+        assert_eq!(target.data(), opaque);
+    }
+
+    #[test]
+    fn sub_images_and_fills_are_clipped_to_the_image() {
+        let source = image();
+        let mut target = Image::new(4, 3, vec![0; 48]).unwrap();
+        let area = Rectangle::new(Point::new(-1, 1), Size::new(3, 5));
+        source.draw_sub_image(&mut target, &area).unwrap();
+        // Only columns 0 and 1 of rows 1 and 2 lie inside the source, and
+        // they are drawn one column to the right of the target's origin.
+        for y in 0..3 {
+            for x in 0..4 {
+                let expected = match (x, y) {
+                    (1..=2, 0..=1) => source.pixel(Point::new(x - 1, y + 1)),
+                    _ => Some(Rgb888::new(0, 0, 0)),
+                };
+                assert_eq!(target.pixel(Point::new(x, y)), expected, "({x}, {y})");
+            }
+        }
+
+        let mut target = Image::new(4, 3, vec![0; 48]).unwrap();
+        let red = Rgb888::new(255, 0, 0);
+        target
+            .fill_solid(&Rectangle::new(Point::new(2, -1), Size::new(5, 3)), red)
+            .unwrap();
+        target
+            .draw_iter([Pixel(Point::new(0, 2), red), Pixel(Point::new(9, 9), red)])
+            .unwrap();
+        let filled: Vec<_> = (0..3)
+            .flat_map(|y| (0..4).map(move |x| (x, y)))
+            .filter(|&(x, y)| target.pixel(Point::new(x, y)) == Some(red))
+            .collect();
+        assert_eq!(filled, [(2, 0), (3, 0), (2, 1), (3, 1), (0, 2)]);
+    }
+}
@@ -0,0 +1,182 @@
+use ::embedded_io::ErrorKind as EmbeddedErrorKind;
+
+use crate::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Adapts an `embedded-io` reader or writer, such as a file on an SD card or
+/// a serial port, to the [`io`](crate::io) traits [`Decoder`](crate::Decoder)
+/// and [`Image::write_with_options`](crate::Image::write_with_options) take.
+///
+/// Errors keep the kind reported by the device, as far as [`io::ErrorKind`]
+/// has a matching one, and become [`io::ErrorKind::Other`] otherwise.
+#[derive(Debug, Default)]
+pub struct EmbeddedIo<T> {
+    inner: T,
+}
+
+impl<T> EmbeddedIo<T> {
+    pub fn new(inner: T) -> Self {
+        EmbeddedIo { inner }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<T> io::Sealed for EmbeddedIo<T> {}
+
+impl<T: ::embedded_io::Read> Read for EmbeddedIo<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf).map_err(to_io_error)
+    }
+}
+
+impl<T: ::embedded_io::Write> Write for EmbeddedIo<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf).map_err(to_io_error)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush().map_err(to_io_error)
+    }
+}
+
+impl<T: ::embedded_io::Seek> Seek for EmbeddedIo<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => ::embedded_io::SeekFrom::Start(offset),
+            SeekFrom::End(offset) => ::embedded_io::SeekFrom::End(offset),
+            SeekFrom::Current(offset) => ::embedded_io::SeekFrom::Current(offset),
+        };
+
+        self.inner.seek(pos).map_err(to_io_error)
+    }
+}
+
+/// Converts an error of a device to an I/O error of the same kind. Only the
+/// kind is kept, as device errors need not be `Send` or `Sync`.
+fn to_io_error(error: impl ::embedded_io::Error) -> io::Error {
+    let kind = match error.kind() {
+        EmbeddedErrorKind::InvalidInput => io::ErrorKind::InvalidInput,
+        EmbeddedErrorKind::InvalidData => io::ErrorKind::InvalidData,
+        EmbeddedErrorKind::Interrupted => io::ErrorKind::Interrupted,
+        EmbeddedErrorKind::Unsupported => io::ErrorKind::Unsupported,
+        EmbeddedErrorKind::WriteZero => io::ErrorKind::WriteZero,
+        _ => io::ErrorKind::Other,
+    };
+
+    io::Error::from(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Decoder, Image};
+    use alloc::vec::Vec;
+
+    /// An in-memory device that fails every operation once `failure` is set.
+    #[derive(Default)]
+    struct Device {
+        data: Vec<u8>,
+        position: usize,
+        failure: Option<EmbeddedErrorKind>,
+    }
+
+    impl Device {
+        fn check(&self) -> Result<(), EmbeddedErrorKind> {
+            self.failure.map_or(Ok(()), Err)
+        }
+    }
+
+    impl ::embedded_io::ErrorType for Device {
+        type Error = EmbeddedErrorKind;
+    }
+
+    impl ::embedded_io::Read for Device {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            self.check()?;
+            let source = self.data.get(self.position..).unwrap_or_default();
+            let length = source.len().min(buf.len());
+            buf[..length].copy_from_slice(&source[..length]);
+            self.position += length;
+
+            Ok(length)
+        }
+    }
+
+    impl ::embedded_io::Write for Device {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.check()?;
+            let end = self.position + buf.len();
+            if self.data.len() < end {
+                self.data.resize(end, 0);
+            }
+            self.data[self.position..end].copy_from_slice(buf);
+            self.position = end;
+
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            self.check()
+        }
+    }
+
+    impl ::embedded_io::Seek for Device {
+        fn seek(&mut self, pos: ::embedded_io::SeekFrom) -> Result<u64, Self::Error> {
+            self.check()?;
+            let position = match pos {
+                ::embedded_io::SeekFrom::Start(offset) => offset as i64,
+                ::embedded_io::SeekFrom::End(offset) => self.data.len() as i64 + offset,
+                ::embedded_io::SeekFrom::Current(offset) => self.position as i64 + offset,
+            };
+            self.position =
+                usize::try_from(position).map_err(|_| EmbeddedErrorKind::InvalidInput)?;
+
+            Ok(self.position as u64)
+        }
+    }
+
+    #[test]
+    fn images_are_written_to_and_read_from_devices() {
+        let image = Image::new(3, 2, (0..24).collect()).unwrap();
+        let mut device = EmbeddedIo::new(Device::default());
+        image.write_to(&mut device).unwrap();
+        device.flush().unwrap();
+
+        let mut file = Vec::new();
+        image.write_to(&mut file).unwrap();
+        assert_eq!(device.get_ref().data, file);
+
+        device.get_mut().position = 0;
+        let decoded = Decoder::new(device).unwrap().read_image().unwrap();
+        assert_eq!(decoded.data(), image.data());
+    }
+
+    #[test]
+    fn device_errors_keep_their_kind() {
+        for (failure, kind) in [
+            (EmbeddedErrorKind::InvalidData, io::ErrorKind::InvalidData),
+            (EmbeddedErrorKind::WriteZero, io::ErrorKind::WriteZero),
+            (EmbeddedErrorKind::NotConnected, io::ErrorKind::Other),
+        ] {
+            let mut device = EmbeddedIo::new(Device {
+                failure: Some(failure),
+                ..Device::default()
+            });
+            assert_eq!(device.read(&mut [0; 4]).unwrap_err().kind(), kind);
+            assert_eq!(device.write(&[0; 4]).unwrap_err().kind(), kind);
+            assert_eq!(device.seek(SeekFrom::Start(0)).unwrap_err().kind(), kind);
+            assert!(device.into_inner().data.is_empty());
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+use std::io;
+
+use ::fast_image_resize::images::{Image as ResizeImage, ImageRef};
+use ::fast_image_resize::{FilterType, PixelType, ResizeAlg, ResizeOptions, Resizer};
+
+use crate::{Error, Image, Result};
+
+impl Image {
+    /// Resamples the image to `width` by `height` pixels with the convolution
+    /// `filter`, such as [`FilterType::Lanczos3`] or
+    /// [`FilterType::CatmullRom`], using SIMD instructions where the CPU
+    /// supports them.
+    ///
+    /// Colors are weighted by their alpha, so transparent pixels do not bleed
+    /// into their neighbours. The result has no row padding, and keeps
+    /// neither the ID, the extension area, the developer tags nor the postage
+    /// stamp. Fails if either image has zero width or height.
+    pub fn resize(&self, width: u16, height: u16, filter: FilterType) -> Result<Image> {
+        self.resize_with(&mut Resizer::new(), width, height, filter)
+    }
+
+    /// Shrinks the image to fit within `max_width` by `max_height` pixels,
+    /// keeping its aspect ratio, as [`Image::resize`] does.
+    ///
+    /// Images that already fit are copied without resampling. Thumbnails of
+    /// at most 64 by 64 pixels can be stored as the postage stamp of the
+    /// image.
+    pub fn thumbnail(&self, max_width: u16, max_height: u16, filter: FilterType) -> Result<Image> {
+        if max_width == 0 || max_height == 0 {
+            return Err(Error::InvalidDimensions("thumbnails cannot be empty"));
+        }
+        if self.width <= max_width && self.height <= max_height {
+            let pixels = self.contiguous_pixels().into_owned();
+            return Image::new(self.width, self.height, pixels);
+        }
+
+        // Scale by whichever side has to shrink the most, rounding the other
+        // side to the nearest pixel.
+        let (width, height) = (self.width as u64, self.height as u64);
+        let (width, height) = if width * max_height as u64 >= height * max_width as u64 {
+            let height = (2 * height * max_width as u64 + width) / (2 * width);
+            (max_width, height.max(1) as u16)
+        } else {
+            let width = (2 * width * max_height as u64 + height) / (2 * height);
+            (width.max(1) as u16, max_height)
+        };
+
+        self.resize_with(&mut Resizer::new(), width, height, filter)
+    }
+
+    /// Generates the mipmaps of the image, halving each side, rounded down,
+    /// down to 1 by 1 pixel.
+    ///
+    /// Every level is resampled from the previous one with `filter`, and the
+    /// image itself is not included. Images of 1 by 1 pixel have no mipmaps.
+    pub fn mipmaps(&self, filter: FilterType) -> Result<Vec<Image>> {
+        let mut resizer = Resizer::new();
+        let mut levels: Vec<Image> = Vec::new();
+        loop {
+            let previous = levels.last().unwrap_or(self);
+            if previous.width <= 1 && previous.height <= 1 {
+                break;
+            }
+
+            let width = (previous.width / 2).max(1);
+            let height = (previous.height / 2).max(1);
+            let level = previous.resize_with(&mut resizer, width, height, filter)?;
+            levels.push(level);
+        }
+
+        Ok(levels)
+    }
+
+    /// Like [`Image::resize`], but reuses the buffers of `resizer`.
+    fn resize_with(
+        &self,
+        resizer: &mut Resizer,
+        width: u16,
+        height: u16,
+        filter: FilterType,
+    ) -> Result<Image> {
+        if self.width == 0 || self.height == 0 || width == 0 || height == 0 {
+            return Err(Error::InvalidDimensions("cannot resize empty images"));
+        }
+
+        let pixels = self.contiguous_pixels();
+        let source = ImageRef::new(
+            self.width as u32,
+            self.height as u32,
+            &pixels,
+            PixelType::U8x4,
+        )
+        .map_err(io::Error::other)?;
+        let mut destination = ResizeImage::new(width as u32, height as u32, PixelType::U8x4);
+        let options = ResizeOptions::new().resize_alg(ResizeAlg::Convolution(filter));
+        resizer
+            .resize(&source, &mut destination, &options)
+            .map_err(io::Error::other)?;
+
+        Image::new(width, height, destination.into_vec())
+    }
+
+    /// The pixels without any row padding, only copied if there is some.
+    fn contiguous_pixels(&self) -> Cow<'_, [u8]> {
+        let row_size = Image::effective_size(self.width, 1);
+        if self.stride == row_size {
+            Cow::Borrowed(&self.data[..row_size * self.height as usize])
+        } else {
+            let rows = (0..self.height as usize).flat_map(|y| self.row(y));
+            Cow::Owned(rows.copied().collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u16, height: u16) -> Image {
+        let data = [10, 20, 30, 255].repeat(width as usize * height as usize);
+
+        Image::new(width, height, data).unwrap()
+    }
+
+    #[test]
+    fn resizing_keeps_solid_colors() {
+        let image = solid(9, 5);
+        let resized = image.resize(4, 7, FilterType::Lanczos3).unwrap();
+        assert_eq!((resized.width(), resized.height()), (4, 7));
+        assert_eq!(resized.data(), solid(4, 7).data());
+
+        // Padding does not change the result.
+        let mut data = Vec::new();
+        for row in image.data().chunks_exact(36) {
+            data.extend_from_slice(row);
+            data.extend_from_slice(&[0xee; 12]);
+        }
+        let padded = Image::with_stride(9, 5, 48, data);
+        assert_eq!(
+            padded.resize(4, 7, FilterType::Lanczos3).unwrap().data(),
+            resized.data()
+        );
+
+        assert!(matches!(
+            image.resize(0, 7, FilterType::Box),
+            Err(Error::InvalidDimensions(_))
+        ));
+    }
+
+    #[test]
+    fn thumbnails_keep_the_aspect_ratio() {
+        let image = solid(200, 100);
+        for ((max_width, max_height), size) in [
+            ((64, 64), (64, 32)),
+            ((50, 10), (20, 10)),
+            ((300, 300), (200, 100)),
+            ((1, 64), (1, 1)),
+        ] {
+            let thumbnail = image
+                .thumbnail(max_width, max_height, FilterType::CatmullRom)
+                .unwrap();
+            assert_eq!((thumbnail.width(), thumbnail.height()), size);
+            assert_eq!(thumbnail.data(), solid(size.0, size.1).data());
+        }
+        assert!(image.thumbnail(64, 0, FilterType::Box).is_err());
+    }
+
+    #[test]
+    fn mipmaps_halve_down_to_a_single_pixel() {
+        let sizes: Vec<_> = solid(10, 3)
+            .mipmaps(FilterType::Box)
+            .unwrap()
+            .iter()
+            .map(|level| (level.width(), level.height()))
+            .collect();
+        assert_eq!(sizes, [(5, 1), (2, 1), (1, 1)]);
+        assert!(solid(1, 1).mipmaps(FilterType::Box).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+use std::future;
+use std::io;
+use std::pin::Pin;
+
+use ::futures_io::{AsyncRead, AsyncWrite};
+
+use crate::{EncodeOptions, Image, Result};
+
+/// The number of bytes read at a time.
+const CHUNK_SIZE: usize = 32 * 1024;
+
+impl Image {
+    /// Like [`Image::write_to`], but writes to an asynchronous writer of the
+    /// `futures` crate, as used by async-std and smol.
+    pub async fn write_to_futures<T: AsyncWrite + Unpin>(&self, w: &mut T) -> Result<()> {
+        self.write_with_options_futures(w, &EncodeOptions::default())
+            .await
+    }
+
+    /// Like [`Image::write_with_options`], but writes to an asynchronous
+    /// writer of the `futures` crate.
+    ///
+    /// The file is encoded into memory on the calling task and then written
+    /// out without blocking. The writer is flushed afterwards.
+    pub async fn write_with_options_futures<T: AsyncWrite + Unpin>(
+        &self,
+        w: &mut T,
+        options: &EncodeOptions,
+    ) -> Result<()> {
+        let mut data = Vec::with_capacity(self.encoded_size(options).unwrap_or(0));
+        self.write_with_options(&mut data, options)?;
+        write_all(w, &data).await?;
+        future::poll_fn(|cx| Pin::new(&mut *w).poll_flush(cx)).await?;
+
+        Ok(())
+    }
+
+    /// Like [`Image::read_from`], but reads from an asynchronous reader of the
+    /// `futures` crate.
+    ///
+    /// The file is read into memory until `r` reaches its end, without
+    /// blocking, and then decoded on the calling task as with
+    /// [`decode_untrusted`](crate::decode_untrusted). Limit the length of `r`
+    /// to bound the memory used.
+    pub async fn read_from_futures<T: AsyncRead + Unpin>(r: &mut T) -> Result<Self> {
+        let data = read_to_end(r).await?;
+
+        crate::decode_untrusted(&data)
+    }
+}
+
+/// Writes all of `buf`, retrying writes that are interrupted.
+async fn write_all<T: AsyncWrite + Unpin>(w: &mut T, mut buf: &[u8]) -> io::Result<()> {
+    while !buf.is_empty() {
+        match future::poll_fn(|cx| Pin::new(&mut *w).poll_write(cx, buf)).await {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(length) => buf = &buf[length..],
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads until the end of `r`, retrying reads that are interrupted.
+async fn read_to_end<T: AsyncRead + Unpin>(r: &mut T) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        match future::poll_fn(|cx| Pin::new(&mut *r).poll_read(cx, &mut chunk)).await {
+            Ok(0) => return Ok(data),
+            Ok(length) => data.extend_from_slice(&chunk[..length]),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interop::block_on;
+    use crate::Error;
+    use std::task::{Context, Poll};
+
+    /// A reader and writer that moves at most 7 bytes at a time, and is
+    /// interrupted or not ready every few calls.
+    #[derive(Default)]
+    struct Trickle {
+        data: Vec<u8>,
+        position: usize,
+        calls: usize,
+        flushes: usize,
+        write_limit: Option<usize>,
+    }
+
+    impl Trickle {
+        /// How the next call fares: `None` if it goes through.
+        fn hiccup(&mut self, cx: &mut Context<'_>) -> Option<Poll<io::Error>> {
+            self.calls += 1;
+            match self.calls % 5 {
+                1 => Some(Poll::Ready(io::ErrorKind::Interrupted.into())),
+                3 => {
+                    cx.waker().wake_by_ref();
+                    Some(Poll::Pending)
+                }
+                _ => None,
+            }
+        }
+    }
+
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            if let Some(hiccup) = self.hiccup(cx) {
+                return hiccup.map(Err);
+            }
+            let source = &self.data[self.position..];
+            let length = source.len().min(buf.len()).min(7);
+            buf[..length].copy_from_slice(&source[..length]);
+            self.position += length;
+
+            Poll::Ready(Ok(length))
+        }
+    }
+
+    impl AsyncWrite for Trickle {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            if let Some(hiccup) = self.hiccup(cx) {
+                return hiccup.map(Err);
+            }
+            let limit = self.write_limit.unwrap_or(usize::MAX) - self.data.len();
+            let length = buf.len().min(7).min(limit);
+            self.data.extend_from_slice(&buf[..length]);
+
+            Poll::Ready(Ok(length))
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            self.flushes += 1;
+
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn images_round_trip_through_slow_streams() {
+        let image = Image::new(5, 4, (0..80).collect()).unwrap();
+        let options = EncodeOptions {
+            rle: true,
+            ..EncodeOptions::default()
+        };
+        let mut stream = Trickle::default();
+        block_on(image.write_with_options_futures(&mut stream, &options)).unwrap();
+        assert_eq!(stream.flushes, 1);
+
+        let mut expected = Vec::new();
+        image.write_with_options(&mut expected, &options).unwrap();
+        assert_eq!(stream.data, expected);
+
+        let decoded = block_on(Image::read_from_futures(&mut stream)).unwrap();
+        assert_eq!(decoded.data(), image.data());
+    }
+
+    #[test]
+    fn short_streams_are_reported() {
+        let image = Image::new(5, 4, (0..80).collect()).unwrap();
+        let mut stream = Trickle {
+            write_limit: Some(30),
+            ..Trickle::default()
+        };
+        let error = block_on(image.write_to_futures(&mut stream)).unwrap_err();
+        assert!(
+            matches!(error.root(), Error::Io(error) if error.kind() == io::ErrorKind::WriteZero)
+        );
+        assert_eq!(stream.flushes, 0);
+
+        assert!(block_on(Image::read_from_futures(&mut stream)).is_err());
+    }
+}
@@ -0,0 +1,308 @@
+use std::io::{Read, Seek, Write};
+use std::num::TryFromIntError;
+use std::ops::Deref;
+
+use ::image::error::{
+    DecodingError, EncodingError, ImageFormatHint, LimitError, LimitErrorKind, UnsupportedError,
+    UnsupportedErrorKind,
+};
+use ::image::{
+    ColorType, ExtendedColorType, ImageBuffer, ImageDecoder, ImageEncoder, ImageError, ImageResult,
+    Rgba, RgbaImage,
+};
+
+use crate::swizzle::swap_red_blue;
+use crate::{parallel, Decoder, EncodeOptions, Error, Image, PixelDepth};
+
+/// An encoder for the `image` crate, writing images through
+/// [`Image::write_with_options`].
+///
+/// 8-bit grayscale, RGB and RGBA pixels, with or without alpha, are accepted.
+/// They are converted to the 32-bit pixels [`Image`] holds and then encoded
+/// as described by the options.
+pub struct Encoder<W: Write> {
+    writer: W,
+    options: EncodeOptions,
+}
+
+impl<W: Write> Encoder<W> {
+    /// Encodes uncompressed 32-bit pixels into `writer`.
+    pub fn new(writer: W) -> Self {
+        Encoder::with_options(writer, EncodeOptions::default())
+    }
+
+    /// Encodes pixels into `writer` as described by `options`.
+    pub fn with_options(writer: W, options: EncodeOptions) -> Self {
+        Encoder { writer, options }
+    }
+}
+
+impl<R: Read + Seek> ImageDecoder for Decoder<R> {
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width() as u32, self.height() as u32)
+    }
+
+    /// Always [`ColorType::Rgba8`], which holds pixels of every depth
+    /// without loss.
+    fn color_type(&self) -> ColorType {
+        ColorType::Rgba8
+    }
+
+    fn original_color_type(&self) -> ExtendedColorType {
+        match self.pixel_depth() {
+            PixelDepth::B24 => ExtendedColorType::Bgr8,
+            PixelDepth::B32 => ExtendedColorType::Bgra8,
+            _ => ExtendedColorType::Rgb5x1,
+        }
+    }
+
+    /// Reads the image as [`Decoder::read_image`] does, which has to be called
+    /// as `ImageDecoder::read_image(decoder, buf)` for this one to be used.
+    fn read_image(mut self, buf: &mut [u8]) -> ImageResult<()> {
+        assert_eq!(buf.len() as u64, self.total_bytes());
+        self.read_image_into(buf)?;
+        parallel::for_each_block(buf, swap_red_blue);
+
+        Ok(())
+    }
+
+    fn read_image_boxed(self: Box<Self>, buf: &mut [u8]) -> ImageResult<()> {
+        ImageDecoder::read_image(*self, buf)
+    }
+}
+
+impl<W: Write> ImageEncoder for Encoder<W> {
+    fn write_image(
+        mut self,
+        buf: &[u8],
+        width: u32,
+        height: u32,
+        color_type: ExtendedColorType,
+    ) -> ImageResult<()> {
+        let channels = match color_type {
+            ExtendedColorType::L8 => 1,
+            ExtendedColorType::La8 => 2,
+            ExtendedColorType::Rgb8 => 3,
+            ExtendedColorType::Rgba8 => 4,
+            _ => {
+                return Err(ImageError::Unsupported(
+                    UnsupportedError::from_format_and_kind(
+                        format(),
+                        UnsupportedErrorKind::Color(color_type),
+                    ),
+                ))
+            }
+        };
+        assert_eq!(buf.len() as u64, width as u64 * height as u64 * channels);
+
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(ImageError::Limits(LimitError::from_kind(
+                LimitErrorKind::DimensionError,
+            )));
+        };
+        let data = buf
+            .chunks_exact(channels as usize)
+            .flat_map(|pixel| match *pixel {
+                [l] => [l, l, l, u8::MAX],
+                [l, a] => [l, l, l, a],
+                [r, g, b] => [b, g, r, u8::MAX],
+                [r, g, b, a] => [b, g, r, a],
+                _ => unreachable!("pixels have one to four channels"),
+            })
+            .collect();
+
+        Image::from_parts(width, height, data)
+            .write_with_options(&mut self.writer, &self.options)
+            .map_err(|error| match error {
+                Error::Io(error) => ImageError::IoError(error),
+                error => ImageError::Encoding(EncodingError::new(format(), error)),
+            })
+    }
+}
+
+impl From<Error> for ImageError {
+    /// Problems with the file itself become [`ImageError::Decoding`], keeping
+    /// the offset they were found at.
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Io(error) => ImageError::IoError(error),
+            Error::UnsupportedImageType { .. } => {
+                ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+                    format(),
+                    UnsupportedErrorKind::GenericFeature(error.to_string()),
+                ))
+            }
+            Error::LimitExceeded(_) => {
+                ImageError::Limits(LimitError::from_kind(LimitErrorKind::InsufficientMemory))
+            }
+            Error::Parse { source, .. }
+                if matches!(
+                    *source,
+                    Error::Io(_) | Error::UnsupportedImageType { .. } | Error::LimitExceeded(_)
+                ) =>
+            {
+                ImageError::from(*source)
+            }
+            error => ImageError::Decoding(DecodingError::new(format(), error)),
+        }
+    }
+}
+
+impl From<&Image> for RgbaImage {
+    fn from(image: &Image) -> Self {
+        let (width, height) = (image.width as u32, image.height as u32);
+
+        RgbaImage::from_raw(width, height, image.to_rgba()).expect("pixels fill the image")
+    }
+}
+
+impl From<Image> for RgbaImage {
+    /// Converts the pixels in place unless the rows are padded.
+    fn from(image: Image) -> Self {
+        let row_size = Image::effective_size(image.width, 1);
+        if image.stride != row_size {
+            return RgbaImage::from(&image);
+        }
+
+        let (width, height) = (image.width as u32, image.height as u32);
+        let mut data = image.data;
+        data.truncate(row_size * image.height as usize);
+        parallel::for_each_block(&mut data, swap_red_blue);
+
+        RgbaImage::from_raw(width, height, data).expect("pixels fill the image")
+    }
+}
+
+impl TryFrom<RgbaImage> for Image {
+    type Error = TryFromIntError;
+
+    /// Converts the pixels in place. Fails for images wider or taller than
+    /// 65535 pixels.
+    fn try_from(value: RgbaImage) -> Result<Self, Self::Error> {
+        let width = u16::try_from(value.width())?;
+        let height = u16::try_from(value.height())?;
+        let mut data = value.into_raw();
+        data.truncate(Image::effective_size(width, height));
+
+        Ok(Image::from_rgba_parts(width, height, data))
+    }
+}
+
+impl<C: Deref<Target = [u8]>> TryFrom<&ImageBuffer<Rgba<u8>, C>> for Image {
+    type Error = TryFromIntError;
+
+    /// Fails for images wider or taller than 65535 pixels.
+    fn try_from(value: &ImageBuffer<Rgba<u8>, C>) -> Result<Self, Self::Error> {
+        let width = u16::try_from(value.width())?;
+        let height = u16::try_from(value.height())?;
+        let data = value.as_raw()[..Image::effective_size(width, height)].to_vec();
+
+        Ok(Image::from_rgba_parts(width, height, data))
+    }
+}
+
+fn format() -> ImageFormatHint {
+    ImageFormatHint::Exact(::image::ImageFormat::Tga)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Cursor};
+    use crate::PixelFormat;
+
+    /// A 3 by 2 image of distinct, partly transparent pixels.
+    fn image() -> Image {
+        Image::new(3, 2, (0..24).map(|index| index * 10).collect()).unwrap()
+    }
+
+    #[test]
+    fn decoders_read_rgba_pixels() {
+        let image = image();
+        for (pixel_format, color_type) in [
+            (PixelFormat::Bgra32, ExtendedColorType::Bgra8),
+            (PixelFormat::Bgr24, ExtendedColorType::Bgr8),
+        ] {
+            let options = EncodeOptions {
+                pixel_format,
+                rle: true,
+                ..EncodeOptions::default()
+            };
+            let mut file = Vec::new();
+            image.write_with_options(&mut file, &options).unwrap();
+            let decoder = Decoder::new(Cursor::new(&file[..])).unwrap();
+            assert_eq!(decoder.dimensions(), (3, 2));
+            assert_eq!(decoder.color_type(), ColorType::Rgba8);
+            assert_eq!(decoder.original_color_type(), color_type);
+
+            let mut buf = vec![0; 24];
+            ImageDecoder::read_image(decoder, &mut buf).unwrap();
+            // Without an alpha channel in the file, only the colors are kept.
+            let channels = pixel_format.bytes_per_pixel();
+            let expected = RgbaImage::from(&image);
+            for (pixel, expected) in buf.chunks_exact(4).zip(expected.pixels()) {
+                assert_eq!(pixel[..channels], expected.0[..channels]);
+            }
+        }
+    }
+
+    #[test]
+    fn encoders_accept_every_8_bit_color_type() {
+        for (color_type, pixel, expected) in [
+            (ExtendedColorType::L8, &[7][..], [7, 7, 7, 255]),
+            (ExtendedColorType::La8, &[7, 9], [7, 7, 7, 9]),
+            (ExtendedColorType::Rgb8, &[1, 2, 3], [3, 2, 1, 255]),
+            (ExtendedColorType::Rgba8, &[1, 2, 3, 4], [3, 2, 1, 4]),
+        ] {
+            let mut file = Vec::new();
+            let buf = pixel.repeat(2);
+            Encoder::new(&mut file)
+                .write_image(&buf, 2, 1, color_type)
+                .unwrap();
+            let decoded = Image::read_from(&mut Cursor::new(&file[..])).unwrap();
+            assert_eq!(decoded.data(), expected.repeat(2), "{color_type:?}");
+        }
+
+        let mut file = Vec::new();
+        let error = Encoder::new(&mut file)
+            .write_image(&[0; 6], 1, 1, ExtendedColorType::Rgb16)
+            .unwrap_err();
+        assert!(matches!(error, ImageError::Unsupported(_)));
+        let error = Encoder::new(&mut file)
+            .write_image(&[], 70000, 0, ExtendedColorType::Rgba8)
+            .unwrap_err();
+        assert!(matches!(error, ImageError::Limits(_)));
+        assert!(file.is_empty());
+    }
+
+    #[test]
+    fn conversions_swap_red_and_blue() {
+        let image = image();
+        let rgba = RgbaImage::from(&image);
+        assert_eq!(rgba.get_pixel(1, 0).0, [60, 50, 40, 70]);
+        assert_eq!(RgbaImage::from(image.clone()), rgba);
+        assert_eq!(RgbaImage::from(image.to_aligned(16)), rgba);
+
+        assert_eq!(Image::try_from(&rgba).unwrap().data(), image.data());
+        assert_eq!(Image::try_from(rgba).unwrap().data(), image.data());
+        assert!(Image::try_from(RgbaImage::new(70000, 1)).is_err());
+    }
+
+    #[test]
+    fn errors_keep_their_image_error_kind() {
+        let unsupported = Error::UnsupportedImageType {
+            image_type: 1,
+            pixel_depth: 8,
+        };
+        assert!(matches!(
+            ImageError::from(unsupported),
+            ImageError::Unsupported(_)
+        ));
+        let parse = Error::Io(io::ErrorKind::UnexpectedEof.into()).at(4, crate::Context::PixelData);
+        assert!(matches!(ImageError::from(parse), ImageError::IoError(_)));
+        assert!(matches!(
+            ImageError::from(Error::InvalidOffset),
+            ImageError::Decoding(_)
+        ));
+    }
+}
@@ -0,0 +1,131 @@
+use alloc::vec::Vec;
+use core::num::TryFromIntError;
+
+use ::imgref::{Img, ImgRef, ImgVec};
+
+use crate::{Bgra8, Image};
+
+const BYTES_PER_PIXEL: usize = 4;
+
+impl Image {
+    /// Borrows the pixels as an imgref image with the same stride.
+    ///
+    /// Returns `None` if the stride is not a whole number of pixels or the
+    /// image has zero width, neither of which imgref can describe.
+    pub fn as_imgref(&self) -> Option<ImgRef<'_, Bgra8>> {
+        if !self.stride.is_multiple_of(BYTES_PER_PIXEL) || self.width == 0 {
+            return None;
+        }
+
+        let length = self.data.len() / BYTES_PER_PIXEL * BYTES_PER_PIXEL;
+        let pixels = bytemuck::cast_slice(&self.data[..length]);
+        let (width, height) = (self.width as usize, self.height as usize);
+        let stride = self.stride / BYTES_PER_PIXEL;
+
+        Some(Img::new_stride(pixels, width, height, stride))
+    }
+
+    /// Copies the pixels into an imgref image without any row padding,
+    /// converting them to `P`, such as `rgb::RGBA8` with the `rgb` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the image has zero width, which imgref cannot describe.
+    pub fn to_imgvec<P: From<Bgra8>>(&self) -> ImgVec<P> {
+        let mut pixels = Vec::with_capacity(self.width as usize * self.height as usize);
+        for y in 0..self.height as usize {
+            let row = bytemuck::cast_slice::<u8, Bgra8>(self.row(y));
+            pixels.extend(row.iter().map(|&pixel| P::from(pixel)));
+        }
+
+        Img::new(pixels, self.width as usize, self.height as usize)
+    }
+}
+
+impl TryFrom<ImgVec<Bgra8>> for Image {
+    type Error = TryFromIntError;
+
+    /// Takes over the buffer of the image, keeping its stride. Fails for
+    /// images wider or taller than 65535 pixels.
+    fn try_from(value: ImgVec<Bgra8>) -> Result<Self, Self::Error> {
+        let width = u16::try_from(value.width())?;
+        let height = u16::try_from(value.height())?;
+        let stride = value.stride() * BYTES_PER_PIXEL;
+        let data = bytemuck::allocation::try_cast_vec(value.into_buf())
+            .unwrap_or_else(|(_, pixels)| bytemuck::cast_slice(&pixels).to_vec());
+
+        Ok(Image::with_stride(width, height, stride, data))
+    }
+}
+
+impl<P: Copy + Into<Bgra8>> TryFrom<ImgRef<'_, P>> for Image {
+    type Error = TryFromIntError;
+
+    /// Copies and converts the pixels, dropping the row padding. Fails for
+    /// images wider or taller than 65535 pixels.
+    fn try_from(value: ImgRef<'_, P>) -> Result<Self, Self::Error> {
+        let width = u16::try_from(value.width())?;
+        let height = u16::try_from(value.height())?;
+        let mut data = Vec::with_capacity(Image::effective_size(width, height));
+        for pixel in value.pixels() {
+            let pixel: Bgra8 = pixel.into();
+            data.extend_from_slice(bytemuck::bytes_of(&pixel));
+        }
+
+        Ok(Image::from_parts(width, height, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// A 2 by 3 image whose rows are padded to 3 pixels.
+    fn padded() -> Image {
+        let data = (0..36).collect();
+
+        Image::with_stride(2, 3, 12, data)
+    }
+
+    #[test]
+    fn borrowed_views_keep_the_stride() {
+        let image = padded();
+        let view = image.as_imgref().unwrap();
+        assert_eq!((view.width(), view.height(), view.stride()), (2, 3, 3));
+        assert_eq!(view[(1usize, 2usize)], Bgra8::new(28, 29, 30, 31));
+
+        assert!(Image::with_stride(2, 3, 10, vec![0; 28])
+            .as_imgref()
+            .is_none());
+        assert!(Image::new(0, 3, Vec::new()).unwrap().as_imgref().is_none());
+    }
+
+    #[test]
+    fn copies_drop_the_padding() {
+        let image = padded();
+        let copy = image.to_imgvec::<Bgra8>();
+        assert_eq!((copy.width(), copy.stride()), (2, 2));
+        let pixels: Vec<Bgra8> = copy.pixels().collect();
+        assert_eq!(
+            pixels,
+            image.as_imgref().unwrap().pixels().collect::<Vec<_>>()
+        );
+
+        let image = Image::try_from(copy.as_ref()).unwrap();
+        assert_eq!(image.stride(), 8);
+        assert_eq!(image.row(2), [24, 25, 26, 27, 28, 29, 30, 31]);
+    }
+
+    #[test]
+    fn owned_images_are_taken_over_with_their_stride() {
+        let pixels: Vec<_> = (0..6u8).map(|value| Bgra8::new(value, 0, 0, 255)).collect();
+        let image = Image::try_from(Img::new_stride(pixels, 2, 2, 3)).unwrap();
+        assert_eq!((image.width(), image.height(), image.stride()), (2, 2, 12));
+        assert_eq!(image.row(1), [3, 0, 0, 255, 4, 0, 0, 255]);
+
+        let wide = Img::new(vec![Bgra8::default(); 70000], 70000, 1);
+        assert!(Image::try_from(wide.as_ref()).is_err());
+        assert!(Image::try_from(wide).is_err());
+    }
+}
@@ -0,0 +1,69 @@
+//! Conversions to and from types of other crates, each behind a feature of the
+//! same name.
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "embedded-graphics")]
+mod embedded_graphics;
+#[cfg(feature = "embedded-io")]
+mod embedded_io;
+#[cfg(feature = "fast_image_resize")]
+mod fast_image_resize;
+#[cfg(feature = "futures-io")]
+mod futures_io;
+#[cfg(feature = "image")]
+mod image;
+#[cfg(feature = "imgref")]
+mod imgref;
+#[cfg(feature = "ndarray")]
+mod ndarray;
+#[cfg(feature = "palette")]
+mod palette;
+#[cfg(feature = "png")]
+mod png;
+#[cfg(feature = "rgb")]
+mod rgb;
+#[cfg(feature = "sdl2")]
+mod sdl2;
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "softbuffer")]
+mod softbuffer;
+#[cfg(feature = "time")]
+mod time;
+#[cfg(feature = "tiny-skia")]
+mod tiny_skia;
+#[cfg(feature = "tokio")]
+mod tokio;
+#[cfg(feature = "wgpu")]
+mod wgpu;
+#[cfg(feature = "zune-image")]
+mod zune_image;
+
+#[cfg(feature = "embedded-io")]
+pub use self::embedded_io::EmbeddedIo;
+#[cfg(feature = "image")]
+pub use self::image::Encoder;
+#[cfg(feature = "png")]
+pub use self::png::transcode_to_png;
+#[cfg(feature = "serde")]
+pub(crate) use self::serde::color_correction_entries;
+#[cfg(feature = "wgpu")]
+pub use self::wgpu::TextureUpload;
+#[cfg(feature = "zune-image")]
+pub use self::zune_image::ZuneEncoder;
+
+/// Polls `future` on the current thread until it completes, for testing
+/// asynchronous readers and writers that never wait on anything else.
+#[cfg(all(test, any(feature = "futures-io", feature = "tokio")))]
+pub(crate) fn block_on<F: core::future::Future>(future: F) -> F::Output {
+    use core::task::{Context, Poll, Waker};
+
+    let mut future = core::pin::pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::io::Write;
+
+use ::ndarray::{Array3, ArrayView3};
+
+use crate::{EncodeOptions, Error, Image, Result};
+
+impl Image {
+    /// Copies the pixels into an array of `height` by `width` by 4 bytes, with
+    /// the channels of each pixel in red, green, blue, alpha order.
+    pub fn to_rgba_array(&self) -> Array3<u8> {
+        let shape = (self.height as usize, self.width as usize, 4);
+
+        Array3::from_shape_vec(shape, self.to_rgba()).expect("pixels fill the array")
+    }
+
+    /// Creates an image from an array of `height` by `width` by channels
+    /// bytes, which may be laid out in memory in any order.
+    ///
+    /// Pixels with one channel are gray, and pixels with three or four
+    /// channels are red, green and blue, followed by alpha if there are four.
+    /// Fails for other numbers of channels and for images wider or taller than
+    /// 65535 pixels.
+    pub fn from_array(pixels: ArrayView3<'_, u8>) -> Result<Image> {
+        let (height, width, channels) = pixels.dim();
+        if !matches!(channels, 1 | 3 | 4) {
+            return Err(Error::InvalidDimensions(
+                "array must have 1, 3 or 4 channels",
+            ));
+        }
+        let width = u16::try_from(width).map_err(|_| Error::TooLarge("image"))?;
+        let height = u16::try_from(height).map_err(|_| Error::TooLarge("image"))?;
+
+        let mut data = Vec::with_capacity(Image::effective_size(width, height));
+        for pixel in pixels.rows() {
+            let pixel = match channels {
+                1 => [pixel[0], pixel[0], pixel[0], u8::MAX],
+                3 => [pixel[2], pixel[1], pixel[0], u8::MAX],
+                _ => [pixel[2], pixel[1], pixel[0], pixel[3]],
+            };
+            data.extend_from_slice(&pixel);
+        }
+
+        Image::new(width, height, data)
+    }
+
+    /// Writes the pixels of an array as [`Image::from_array`] reads them,
+    /// encoding them as described by `options`.
+    pub fn write_array<T: Write>(
+        w: &mut T,
+        pixels: ArrayView3<'_, u8>,
+        options: &EncodeOptions,
+    ) -> Result<()> {
+        Image::from_array(pixels)?.write_with_options(w, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::Cursor;
+
+    #[test]
+    fn arrays_hold_rgba_rows() {
+        let image = Image::new(3, 2, (0..24).collect()).unwrap();
+        let array = image.to_rgba_array();
+        assert_eq!(array.dim(), (2, 3, 4));
+        assert_eq!(
+            array.slice(::ndarray::s![1, 0, ..]).to_vec(),
+            [14, 13, 12, 15]
+        );
+
+        let back = Image::from_array(array.view()).unwrap();
+        assert_eq!(back.data(), image.data());
+    }
+
+    #[test]
+    fn arrays_of_any_layout_and_channel_count_are_read() {
+        // Stored column by column, as a transposed array is.
+        let mut columns = Array3::from_shape_fn((3, 2, 3), |(x, y, c)| (y * 30 + x * 10 + c) as u8);
+        columns.swap_axes(0, 1);
+        let image = Image::from_array(columns.view()).unwrap();
+        assert_eq!((image.width(), image.height()), (3, 2));
+        assert_eq!(image.row(1)[4..8], [42, 41, 40, 255]);
+
+        let gray = Array3::from_shape_fn((1, 2, 1), |(_, x, _)| x as u8 * 100);
+        let image = Image::from_array(gray.view()).unwrap();
+        assert_eq!(image.data(), [0, 0, 0, 255, 100, 100, 100, 255]);
+
+        let two = Array3::<u8>::zeros((1, 1, 2));
+        assert!(matches!(
+            Image::from_array(two.view()),
+            Err(Error::InvalidDimensions(_))
+        ));
+        let tall = Array3::<u8>::zeros((70000, 1, 1));
+        assert!(matches!(
+            Image::from_array(tall.view()),
+            Err(Error::TooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn arrays_are_written_as_they_are_read() {
+        let array = Array3::from_shape_fn((2, 2, 4), |(y, x, c)| (y * 8 + x * 4 + c) as u8);
+        let options = EncodeOptions {
+            rle: true,
+            ..EncodeOptions::default()
+        };
+        let mut file = Vec::new();
+        Image::write_array(&mut file, array.view(), &options).unwrap();
+        let decoded = Image::read_from(&mut Cursor::new(&file[..])).unwrap();
+        assert_eq!(decoded.to_rgba_array(), array);
+    }
+}
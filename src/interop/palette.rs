@@ -0,0 +1,161 @@
+use ::palette::{cast, LinSrgba, Srgba};
+
+use crate::{Bgra8, Image};
+
+impl From<Bgra8> for Srgba<u8> {
+    fn from(value: Bgra8) -> Self {
+        Srgba::new(value.r, value.g, value.b, value.a)
+    }
+}
+
+impl From<Srgba<u8>> for Bgra8 {
+    fn from(value: Srgba<u8>) -> Self {
+        Bgra8::new(value.blue, value.green, value.red, value.alpha)
+    }
+}
+
+impl From<Bgra8> for LinSrgba {
+    /// Decodes the sRGB colors of the pixel into linear light. Alpha is
+    /// already linear and only scaled.
+    fn from(value: Bgra8) -> Self {
+        Srgba::<u8>::from(value).into_linear()
+    }
+}
+
+impl From<LinSrgba> for Bgra8 {
+    /// Encodes the color as sRGB, clamping channels outside of 0 to 1.
+    fn from(value: LinSrgba) -> Self {
+        Srgba::<u8>::from_linear(value).into()
+    }
+}
+
+impl Image {
+    /// Creates an image from rows of `pixels` from top to bottom, without any
+    /// padding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not exactly `width * height` pixels.
+    pub fn from_srgba(width: u16, height: u16, pixels: &[Srgba<u8>]) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize);
+
+        Image::from_rgba_parts(width, height, cast::into_component_slice(pixels).to_vec())
+    }
+
+    /// Like [`Image::from_srgba`], but for colors in linear light, which are
+    /// encoded as sRGB.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not exactly `width * height` pixels.
+    pub fn from_lin_srgba(width: u16, height: u16, pixels: &[LinSrgba]) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize);
+
+        let mut data = Vec::with_capacity(Image::effective_size(width, height));
+        for &pixel in pixels {
+            data.extend_from_slice(bytemuck::bytes_of(&Bgra8::from(pixel)));
+        }
+
+        Image::from_parts(width, height, data)
+    }
+
+    /// Copies the pixels into sRGB colors, without any row padding.
+    pub fn to_srgba(&self) -> Vec<Srgba<u8>> {
+        cast::from_component_vec(self.to_rgba())
+    }
+
+    /// Copies the pixels into colors in linear light, without any row
+    /// padding, decoding their sRGB colors.
+    pub fn to_lin_srgba(&self) -> Vec<LinSrgba> {
+        let mut pixels = Vec::with_capacity(self.width as usize * self.height as usize);
+        for y in 0..self.height as usize {
+            let row = bytemuck::cast_slice::<u8, Bgra8>(self.row(y));
+            pixels.extend(row.iter().map(|&pixel| LinSrgba::from(pixel)));
+        }
+
+        pixels
+    }
+
+    /// The pixel at column `x` of row `y`, counting from the top left corner.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pixel is outside the image.
+    pub fn srgba_pixel(&self, x: u16, y: u16) -> Srgba<u8> {
+        assert!(
+            x < self.width && y < self.height,
+            "pixel is outside the image"
+        );
+
+        let row = bytemuck::cast_slice::<u8, Bgra8>(self.row(y as usize));
+        row[x as usize].into()
+    }
+
+    /// Like [`Image::srgba_pixel`], but decodes the color into linear light.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pixel is outside the image.
+    pub fn lin_srgba_pixel(&self, x: u16, y: u16) -> LinSrgba {
+        self.srgba_pixel(x, y).into_linear()
+    }
+
+    /// Sets the pixel at column `x` of row `y`, ignoring coordinates outside
+    /// the image like [`Image::draw_pixel`] does.
+    ///
+    /// Colors in linear light can be set by converting them first, with
+    /// `image.set_srgba_pixel(x, y, Srgba::from_linear(color))`.
+    pub fn set_srgba_pixel(&mut self, x: u16, y: u16, color: Srgba<u8>) {
+        self.draw_pixel(x as i32, y as i32, color.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colors() -> Vec<Srgba<u8>> {
+        (0..6u8)
+            .map(|index| Srgba::new(index * 50, 255 - index * 7, index, 100 + index))
+            .collect()
+    }
+
+    #[test]
+    fn srgba_colors_round_trip() {
+        let colors = colors();
+        let image = Image::from_srgba(3, 2, &colors);
+        assert_eq!(image.row(0)[4..8], [1, 248, 50, 101]);
+        assert_eq!(image.to_srgba(), colors);
+        assert_eq!(image.to_aligned(16).to_srgba(), colors);
+        assert_eq!(image.srgba_pixel(2, 1), colors[5]);
+    }
+
+    #[test]
+    fn linear_colors_round_trip() {
+        let image = Image::from_srgba(3, 2, &colors());
+        let linear = image.to_lin_srgba();
+        assert_eq!(linear[4], image.lin_srgba_pixel(1, 1));
+        assert!(linear[1].red < 50.0 / 255.0);
+        assert_eq!(Image::from_lin_srgba(3, 2, &linear).data(), image.data());
+
+        let clamped = Bgra8::from(LinSrgba::new(2.0, -1.0, 0.5, 1.0));
+        assert_eq!((clamped.r, clamped.g, clamped.a), (255, 0, 255));
+    }
+
+    #[test]
+    fn pixels_outside_the_image_are_not_set() {
+        let mut image = Image::from_srgba(3, 2, &colors());
+        let red = Srgba::new(255, 0, 0, 255);
+        image.set_srgba_pixel(0, 1, red);
+        image.set_srgba_pixel(3, 0, red);
+        let mut expected = colors();
+        expected[3] = red;
+        assert_eq!(image.to_srgba(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "pixel is outside the image")]
+    fn reading_pixels_outside_the_image_panics() {
+        Image::from_srgba(3, 2, &colors()).srgba_pixel(0, 2);
+    }
+}
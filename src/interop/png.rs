@@ -0,0 +1,104 @@
+use std::io::{self, Read, Seek, Write};
+
+use ::png::{BitDepth, ColorType, EncodingError};
+
+use crate::{Decoder, Error, Image, Result};
+
+impl From<EncodingError> for Error {
+    fn from(error: EncodingError) -> Self {
+        match error {
+            EncodingError::IoError(error) => Error::Io(error),
+            error => Error::Io(io::Error::other(error)),
+        }
+    }
+}
+
+impl Image {
+    /// Writes the image as an 8-bit RGBA PNG file, keeping the alpha channel
+    /// and the top-to-bottom order of the rows.
+    ///
+    /// Fails for images with zero width or height, which PNG cannot store.
+    pub fn write_png<T: Write>(&self, w: &mut T) -> Result<()> {
+        if self.width == 0 || self.height == 0 {
+            return Err(Error::InvalidDimensions("PNG images cannot be empty"));
+        }
+
+        let mut encoder = ::png::Encoder::new(w, self.width as u32, self.height as u32);
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.to_rgba())?;
+        writer.finish()?;
+
+        Ok(())
+    }
+}
+
+/// Decodes the TGA file read from `reader` and writes it to `writer` as a PNG
+/// file, as [`Image::write_png`] does.
+///
+/// The pixels end up in the same orientation whichever corner the TGA file
+/// stores its first pixel in.
+pub fn transcode_to_png<R: Read + Seek, W: Write>(reader: R, writer: &mut W) -> Result<()> {
+    Decoder::new(reader)?.read_image()?.write_png(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Decodes a PNG file into its dimensions and RGBA pixels.
+    fn read_png(file: &[u8]) -> (u32, u32, Vec<u8>) {
+        let mut reader = ::png::Decoder::new(Cursor::new(file)).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(
+            (info.color_type, info.bit_depth),
+            (ColorType::Rgba, BitDepth::Eight)
+        );
+
+        (info.width, info.height, pixels)
+    }
+
+    fn image() -> Image {
+        Image::new(3, 2, (0..24).map(|index| index * 10).collect()).unwrap()
+    }
+
+    #[test]
+    fn png_files_hold_the_rgba_pixels() {
+        let image = image();
+        let mut file = Vec::new();
+        image.write_png(&mut file).unwrap();
+        assert_eq!(read_png(&file), (3, 2, image.to_rgba()));
+
+        let empty = Image::new(3, 0, Vec::new()).unwrap();
+        assert!(matches!(
+            empty.write_png(&mut Vec::new()),
+            Err(Error::InvalidDimensions(_))
+        ));
+    }
+
+    #[test]
+    fn transcoding_keeps_the_orientation() {
+        let image = image();
+        let mut header = crate::Header::new(3, 2, &crate::EncodeOptions::default());
+        header.set_descriptor(header.descriptor() & !0x20);
+        let mut tga = Vec::new();
+        header.write_to(&mut tga).unwrap();
+        tga.extend_from_slice(image.row(1));
+        tga.extend_from_slice(image.row(0));
+
+        let mut png = Vec::new();
+        transcode_to_png(Cursor::new(&tga[..]), &mut png).unwrap();
+        assert_eq!(read_png(&png), (3, 2, image.to_rgba()));
+
+        assert!(transcode_to_png(Cursor::new(&tga[..20]), &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn write_errors_are_io_errors() {
+        let error = image().write_png(&mut &mut [0u8; 16][..]).unwrap_err();
+        assert!(matches!(error, Error::Io(_)));
+    }
+}
@@ -0,0 +1,211 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ::rgb::alt::BGRA8;
+use ::rgb::RGBA8;
+
+use crate::io::Write;
+use crate::swizzle::swap_red_blue;
+use crate::{parallel, Bgra8, EncodeOptions, Image, Result};
+
+impl From<BGRA8> for Bgra8 {
+    fn from(value: BGRA8) -> Self {
+        Bgra8::new(value.b, value.g, value.r, value.a)
+    }
+}
+
+impl From<Bgra8> for BGRA8 {
+    fn from(value: Bgra8) -> Self {
+        BGRA8 {
+            b: value.b,
+            g: value.g,
+            r: value.r,
+            a: value.a,
+        }
+    }
+}
+
+impl From<RGBA8> for Bgra8 {
+    fn from(value: RGBA8) -> Self {
+        Bgra8::new(value.b, value.g, value.r, value.a)
+    }
+}
+
+impl From<Bgra8> for RGBA8 {
+    fn from(value: Bgra8) -> Self {
+        RGBA8::new(value.r, value.g, value.b, value.a)
+    }
+}
+
+impl Image {
+    /// Creates an image from rows of `pixels` from top to bottom, without any
+    /// padding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not exactly `width * height` pixels.
+    pub fn from_bgra8(width: u16, height: u16, pixels: &[BGRA8]) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize);
+
+        Image::from_parts(width, height, bytemuck::cast_slice(pixels).to_vec())
+    }
+
+    /// Like [`Image::from_bgra8`], but for pixels in red, green, blue, alpha
+    /// order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not exactly `width * height` pixels.
+    pub fn from_rgba8(width: u16, height: u16, pixels: &[RGBA8]) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize);
+
+        Image::from_rgba_parts(width, height, bytemuck::cast_slice(pixels).to_vec())
+    }
+
+    /// The pixels of row `y`, counting from the top.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` is not less than the height of the image.
+    pub fn bgra8_row(&self, y: u16) -> &[BGRA8] {
+        assert!(y < self.height, "row is outside the image");
+
+        bytemuck::cast_slice(self.row(y as usize))
+    }
+
+    /// Like [`Image::bgra8_row`], but allows changing the pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` is not less than the height of the image.
+    pub fn bgra8_row_mut(&mut self, y: u16) -> &mut [BGRA8] {
+        assert!(y < self.height, "row is outside the image");
+
+        let start = y as usize * self.stride;
+        let row = &mut self.data[start..start + Image::effective_size(self.width, 1)];
+        bytemuck::cast_slice_mut(row)
+    }
+
+    /// Copies the pixels into red, green, blue, alpha order, without any row
+    /// padding.
+    pub fn to_rgba8(&self) -> Vec<RGBA8> {
+        let mut pixels = vec![RGBA8::default(); self.width as usize * self.height as usize];
+        let data = bytemuck::cast_slice_mut(&mut pixels);
+        let row_size = Image::effective_size(self.width, 1);
+        for (y, row) in data.chunks_exact_mut(row_size).enumerate() {
+            row.copy_from_slice(self.row(y));
+        }
+        parallel::for_each_block(data, swap_red_blue);
+
+        pixels
+    }
+
+    /// Writes rows of `pixels` from top to bottom, without any padding,
+    /// encoding them as described by `options` like
+    /// [`Image::write_raw_parts`] does, but without copying them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not exactly `width * height` pixels.
+    pub fn write_bgra8<T: Write>(
+        w: &mut T,
+        pixels: &[BGRA8],
+        width: u16,
+        height: u16,
+        options: &EncodeOptions,
+    ) -> Result<()> {
+        assert_eq!(pixels.len(), width as usize * height as usize);
+        let stride = Image::effective_size(width, 1);
+
+        // SAFETY: The slice holds exactly `height` rows of `stride` bytes.
+        unsafe { Image::write_raw_parts(w, pixels.as_ptr().cast(), width, height, stride, options) }
+    }
+
+    /// Like [`Image::write_bgra8`], but for pixels in red, green, blue, alpha
+    /// order, which are converted in a copy first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not exactly `width * height` pixels.
+    pub fn write_rgba8<T: Write>(
+        w: &mut T,
+        pixels: &[RGBA8],
+        width: u16,
+        height: u16,
+        options: &EncodeOptions,
+    ) -> Result<()> {
+        Image::from_rgba8(width, height, pixels).write_with_options(w, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixels() -> Vec<RGBA8> {
+        (0..6u8)
+            .map(|index| RGBA8::new(index, index * 2, index * 3, 255 - index))
+            .collect()
+    }
+
+    #[test]
+    fn pixels_convert_in_both_orders() {
+        let rgba = pixels();
+        let image = Image::from_rgba8(3, 2, &rgba);
+        assert_eq!(image.row(0)[4..8], [3, 2, 1, 254]);
+        assert_eq!(image.to_rgba8(), rgba);
+        assert_eq!(image.to_aligned(16).to_rgba8(), rgba);
+
+        let bgra: Vec<BGRA8> = rgba
+            .iter()
+            .map(|&pixel| Bgra8::from(pixel).into())
+            .collect();
+        assert_eq!(Image::from_bgra8(3, 2, &bgra).data(), image.data());
+        assert_eq!(image.bgra8_row(1), &bgra[3..]);
+    }
+
+    #[test]
+    fn rows_can_be_changed_in_place() {
+        let mut image = Image::from_rgba8(3, 2, &pixels()).to_aligned(16);
+        image.bgra8_row_mut(1)[2] = BGRA8 {
+            b: 9,
+            g: 8,
+            r: 7,
+            a: 6,
+        };
+        assert_eq!(image.to_rgba8()[5], RGBA8::new(7, 8, 9, 6));
+        assert_eq!(image.to_rgba8()[..5], pixels()[..5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "row is outside the image")]
+    fn rows_outside_the_image_panic() {
+        Image::from_rgba8(3, 2, &pixels()).bgra8_row(2);
+    }
+
+    #[test]
+    fn pixels_are_written_as_images_write_them() {
+        let image = Image::from_rgba8(3, 2, &pixels());
+        let bgra: Vec<BGRA8> = image
+            .bgra8_row(0)
+            .iter()
+            .chain(image.bgra8_row(1))
+            .copied()
+            .collect();
+        for rle in [false, true] {
+            let options = EncodeOptions {
+                rle,
+                ..EncodeOptions::default()
+            };
+            let mut expected = Vec::new();
+            image.write_with_options(&mut expected, &options).unwrap();
+
+            let mut file = Vec::new();
+            Image::write_bgra8(&mut file, &bgra, 3, 2, &options).unwrap();
+            assert_eq!(file, expected);
+            let mut file = Vec::new();
+            Image::write_rgba8(&mut file, &pixels(), 3, 2, &options).unwrap();
+            assert_eq!(file, expected);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::io;
+
+use ::sdl2::pixels::PixelFormatEnum;
+use ::sdl2::surface::{Surface, SurfaceRef};
+
+use crate::{Error, Image, Result};
+
+impl Image {
+    /// Copies the pixels into a new SDL surface, in the
+    /// [`PixelFormatEnum::BGRA32`] format that matches their layout.
+    pub fn to_surface(&self) -> Result<Surface<'static>> {
+        let (width, height) = (self.width as u32, self.height as u32);
+        let mut surface =
+            Surface::new(width, height, PixelFormatEnum::BGRA32).map_err(io::Error::other)?;
+
+        if self.width == 0 || self.height == 0 {
+            return Ok(surface);
+        }
+
+        let pitch = surface.pitch() as usize;
+        let row_size = Image::effective_size(self.width, 1);
+        surface.with_lock_mut(|pixels| {
+            for (y, row) in pixels.chunks_exact_mut(pitch).enumerate() {
+                row[..row_size].copy_from_slice(self.row(y));
+            }
+        });
+
+        Ok(surface)
+    }
+
+    /// Borrows the pixels as an SDL surface, without copying them, so that SDL
+    /// can draw into the image or blit from it.
+    pub fn as_surface(&mut self) -> Result<Surface<'_>> {
+        let (width, height) = (self.width as u32, self.height as u32);
+        let pitch = u32::try_from(self.stride).map_err(|_| Error::TooLarge("stride"))?;
+        let surface = Surface::from_data(
+            &mut self.data,
+            width,
+            height,
+            pitch,
+            PixelFormatEnum::BGRA32,
+        )
+        .map_err(io::Error::other)?;
+
+        Ok(surface)
+    }
+
+    /// Copies the pixels of an SDL surface into an image, converting them
+    /// first unless they are already in the [`PixelFormatEnum::BGRA32`]
+    /// format.
+    ///
+    /// Pixels of formats without alpha become fully opaque. Fails for
+    /// surfaces wider or taller than 65535 pixels.
+    pub fn from_surface(surface: &SurfaceRef) -> Result<Image> {
+        if surface.pixel_format_enum() != PixelFormatEnum::BGRA32 {
+            let converted = surface
+                .convert_format(PixelFormatEnum::BGRA32)
+                .map_err(io::Error::other)?;
+            return Image::from_surface(&converted);
+        }
+
+        let width = u16::try_from(surface.width()).map_err(|_| Error::TooLarge("surface"))?;
+        let height = u16::try_from(surface.height()).map_err(|_| Error::TooLarge("surface"))?;
+        if width == 0 || height == 0 {
+            return Image::new(width, height, Vec::new());
+        }
+
+        let pitch = surface.pitch() as usize;
+        let row_size = Image::effective_size(width, 1);
+
+        let mut data = Vec::with_capacity(Image::effective_size(width, height));
+        surface.with_lock(|pixels| {
+            for row in pixels.chunks_exact(pitch).take(height as usize) {
+                data.extend_from_slice(&row[..row_size]);
+            }
+        });
+
+        Image::new(width, height, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image() -> Image {
+        Image::new(3, 2, (0..24).map(|index| index * 10).collect()).unwrap()
+    }
+
+    #[test]
+    fn surfaces_hold_the_pixels() {
+        let image = image().to_aligned(16);
+        let surface = image.to_surface().unwrap();
+        assert_eq!((surface.width(), surface.height()), (3, 2));
+        assert_eq!(surface.pixel_format_enum(), PixelFormatEnum::BGRA32);
+        assert_eq!(
+            Image::from_surface(&surface).unwrap().data(),
+            self::image().data()
+        );
+
+        let empty = Image::new(0, 2, Vec::new()).unwrap();
+        let surface = empty.to_surface().unwrap();
+        assert_eq!(Image::from_surface(&surface).unwrap().height(), 2);
+    }
+
+    #[test]
+    fn borrowed_surfaces_write_into_the_image() {
+        let mut image = image().to_aligned(16);
+        {
+            let mut surface = image.as_surface().unwrap();
+            assert_eq!(surface.pitch(), 16);
+            let color = ::sdl2::pixels::Color::RGBA(1, 2, 3, 4);
+            surface
+                .fill_rect(::sdl2::rect::Rect::new(1, 1, 2, 1), color)
+                .unwrap();
+        }
+        assert_eq!(image.row(1)[4..], [3, 2, 1, 4, 3, 2, 1, 4]);
+        assert_eq!(image.row(0), self::image().row(0));
+    }
+
+    #[test]
+    fn other_formats_are_converted() {
+        let rgb = Surface::new(2, 1, PixelFormatEnum::RGB24).unwrap();
+        let image = Image::from_surface(&rgb).unwrap();
+        assert_eq!(image.data(), [0, 0, 0, 255, 0, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,98 @@
+use ::serde::de::{self, Deserialize, Deserializer};
+use ::serde::ser::{Serialize, Serializer};
+
+use crate::Comments;
+
+impl Serialize for Comments {
+    /// Serializes the comments as an array of their four lines.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.lines().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Comments {
+    /// Fails for lines that could not be stored unchanged, as reported by
+    /// [`Comments::set_line`].
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let lines = <[String; Comments::LINE_COUNT]>::deserialize(deserializer)?;
+        let mut comments = Comments::new();
+        for (index, line) in lines.iter().enumerate() {
+            if !comments.set_line(index, line) {
+                return Err(de::Error::invalid_value(
+                    de::Unexpected::Str(line),
+                    &"a comment line of at most 80 bytes without NULs",
+                ));
+            }
+        }
+
+        Ok(comments)
+    }
+}
+
+/// Serializes the entries of a color correction table as a sequence, which
+/// serde does not derive for arrays as long as the table.
+pub(crate) mod color_correction_entries {
+    use ::serde::de::{self, Deserialize, Deserializer};
+    use ::serde::ser::{Serialize, Serializer};
+
+    use crate::extension::COLOR_CORRECTION_ENTRY_COUNT;
+    use crate::ColorCorrectionEntry;
+
+    type Entries = [ColorCorrectionEntry; COLOR_CORRECTION_ENTRY_COUNT];
+
+    pub(crate) fn serialize<S: Serializer>(
+        entries: &Entries,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        entries.as_slice().serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Entries, D::Error> {
+        let entries = Vec::<ColorCorrectionEntry>::deserialize(deserializer)?;
+        entries.try_into().map_err(|entries: Vec<_>| {
+            de::Error::invalid_length(entries.len(), &"256 color correction entries")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::serde::de::value::{Error, SeqDeserializer};
+    use ::serde::de::IntoDeserializer;
+
+    fn deserialize_comments(lines: [&str; 4]) -> Result<Comments, Error> {
+        let lines = lines.map(String::from);
+        Comments::deserialize(SeqDeserializer::<_, Error>::new(lines.into_iter()))
+    }
+
+    #[test]
+    fn comments_are_read_from_their_lines() {
+        let comments = deserialize_comments(["first", "", "third", "fourth"]).unwrap();
+        assert_eq!(*comments.lines(), ["first", "", "third", "fourth"]);
+
+        let long = "x".repeat(81);
+        let error = deserialize_comments(["", &long, "", ""]).unwrap_err();
+        assert!(error.to_string().contains("at most 80 bytes"), "{error}");
+        assert!(deserialize_comments(["", "", "nul\0", ""]).is_err());
+    }
+
+    #[test]
+    fn color_correction_tables_need_every_entry() {
+        let entries = |count: u16| -> Result<_, Error> {
+            let entries: Vec<Vec<u16>> = (0..count).map(|index| vec![index, 1, 2, 3]).collect();
+            color_correction_entries::deserialize(entries.into_deserializer())
+        };
+        let table = entries(256).unwrap();
+        assert_eq!(table[255].alpha, 255);
+        assert_eq!(table[7].blue, 3);
+
+        let error = entries(3).unwrap_err();
+        assert!(
+            error.to_string().contains("256 color correction entries"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use ::raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+use ::softbuffer::Buffer;
+
+use crate::{Bgra8, Image};
+
+/// Packs a pixel as softbuffer expects, with zero in the top byte followed by
+/// red, green and blue.
+fn pack_0rgb(pixel: Bgra8) -> u32 {
+    u32::from_be_bytes([0, pixel.r, pixel.g, pixel.b])
+}
+
+impl Image {
+    /// Copies the image into `buffer` with its top left corner at `(x, y)`,
+    /// ready to be presented.
+    ///
+    /// Like [`Image::blit_0rgb`], this leaves out the parts of the image
+    /// outside the buffer, leaves the rest of the buffer as it is and ignores
+    /// the alpha channel.
+    pub fn blit_to_buffer<D, W>(&self, buffer: &mut Buffer<'_, D, W>, x: i32, y: i32)
+    where
+        D: HasDisplayHandle,
+        W: HasWindowHandle,
+    {
+        let width = buffer.width().get();
+        self.blit_0rgb(buffer, width, x, y);
+    }
+
+    /// Copies the image into rows of `width` pixels packed as `0x00RRGGBB`,
+    /// with its top left corner at `(x, y)`.
+    ///
+    /// The parts of the image outside `pixels` are left out, and the pixels
+    /// not covered by the image are left as they are. Alpha is ignored, since
+    /// the packed pixels have no room for it.
+    pub fn blit_0rgb(&self, pixels: &mut [u32], width: u32, x: i32, y: i32) {
+        let width = width as i64;
+        if width == 0 {
+            return;
+        }
+        let height = pixels.len() as i64 / width;
+
+        let left = (x as i64).max(0);
+        let top = (y as i64).max(0);
+        let right = (x as i64 + self.width as i64).min(width);
+        let bottom = (y as i64 + self.height as i64).min(height);
+        if left >= right {
+            return;
+        }
+
+        for row in top..bottom {
+            let source = bytemuck::cast_slice::<u8, Bgra8>(self.row((row - y as i64) as usize));
+            let source = &source[(left - x as i64) as usize..(right - x as i64) as usize];
+            let start = (row * width + left) as usize;
+            let destination = &mut pixels[start..start + source.len()];
+            for (packed, &pixel) in destination.iter_mut().zip(source) {
+                *packed = pack_0rgb(pixel);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2 by 2 image whose pixels pack to 1, 2, 3 and 4.
+    fn image() -> Image {
+        let data = (1..=4).flat_map(|index| [index, 0, 0, 0x80]).collect();
+
+        Image::new(2, 2, data).unwrap()
+    }
+
+    #[test]
+    fn pixels_are_packed_without_alpha() {
+        assert_eq!(pack_0rgb(Bgra8::new(3, 2, 1, 0xff)), 0x0001_0203);
+
+        let mut pixels = [u32::MAX; 6];
+        image().blit_0rgb(&mut pixels, 3, 1, 0);
+        assert_eq!(pixels, [u32::MAX, 1, 2, u32::MAX, 3, 4]);
+    }
+
+    #[test]
+    fn parts_outside_the_buffer_are_left_out() {
+        for ((x, y), expected) in [
+            ((-1, -1), [4, 0, 0, 0, 0, 0]),
+            ((2, 1), [0, 0, 0, 0, 0, 1]),
+            ((3, 0), [0; 6]),
+            ((0, 2), [0; 6]),
+            ((i32::MIN, i32::MAX), [0; 6]),
+        ] {
+            let mut pixels = [0; 6];
+            image().blit_0rgb(&mut pixels, 3, x, y);
+            assert_eq!(pixels, expected, "at ({x}, {y})");
+        }
+
+        let mut pixels = [0; 6];
+        image().blit_0rgb(&mut pixels, 0, 0, 0);
+        assert_eq!(pixels, [0; 6]);
+    }
+}
@@ -0,0 +1,115 @@
+use std::num::TryFromIntError;
+
+use ::time::error::ComponentRange;
+use ::time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+use crate::Timestamp;
+
+impl TryFrom<Timestamp> for PrimitiveDateTime {
+    type Error = ComponentRange;
+
+    /// Fails if the stamp is unset or does not describe a valid date and time.
+    fn try_from(value: Timestamp) -> Result<Self, Self::Error> {
+        let month = Month::try_from(saturate(value.month))?;
+        let date = Date::from_calendar_date(value.year as i32, month, saturate(value.day))?;
+        let time = Time::from_hms(
+            saturate(value.hour),
+            saturate(value.minute),
+            saturate(value.second),
+        )?;
+
+        Ok(PrimitiveDateTime::new(date, time))
+    }
+}
+
+impl TryFrom<Timestamp> for OffsetDateTime {
+    type Error = ComponentRange;
+
+    /// Interprets the stamp as UTC, since TGA does not record a time zone.
+    fn try_from(value: Timestamp) -> Result<Self, Self::Error> {
+        PrimitiveDateTime::try_from(value).map(PrimitiveDateTime::assume_utc)
+    }
+}
+
+impl TryFrom<PrimitiveDateTime> for Timestamp {
+    type Error = TryFromIntError;
+
+    /// Fails for years before 0 or after 65535.
+    fn try_from(value: PrimitiveDateTime) -> Result<Self, Self::Error> {
+        Ok(Timestamp {
+            month: value.month() as u16,
+            day: value.day() as u16,
+            year: u16::try_from(value.year())?,
+            hour: value.hour() as u16,
+            minute: value.minute() as u16,
+            second: value.second() as u16,
+        })
+    }
+}
+
+impl TryFrom<OffsetDateTime> for Timestamp {
+    type Error = TryFromIntError;
+
+    /// Converts the date and time to UTC before storing it.
+    fn try_from(value: OffsetDateTime) -> Result<Self, Self::Error> {
+        let value = value.to_offset(::time::UtcOffset::UTC);
+
+        Timestamp::try_from(PrimitiveDateTime::new(value.date(), value.time()))
+    }
+}
+
+/// Narrows a field so that out-of-range values stay out of range.
+fn saturate(value: u16) -> u8 {
+    value.min(u8::MAX as u16) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::time::UtcOffset;
+
+    const STAMP: Timestamp = Timestamp {
+        month: 2,
+        day: 29,
+        year: 2024,
+        hour: 23,
+        minute: 59,
+        second: 58,
+    };
+
+    fn datetime(year: i32, month: Month, day: u8, hour: u8, minute: u8) -> PrimitiveDateTime {
+        let date = Date::from_calendar_date(year, month, day).unwrap();
+
+        PrimitiveDateTime::new(date, Time::from_hms(hour, minute, 58).unwrap())
+    }
+
+    #[test]
+    fn stamps_convert_to_and_from_dates() {
+        let date = datetime(2024, Month::February, 29, 23, 59);
+        assert_eq!(PrimitiveDateTime::try_from(STAMP).unwrap(), date);
+        assert_eq!(Timestamp::try_from(date).unwrap(), STAMP);
+        assert_eq!(OffsetDateTime::try_from(STAMP).unwrap(), date.assume_utc());
+
+        let local = datetime(2024, Month::March, 1, 1, 59)
+            .assume_offset(UtcOffset::from_hms(2, 0, 0).unwrap());
+        assert_eq!(Timestamp::try_from(local).unwrap(), STAMP);
+    }
+
+    #[test]
+    fn invalid_stamps_are_rejected() {
+        for stamp in [
+            Timestamp::default(),
+            Timestamp { day: 30, ..STAMP },
+            Timestamp { month: 13, ..STAMP },
+            Timestamp { hour: 24, ..STAMP },
+            Timestamp {
+                second: 256 + 5,
+                ..STAMP
+            },
+        ] {
+            assert!(PrimitiveDateTime::try_from(stamp).is_err(), "{stamp:?}");
+        }
+
+        assert!(Timestamp::try_from(datetime(-1, Month::January, 1, 0, 0)).is_err());
+    }
+}
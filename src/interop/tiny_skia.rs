@@ -0,0 +1,101 @@
+use std::num::TryFromIntError;
+
+use ::tiny_skia::{ColorU8, Pixmap, PixmapRef};
+
+use crate::{Bgra8, Error, Image};
+
+impl TryFrom<&Image> for Pixmap {
+    type Error = Error;
+
+    /// Premultiplies the color channels by alpha, as pixmaps store them.
+    /// Fails for images with zero width or height, which pixmaps cannot have.
+    fn try_from(image: &Image) -> Result<Self, Self::Error> {
+        let (width, height) = (image.width as u32, image.height as u32);
+        let mut pixmap = Pixmap::new(width, height)
+            .ok_or(Error::InvalidDimensions("image has zero width or height"))?;
+
+        let rows = pixmap.pixels_mut().chunks_exact_mut(width as usize);
+        for (y, row) in rows.enumerate() {
+            let source = bytemuck::cast_slice::<u8, Bgra8>(image.row(y));
+            for (pixel, source) in row.iter_mut().zip(source) {
+                *pixel = ColorU8::from_rgba(source.r, source.g, source.b, source.a).premultiply();
+            }
+        }
+
+        Ok(pixmap)
+    }
+}
+
+impl TryFrom<PixmapRef<'_>> for Image {
+    type Error = TryFromIntError;
+
+    /// Divides the color channels by alpha again, which loses precision for
+    /// translucent pixels. Fails for pixmaps wider or taller than 65535
+    /// pixels.
+    fn try_from(value: PixmapRef<'_>) -> Result<Self, Self::Error> {
+        let width = u16::try_from(value.width())?;
+        let height = u16::try_from(value.height())?;
+        let mut data = Vec::with_capacity(Image::effective_size(width, height));
+        for pixel in value.pixels() {
+            let color = pixel.demultiply();
+            data.extend_from_slice(&[color.blue(), color.green(), color.red(), color.alpha()]);
+        }
+
+        Ok(Image::from_parts(width, height, data))
+    }
+}
+
+impl TryFrom<&Pixmap> for Image {
+    type Error = TryFromIntError;
+
+    /// Like the conversion from [`PixmapRef`].
+    fn try_from(value: &Pixmap) -> Result<Self, Self::Error> {
+        Image::try_from(value.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixmaps_hold_premultiplied_pixels() {
+        let data = vec![10, 20, 30, 255, 200, 100, 50, 128, 0, 0, 0, 0, 9, 9, 9, 255];
+        let image = Image::new(2, 2, data).unwrap().to_aligned(16);
+        let pixmap = Pixmap::try_from(&image).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (2, 2));
+        let pixel = pixmap.pixel(1, 0).unwrap();
+        assert_eq!(
+            [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()],
+            [25, 50, 100, 128]
+        );
+
+        let back = Image::try_from(&pixmap).unwrap();
+        assert_eq!((back.width(), back.height()), (2, 2));
+        for y in 0..2 {
+            for (pixel, expected) in back
+                .row(y)
+                .chunks_exact(4)
+                .zip(image.row(y).chunks_exact(4))
+            {
+                // Translucent pixels lose a little precision.
+                for (channel, expected) in pixel.iter().zip(expected) {
+                    assert!(
+                        channel.abs_diff(*expected) <= 1,
+                        "{pixel:?} != {expected:?}"
+                    );
+                }
+            }
+        }
+        assert_eq!(back.row(0)[..4], [10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn empty_images_are_rejected() {
+        let image = Image::new(0, 2, Vec::new()).unwrap();
+        assert!(matches!(
+            Pixmap::try_from(&image),
+            Err(Error::InvalidDimensions(_))
+        ));
+    }
+}
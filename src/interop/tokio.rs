@@ -0,0 +1,82 @@
+use ::tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{EncodeOptions, Image, Result};
+
+impl Image {
+    /// Like [`Image::write_to`], but writes to an asynchronous writer.
+    pub async fn write_to_async<T: AsyncWrite + Unpin>(&self, w: &mut T) -> Result<()> {
+        self.write_with_options_async(w, &EncodeOptions::default())
+            .await
+    }
+
+    /// Like [`Image::write_with_options`], but writes to an asynchronous
+    /// writer.
+    ///
+    /// The file is encoded into memory on the calling task and then written
+    /// out without blocking. The writer is flushed afterwards.
+    pub async fn write_with_options_async<T: AsyncWrite + Unpin>(
+        &self,
+        w: &mut T,
+        options: &EncodeOptions,
+    ) -> Result<()> {
+        let mut data = Vec::with_capacity(self.encoded_size(options).unwrap_or(0));
+        self.write_with_options(&mut data, options)?;
+        w.write_all(&data).await?;
+        w.flush().await?;
+
+        Ok(())
+    }
+
+    /// Like [`Image::read_from`], but reads from an asynchronous reader.
+    ///
+    /// The file is read into memory until `r` reaches its end, without
+    /// blocking, and then decoded on the calling task as with
+    /// [`decode_untrusted`](crate::decode_untrusted), so uploads of unknown
+    /// origin can be passed straight in. Limit the length of `r`, for example
+    /// with [`AsyncReadExt::take`], to bound the memory used.
+    pub async fn read_from_async<T: AsyncRead + Unpin>(r: &mut T) -> Result<Self> {
+        let mut data = Vec::new();
+        r.read_to_end(&mut data).await?;
+
+        crate::decode_untrusted(&data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interop::block_on;
+
+    #[test]
+    fn images_round_trip_through_async_readers_and_writers() {
+        let mut image = Image::new(5, 4, (0..80).collect()).unwrap();
+        image.add_developer_tag(3, vec![1; 4]);
+        let options = EncodeOptions {
+            rle: true,
+            scan_line_table: true,
+            ..EncodeOptions::default()
+        };
+        let mut file = Vec::new();
+        block_on(image.write_with_options_async(&mut file, &options)).unwrap();
+
+        let mut expected = Vec::new();
+        image.write_with_options(&mut expected, &options).unwrap();
+        assert_eq!(file, expected);
+
+        let decoded = block_on(Image::read_from_async(&mut &file[..])).unwrap();
+        assert_eq!(decoded.data(), image.data());
+        assert_eq!(decoded.developer_tags(), image.developer_tags());
+    }
+
+    #[test]
+    fn limited_readers_cut_files_short() {
+        let image = Image::new(5, 4, (0..80).collect()).unwrap();
+        let mut file = Vec::new();
+        block_on(image.write_to_async(&mut file)).unwrap();
+
+        let mut reader = (&file[..]).take(50);
+        assert!(block_on(Image::read_from_async(&mut reader)).is_err());
+        let mut reader = (&file[..]).take(file.len() as u64);
+        assert!(block_on(Image::read_from_async(&mut reader)).is_ok());
+    }
+}
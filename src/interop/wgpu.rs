@@ -0,0 +1,192 @@
+use std::io;
+use std::sync::mpsc;
+
+use ::wgpu::{
+    BufferDescriptor, BufferUsages, Device, Extent3d, MapMode, PollType, Queue,
+    TexelCopyBufferInfo, TexelCopyBufferLayout, Texture, TextureDescriptor, TextureDimension,
+    TextureFormat, TextureUsages, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+
+use crate::swizzle::swap_red_blue;
+use crate::{parallel, Error, Image, Result};
+
+/// Pixels laid out for copying into a wgpu texture, together with a
+/// description of the texture, as made by [`Image::to_texture_upload`].
+#[derive(Clone, Debug)]
+pub struct TextureUpload {
+    /// A two-dimensional texture the size of the image, holding
+    /// [`TextureFormat::Bgra8UnormSrgb`] texels and usable as a binding and
+    /// as the source or destination of copies.
+    ///
+    /// The format can be changed to [`TextureFormat::Bgra8Unorm`] for images
+    /// that do not hold sRGB colors, without changing the data.
+    pub descriptor: TextureDescriptor<'static>,
+    /// The number of bytes from the start of one row to the next, which wgpu
+    /// requires to be a multiple of 256.
+    pub bytes_per_row: u32,
+    /// The rows of pixels from top to bottom, each padded to `bytes_per_row`.
+    pub data: Vec<u8>,
+}
+
+impl TextureUpload {
+    /// Creates the texture on `device` and queues writing the pixels to it.
+    pub fn create_texture(&self, device: &Device, queue: &Queue) -> Texture {
+        let texture = device.create_texture(&self.descriptor);
+        let layout = TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(self.bytes_per_row),
+            rows_per_image: None,
+        };
+        queue.write_texture(
+            texture.as_image_copy(),
+            &self.data,
+            layout,
+            self.descriptor.size,
+        );
+
+        texture
+    }
+}
+
+impl Image {
+    /// Lays the pixels out for copying into a wgpu texture, padding each row
+    /// to the pitch wgpu requires.
+    ///
+    /// The pixels are copied as they are, since their blue, green, red, alpha
+    /// order matches the format of the texture.
+    pub fn to_texture_upload(&self) -> TextureUpload {
+        let image = self.to_aligned(COPY_BYTES_PER_ROW_ALIGNMENT as usize);
+        let descriptor = TextureDescriptor {
+            label: None,
+            size: Extent3d {
+                width: self.width as u32,
+                height: self.height as u32,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        };
+
+        TextureUpload {
+            descriptor,
+            bytes_per_row: image.stride as u32,
+            data: image.data,
+        }
+    }
+
+    /// Reads the first mip level of a two-dimensional `texture` back into an
+    /// image, blocking until the GPU has copied it.
+    ///
+    /// The texture must have been created with [`TextureUsages::COPY_SRC`]
+    /// and hold 8-bit BGRA or RGBA texels, in either the linear or the sRGB
+    /// variant. The rows of the image keep the padding of the copy, which
+    /// [`Image::to_aligned`] can remove.
+    ///
+    /// On the web, where waiting for the GPU is not possible, this never
+    /// returns.
+    pub fn read_texture(device: &Device, queue: &Queue, texture: &Texture) -> Result<Image> {
+        let is_rgba = match texture.format() {
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => false,
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => true,
+            _ => {
+                let message = "texture does not hold 8-bit BGRA or RGBA texels";
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message).into());
+            }
+        };
+        let width = u16::try_from(texture.width()).map_err(|_| Error::TooLarge("texture"))?;
+        let height = u16::try_from(texture.height()).map_err(|_| Error::TooLarge("texture"))?;
+
+        let bytes_per_row = Image::aligned_stride(width, COPY_BYTES_PER_ROW_ALIGNMENT as usize);
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: None,
+            size: (bytes_per_row * height as usize) as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&Default::default());
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row as u32),
+                    rows_per_image: None,
+                },
+            },
+            Extent3d {
+                width: width as u32,
+                height: height as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+        let submission_index = queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = mpsc::channel();
+        buffer.map_async(MapMode::Read, .., move |result| {
+            let _ = sender.send(result);
+        });
+        device
+            .poll(PollType::Wait {
+                submission_index: Some(submission_index),
+                timeout: None,
+            })
+            .map_err(io::Error::other)?;
+        receiver
+            .recv()
+            .map_err(io::Error::other)?
+            .map_err(io::Error::other)?;
+
+        let mut data = buffer
+            .get_mapped_range(..)
+            .map_err(io::Error::other)?
+            .to_vec();
+        buffer.unmap();
+        if is_rgba {
+            parallel::for_each_block(&mut data, swap_red_blue);
+        }
+
+        Ok(Image::with_stride(width, height, bytes_per_row, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uploads_pad_rows_to_the_copy_alignment() {
+        let image = Image::new(3, 2, (0..24).collect()).unwrap();
+        let upload = image.to_texture_upload();
+        assert_eq!(upload.bytes_per_row, COPY_BYTES_PER_ROW_ALIGNMENT);
+        assert_eq!(
+            upload.descriptor.size,
+            Extent3d {
+                width: 3,
+                height: 2,
+                depth_or_array_layers: 1
+            }
+        );
+        assert_eq!(upload.descriptor.format, TextureFormat::Bgra8UnormSrgb);
+        assert!(upload.descriptor.usage.contains(TextureUsages::COPY_DST));
+
+        let stride = upload.bytes_per_row as usize;
+        assert!(upload.data.len() >= stride + 12);
+        assert_eq!(upload.data[..12], image.row(0)[..]);
+        assert_eq!(upload.data[stride..stride + 12], image.row(1)[..]);
+    }
+
+    #[test]
+    fn wide_rows_are_padded_to_the_next_multiple() {
+        let image = Image::new(65, 1, vec![7; 260]).unwrap();
+        let upload = image.to_texture_upload();
+        assert_eq!(upload.bytes_per_row, 2 * COPY_BYTES_PER_ROW_ALIGNMENT);
+        assert_eq!(upload.data[..260], [7; 260]);
+    }
+}
@@ -0,0 +1,265 @@
+use std::io::{Read, Seek};
+
+use ::zune_core::bit_depth::BitDepth;
+use ::zune_core::bytestream::ZByteWriterTrait;
+use ::zune_core::colorspace::ColorSpace;
+use ::zune_image::codecs::ImageFormat;
+use ::zune_image::errors::{ImageErrors, ImgEncodeErrors};
+use ::zune_image::metadata::{AlphaState, ImageMetadata};
+use ::zune_image::traits::{DecoderTrait, EncoderTrait};
+
+use crate::{AttributesType, Decoder, EncodeOptions, Error, ExtensionArea, Image};
+
+/// An encoder for zune-image, writing images through
+/// [`Image::write_with_options`].
+///
+/// 8-bit grayscale, RGB, RGBA and BGRA pixels, with or without alpha, are
+/// accepted, and zune-image converts pixels of other kinds to RGBA first.
+/// Images with premultiplied alpha are marked as such in the extension area.
+#[derive(Clone, Debug, Default)]
+pub struct ZuneEncoder {
+    options: EncodeOptions,
+}
+
+impl ZuneEncoder {
+    /// Encodes uncompressed 32-bit pixels.
+    pub fn new() -> Self {
+        ZuneEncoder::default()
+    }
+
+    /// Encodes pixels as described by `options`.
+    pub fn with_options(options: EncodeOptions) -> Self {
+        ZuneEncoder { options }
+    }
+}
+
+impl<R: Read + Seek> DecoderTrait for Decoder<R> {
+    /// Reads the pixels as [`Decoder::read_image`] does, dropping the alpha
+    /// channel of pixels without one.
+    fn decode(&mut self) -> Result<::zune_image::image::Image, ImageErrors> {
+        let mut data = vec![0; Image::effective_size(self.width(), self.height())];
+        self.read_image_into(&mut data)?;
+
+        let colorspace = DecoderTrait::out_colorspace(self);
+        let pixels: Vec<u8> = if colorspace == ColorSpace::RGBA {
+            data.chunks_exact(4)
+                .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+                .collect()
+        } else {
+            data.chunks_exact(4)
+                .flat_map(|pixel| [pixel[2], pixel[1], pixel[0]])
+                .collect()
+        };
+        let (width, height) = (self.width() as usize, self.height() as usize);
+        let mut image = ::zune_image::image::Image::from_u8(&pixels, width, height, colorspace);
+        if let Some(metadata) = DecoderTrait::read_headers(self)? {
+            *image.metadata_mut() = metadata;
+        }
+
+        Ok(image)
+    }
+
+    fn dimensions(&self) -> Option<(usize, usize)> {
+        Some((self.width() as usize, self.height() as usize))
+    }
+
+    /// [`ColorSpace::RGBA`] for pixels with alpha and [`ColorSpace::RGB`] for
+    /// the others.
+    fn out_colorspace(&self) -> ColorSpace {
+        if self.has_alpha() {
+            ColorSpace::RGBA
+        } else {
+            ColorSpace::RGB
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "TGA Decoder"
+    }
+
+    /// Describes the image, including its gamma and whether its alpha is
+    /// premultiplied, as recorded in the extension area.
+    fn read_headers(&mut self) -> Result<Option<ImageMetadata>, ImageErrors> {
+        let mut metadata = ImageMetadata::default();
+        metadata.set_dimensions(self.width() as usize, self.height() as usize);
+        metadata.set_colorspace(DecoderTrait::out_colorspace(self));
+        metadata.set_depth(BitDepth::Eight);
+        if let Some(extension_area) = self.extension_area() {
+            let gamma = extension_area.gamma;
+            if gamma.denominator != 0 {
+                metadata.set_default_gamma(gamma.numerator as f32 / gamma.denominator as f32);
+            }
+            if extension_area.attributes_type == AttributesType::Premultiplied {
+                metadata.set_alpha(AlphaState::PreMultiplied);
+            }
+        }
+
+        Ok(Some(metadata))
+    }
+}
+
+impl EncoderTrait for ZuneEncoder {
+    fn name(&self) -> &'static str {
+        "tga"
+    }
+
+    fn encode_inner<T: ZByteWriterTrait>(
+        &mut self,
+        image: &::zune_image::image::Image,
+        mut sink: T,
+    ) -> Result<usize, ImageErrors> {
+        let (width, height) = image.dimensions();
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(ImgEncodeErrors::GenericStatic("image is too large for TGA").into());
+        };
+
+        let frames = image.flatten_to_u8();
+        let colorspace = image.colorspace();
+        let data = frames[0]
+            .chunks_exact(colorspace.num_components())
+            .flat_map(|pixel| match *pixel {
+                [b, g, r, a] if colorspace == ColorSpace::BGRA => [b, g, r, a],
+                [l] => [l, l, l, u8::MAX],
+                [l, a] => [l, l, l, a],
+                [r, g, b] => [b, g, r, u8::MAX],
+                [r, g, b, a] => [b, g, r, a],
+                _ => unreachable!("pixels have one to four channels"),
+            })
+            .collect();
+
+        let mut tga = Image::from_parts(width, height, data);
+        if image.metadata().is_premultiplied_alpha() {
+            tga.set_extension_area(Some(ExtensionArea {
+                attributes_type: AttributesType::Premultiplied,
+                ..ExtensionArea::default()
+            }));
+        }
+        let mut buffer = Vec::new();
+        tga.write_with_options(&mut buffer, &self.options)
+            .map_err(|error| match error {
+                Error::Io(error) => ImageErrors::IoError(error),
+                error => ImgEncodeErrors::ImageEncodeErrors(error.to_string()).into(),
+            })?;
+        sink.write_all_bytes(&buffer)?;
+
+        Ok(buffer.len())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::Luma,
+            ColorSpace::LumaA,
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::BGRA,
+        ]
+    }
+
+    /// Always [`ImageFormat::Unknown`], since zune-image has no variant for
+    /// TGA.
+    fn format(&self) -> ImageFormat {
+        ImageFormat::Unknown
+    }
+
+    fn supported_bit_depth(&self) -> &'static [BitDepth] {
+        &[BitDepth::Eight]
+    }
+
+    fn default_depth(&self, _: BitDepth) -> BitDepth {
+        BitDepth::Eight
+    }
+
+    fn default_colorspace(&self, _: ColorSpace) -> ColorSpace {
+        ColorSpace::RGBA
+    }
+}
+
+impl From<Error> for ImageErrors {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Io(error) => ImageErrors::IoError(error),
+            error => ImageErrors::ImageDecodeErrors(error.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::Cursor;
+    use crate::PixelFormat;
+
+    fn image() -> Image {
+        Image::new(2, 1, vec![1, 2, 3, 4, 5, 6, 7, 8]).unwrap()
+    }
+
+    fn decode(file: &[u8]) -> ::zune_image::image::Image {
+        let mut decoder = Decoder::new(Cursor::new(file)).unwrap();
+        DecoderTrait::decode(&mut decoder).unwrap()
+    }
+
+    #[test]
+    fn decoded_pixels_are_rgb_or_rgba() {
+        let mut file = Vec::new();
+        image().write_to(&mut file).unwrap();
+        let decoded = decode(&file);
+        assert_eq!(decoded.colorspace(), ColorSpace::RGBA);
+        assert_eq!(decoded.dimensions(), (2, 1));
+        assert_eq!(decoded.flatten_to_u8()[0], [3, 2, 1, 4, 7, 6, 5, 8]);
+
+        let options = EncodeOptions {
+            pixel_format: PixelFormat::Bgr24,
+            ..EncodeOptions::default()
+        };
+        let mut file = Vec::new();
+        image().write_with_options(&mut file, &options).unwrap();
+        let decoded = decode(&file);
+        assert_eq!(decoded.colorspace(), ColorSpace::RGB);
+        assert_eq!(decoded.flatten_to_u8()[0], [3, 2, 1, 7, 6, 5]);
+    }
+
+    #[test]
+    fn premultiplied_alpha_is_kept_in_the_extension_area() {
+        let mut image = image();
+        image.set_extension_area(Some(ExtensionArea {
+            attributes_type: AttributesType::Premultiplied,
+            ..ExtensionArea::default()
+        }));
+        let mut file = Vec::new();
+        image.write_to(&mut file).unwrap();
+        let decoded = decode(&file);
+        assert!(decoded.metadata().is_premultiplied_alpha());
+
+        let mut encoded = Vec::new();
+        ZuneEncoder::new().encode(&decoded, &mut encoded).unwrap();
+        let reread = Image::read_from(&mut Cursor::new(&encoded[..])).unwrap();
+        assert_eq!(reread.data(), image.data());
+        let attributes_type = reread.extension_area().unwrap().attributes_type;
+        assert_eq!(attributes_type, AttributesType::Premultiplied);
+    }
+
+    #[test]
+    fn every_supported_colorspace_is_encoded() {
+        for (colorspace, pixel, expected) in [
+            (ColorSpace::Luma, &[7][..], [7, 7, 7, 255]),
+            (ColorSpace::LumaA, &[7, 9], [7, 7, 7, 9]),
+            (ColorSpace::RGB, &[1, 2, 3], [3, 2, 1, 255]),
+            (ColorSpace::RGBA, &[1, 2, 3, 4], [3, 2, 1, 4]),
+            (ColorSpace::BGRA, &[1, 2, 3, 4], [1, 2, 3, 4]),
+        ] {
+            let image = ::zune_image::image::Image::from_u8(pixel, 1, 1, colorspace);
+            let options = EncodeOptions {
+                rle: true,
+                ..EncodeOptions::default()
+            };
+            let mut encoded = Vec::new();
+            let length = ZuneEncoder::with_options(options)
+                .encode(&image, &mut encoded)
+                .unwrap();
+            assert_eq!(length, encoded.len());
+            let decoded = Image::read_from(&mut Cursor::new(&encoded[..])).unwrap();
+            assert_eq!(decoded.data(), expected, "{colorspace:?}");
+            assert!(decoded.extension_area().is_none());
+        }
+    }
+}
@@ -0,0 +1,628 @@
+//! The traits images are read and written through.
+//!
+//! With the `std` feature, which is enabled by default, these are the ones of
+//! `std::io`. Without it, this module provides a minimal stand-in for them,
+//! implemented for byte slices, vectors and [`Cursor`]s, so that images can be
+//! decoded from and encoded to memory with only `alloc`.
+//!
+//! The stand-in traits are sealed, since enabling `std` anywhere in the
+//! dependency graph swaps them for those of `std::io` and would break any
+//! implementation of them. Other sources and sinks, such as flash memory, are
+//! read and written through `EmbeddedIo` with the `embedded-io` feature
+//! instead, which implements whichever of the traits are in use for the
+//! readers and writers of the `embedded-io` crate.
+
+#[cfg(feature = "std")]
+pub(crate) use byteorder::{ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+pub use std::io::{Cursor, Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+
+#[cfg(not(feature = "std"))]
+pub use self::core_io::{Cursor, Error, ErrorKind, Read, Result, Seek, SeekFrom, Take, Write};
+#[cfg(not(feature = "std"))]
+pub(crate) use self::core_io::{ReadBytesExt, Sealed, WriteBytesExt};
+
+#[cfg(not(feature = "std"))]
+mod core_io {
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+    use core::{cmp, fmt};
+
+    use byteorder::ByteOrder;
+
+    /// The result of reading or writing.
+    pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+    /// The kind of an [`Error`], named after the `std::io` kind it stands for.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+    #[non_exhaustive]
+    pub enum ErrorKind {
+        InvalidInput,
+        InvalidData,
+        UnexpectedEof,
+        WriteZero,
+        Interrupted,
+        Unsupported,
+        Other,
+    }
+
+    impl ErrorKind {
+        fn description(self) -> &'static str {
+            match self {
+                ErrorKind::InvalidInput => "invalid input parameter",
+                ErrorKind::InvalidData => "invalid data",
+                ErrorKind::UnexpectedEof => "unexpected end of file",
+                ErrorKind::WriteZero => "write zero",
+                ErrorKind::Interrupted => "operation interrupted",
+                ErrorKind::Unsupported => "unsupported",
+                ErrorKind::Other => "other error",
+            }
+        }
+    }
+
+    impl fmt::Display for ErrorKind {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.description())
+        }
+    }
+
+    /// An error raised while reading or writing, of some [`ErrorKind`] and
+    /// optionally caused by another error.
+    #[derive(Debug)]
+    pub struct Error {
+        kind: ErrorKind,
+        error: Option<Box<dyn core::error::Error + Send + Sync>>,
+    }
+
+    impl Error {
+        pub fn new<E>(kind: ErrorKind, error: E) -> Self
+        where
+            E: Into<Box<dyn core::error::Error + Send + Sync>>,
+        {
+            Error {
+                kind,
+                error: Some(error.into()),
+            }
+        }
+
+        pub fn other<E>(error: E) -> Self
+        where
+            E: Into<Box<dyn core::error::Error + Send + Sync>>,
+        {
+            Error::new(ErrorKind::Other, error)
+        }
+
+        pub fn kind(&self) -> ErrorKind {
+            self.kind
+        }
+
+        pub fn get_ref(&self) -> Option<&(dyn core::error::Error + Send + Sync + 'static)> {
+            self.error.as_deref()
+        }
+
+        pub fn into_inner(self) -> Option<Box<dyn core::error::Error + Send + Sync>> {
+            self.error
+        }
+    }
+
+    impl From<ErrorKind> for Error {
+        fn from(kind: ErrorKind) -> Self {
+            Error { kind, error: None }
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match &self.error {
+                Some(error) => error.fmt(f),
+                None => self.kind.fmt(f),
+            }
+        }
+    }
+
+    impl core::error::Error for Error {
+        fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+            self.error.as_ref()?.source()
+        }
+    }
+
+    /// Keeps types outside the crate from implementing [`Read`], [`Write`]
+    /// and [`Seek`].
+    pub trait Sealed {}
+
+    /// A source of bytes.
+    ///
+    /// This trait is sealed; see the [module documentation](super).
+    pub trait Read: Sealed {
+        /// Reads up to `buf.len()` bytes into `buf`, returning how many were
+        /// read. Returns zero only at the end of the source or if `buf` is
+        /// empty.
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+
+        /// Fills `buf`, failing with [`ErrorKind::UnexpectedEof`] if the
+        /// source ends first.
+        fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.read(buf) {
+                    Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                    Ok(length) => buf = &mut buf[length..],
+                    Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                    Err(error) => return Err(error),
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Appends bytes to `buf` until the end of the source, returning how
+        /// many were read.
+        fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
+            let start = buf.len();
+            let mut chunk = [0; 512];
+            loop {
+                match self.read(&mut chunk) {
+                    Ok(0) => return Ok(buf.len() - start),
+                    Ok(length) => buf.extend_from_slice(&chunk[..length]),
+                    Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                    Err(error) => return Err(error),
+                }
+            }
+        }
+
+        /// Reads at most `limit` bytes from the source.
+        fn take(self, limit: u64) -> Take<Self>
+        where
+            Self: Sized,
+        {
+            Take { inner: self, limit }
+        }
+
+        fn by_ref(&mut self) -> &mut Self
+        where
+            Self: Sized,
+        {
+            self
+        }
+    }
+
+    /// A sink for bytes.
+    ///
+    /// This trait is sealed; see the [module documentation](super).
+    pub trait Write: Sealed {
+        /// Writes some of `buf`, returning how many bytes were written.
+        fn write(&mut self, buf: &[u8]) -> Result<usize>;
+
+        fn flush(&mut self) -> Result<()>;
+
+        /// Writes all of `buf`, failing with [`ErrorKind::WriteZero`] if the
+        /// sink is full.
+        fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.write(buf) {
+                    Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                    Ok(length) => buf = &buf[length..],
+                    Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                    Err(error) => return Err(error),
+                }
+            }
+
+            Ok(())
+        }
+
+        fn by_ref(&mut self) -> &mut Self
+        where
+            Self: Sized,
+        {
+            self
+        }
+    }
+
+    /// A position to seek to.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub enum SeekFrom {
+        /// A number of bytes from the start.
+        Start(u64),
+        /// A number of bytes from the end, usually negative.
+        End(i64),
+        /// A number of bytes from the current position.
+        Current(i64),
+    }
+
+    /// A source or sink whose position can be moved.
+    ///
+    /// This trait is sealed; see the [module documentation](super).
+    pub trait Seek: Sealed {
+        /// Moves to `position`, returning the new position from the start.
+        fn seek(&mut self, position: SeekFrom) -> Result<u64>;
+
+        fn rewind(&mut self) -> Result<()> {
+            self.seek(SeekFrom::Start(0)).map(|_| ())
+        }
+
+        fn stream_position(&mut self) -> Result<u64> {
+            self.seek(SeekFrom::Current(0))
+        }
+    }
+
+    impl<T: Sealed + ?Sized> Sealed for &mut T {}
+    impl Sealed for &[u8] {}
+    impl Sealed for &mut [u8] {}
+    impl Sealed for Vec<u8> {}
+    impl<R> Sealed for Take<R> {}
+    impl<T> Sealed for Cursor<T> {}
+
+    impl<R: Read + ?Sized> Read for &mut R {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            (**self).read(buf)
+        }
+    }
+
+    impl<W: Write + ?Sized> Write for &mut W {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            (**self).write(buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            (**self).flush()
+        }
+    }
+
+    impl<S: Seek + ?Sized> Seek for &mut S {
+        fn seek(&mut self, position: SeekFrom) -> Result<u64> {
+            (**self).seek(position)
+        }
+    }
+
+    impl Read for &[u8] {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let length = cmp::min(buf.len(), self.len());
+            let (read, rest) = self.split_at(length);
+            buf[..length].copy_from_slice(read);
+            *self = rest;
+
+            Ok(length)
+        }
+    }
+
+    impl Write for &mut [u8] {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            let length = cmp::min(buf.len(), self.len());
+            let (written, rest) = core::mem::take(self).split_at_mut(length);
+            written.copy_from_slice(&buf[..length]);
+            *self = rest;
+
+            Ok(length)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Write for Vec<u8> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.extend_from_slice(buf);
+
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Reads at most a limited number of bytes from another source, as
+    /// returned by [`Read::take`].
+    #[derive(Debug)]
+    pub struct Take<R> {
+        inner: R,
+        limit: u64,
+    }
+
+    impl<R> Take<R> {
+        /// The number of bytes left to read before the limit.
+        pub fn limit(&self) -> u64 {
+            self.limit
+        }
+
+        pub fn into_inner(self) -> R {
+            self.inner
+        }
+    }
+
+    impl<R: Read> Read for Take<R> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let length = cmp::min(buf.len() as u64, self.limit) as usize;
+            let length = self.inner.read(&mut buf[..length])?;
+            self.limit -= length as u64;
+
+            Ok(length)
+        }
+    }
+
+    /// Makes a buffer in memory readable, writable and seekable, keeping the
+    /// position of the next byte in it.
+    #[derive(Clone, Debug, Default, Eq, PartialEq)]
+    pub struct Cursor<T> {
+        inner: T,
+        position: u64,
+    }
+
+    impl<T> Cursor<T> {
+        pub fn new(inner: T) -> Self {
+            Cursor { inner, position: 0 }
+        }
+
+        pub fn into_inner(self) -> T {
+            self.inner
+        }
+
+        pub fn get_ref(&self) -> &T {
+            &self.inner
+        }
+
+        pub fn get_mut(&mut self) -> &mut T {
+            &mut self.inner
+        }
+
+        pub fn position(&self) -> u64 {
+            self.position
+        }
+
+        pub fn set_position(&mut self, position: u64) {
+            self.position = position;
+        }
+    }
+
+    impl<T: AsRef<[u8]>> Cursor<T> {
+        /// The bytes after the position, which are empty past the end.
+        fn remaining(&self) -> &[u8] {
+            let inner = self.inner.as_ref();
+            let start = cmp::min(self.position, inner.len() as u64) as usize;
+
+            &inner[start..]
+        }
+    }
+
+    impl<T: AsRef<[u8]>> Read for Cursor<T> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let length = self.remaining().read(buf)?;
+            self.position += length as u64;
+
+            Ok(length)
+        }
+    }
+
+    impl<T: AsRef<[u8]>> Seek for Cursor<T> {
+        fn seek(&mut self, position: SeekFrom) -> Result<u64> {
+            let (base, offset) = match position {
+                SeekFrom::Start(offset) => {
+                    self.position = offset;
+                    return Ok(offset);
+                }
+                SeekFrom::End(offset) => (self.inner.as_ref().len() as u64, offset),
+                SeekFrom::Current(offset) => (self.position, offset),
+            };
+
+            match base.checked_add_signed(offset) {
+                Some(position) => {
+                    self.position = position;
+                    Ok(position)
+                }
+                None => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "invalid seek to a negative or overflowing position",
+                )),
+            }
+        }
+    }
+
+    impl Write for Cursor<&mut [u8]> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            let start = cmp::min(self.position, self.inner.len() as u64) as usize;
+            let length = (&mut self.inner[start..]).write(buf)?;
+            self.position += length as u64;
+
+            Ok(length)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Write for Cursor<Vec<u8>> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            write_to_vec(&mut self.position, &mut self.inner, buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Write for Cursor<&mut Vec<u8>> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            write_to_vec(&mut self.position, self.inner, buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Overwrites the bytes of `vec` at `position` with `buf`, growing it as
+    /// needed and filling any gap before `position` with zeros.
+    fn write_to_vec(position: &mut u64, vec: &mut Vec<u8>, buf: &[u8]) -> Result<usize> {
+        let start = usize::try_from(*position)
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "cursor position exceeds usize"))?;
+        let end = start + buf.len();
+        if vec.len() < end {
+            vec.resize(end, 0);
+        }
+        vec[start..end].copy_from_slice(buf);
+        *position = end as u64;
+
+        Ok(buf.len())
+    }
+
+    /// Reads integers, as `byteorder::ReadBytesExt` does.
+    pub(crate) trait ReadBytesExt: Read {
+        fn read_u8(&mut self) -> Result<u8> {
+            let mut buf = [0; 1];
+            self.read_exact(&mut buf)?;
+
+            Ok(buf[0])
+        }
+
+        fn read_u16<B: ByteOrder>(&mut self) -> Result<u16> {
+            let mut buf = [0; 2];
+            self.read_exact(&mut buf)?;
+
+            Ok(B::read_u16(&buf))
+        }
+
+        fn read_u32<B: ByteOrder>(&mut self) -> Result<u32> {
+            let mut buf = [0; 4];
+            self.read_exact(&mut buf)?;
+
+            Ok(B::read_u32(&buf))
+        }
+
+        #[cfg(feature = "bmp")]
+        fn read_i32<B: ByteOrder>(&mut self) -> Result<i32> {
+            let mut buf = [0; 4];
+            self.read_exact(&mut buf)?;
+
+            Ok(B::read_i32(&buf))
+        }
+    }
+
+    impl<R: Read + ?Sized> ReadBytesExt for R {}
+
+    /// Writes integers, as `byteorder::WriteBytesExt` does.
+    pub(crate) trait WriteBytesExt: Write {
+        fn write_u8(&mut self, n: u8) -> Result<()> {
+            self.write_all(&[n])
+        }
+
+        fn write_u16<B: ByteOrder>(&mut self, n: u16) -> Result<()> {
+            let mut buf = [0; 2];
+            B::write_u16(&mut buf, n);
+
+            self.write_all(&buf)
+        }
+
+        fn write_u32<B: ByteOrder>(&mut self, n: u32) -> Result<()> {
+            let mut buf = [0; 4];
+            B::write_u32(&mut buf, n);
+
+            self.write_all(&buf)
+        }
+
+        #[cfg(feature = "bmp")]
+        fn write_i32<B: ByteOrder>(&mut self, n: i32) -> Result<()> {
+            let mut buf = [0; 4];
+            B::write_i32(&mut buf, n);
+
+            self.write_all(&buf)
+        }
+    }
+
+    impl<W: Write + ?Sized> WriteBytesExt for W {}
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use alloc::string::ToString;
+        use byteorder::LittleEndian;
+
+        #[test]
+        fn cursors_read_write_and_seek() {
+            let mut cursor = Cursor::new(Vec::new());
+            cursor.write_all(b"abcdef").unwrap();
+            assert_eq!(cursor.seek(SeekFrom::End(-2)).unwrap(), 4);
+            cursor.write_all(b"EFGH").unwrap();
+            assert_eq!(cursor.seek(SeekFrom::Start(10)).unwrap(), 10);
+            cursor.write_all(b"!").unwrap();
+            assert_eq!(cursor.get_ref(), b"abcdEFGH\0\0!");
+
+            cursor.rewind().unwrap();
+            let mut buf = [0; 3];
+            cursor.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"abc");
+            assert_eq!(cursor.seek(SeekFrom::Current(2)).unwrap(), 5);
+            let mut rest = Vec::new();
+            assert_eq!(cursor.read_to_end(&mut rest).unwrap(), 6);
+            assert_eq!(rest, b"FGH\0\0!");
+
+            // Reading past the end finds nothing rather than failing.
+            cursor.set_position(100);
+            assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+            assert_eq!(cursor.stream_position().unwrap(), 100);
+        }
+
+        #[test]
+        fn invalid_seeks_are_rejected() {
+            let mut cursor = Cursor::new(&[1, 2, 3][..]);
+            let error = cursor.seek(SeekFrom::Current(-1)).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+            assert!(cursor.seek(SeekFrom::End(-4)).is_err());
+            assert_eq!(cursor.position(), 0);
+        }
+
+        #[test]
+        fn sources_and_sinks_report_their_ends() {
+            let mut buf = [0; 4];
+            let error = (&b"abc"[..]).read_exact(&mut buf).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+
+            let mut sink = [0; 2];
+            let error = (&mut sink[..]).write_all(b"abc").unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::WriteZero);
+            assert_eq!(sink, *b"ab");
+
+            let mut slice = [0; 2];
+            let mut cursor = Cursor::new(&mut slice[..]);
+            assert_eq!(cursor.write(b"xyz").unwrap(), 2);
+            assert_eq!(cursor.write(b"z").unwrap(), 0);
+        }
+
+        #[test]
+        fn takes_stop_at_their_limit() {
+            let mut take = (&b"abcdef"[..]).take(4);
+            let mut read = Vec::new();
+            assert_eq!(take.read_to_end(&mut read).unwrap(), 4);
+            assert_eq!(read, b"abcd");
+            assert_eq!(take.limit(), 0);
+            assert_eq!(take.into_inner(), b"ef");
+        }
+
+        #[test]
+        fn integers_round_trip() {
+            let mut data = Vec::new();
+            data.write_u8(1).unwrap();
+            data.write_u16::<LittleEndian>(0x0203).unwrap();
+            data.write_u32::<LittleEndian>(0x0405_0607).unwrap();
+            assert_eq!(data[..7], [1, 3, 2, 7, 6, 5, 4]);
+
+            let mut r = &data[..];
+            assert_eq!(r.read_u8().unwrap(), 1);
+            assert_eq!(r.read_u16::<LittleEndian>().unwrap(), 0x0203);
+            assert_eq!(r.read_u32::<LittleEndian>().unwrap(), 0x0405_0607);
+            assert!(r.read_u8().is_err());
+        }
+
+        #[test]
+        fn errors_describe_their_kind_and_cause() {
+            let error = Error::from(ErrorKind::InvalidData);
+            assert_eq!(error.to_string(), "invalid data");
+            assert!(error.get_ref().is_none());
+
+            let error = Error::other("disk on fire");
+            assert_eq!(error.kind(), ErrorKind::Other);
+            assert_eq!(error.to_string(), "disk on fire");
+            assert!(error.into_inner().is_some());
+        }
+    }
+}
@@ -1,27 +1,164 @@
-use std::io::Write;
-use std::{io, mem, slice};
-use byteorder::{LittleEndian, WriteBytesExt};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use bytemuck::{Pod, Zeroable};
+use byteorder::LittleEndian;
+use io::{Read, ReadBytesExt, Seek, Write, WriteBytesExt};
+
+pub use codec::{Codec, ReadSeek, TgaDecode, TgaEncode};
+pub use decoder::{decode_untrusted, DecodeOptions, Decoded, Decoder, ViolationPolicy};
+pub use developer::DeveloperTag;
+pub use diagnostic::{Diagnostic, DiagnosticCode, Severity};
+pub use editor::MetadataEditor;
+pub use encoder::{EncodeOptions, PixelFormat};
+pub use error::{Context, Error, Result};
+pub use extension::{
+    AttributesType, ColorCorrectionEntry, ColorCorrectionTable, Comments, ExtensionArea, JobTime,
+    ParseSoftwareVersionError, Ratio, SoftwareVersion, Timestamp,
+};
+#[cfg(feature = "png")]
+pub use interop::transcode_to_png;
+#[cfg(feature = "embedded-io")]
+pub use interop::EmbeddedIo;
+#[cfg(feature = "image")]
+pub use interop::Encoder;
+#[cfg(feature = "wgpu")]
+pub use interop::TextureUpload;
+#[cfg(feature = "zune-image")]
+pub use interop::ZuneEncoder;
+#[cfg(feature = "mmap")]
+pub use mmap::MappedImage;
+#[cfg(feature = "std")]
+pub use pool::BufferPool;
+pub use source::{decode, decode_with_options, Seekable, Source, Streaming};
+#[cfg(feature = "futures-core")]
+pub use stream::{Row, RowStream};
+#[cfg(feature = "std")]
+pub use transcode::transcode_pipelined;
+pub use transcode::{transcode, TranscodeOptions};
+#[cfg(all(feature = "uring", target_os = "linux"))]
+pub use uring::UringWriter;
+pub use validate::{validate_header, HeaderViolation};
+
+#[cfg(feature = "atlas")]
+pub mod atlas;
+#[cfg(feature = "bmp")]
+mod bmp;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod codec;
+#[cfg(feature = "dds")]
+mod dds;
+mod decoder;
+mod developer;
+mod diagnostic;
+mod draw;
+mod editor;
+mod encoder;
+mod error;
+mod extension;
+mod flip;
+mod interop;
+pub mod io;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod parallel;
+#[cfg(feature = "std")]
+mod pool;
+#[cfg(feature = "pyo3")]
+mod python;
+mod rle;
+mod source;
+#[cfg(feature = "futures-core")]
+mod stream;
+mod swizzle;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod tile;
+mod trace;
+mod transcode;
+#[cfg(all(feature = "uring", target_os = "linux"))]
+mod uring;
+mod validate;
+#[cfg(feature = "wasm")]
+mod wasm;
 
 const SIGNATURE: [u8; SIGNATURE_SIZE] = *b"TRUEVISION-XFILE";
 const SIGNATURE_SIZE: usize = 16;
 const HEADER_SIZE: usize = 18;
 const FOOTER_SIZE: usize = 26;
+const MAX_POSTAGE_STAMP_SIZE: u16 = 64;
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
-struct BitDepth(u8);
+/// The number of bits per pixel, as told by the header.
+///
+/// This counts the bits of a color map index for color-mapped images, and
+/// includes the alpha bits, so that 32-bit pixels have 8 of them.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PixelDepth {
+    /// Grayscale pixels or color map indices.
+    B8,
+    /// 5 bits per color channel.
+    B15,
+    /// 5 bits per color channel and an attribute bit, or 8-bit grayscale
+    /// pixels with 8 alpha bits.
+    B16,
+    /// 8 bits per color channel.
+    B24,
+    /// 8 bits per color channel and 8 alpha bits.
+    #[default]
+    B32,
+    /// A depth the spec does not define. Converting a byte only gives this
+    /// for values no other variant stands for.
+    Unknown(u8),
+}
 
-impl Default for BitDepth {
-    fn default() -> Self {
-        BitDepth::B32
+impl PixelDepth {
+    pub fn bits(self) -> u8 {
+        match self {
+            PixelDepth::B8 => 8,
+            PixelDepth::B15 => 15,
+            PixelDepth::B16 => 16,
+            PixelDepth::B24 => 24,
+            PixelDepth::B32 => 32,
+            PixelDepth::Unknown(bits) => bits,
+        }
+    }
+
+    /// The number of bytes a pixel of this depth takes up, rounding partial
+    /// bytes up as the spec does for 15-bit pixels.
+    pub fn bytes_per_pixel(self) -> usize {
+        (self.bits() as usize).div_ceil(8)
+    }
+}
+
+impl From<u8> for PixelDepth {
+    fn from(value: u8) -> Self {
+        match value {
+            8 => PixelDepth::B8,
+            15 => PixelDepth::B15,
+            16 => PixelDepth::B16,
+            24 => PixelDepth::B24,
+            32 => PixelDepth::B32,
+            value => PixelDepth::Unknown(value),
+        }
     }
 }
 
-impl BitDepth {
-    const B8: BitDepth = BitDepth(8);
-    const B32: BitDepth = BitDepth(32);
+impl From<PixelDepth> for u8 {
+    fn from(value: PixelDepth) -> Self {
+        value.bits()
+    }
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct ColorMapType(u8);
 
 impl Default for ColorMapType {
@@ -32,58 +169,143 @@ impl Default for ColorMapType {
 
 impl ColorMapType {
     const ABSENT: ColorMapType = ColorMapType(0);
+    const PRESENT: ColorMapType = ColorMapType(1);
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
-struct ImageType(u8);
+/// The kind of pixel data a file holds, as told by its header.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ImageType {
+    /// There is no pixel data.
+    NoImage,
+    /// Uncompressed indices into the color map.
+    ColorMapped,
+    /// Uncompressed true-color pixels.
+    #[default]
+    TrueColor,
+    /// Uncompressed grayscale pixels.
+    Grayscale,
+    /// Run-length encoded indices into the color map.
+    RleColorMapped,
+    /// Run-length encoded true-color pixels.
+    RleTrueColor,
+    /// Run-length encoded grayscale pixels.
+    RleGrayscale,
+    /// A value reserved by the spec or defined by a developer. Converting a
+    /// byte only gives this for values no other variant stands for.
+    Unknown(u8),
+}
 
-impl Default for ImageType {
-    fn default() -> Self {
-        ImageType::TRUE_COLOR
+impl ImageType {
+    /// Whether the pixel data is run-length encoded.
+    pub fn is_rle(self) -> bool {
+        matches!(
+            self,
+            ImageType::RleColorMapped | ImageType::RleTrueColor | ImageType::RleGrayscale
+        )
+    }
+
+    pub fn is_color_mapped(self) -> bool {
+        matches!(self, ImageType::ColorMapped | ImageType::RleColorMapped)
+    }
+
+    pub fn is_true_color(self) -> bool {
+        matches!(self, ImageType::TrueColor | ImageType::RleTrueColor)
+    }
+
+    pub fn is_grayscale(self) -> bool {
+        matches!(self, ImageType::Grayscale | ImageType::RleGrayscale)
     }
 }
 
-impl ImageType {
-    const TRUE_COLOR: ImageType = ImageType(2);
+impl From<u8> for ImageType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => ImageType::NoImage,
+            1 => ImageType::ColorMapped,
+            2 => ImageType::TrueColor,
+            3 => ImageType::Grayscale,
+            9 => ImageType::RleColorMapped,
+            10 => ImageType::RleTrueColor,
+            11 => ImageType::RleGrayscale,
+            value => ImageType::Unknown(value),
+        }
+    }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+impl From<ImageType> for u8 {
+    fn from(value: ImageType) -> Self {
+        match value {
+            ImageType::NoImage => 0,
+            ImageType::ColorMapped => 1,
+            ImageType::TrueColor => 2,
+            ImageType::Grayscale => 3,
+            ImageType::RleColorMapped => 9,
+            ImageType::RleTrueColor => 10,
+            ImageType::RleGrayscale => 11,
+            ImageType::Unknown(value) => value,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd)]
 enum HorizontalOrdering {
+    #[default]
     LeftToRight,
     RightToLeft,
 }
 
-impl Default for HorizontalOrdering {
-    fn default() -> Self {
-        HorizontalOrdering::LeftToRight
-    }
-}
-
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd)]
 enum VerticalOrdering {
+    #[default]
     BottomToTop,
     TopToBottom,
 }
 
-impl Default for VerticalOrdering {
-    fn default() -> Self {
-        VerticalOrdering::BottomToTop
-    }
-}
-
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct ImageDescriptor(u8);
 
+impl ImageDescriptor {
+    fn horizontal_ordering(&self) -> HorizontalOrdering {
+        if self.0 & ImageDescriptorBuilder::HORIZONTAL_ORDERING_BITMASK != 0 {
+            HorizontalOrdering::RightToLeft
+        } else {
+            HorizontalOrdering::LeftToRight
+        }
+    }
+
+    fn vertical_ordering(&self) -> VerticalOrdering {
+        if self.0 & ImageDescriptorBuilder::VERTICAL_ORDERING_BITMASK != 0 {
+            VerticalOrdering::TopToBottom
+        } else {
+            VerticalOrdering::BottomToTop
+        }
+    }
+
+    fn alpha_depth(&self) -> u8 {
+        self.0 & ImageDescriptorBuilder::ALPHA_DEPTH_BITMASK
+    }
+
+    /// Whether bits 6 and 7, which the spec requires to be zero, are set.
+    fn has_reserved_bits(&self) -> bool {
+        self.0 & ImageDescriptorBuilder::RESERVED_BITMASK != 0
+    }
+}
+
 #[derive(Copy, Clone, Debug, Default)]
 struct ImageDescriptorBuilder {
-    alpha_depth: BitDepth,
+    alpha_depth: u8,
     horizontal_ordering: HorizontalOrdering,
     vertical_ordering: VerticalOrdering,
 }
 
 impl ImageDescriptorBuilder {
+    const ALPHA_DEPTH_BITMASK: u8 = 0b00001111;
     const HORIZONTAL_ORDERING_BITMASK: u8 = 0b00010000;
     const VERTICAL_ORDERING_BITMASK: u8 = 0b00100000;
+    const RESERVED_BITMASK: u8 = 0b11000000;
 
     fn new() -> Self {
         ImageDescriptorBuilder::default()
@@ -92,8 +314,7 @@ impl ImageDescriptorBuilder {
     fn build(&self) -> ImageDescriptor {
         let mut value = 0;
 
-        let BitDepth(alpha_depth) = self.alpha_depth;
-        value |= alpha_depth;
+        value |= self.alpha_depth & ImageDescriptorBuilder::ALPHA_DEPTH_BITMASK;
 
         if self.horizontal_ordering == HorizontalOrdering::RightToLeft {
             value |= ImageDescriptorBuilder::HORIZONTAL_ORDERING_BITMASK;
@@ -106,12 +327,13 @@ impl ImageDescriptorBuilder {
         ImageDescriptor(value)
     }
 
-    fn with_alpha(&mut self, depth: BitDepth) -> &mut Self {
+    fn with_alpha(&mut self, depth: u8) -> &mut Self {
         self.alpha_depth = depth;
 
         self
     }
 
+    #[allow(dead_code)]
     fn with_horizontal_ordering(&mut self, ordering: HorizontalOrdering) -> &mut Self {
         self.horizontal_ordering = ordering;
 
@@ -125,48 +347,97 @@ impl ImageDescriptorBuilder {
     }
 }
 
-#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct ColorMapSpecification {
     pub first_entry_index: u16,
     pub entry_count: u16,
-    pub color_depth: BitDepth,
+    pub color_depth: u8,
+}
+
+impl Default for ColorMapSpecification {
+    /// The specification of an absent color map, which the spec requires to
+    /// be all zeros.
+    fn default() -> Self {
+        ColorMapSpecification {
+            first_entry_index: 0,
+            entry_count: 0,
+            color_depth: 0,
+        }
+    }
 }
 
 impl ColorMapSpecification {
+    fn read_from<T: Read>(r: &mut T) -> io::Result<Self> {
+        Ok(ColorMapSpecification {
+            first_entry_index: r.read_u16::<LittleEndian>()?,
+            entry_count: r.read_u16::<LittleEndian>()?,
+            color_depth: r.read_u8()?,
+        })
+    }
+
+    /// The size in bytes of the color map data that follows the ID field.
+    fn data_size(&self) -> u64 {
+        self.entry_count as u64 * (self.color_depth as u64).div_ceil(8)
+    }
+
     fn write_to<T: Write>(&self, w: &mut T) -> io::Result<()> {
         w.write_u16::<LittleEndian>(self.first_entry_index)?;
         w.write_u16::<LittleEndian>(self.entry_count)?;
-        w.write_u8(self.color_depth.0)?;
+        w.write_u8(self.color_depth)?;
 
         Ok(())
     }
 }
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct ImageSpecification {
     pub x_origin: u16,
     pub y_origin: u16,
     pub width: u16,
     pub height: u16,
-    pub pixel_depth: BitDepth,
+    pub pixel_depth: PixelDepth,
     pub descriptor: ImageDescriptor,
 }
 
 impl ImageSpecification {
+    fn read_from<T: Read>(r: &mut T) -> io::Result<Self> {
+        Ok(ImageSpecification {
+            x_origin: r.read_u16::<LittleEndian>()?,
+            y_origin: r.read_u16::<LittleEndian>()?,
+            width: r.read_u16::<LittleEndian>()?,
+            height: r.read_u16::<LittleEndian>()?,
+            pixel_depth: PixelDepth::from(r.read_u8()?),
+            descriptor: ImageDescriptor(r.read_u8()?),
+        })
+    }
+
     fn write_to<T: Write>(&self, w: &mut T) -> io::Result<()> {
         w.write_u16::<LittleEndian>(self.x_origin)?;
         w.write_u16::<LittleEndian>(self.y_origin)?;
         w.write_u16::<LittleEndian>(self.width)?;
         w.write_u16::<LittleEndian>(self.height)?;
-        w.write_u8(self.pixel_depth.0)?;
+        w.write_u8(self.pixel_depth.bits())?;
         w.write_u8(self.descriptor.0)?;
 
         Ok(())
     }
 }
 
+/// The 18 bytes at the start of every file, describing the image ID, color
+/// map and pixel data that follow.
+///
+/// Together with [`Footer`], this lets files be laid out by hand, such as
+/// around pixel data produced elsewhere. Fields are kept as they are set or
+/// read, without being checked against each other; [`Header::validate`] does
+/// that.
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
-struct Header {
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Header {
     id_length: u8,
     color_map_type: ColorMapType,
     image_type: ImageType,
@@ -175,21 +446,192 @@ struct Header {
 }
 
 impl Header {
-    fn write_to<T: Write>(&self, w: &mut T) -> io::Result<()> {
-        w.write_u8(self.id_length)?;
-        w.write_u8(self.color_map_type.0)?;
-        w.write_u8(self.image_type.0)?;
-        self.color_map_specification.write_to(w)?;
-        self.image_specification.write_to(w)?;
+    pub const SIZE: usize = HEADER_SIZE;
 
-        Ok(())
+    /// The header [`Image::write_with_options`] writes for an image of
+    /// `width` by `height` pixels without an image ID: true-color or
+    /// grayscale pixels in the format of `options`, run-length encoded if it
+    /// says so, stored from top to bottom.
+    pub fn new(width: u16, height: u16, options: &EncodeOptions) -> Self {
+        let format = options.pixel_format;
+        Header {
+            image_type: format.image_type(options.rle),
+            image_specification: ImageSpecification {
+                width,
+                height,
+                pixel_depth: format.pixel_depth(),
+                descriptor: ImageDescriptorBuilder::new()
+                    .with_alpha(format.alpha_depth())
+                    .with_vertical_ordering(VerticalOrdering::TopToBottom)
+                    .build(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// The length of the image ID following the header.
+    pub fn id_length(&self) -> u8 {
+        self.id_length
+    }
+
+    pub fn set_id_length(&mut self, id_length: u8) {
+        self.id_length = id_length;
+    }
+
+    /// The color map type: 0 if there is no color map and 1 if there is.
+    pub fn color_map_type(&self) -> u8 {
+        self.color_map_type.0
+    }
+
+    pub fn set_color_map_type(&mut self, color_map_type: u8) {
+        self.color_map_type = ColorMapType(color_map_type);
+    }
+
+    pub fn image_type(&self) -> ImageType {
+        self.image_type
+    }
+
+    pub fn set_image_type(&mut self, image_type: ImageType) {
+        self.image_type = image_type;
+    }
+
+    /// The index of the first entry of the color map.
+    pub fn first_entry_index(&self) -> u16 {
+        self.color_map_specification.first_entry_index
+    }
+
+    /// The number of entries of the color map.
+    pub fn color_map_length(&self) -> u16 {
+        self.color_map_specification.entry_count
+    }
+
+    /// The size of a color map entry in bits.
+    pub fn color_map_entry_size(&self) -> u8 {
+        self.color_map_specification.color_depth
+    }
+
+    /// Sets the color map specification, which has to be all zeros if there
+    /// is no color map. The color map type is left as it is.
+    pub fn set_color_map_specification(
+        &mut self,
+        first_entry_index: u16,
+        length: u16,
+        entry_size: u8,
+    ) {
+        self.color_map_specification = ColorMapSpecification {
+            first_entry_index,
+            entry_count: length,
+            color_depth: entry_size,
+        };
+    }
+
+    pub fn x_origin(&self) -> u16 {
+        self.image_specification.x_origin
+    }
+
+    pub fn y_origin(&self) -> u16 {
+        self.image_specification.y_origin
+    }
+
+    pub fn set_origin(&mut self, x_origin: u16, y_origin: u16) {
+        self.image_specification.x_origin = x_origin;
+        self.image_specification.y_origin = y_origin;
+    }
+
+    pub fn width(&self) -> u16 {
+        self.image_specification.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.image_specification.height
+    }
+
+    pub fn set_dimensions(&mut self, width: u16, height: u16) {
+        self.image_specification.width = width;
+        self.image_specification.height = height;
+    }
+
+    pub fn pixel_depth(&self) -> PixelDepth {
+        self.image_specification.pixel_depth
+    }
+
+    pub fn set_pixel_depth(&mut self, pixel_depth: PixelDepth) {
+        self.image_specification.pixel_depth = pixel_depth;
+    }
+
+    /// The image descriptor byte, holding the alpha depth, the order of the
+    /// pixels and two reserved bits.
+    pub fn descriptor(&self) -> u8 {
+        self.image_specification.descriptor.0
+    }
+
+    pub fn set_descriptor(&mut self, descriptor: u8) {
+        self.image_specification.descriptor = ImageDescriptor(descriptor);
+    }
+
+    /// The number of alpha bits per pixel, as told by the image descriptor.
+    pub fn alpha_depth(&self) -> u8 {
+        self.image_specification.descriptor.alpha_depth()
+    }
+
+    /// Whether the rows are stored from the bottom up, as told by the image
+    /// descriptor.
+    pub fn is_bottom_to_top(&self) -> bool {
+        self.image_specification.descriptor.vertical_ordering() == VerticalOrdering::BottomToTop
+    }
+
+    /// Whether the pixels of a row are stored from right to left, as told by
+    /// the image descriptor.
+    pub fn is_right_to_left(&self) -> bool {
+        self.image_specification.descriptor.horizontal_ordering() == HorizontalOrdering::RightToLeft
+    }
+
+    pub fn read_from<T: Read>(r: &mut T) -> io::Result<Self> {
+        Ok(Header {
+            id_length: r.read_u8()?,
+            color_map_type: ColorMapType(r.read_u8()?),
+            image_type: ImageType::from(r.read_u8()?),
+            color_map_specification: ColorMapSpecification::read_from(r)?,
+            image_specification: ImageSpecification::read_from(r)?,
+        })
+    }
+
+    /// Writes the header with a single write, as unbuffered writers would
+    /// otherwise see a call for every field.
+    pub fn write_to<T: Write>(&self, w: &mut T) -> io::Result<()> {
+        w.write_all(&self.to_bytes())
+    }
+
+    fn to_bytes(self) -> [u8; HEADER_SIZE] {
+        let mut buffer = [0; HEADER_SIZE];
+        let mut cursor = &mut buffer[..];
+        // Writing to a slice of the right size cannot fail.
+        let _ = cursor.write_u8(self.id_length);
+        let _ = cursor.write_u8(self.color_map_type.0);
+        let _ = cursor.write_u8(self.image_type.into());
+        let _ = self.color_map_specification.write_to(&mut cursor);
+        let _ = self.image_specification.write_to(&mut cursor);
+
+        buffer
     }
 }
 
+/// The 26 bytes at the end of a TGA 2.0 file, pointing at the extension and
+/// developer areas.
+///
+/// The default footer points at neither, and has the signature marking the
+/// file as a TGA 2.0 file.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-struct Footer {
-    extension_offset: u32,
-    developer_offset: u32,
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Footer {
+    /// The offset of the extension area from the start of the file, or 0 if
+    /// there is none.
+    pub extension_offset: u32,
+    /// The offset of the developer directory from the start of the file, or 0
+    /// if there is none.
+    pub developer_offset: u32,
     signature: [u8; SIGNATURE_SIZE],
     dot: u8,
     nul: u8,
@@ -208,59 +650,457 @@ impl Default for Footer {
 }
 
 impl Footer {
-    fn write_to<T: Write>(&self, w: &mut T) -> io::Result<()> {
-        w.write_u32::<LittleEndian>(self.extension_offset)?;
-        w.write_u32::<LittleEndian>(self.developer_offset)?;
-        w.write_all(&self.signature)?;
-        w.write_u8(self.dot)?;
-        w.write_u8(self.nul)?;
+    pub const SIZE: usize = FOOTER_SIZE;
 
-        Ok(())
+    /// A footer with a valid signature pointing at the given areas.
+    pub fn new(extension_offset: u32, developer_offset: u32) -> Self {
+        Footer {
+            extension_offset,
+            developer_offset,
+            ..Default::default()
+        }
+    }
+
+    /// Reads the footer, which is accepted whatever its signature is; see
+    /// [`Footer::is_valid`].
+    pub fn read_from<T: Read>(r: &mut T) -> io::Result<Self> {
+        let extension_offset = r.read_u32::<LittleEndian>()?;
+        let developer_offset = r.read_u32::<LittleEndian>()?;
+        let mut signature = [0; SIGNATURE_SIZE];
+        r.read_exact(&mut signature)?;
+
+        Ok(Footer {
+            extension_offset,
+            developer_offset,
+            signature,
+            dot: r.read_u8()?,
+            nul: r.read_u8()?,
+        })
+    }
+
+    /// Whether the footer marks the file as a TGA 2.0 file.
+    pub fn is_valid(&self) -> bool {
+        self.signature == SIGNATURE && self.dot == b'.' && self.nul == b'\0'
+    }
+
+    /// Whether the footer is invalid but close enough to a valid one that the
+    /// file was likely meant to be a TGA 2.0 file, which is the case if at
+    /// least half of the signature matches regardless of case.
+    fn is_malformed(&self) -> bool {
+        let matching = self
+            .signature
+            .iter()
+            .zip(SIGNATURE)
+            .filter(|(a, b)| a.eq_ignore_ascii_case(b))
+            .count();
+
+        !self.is_valid() && matching >= SIGNATURE_SIZE / 2
+    }
+
+    /// Writes the footer with a single write, like [`Header::write_to`].
+    pub fn write_to<T: Write>(&self, w: &mut T) -> io::Result<()> {
+        w.write_all(&self.to_bytes())
+    }
+
+    fn to_bytes(self) -> [u8; FOOTER_SIZE] {
+        let mut buffer = [0; FOOTER_SIZE];
+        let mut cursor = &mut buffer[..];
+        // Writing to a slice of the right size cannot fail.
+        let _ = cursor.write_u32::<LittleEndian>(self.extension_offset);
+        let _ = cursor.write_u32::<LittleEndian>(self.developer_offset);
+        let _ = cursor.write_all(&self.signature);
+        let _ = cursor.write_u8(self.dot);
+        let _ = cursor.write_u8(self.nul);
+
+        buffer
     }
 }
 
-/// A 32-bit uncompressed true-color Truevision TGA file.
+/// A pixel in the blue, green, red, alpha order used by TGA.
+///
+/// Slices of pixels can be cast to and from bytes with `bytemuck`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Pod, Zeroable)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct Bgra8 {
+    pub b: u8,
+    pub g: u8,
+    pub r: u8,
+    pub a: u8,
+}
+
+impl Bgra8 {
+    pub const fn new(b: u8, g: u8, r: u8, a: u8) -> Self {
+        Bgra8 { b, g, r, a }
+    }
+}
+
+/// A true-color Truevision TGA image, held in memory as 32-bit pixels.
 #[derive(Clone, Debug, Default)]
 pub struct Image {
+    id: Vec<u8>,
     data: Vec<u8>,
     width: u16,
     height: u16,
+    stride: usize,
+    extension_area: Option<ExtensionArea>,
+    developer_tags: Vec<DeveloperTag>,
+    postage_stamp: Option<Box<Image>>,
 }
 
 impl Image {
     /// Calculates the size in bytes of an image with the given dimensions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size does not fit into a `usize`, which can only happen on
+    /// targets where `usize` is narrower than 64 bits. Use
+    /// [`Image::checked_effective_size`] to handle that case.
     pub fn effective_size(width: u16, height: u16) -> usize {
-        width as usize * BitDepth::B32.0 as usize / 8 * height as usize
+        Image::checked_effective_size(width, height).expect("image size overflows usize")
+    }
+
+    /// Calculates the size in bytes of an image with the given dimensions, or
+    /// `None` if it does not fit into a `usize`.
+    pub fn checked_effective_size(width: u16, height: u16) -> Option<usize> {
+        (width as usize)
+            .checked_mul(PixelDepth::B32.bytes_per_pixel())?
+            .checked_mul(height as usize)
+    }
+
+    /// Calculates the number of bytes holding `height` rows that start every
+    /// `stride` bytes, which the last row needs no padding after, or `None`
+    /// if it does not fit into a `usize`.
+    pub fn strided_size(width: u16, height: u16, stride: usize) -> Option<usize> {
+        if width == 0 || height == 0 {
+            return Some(0);
+        }
+
+        stride
+            .checked_mul(height as usize - 1)?
+            .checked_add(Image::effective_size(width, 1))
+    }
+
+    /// Calculates the smallest row stride that holds `width` pixels and is a
+    /// multiple of `alignment` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alignment` is zero.
+    pub fn aligned_stride(width: u16, alignment: usize) -> usize {
+        assert!(alignment > 0, "alignment must be nonzero");
+
+        Image::effective_size(width, 1).div_ceil(alignment) * alignment
+    }
+
+    /// Creates an image of `width` by `height` 32-bit pixels stored row after
+    /// row in `data`.
+    ///
+    /// Fails with [`Error::LengthMismatch`] if `data` is too short to hold
+    /// every pixel. Any bytes after the last pixel are kept but never written
+    /// out.
+    pub fn new(width: u16, height: u16, data: Vec<u8>) -> Result<Self> {
+        let expected =
+            Image::checked_effective_size(width, height).ok_or(Error::TooLarge("image"))?;
+        if data.len() < expected {
+            return Err(Error::LengthMismatch {
+                expected,
+                actual: data.len(),
+            });
+        }
+
+        Ok(Image::from_parts(width, height, data))
     }
 
-    pub fn new(width: u16, height: u16, data: Vec<u8>) -> Self {
+    /// Like [`Image::new`], for `data` known to hold every pixel.
+    pub(crate) fn from_parts(width: u16, height: u16, data: Vec<u8>) -> Self {
+        debug_assert!(data.len() >= Image::effective_size(width, height));
+
         Image {
             data,
+            ..Image::without_pixels(width, height)
+        }
+    }
+
+    /// An image of `width` by `height` pixels that holds no pixel data, only
+    /// carrying what is written around pixels taken from elsewhere. Its rows
+    /// must not be read.
+    pub(crate) fn without_pixels(width: u16, height: u16) -> Self {
+        Image {
+            id: Vec::new(),
+            data: Vec::new(),
             width,
             height,
+            stride: Image::effective_size(width, 1),
+            extension_area: None,
+            developer_tags: Vec::new(),
+            postage_stamp: None,
         }
     }
 
-    pub fn write_to<T: Write>(&self, w: &mut T) -> io::Result<()> {
-        let header = Header {
-            image_specification: ImageSpecification {
-                width: self.width,
-                height: self.height,
-                descriptor: ImageDescriptorBuilder::new()
-                    .with_alpha(BitDepth::B8)
-                    .with_vertical_ordering(VerticalOrdering::TopToBottom)
-                    .build(),
-                ..Default::default()
-            },
-            ..Default::default()
-        };
+    /// Creates an image whose rows start every `stride` bytes in `data`.
+    ///
+    /// Any bytes between the end of a row and the start of the next are
+    /// padding and are never written out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stride` is smaller than a row of `width` pixels, or if
+    /// `data` is shorter than the [`Image::strided_size`] of the image.
+    pub fn with_stride(width: u16, height: u16, stride: usize, data: Vec<u8>) -> Self {
+        assert!(
+            stride >= Image::effective_size(width, 1),
+            "stride must hold at least one row of pixels"
+        );
+        assert!(
+            Image::strided_size(width, height, stride).is_some_and(|size| data.len() >= size),
+            "data must hold every row of pixels"
+        );
 
-        let footer = Footer::default();
+        Image {
+            id: Vec::new(),
+            data,
+            width,
+            height,
+            stride,
+            extension_area: None,
+            developer_tags: Vec::new(),
+            postage_stamp: None,
+        }
+    }
 
-        header.write_to(w)?;
-        w.write_all(&self.data)?;
-        footer.write_to(w)?;
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The distance in bytes between the starts of consecutive rows.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// The raw pixel data, including any row padding.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The free-form identification field stored after the header.
+    pub fn id(&self) -> &[u8] {
+        &self.id
+    }
+
+    /// Sets the identification field, which can hold at most 255 bytes.
+    pub fn set_id(&mut self, id: Vec<u8>) -> Result<()> {
+        if id.len() > u8::MAX as usize {
+            return Err(Error::TooLarge("ID field"));
+        }
+
+        self.id = id;
+
+        Ok(())
+    }
+
+    pub fn extension_area(&self) -> Option<&ExtensionArea> {
+        self.extension_area.as_ref()
+    }
+
+    /// Sets the extension area written after the pixel data.
+    ///
+    /// The offsets stored in the extension area are ignored. The color
+    /// correction table and postage stamp are written if present, and the
+    /// scan line table if [`EncodeOptions::scan_line_table`] is set.
+    pub fn set_extension_area(&mut self, extension_area: Option<ExtensionArea>) {
+        self.extension_area = extension_area;
+    }
+
+    /// The entries of the developer area, in the order they were listed in the
+    /// developer directory.
+    pub fn developer_tags(&self) -> &[DeveloperTag] {
+        &self.developer_tags
+    }
+
+    /// Appends an entry to the developer area written after the pixel data.
+    pub fn add_developer_tag(&mut self, tag: u16, data: Vec<u8>) {
+        self.developer_tags.push(DeveloperTag { tag, data });
+    }
+
+    /// Replaces all entries of the developer area.
+    pub fn set_developer_tags(&mut self, tags: Vec<DeveloperTag>) {
+        self.developer_tags = tags;
+    }
+
+    /// The thumbnail embedded in the extension area.
+    pub fn postage_stamp(&self) -> Option<&Image> {
+        self.postage_stamp.as_deref()
+    }
+
+    /// Sets the thumbnail embedded in the extension area.
+    ///
+    /// The stamp is written in the same format as the image itself, and an
+    /// empty extension area is written to hold it if none is set. The spec
+    /// limits stamps to 64 by 64 pixels, so larger ones are rejected, as are
+    /// empty ones.
+    pub fn set_postage_stamp(&mut self, postage_stamp: Option<Image>) -> Result<()> {
+        if let Some(stamp) = &postage_stamp {
+            if stamp.width > MAX_POSTAGE_STAMP_SIZE || stamp.height > MAX_POSTAGE_STAMP_SIZE {
+                return Err(Error::InvalidDimensions(
+                    "postage stamp is larger than 64x64 pixels",
+                ));
+            }
+            if stamp.width == 0 || stamp.height == 0 {
+                return Err(Error::InvalidDimensions(
+                    "postage stamp has zero width or height",
+                ));
+            }
+        }
+
+        self.postage_stamp = postage_stamp.map(Box::new);
 
         Ok(())
     }
+
+    /// Copies the image into a new one whose rows are aligned to `alignment`
+    /// bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alignment` is zero.
+    pub fn to_aligned(&self, alignment: usize) -> Image {
+        let stride = Image::aligned_stride(self.width, alignment);
+        let mut data = vec![0; stride * self.height as usize];
+        if stride > 0 {
+            for (y, row) in data.chunks_exact_mut(stride).enumerate() {
+                let source = self.row(y);
+                row[..source.len()].copy_from_slice(source);
+            }
+        }
+
+        let mut image = Image::with_stride(self.width, self.height, stride, data);
+        image.id = self.id.clone();
+        image.extension_area = self.extension_area.clone();
+        image.developer_tags = self.developer_tags.clone();
+        image.postage_stamp = self.postage_stamp.clone();
+
+        image
+    }
+
+    /// The pixels of row `y`, excluding padding.
+    pub(crate) fn row(&self, y: usize) -> &[u8] {
+        let start = y * self.stride;
+
+        &self.data[start..start + Image::effective_size(self.width, 1)]
+    }
+
+    /// Writes the image as uncompressed 32-bit pixels.
+    pub fn write_to<T: Write>(&self, w: &mut T) -> Result<()> {
+        self.write_with_options(w, &EncodeOptions::default())
+    }
+
+    /// Reads a 15-, 16-, 24- or 32-bit true-color or 8-bit grayscale image,
+    /// either uncompressed or run-length encoded.
+    ///
+    /// The pixels are converted to 32 bits and stored top-to-bottom and
+    /// left-to-right regardless of their order in the file. If the file has a
+    /// TGA 2.0 footer pointing at an extension area or developer area, those
+    /// are read as well.
+    pub fn read_from<T: Read + Seek>(r: &mut T) -> Result<Self> {
+        Decoder::new(r)?.read_image()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` bytes of a fixed pseudo-random sequence.
+    pub(crate) fn pseudo_random_bytes(count: usize) -> Vec<u8> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        (0..count)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn headers_and_footers_write_the_bytes_they_were_read_from() {
+        let bytes = pseudo_random_bytes(500 * HEADER_SIZE);
+        for bytes in bytes.chunks_exact(HEADER_SIZE) {
+            let header = Header::read_from(&mut &bytes[..]).unwrap();
+            let mut written = Vec::new();
+            header.write_to(&mut written).unwrap();
+            assert_eq!(written, bytes);
+            assert_eq!(header.to_bytes(), bytes);
+        }
+
+        let bytes = pseudo_random_bytes(500 * FOOTER_SIZE);
+        for bytes in bytes.chunks_exact(FOOTER_SIZE) {
+            let footer = Footer::read_from(&mut &bytes[..]).unwrap();
+            let mut written = Vec::new();
+            footer.write_to(&mut written).unwrap();
+            assert_eq!(written, bytes);
+            assert_eq!(footer.to_bytes(), bytes);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary")]
+    fn arbitrary_headers_and_footers_read_back_unchanged() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let bytes = pseudo_random_bytes(500 * (HEADER_SIZE + FOOTER_SIZE));
+        let mut u = Unstructured::new(&bytes);
+        for _ in 0..500 {
+            let header = Header::arbitrary(&mut u).unwrap();
+            let written = header.to_bytes();
+            assert_eq!(Header::read_from(&mut &written[..]).unwrap(), header);
+
+            let footer = Footer::arbitrary(&mut u).unwrap();
+            let written = footer.to_bytes();
+            assert_eq!(Footer::read_from(&mut &written[..]).unwrap(), footer);
+        }
+    }
+
+    #[test]
+    fn strided_size_leaves_out_the_last_padding() {
+        assert_eq!(Image::strided_size(3, 4, 16), Some(16 * 3 + 12));
+        assert_eq!(Image::strided_size(0, 4, 16), Some(0));
+        assert_eq!(Image::strided_size(3, 0, 16), Some(0));
+        assert_eq!(Image::strided_size(1, 3, usize::MAX), None);
+    }
+
+    #[test]
+    fn new_rejects_data_too_short_for_the_pixels() {
+        let error = Image::new(3, 2, vec![0; 23]).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::LengthMismatch {
+                expected: 24,
+                actual: 23
+            }
+        ));
+
+        let mut image = Image::new(3, 2, vec![0; 25]).unwrap();
+        image.flip_vertical();
+        assert_eq!(image.to_aligned(16).row(1).len(), 12);
+    }
+
+    #[test]
+    fn with_stride_accepts_data_without_trailing_padding() {
+        let image = Image::with_stride(3, 4, 16, vec![0; 60]);
+        assert_eq!(image.row(3).len(), 12);
+    }
+
+    #[test]
+    #[should_panic(expected = "data must hold every row of pixels")]
+    fn with_stride_rejects_short_data() {
+        Image::with_stride(3, 4, 16, vec![0; 59]);
+    }
 }
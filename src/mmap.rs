@@ -0,0 +1,212 @@
+//! Decoding and encoding files mapped into memory, behind the `mmap` feature.
+
+use std::fs::{File, OpenOptions};
+use std::io::Cursor;
+use std::ops::Range;
+use std::path::Path;
+
+use memmap2::{Mmap, MmapMut};
+
+use crate::{Decoder, EncodeOptions, Error, Image, Result};
+
+/// A file mapped into memory, whose pixels can be borrowed without reading
+/// the file if they are stored the way [`Image`] holds them.
+#[derive(Debug)]
+pub struct MappedImage {
+    map: Mmap,
+    width: u16,
+    height: u16,
+    pixels: Option<Range<usize>>,
+}
+
+impl Image {
+    /// Maps the file at `path` into memory, checking its header and reading
+    /// the areas the footer points at.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped, by this
+    /// or any other process. See [`Mmap::map`].
+    pub unsafe fn open_mmap<P: AsRef<Path>>(path: P) -> Result<MappedImage> {
+        let file = File::open(path)?;
+        // SAFETY: The caller guarantees that the file is not modified.
+        let map = unsafe { Mmap::map(&file)? };
+
+        let (width, height, pixels) = {
+            let decoder = Decoder::new(Cursor::new(&map[..]))?;
+            let (width, height) = (decoder.width(), decoder.height());
+            let pixels = decoder
+                .raw_pixel_offset()
+                .zip(Image::checked_effective_size(width, height))
+                .map(|(offset, size)| offset as usize..offset as usize + size)
+                .filter(|pixels| pixels.end <= map.len());
+
+            (width, height, pixels)
+        };
+
+        Ok(MappedImage {
+            map,
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Writes the image to a new file at `path` through a memory mapping,
+    /// encoding the pixel data as described by `options`.
+    ///
+    /// The file is sized up front, so the encoder writes straight into the
+    /// mapping without any buffering in between. With run-length encoding the
+    /// file is sized for the worst case and truncated afterwards. An existing
+    /// file at `path` is replaced.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated by any other process while
+    /// it is being written. See [`MmapMut::map_mut`].
+    pub unsafe fn write_mmap<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &EncodeOptions,
+    ) -> Result<()> {
+        if self.width == 0 || self.height == 0 {
+            return Err(Error::InvalidDimensions("image has zero width or height"));
+        }
+
+        let size = self.encoded_size(options).ok_or(Error::TooLarge("file"))?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(size as u64)?;
+
+        // SAFETY: The caller guarantees that the file is not modified.
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        let mut w = &mut map[..];
+        self.encode(&mut w, options, None)?;
+        let length = size - w.len();
+        map.flush()?;
+        drop(map);
+        file.set_len(length as u64)?;
+
+        Ok(())
+    }
+}
+
+impl MappedImage {
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The pixels, borrowed from the file, if it stores them like
+    /// [`Image::data`]: uncompressed 32-bit pixels from top to bottom and left
+    /// to right, as [`Image::write_to`] writes them. Other files have to be
+    /// decoded with [`MappedImage::decode`].
+    pub fn pixels(&self) -> Option<&[u8]> {
+        self.pixels.clone().map(|pixels| &self.map[pixels])
+    }
+
+    /// The contents of the whole file.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Decodes the image from the mapped file, like [`Image::read_from`].
+    pub fn decode(&self) -> Result<Image> {
+        Decoder::new(Cursor::new(&self.map[..]))?.read_image()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn image() -> Image {
+        let data = (0..48).map(|index| index / 8).collect();
+
+        Image::new(4, 3, data).unwrap()
+    }
+
+    /// A path in the temporary directory unique to this process and `name`.
+    fn temporary_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("tga-mmap-{}-{name}.tga", std::process::id()))
+    }
+
+    #[test]
+    fn uncompressed_pixels_are_borrowed_from_the_file() {
+        let image = image();
+        let path = temporary_path("raw");
+        let mut file = Vec::new();
+        image.write_to(&mut file).unwrap();
+        fs::write(&path, &file).unwrap();
+
+        // SAFETY: Nothing else knows about the file.
+        let mapped = unsafe { Image::open_mmap(&path) }.unwrap();
+        assert_eq!((mapped.width(), mapped.height()), (4, 3));
+        assert_eq!(mapped.pixels(), Some(image.data()));
+        assert_eq!(mapped.as_bytes(), file);
+        assert_eq!(mapped.decode().unwrap().data(), image.data());
+
+        drop(mapped);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn compressed_pixels_have_to_be_decoded() {
+        let image = image();
+        let path = temporary_path("rle");
+        let options = EncodeOptions {
+            rle: true,
+            ..EncodeOptions::default()
+        };
+        let mut file = Vec::new();
+        image.write_with_options(&mut file, &options).unwrap();
+        fs::write(&path, &file).unwrap();
+
+        // SAFETY: Nothing else knows about the file.
+        let mapped = unsafe { Image::open_mmap(&path) }.unwrap();
+        assert_eq!(mapped.pixels(), None);
+        assert_eq!(mapped.decode().unwrap().data(), image.data());
+
+        drop(mapped);
+        fs::write(&path, [0; 4]).unwrap();
+        // SAFETY: Nothing else knows about the file.
+        assert!(unsafe { Image::open_mmap(&path) }.is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn mapped_writes_match_buffered_writes() {
+        let mut image = image();
+        image.add_developer_tag(1, vec![5; 3]);
+        let path = temporary_path("write");
+        for (rle, scan_line_table) in [(false, false), (true, false), (true, true)] {
+            let options = EncodeOptions {
+                rle,
+                scan_line_table,
+                ..EncodeOptions::default()
+            };
+            let mut expected = Vec::new();
+            image.write_with_options(&mut expected, &options).unwrap();
+
+            // SAFETY: Nothing else knows about the file.
+            unsafe { image.write_mmap(&path, &options) }.unwrap();
+            assert_eq!(fs::read(&path).unwrap(), expected, "{options:?}");
+        }
+        fs::remove_file(&path).unwrap();
+
+        let empty = Image::new(0, 3, Vec::new()).unwrap();
+        // SAFETY: Nothing else knows about the file.
+        let result = unsafe { empty.write_mmap(&path, &EncodeOptions::default()) };
+        assert!(matches!(result, Err(Error::InvalidDimensions(_))));
+        assert!(!path.exists());
+    }
+}
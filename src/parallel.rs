@@ -0,0 +1,120 @@
+//! Passes over pixel data that touch every row independently, such as
+//! converting pixels or flipping the image. With the `rayon` feature they run
+//! on the rayon thread pool.
+
+/// The number of bytes of pixel data handled at once by passes that work
+/// through the image in blocks. A multiple of the size of a 32-bit pixel.
+pub(crate) const BLOCK_SIZE: usize = 1 << 20;
+
+/// The number of rows of `row_size` bytes that make up a block, at least one.
+pub(crate) fn rows_per_block(row_size: usize) -> usize {
+    (BLOCK_SIZE / row_size.max(1)).max(1)
+}
+
+/// Calls `f` with the index and contents of every row of `data`, which are
+/// `row_size` bytes each.
+#[cfg(feature = "rayon")]
+pub(crate) fn for_each_row(data: &mut [u8], row_size: usize, f: impl Fn(usize, &mut [u8]) + Sync) {
+    use rayon::prelude::*;
+
+    data.par_chunks_exact_mut(row_size)
+        .enumerate()
+        .for_each(|(y, row)| f(y, row));
+}
+
+#[cfg(not(feature = "rayon"))]
+pub(crate) fn for_each_row(data: &mut [u8], row_size: usize, f: impl Fn(usize, &mut [u8]) + Sync) {
+    for (y, row) in data.chunks_exact_mut(row_size).enumerate() {
+        f(y, row);
+    }
+}
+
+/// Calls `f` with each row of `top` and the row of `bottom` mirroring it, the
+/// first row of `top` going with the last row of `bottom`. Rows start every
+/// `stride` bytes, and both halves hold the same number of them.
+#[cfg(feature = "rayon")]
+pub(crate) fn for_each_mirrored_row(
+    top: &mut [u8],
+    bottom: &mut [u8],
+    stride: usize,
+    f: impl Fn(&mut [u8], &mut [u8]) + Sync,
+) {
+    use rayon::prelude::*;
+
+    top.par_chunks_mut(stride)
+        .zip(bottom.par_chunks_mut(stride).rev())
+        .for_each(|(top, bottom)| f(top, bottom));
+}
+
+#[cfg(not(feature = "rayon"))]
+pub(crate) fn for_each_mirrored_row(
+    top: &mut [u8],
+    bottom: &mut [u8],
+    stride: usize,
+    f: impl Fn(&mut [u8], &mut [u8]) + Sync,
+) {
+    for (top, bottom) in top.chunks_mut(stride).zip(bottom.chunks_mut(stride).rev()) {
+        f(top, bottom);
+    }
+}
+
+/// Calls `f` with consecutive blocks of `data`, all but the last of which are
+/// [`BLOCK_SIZE`] bytes.
+#[cfg(feature = "rayon")]
+pub(crate) fn for_each_block(data: &mut [u8], f: impl Fn(&mut [u8]) + Sync) {
+    use rayon::prelude::*;
+
+    data.par_chunks_mut(BLOCK_SIZE).for_each(&f);
+}
+
+#[cfg(not(feature = "rayon"))]
+pub(crate) fn for_each_block(data: &mut [u8], f: impl Fn(&mut [u8]) + Sync) {
+    data.chunks_mut(BLOCK_SIZE).for_each(f);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn blocks_hold_at_least_one_row() {
+        assert_eq!(rows_per_block(BLOCK_SIZE / 4), 4);
+        assert_eq!(rows_per_block(BLOCK_SIZE + 1), 1);
+        assert_eq!(rows_per_block(0), BLOCK_SIZE);
+    }
+
+    #[test]
+    fn rows_are_passed_with_their_index() {
+        let mut data = vec![0; 3 * 5];
+        for_each_row(&mut data, 5, |y, row| row.fill(y as u8 + 1));
+        assert_eq!(data, [[1; 5], [2; 5], [3; 5]].concat());
+    }
+
+    #[test]
+    fn mirrored_rows_are_paired() {
+        let mut top: Vec<u8> = (0..6).collect();
+        let mut bottom: Vec<u8> = (6..12).collect();
+        // Rows are 2 bytes, with a byte of padding before the next one.
+        for_each_mirrored_row(&mut top, &mut bottom, 3, |top, bottom| {
+            top[..2].swap_with_slice(&mut bottom[..2]);
+        });
+        assert_eq!(top, [9, 10, 2, 6, 7, 5]);
+        assert_eq!(bottom, [3, 4, 8, 0, 1, 11]);
+    }
+
+    #[test]
+    fn blocks_cover_the_data() {
+        let mut data = vec![0; 2 * BLOCK_SIZE + 3];
+        let count = AtomicUsize::new(0);
+        for_each_block(&mut data, |block| {
+            assert!(block.len() == BLOCK_SIZE || block.len() == 3);
+            count.fetch_add(1, Ordering::Relaxed);
+            block.fill(1);
+        });
+        assert_eq!(count.into_inner(), 3);
+        assert!(data.iter().all(|&byte| byte == 1));
+    }
+}
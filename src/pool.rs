@@ -0,0 +1,123 @@
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::Image;
+
+/// Pixel buffers kept for reuse, so that decoding a sequence of frames with
+/// [`Decoder::read_image_with_pool`](crate::Decoder::read_image_with_pool)
+/// does not allocate a new buffer for every frame.
+///
+/// The pool can be shared between threads. It holds on to every buffer
+/// returned to it until it is reused or the pool is dropped.
+#[derive(Debug, Default)]
+pub struct BufferPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+}
+
+impl BufferPool {
+    pub fn new() -> Self {
+        BufferPool::default()
+    }
+
+    /// Returns the pixel buffer of `image` to the pool, dropping everything
+    /// else it holds.
+    pub fn recycle(&self, image: Image) {
+        self.put(image.data);
+    }
+
+    /// The number of buffers ready for reuse.
+    pub fn len(&self) -> usize {
+        self.buffers().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Takes a zeroed buffer of `size` bytes, reusing one large enough if
+    /// there is one. Otherwise a smaller buffer is grown, so that the pool
+    /// does not fill up with buffers too small to use.
+    pub(crate) fn take(&self, size: usize) -> Vec<u8> {
+        let mut buffer = {
+            let mut buffers = self.buffers();
+            match buffers.iter().position(|buffer| buffer.capacity() >= size) {
+                Some(index) => buffers.swap_remove(index),
+                None => buffers.pop().unwrap_or_default(),
+            }
+        };
+        buffer.clear();
+        buffer.resize(size, 0);
+
+        buffer
+    }
+
+    pub(crate) fn put(&self, buffer: Vec<u8>) {
+        self.buffers().push(buffer);
+    }
+
+    /// Locks the buffers. A panic while they were locked cannot have left them
+    /// in an inconsistent state, so a poisoned lock is taken over.
+    fn buffers(&self) -> MutexGuard<'_, Vec<Vec<u8>>> {
+        self.buffers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::Cursor;
+    use crate::{Decoder, EncodeOptions};
+
+    #[test]
+    fn buffers_are_reused_zeroed() {
+        let pool = BufferPool::new();
+        assert!(pool.is_empty());
+        pool.put(vec![7; 16]);
+        pool.put(vec![7; 64]);
+
+        let buffer = pool.take(32);
+        assert_eq!(buffer, [0; 32]);
+        assert!(buffer.capacity() >= 64);
+        assert_eq!(pool.len(), 1);
+
+        // Too small to fit, the remaining buffer is grown rather than kept.
+        let buffer = pool.take(128);
+        assert_eq!(buffer, [0; 128]);
+        assert!(pool.is_empty());
+        assert_eq!(pool.take(8), [0; 8]);
+    }
+
+    #[test]
+    fn decoding_frames_reuses_their_buffers() {
+        let image = Image::new(3, 2, (0..24).collect()).unwrap();
+        let options = EncodeOptions {
+            rle: true,
+            ..EncodeOptions::default()
+        };
+        let mut file = Vec::new();
+        image.write_with_options(&mut file, &options).unwrap();
+
+        let pool = BufferPool::new();
+        let decode = |file: &[u8]| {
+            Decoder::new(Cursor::new(file))
+                .unwrap()
+                .read_image_with_pool(&pool)
+        };
+        let first = decode(&file).unwrap().image;
+        assert_eq!(first.data(), image.data());
+        let pointer = first.data().as_ptr();
+        pool.recycle(first);
+
+        let second = decode(&file).unwrap().image;
+        assert_eq!(second.data(), image.data());
+        assert_eq!(second.data().as_ptr(), pointer);
+        assert!(pool.is_empty());
+
+        // A failed decode hands its buffer back.
+        pool.recycle(second);
+        let mut corrupted = file.clone();
+        corrupted[crate::HEADER_SIZE..].fill(0x7f);
+        assert!(decode(&corrupted).is_err());
+        assert_eq!(pool.len(), 1);
+        assert_eq!(decode(&file).unwrap().image.data().as_ptr(), pointer);
+    }
+}
@@ -0,0 +1,382 @@
+//! Python bindings, behind the `pyo3` feature.
+//!
+//! Build and install the `tga` extension module with maturin, which reads
+//! the `pyproject.toml` at the root of the crate:
+//!
+//! ```text
+//! maturin develop --release
+//! ```
+//!
+//! Decoded images expose their pixels through the buffer protocol as a
+//! read-only array of 8-bit red, green, blue, alpha values with a shape of
+//! `(height, width, 4)`, so `numpy.asarray(image)` views them without copying
+//! and numpy is not needed otherwise. `encode` takes any buffer of that shape.
+
+use std::ffi::{c_int, c_void, CStr};
+use std::fs::File;
+use std::io::{self, BufReader, Cursor};
+use std::path::PathBuf;
+use std::ptr;
+
+use ::pyo3::buffer::PyBuffer;
+use ::pyo3::create_exception;
+use ::pyo3::exceptions::{PyBufferError, PyValueError};
+use ::pyo3::ffi;
+use ::pyo3::prelude::*;
+use ::pyo3::types::PyBytes;
+
+use crate::{Decoder, EncodeOptions, Error, Image};
+
+create_exception!(
+    tga,
+    TgaError,
+    PyValueError,
+    "Raised for files that cannot be decoded and pixels that cannot be encoded."
+);
+
+/// The format of the items of the pixel buffer, unsigned bytes.
+const FORMAT: &CStr = c"B";
+
+impl From<Error> for PyErr {
+    /// Reading or writing a file failing raises `OSError`, and anything else,
+    /// including files that end too early, raises `tga.TgaError`.
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Io(error) if error.kind() != io::ErrorKind::UnexpectedEof => error.into(),
+            error => TgaError::new_err(error.to_string()),
+        }
+    }
+}
+
+/// A decoded image, holding its pixels in red, green, blue, alpha order.
+#[pyclass(name = "Image", module = "tga", frozen)]
+struct PyImage {
+    pixels: Vec<u8>,
+    shape: [ffi::Py_ssize_t; 3],
+    strides: [ffi::Py_ssize_t; 3],
+}
+
+impl From<Image> for PyImage {
+    fn from(image: Image) -> Self {
+        let (width, height) = (
+            image.width as ffi::Py_ssize_t,
+            image.height as ffi::Py_ssize_t,
+        );
+
+        PyImage {
+            pixels: image.to_rgba(),
+            shape: [height, width, 4],
+            strides: [width * 4, 4, 1],
+        }
+    }
+}
+
+#[pymethods]
+impl PyImage {
+    #[getter]
+    fn width(&self) -> usize {
+        self.shape[1] as usize
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.shape[0] as usize
+    }
+
+    /// `(height, width, 4)`, as numpy arrays of the pixels are shaped.
+    #[getter]
+    fn shape(&self) -> (usize, usize, usize) {
+        (self.height(), self.width(), 4)
+    }
+
+    /// Copies the pixels into `bytes`, from the top row to the bottom one.
+    fn tobytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.pixels)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "tga.Image(width={}, height={})",
+            self.width(),
+            self.height()
+        )
+    }
+
+    /// Exposes the pixels as a read-only, C-contiguous array of bytes.
+    ///
+    /// # Safety
+    ///
+    /// `view` must be null or valid for writes, as Python guarantees.
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("view is null"));
+        }
+        if flags & ffi::PyBUF_WRITABLE == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err("image pixels are read-only"));
+        }
+
+        // The image is frozen, so the pixels and their shape are neither moved
+        // nor changed while the view holds a reference to it.
+        let image = slf.get();
+        let shape = image.shape.as_ptr().cast_mut();
+        let strides = image.strides.as_ptr().cast_mut();
+        // SAFETY: Python passes a view that is valid for writes.
+        unsafe {
+            (*view).buf = image.pixels.as_ptr().cast_mut().cast::<c_void>();
+            (*view).len = image.pixels.len() as ffi::Py_ssize_t;
+            (*view).readonly = 1;
+            (*view).itemsize = 1;
+            (*view).format = if flags & ffi::PyBUF_FORMAT == ffi::PyBUF_FORMAT {
+                FORMAT.as_ptr().cast_mut()
+            } else {
+                ptr::null_mut()
+            };
+            (*view).ndim = 3;
+            (*view).shape = if flags & ffi::PyBUF_ND == ffi::PyBUF_ND {
+                shape
+            } else {
+                ptr::null_mut()
+            };
+            (*view).strides = if flags & ffi::PyBUF_STRIDES == ffi::PyBUF_STRIDES {
+                strides
+            } else {
+                ptr::null_mut()
+            };
+            (*view).suboffsets = ptr::null_mut();
+            (*view).internal = ptr::null_mut();
+            (*view).obj = slf.into_any().into_ptr();
+        }
+
+        Ok(())
+    }
+}
+
+/// What the header of a file describes, read without decoding the pixels.
+#[pyclass(name = "Info", module = "tga", frozen, get_all)]
+struct PyInfo {
+    width: u16,
+    height: u16,
+    /// The number of bits each pixel takes up in the file.
+    pixel_depth: u8,
+    /// Whether the pixels have alpha, rather than being decoded as opaque.
+    has_alpha: bool,
+    /// Whether the pixels are run-length encoded.
+    rle: bool,
+}
+
+#[pymethods]
+impl PyInfo {
+    fn __repr__(&self) -> String {
+        format!(
+            "tga.Info(width={}, height={}, pixel_depth={}, has_alpha={}, rle={})",
+            self.width,
+            self.height,
+            self.pixel_depth,
+            if self.has_alpha { "True" } else { "False" },
+            if self.rle { "True" } else { "False" },
+        )
+    }
+}
+
+/// Decodes an image from the bytes of a file, which may come from an
+/// untrusted source.
+#[pyfunction]
+fn decode(py: Python<'_>, data: &[u8]) -> PyResult<PyImage> {
+    let image = py.detach(|| crate::decode_untrusted(data))?;
+
+    Ok(image.into())
+}
+
+/// Decodes the image stored in the file at `path`.
+#[pyfunction]
+fn decode_file(py: Python<'_>, path: PathBuf) -> PyResult<PyImage> {
+    let image = py.detach(|| {
+        let file = File::open(path)?;
+        Decoder::new(BufReader::new(file))?.read_image()
+    })?;
+
+    Ok(image.into())
+}
+
+/// Encodes pixels shaped `(height, width, 4)` as 32-bit red, green, blue,
+/// alpha values, optionally run-length encoding them.
+#[pyfunction]
+#[pyo3(signature = (pixels, rle = false))]
+fn encode<'py>(pixels: &Bound<'py, PyAny>, rle: bool) -> PyResult<Bound<'py, PyBytes>> {
+    let py = pixels.py();
+    let buffer = PyBuffer::<u8>::get(pixels)?;
+    let &[height, width, 4] = buffer.shape() else {
+        return Err(PyValueError::new_err(
+            "pixels must be shaped (height, width, 4)",
+        ));
+    };
+    let width = u16::try_from(width).map_err(|_| Error::TooLarge("image"))?;
+    let height = u16::try_from(height).map_err(|_| Error::TooLarge("image"))?;
+
+    let image = Image::from_rgba(width, height, buffer.to_vec(py)?)?;
+    let options = EncodeOptions {
+        rle,
+        ..EncodeOptions::default()
+    };
+    let data = py.detach(|| {
+        let mut data = Vec::new();
+        image.write_with_options(&mut data, &options).map(|()| data)
+    })?;
+
+    Ok(PyBytes::new(py, &data))
+}
+
+/// Reads the header of a file from its bytes, checking the areas the footer
+/// points at without decoding the pixels.
+#[pyfunction]
+fn probe(data: &[u8]) -> PyResult<PyInfo> {
+    let decoder = Decoder::new(Cursor::new(data))?;
+
+    Ok(PyInfo {
+        width: decoder.width(),
+        height: decoder.height(),
+        pixel_depth: decoder.pixel_depth().bits(),
+        has_alpha: decoder.has_alpha(),
+        rle: decoder.encode_options().rle,
+    })
+}
+
+#[pymodule]
+fn tga(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("TgaError", m.py().get_type::<TgaError>())?;
+    m.add_class::<PyImage>()?;
+    m.add_class::<PyInfo>()?;
+    m.add_function(wrap_pyfunction!(decode, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_file, m)?)?;
+    m.add_function(wrap_pyfunction!(encode, m)?)?;
+    m.add_function(wrap_pyfunction!(probe, m)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::pyo3::py_run;
+
+    /// Runs `f` with the `tga` module, initializing Python first.
+    fn with_module(f: impl for<'py> FnOnce(Python<'py>, Bound<'py, PyModule>)) {
+        Python::initialize();
+        Python::attach(|py| {
+            let module = PyModule::new(py, "tga").unwrap();
+            tga(&module).unwrap();
+            f(py, module);
+        });
+    }
+
+    #[test]
+    fn encoded_pixels_decode_unchanged() {
+        with_module(|py, tga| {
+            py_run!(
+                py,
+                tga,
+                r#"
+                pixels = bytes(range(24))
+                for rle in (False, True):
+                    data = tga.encode(memoryview(pixels).cast("B", (3, 2, 4)), rle=rle)
+                    image = tga.decode(data)
+                    assert (image.width, image.height, image.shape) == (2, 3, (3, 2, 4))
+                    assert image.tobytes() == pixels
+                    assert repr(image) == "tga.Image(width=2, height=3)"
+
+                    info = tga.probe(data)
+                    assert (info.width, info.height, info.pixel_depth) == (2, 3, 32)
+                    assert info.has_alpha and info.rle == rle
+                "#
+            );
+        });
+    }
+
+    #[test]
+    fn pixels_are_exposed_as_a_read_only_buffer() {
+        with_module(|py, tga| {
+            py_run!(
+                py,
+                tga,
+                r#"
+                pixels = bytes(range(8))
+                image = tga.decode(tga.encode(memoryview(pixels).cast("B", (1, 2, 4))))
+                view = memoryview(image)
+                assert view.readonly
+                assert (view.format, view.shape, view.strides) == ("B", (1, 2, 4), (8, 4, 1))
+                assert view.tobytes() == pixels
+                assert view[0, 1, 2] == 6
+                "#
+            );
+        });
+    }
+
+    #[test]
+    fn files_are_decoded_by_path() {
+        let path = std::env::temp_dir().join(format!("tga-python-{}.tga", std::process::id()));
+        let path = path.to_str().unwrap();
+        with_module(|py, tga| {
+            py_run!(
+                py,
+                tga path,
+                r#"
+                pixels = bytes(range(16))
+                with open(path, "wb") as file:
+                    file.write(tga.encode(memoryview(pixels).cast("B", (2, 2, 4)), rle=True))
+                assert tga.decode_file(path).tobytes() == pixels
+                "#
+            );
+        });
+        std::fs::remove_file(path).unwrap();
+        with_module(|py, tga| {
+            py_run!(
+                py,
+                tga path,
+                r#"
+                try:
+                    tga.decode_file(path)
+                except OSError:
+                    pass
+                else:
+                    raise AssertionError("missing files raise OSError")
+                "#
+            );
+        });
+    }
+
+    #[test]
+    fn errors_are_raised() {
+        with_module(|py, tga| {
+            py_run!(
+                py,
+                tga,
+                r#"
+                data = tga.encode(memoryview(bytes(16)).cast("B", (2, 2, 4)))
+                try:
+                    tga.decode(data[:10])
+                except tga.TgaError:
+                    pass
+                else:
+                    raise AssertionError("truncated files raise TgaError")
+                try:
+                    tga.probe(data[:10])
+                except tga.TgaError:
+                    pass
+                else:
+                    raise AssertionError("truncated headers raise TgaError")
+                try:
+                    tga.encode(memoryview(bytes(16)).cast("B", (4, 4)))
+                except ValueError:
+                    pass
+                else:
+                    raise AssertionError("misshapen pixels raise ValueError")
+                assert issubclass(tga.TgaError, ValueError)
+                "#
+            );
+        });
+    }
+}
@@ -0,0 +1,593 @@
+use alloc::vec::Vec;
+
+use crate::io::{self, Read, ReadBytesExt};
+use crate::{Bgra8, Error, Result};
+
+const RUN_BITMASK: u8 = 0b10000000;
+const COUNT_BITMASK: u8 = 0b01111111;
+pub(crate) const MAX_PACKET_LENGTH: usize = 128;
+
+/// Decodes run-length encoded pixels one row at a time.
+///
+/// Packets may span rows, so the decoder carries any unfinished packet over to
+/// the next call.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct RleDecoder {
+    run_length: usize,
+    raw_length: usize,
+    run_pixel: [u8; 4],
+    packets: u64,
+}
+
+impl RleDecoder {
+    /// Fills `row` with pixels of `bytes_per_pixel` bytes each.
+    pub(crate) fn read_row<T: Read>(
+        &mut self,
+        r: &mut T,
+        row: &mut [u8],
+        bytes_per_pixel: usize,
+    ) -> Result<()> {
+        let mut position = 0;
+        while position < row.len() {
+            self.start_packet(r, bytes_per_pixel)?;
+
+            let available = (row.len() - position) / bytes_per_pixel;
+            if self.run_length > 0 {
+                let count = self.run_length.min(available);
+                for pixel in
+                    row[position..][..count * bytes_per_pixel].chunks_exact_mut(bytes_per_pixel)
+                {
+                    pixel.copy_from_slice(&self.run_pixel[..bytes_per_pixel]);
+                }
+
+                self.run_length -= count;
+                position += count * bytes_per_pixel;
+            } else {
+                let count = self.raw_length.min(available);
+                r.read_exact(&mut row[position..][..count * bytes_per_pixel])
+                    .map_err(truncated)?;
+
+                self.raw_length -= count;
+                position += count * bytes_per_pixel;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fills `row` with 32-bit pixels decoded from pixels of
+    /// `bytes_per_pixel` bytes each, which `expand` converts to 32 bits.
+    ///
+    /// Runs are splatted straight into the row and raw 32-bit pixels are read
+    /// into it, so the pixels are written once instead of being decoded into
+    /// a separate buffer and converted from there.
+    pub(crate) fn read_row_expanded<T: Read>(
+        &mut self,
+        r: &mut T,
+        row: &mut [u8],
+        bytes_per_pixel: usize,
+        expand: impl Fn(&[u8], &mut [u8]),
+    ) -> Result<()> {
+        let row: &mut [Bgra8] = bytemuck::cast_slice_mut(row);
+        let mut position = 0;
+        while position < row.len() {
+            self.start_packet(r, bytes_per_pixel)?;
+
+            let available = row.len() - position;
+            if self.run_length > 0 {
+                let count = self.run_length.min(available);
+                let mut pixel = [0; 4];
+                expand(&self.run_pixel[..bytes_per_pixel], &mut pixel);
+                row[position..][..count].fill(bytemuck::cast(pixel));
+
+                self.run_length -= count;
+                position += count;
+            } else {
+                let count = self.raw_length.min(available);
+                let pixels = bytemuck::cast_slice_mut(&mut row[position..][..count]);
+                if bytes_per_pixel == 4 {
+                    r.read_exact(pixels).map_err(truncated)?;
+                } else {
+                    let mut source = [0; 3 * MAX_PACKET_LENGTH];
+                    let source = &mut source[..count * bytes_per_pixel];
+                    r.read_exact(source).map_err(truncated)?;
+                    expand(source, pixels);
+                }
+
+                self.raw_length -= count;
+                position += count;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the header of the next packet, and the pixel of a run, if the
+    /// last packet was used up.
+    fn start_packet<T: Read>(&mut self, r: &mut T, bytes_per_pixel: usize) -> Result<()> {
+        if self.run_length > 0 || self.raw_length > 0 {
+            return Ok(());
+        }
+
+        let header = r.read_u8().map_err(truncated)?;
+        let length = (header & COUNT_BITMASK) as usize + 1;
+        if header & RUN_BITMASK != 0 {
+            r.read_exact(&mut self.run_pixel[..bytes_per_pixel])
+                .map_err(truncated)?;
+            self.run_length = length;
+        } else {
+            self.raw_length = length;
+        }
+        self.packets += 1;
+
+        Ok(())
+    }
+
+    /// Checks that the last packet ended with the last pixel decoded, once the
+    /// final row of the image has been read. A packet running past the end of
+    /// the image means the pixel data is corrupted.
+    pub(crate) fn finish(&self) -> Result<()> {
+        if self.run_length > 0 || self.raw_length > 0 {
+            return Err(Error::CorruptRle("packet extends past the last pixel"));
+        }
+
+        Ok(())
+    }
+
+    /// The index of the packet being decoded, or of the next one if the last
+    /// packet was used up.
+    pub(crate) fn packet_index(&self) -> u64 {
+        if self.run_length > 0 || self.raw_length > 0 {
+            self.packets - 1
+        } else {
+            self.packets
+        }
+    }
+}
+
+/// Reports running out of data in the middle of the pixel data as corruption
+/// rather than as a plain I/O error.
+fn truncated(error: io::Error) -> Error {
+    match error.kind() {
+        io::ErrorKind::UnexpectedEof => Error::CorruptRle("pixel data ends before the last pixel"),
+        _ => Error::Io(error),
+    }
+}
+
+/// The largest number of bytes [`encode_row`] can produce for a row of `width`
+/// pixels, or `None` if that does not fit into a `usize`.
+///
+/// For pixels of at least two bytes, a run is always at least a byte shorter
+/// than the pixels it replaces, which pays for the header of any raw packet it
+/// splits. The worst case is then a row of raw packets of the maximum length.
+pub(crate) fn max_encoded_row_size(width: usize, bytes_per_pixel: usize) -> Option<usize> {
+    width
+        .checked_mul(bytes_per_pixel)?
+        .checked_add(width.div_ceil(MAX_PACKET_LENGTH))
+}
+
+/// Appends one row of pixels of `bytes_per_pixel` bytes each to `out` as
+/// run-length encoded packets.
+///
+/// Packets never span rows, as recommended by the TGA 2.0 spec. The row is
+/// encoded in a single pass, deciding between runs and raw packets without
+/// going back over pixels already encoded.
+pub(crate) fn encode_row(row: &[u8], bytes_per_pixel: usize, out: &mut Vec<u8>) {
+    let pixel_count = row.len() / bytes_per_pixel;
+    let pixel = |index: usize| &row[index * bytes_per_pixel..][..bytes_per_pixel];
+
+    let mut start = 0;
+    while start < pixel_count {
+        let pixels = &row[start * bytes_per_pixel..pixel_count * bytes_per_pixel];
+        let run_length = run_length(pixels, bytes_per_pixel);
+        if run_length > 1 {
+            out.push(RUN_BITMASK | (run_length - 1) as u8);
+            out.extend_from_slice(pixel(start));
+            start += run_length;
+            continue;
+        }
+
+        // The raw packet ends before the next pair of identical pixels, which
+        // starts a run, so looking one pixel ahead suffices.
+        let limit = pixel_count.min(start + MAX_PACKET_LENGTH);
+        let pixels =
+            &row[(start + 1) * bytes_per_pixel..pixel_count.min(limit + 1) * bytes_per_pixel];
+        let end = limit.min(start + 1 + first_repeat(pixels, bytes_per_pixel));
+
+        out.push((end - start - 1) as u8);
+        out.extend_from_slice(&row[start * bytes_per_pixel..end * bytes_per_pixel]);
+        start = end;
+    }
+}
+
+/// The number of identical pixels that `pixels` starts with, up to the
+/// length of a packet.
+///
+/// A pixel repeats the one before it exactly if each of its bytes equals the
+/// byte `bytes_per_pixel` places earlier, so the run ends where the pixels
+/// first differ from themselves shifted by one pixel. That comparison works
+/// the same for any pixel size and is done 16 bytes at a time.
+fn run_length(pixels: &[u8], bytes_per_pixel: usize) -> usize {
+    let length = pixels.len().min(MAX_PACKET_LENGTH * bytes_per_pixel);
+    let matching = mismatch(
+        &pixels[bytes_per_pixel..length],
+        &pixels[..length - bytes_per_pixel],
+    );
+
+    1 + matching / bytes_per_pixel
+}
+
+/// The index of the first byte at which `a` and `b`, which have the same
+/// length, differ, or their length if they are equal.
+#[cfg(target_arch = "x86_64")]
+fn mismatch(a: &[u8], b: &[u8]) -> usize {
+    use core::arch::x86_64::{__m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8};
+
+    let mut index = 0;
+    while index + 16 <= a.len() {
+        // SAFETY: SSE2 is part of the x86-64 baseline, and both loads stay
+        // within the slices since at least 16 bytes are left in each.
+        let equal = unsafe {
+            let a = _mm_loadu_si128(a.as_ptr().add(index).cast::<__m128i>());
+            let b = _mm_loadu_si128(b.as_ptr().add(index).cast::<__m128i>());
+            _mm_movemask_epi8(_mm_cmpeq_epi8(a, b)) as u32
+        };
+        if equal != 0xffff {
+            return index + equal.trailing_ones() as usize;
+        }
+        index += 16;
+    }
+
+    index + scalar_mismatch(&a[index..], &b[index..])
+}
+
+#[cfg(target_arch = "aarch64")]
+fn mismatch(a: &[u8], b: &[u8]) -> usize {
+    use core::arch::aarch64::{vceqq_u8, vld1q_u8, vminvq_u8};
+
+    let mut index = 0;
+    while index + 16 <= a.len() {
+        // SAFETY: NEON is always available on AArch64, and both loads stay
+        // within the slices since at least 16 bytes are left in each.
+        let all_equal = unsafe {
+            let a = vld1q_u8(a.as_ptr().add(index));
+            let b = vld1q_u8(b.as_ptr().add(index));
+            vminvq_u8(vceqq_u8(a, b)) == u8::MAX
+        };
+        if !all_equal {
+            break;
+        }
+        index += 16;
+    }
+
+    index + scalar_mismatch(&a[index..], &b[index..])
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn mismatch(a: &[u8], b: &[u8]) -> usize {
+    scalar_mismatch(a, b)
+}
+
+/// Like [`mismatch`], comparing eight bytes at a time.
+fn scalar_mismatch(a: &[u8], b: &[u8]) -> usize {
+    let mut index = 0;
+    while index + 8 <= a.len() {
+        let difference = read_word(&a[index..]) ^ read_word(&b[index..]);
+        if difference != 0 {
+            return index + (difference.trailing_zeros() / 8) as usize;
+        }
+        index += 8;
+    }
+
+    index
+        + a[index..]
+            .iter()
+            .zip(&b[index..])
+            .take_while(|(a, b)| a == b)
+            .count()
+}
+
+/// The index of the first pixel that equals the pixel after it, or the
+/// number of pixels if there is none.
+///
+/// This is where a raw packet ends, as the next packet can start with a run.
+/// Like in [`run_length`], the pixels are compared with themselves shifted by
+/// one pixel, 16 bytes at a time. A pixel repeats if its bytes all compare
+/// equal, which shows as a group of set bits at its position in the mask of
+/// equal bytes.
+#[cfg(target_arch = "x86_64")]
+fn first_repeat(pixels: &[u8], bytes_per_pixel: usize) -> usize {
+    use core::arch::x86_64::{__m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8};
+
+    if bytes_per_pixel > 4 {
+        return scalar_first_repeat(pixels, bytes_per_pixel);
+    }
+
+    // Each comparison covers as many whole pixels as fit into 16 bytes, and
+    // the pixels following them.
+    let step = 16 / bytes_per_pixel;
+    let starts = (0..step).fold(0, |starts, pixel| starts | 1 << (pixel * bytes_per_pixel));
+    let mut index = 0;
+    while (index + 1) * bytes_per_pixel + 16 <= pixels.len() {
+        let position = index * bytes_per_pixel;
+        // SAFETY: SSE2 is part of the x86-64 baseline, and both loads stay
+        // within `pixels` as checked above.
+        let equal = unsafe {
+            let a = _mm_loadu_si128(pixels.as_ptr().add(position).cast::<__m128i>());
+            let b = _mm_loadu_si128(
+                pixels
+                    .as_ptr()
+                    .add(position + bytes_per_pixel)
+                    .cast::<__m128i>(),
+            );
+            _mm_movemask_epi8(_mm_cmpeq_epi8(a, b)) as u32
+        };
+        let repeats =
+            (1..bytes_per_pixel).fold(equal & starts, |repeats, byte| repeats & equal >> byte);
+        if repeats != 0 {
+            return index + repeats.trailing_zeros() as usize / bytes_per_pixel;
+        }
+        index += step;
+    }
+
+    index + scalar_first_repeat(&pixels[index * bytes_per_pixel..], bytes_per_pixel)
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn first_repeat(pixels: &[u8], bytes_per_pixel: usize) -> usize {
+    scalar_first_repeat(pixels, bytes_per_pixel)
+}
+
+fn scalar_first_repeat(pixels: &[u8], bytes_per_pixel: usize) -> usize {
+    if bytes_per_pixel == 1 {
+        return first_repeated_byte(pixels);
+    }
+
+    let pixel_count = pixels.len() / bytes_per_pixel;
+    let mut pairs = pixels.windows(2 * bytes_per_pixel).step_by(bytes_per_pixel);
+    pairs
+        .position(|pair| pair[..bytes_per_pixel] == pair[bytes_per_pixel..])
+        .unwrap_or(pixel_count)
+}
+
+/// Like [`first_repeat`] for the 8-bit pixels of [`PixelFormat::Gray8`].
+///
+/// Bytes are checked eight at a time, as `memchr` does, by looking for a zero
+/// byte in the difference between each word and the word one byte further
+/// on.
+///
+/// [`PixelFormat::Gray8`]: crate::PixelFormat::Gray8
+fn first_repeated_byte(bytes: &[u8]) -> usize {
+    const LOW_BITS: u64 = 0x0101_0101_0101_0101;
+    const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+
+    let mut index = 0;
+    while index + 9 <= bytes.len() {
+        let difference = read_word(&bytes[index..]) ^ read_word(&bytes[index + 1..]);
+        // Only the lowest byte flagged is certain to be zero, which is the
+        // one wanted.
+        let zero_bytes = difference.wrapping_sub(LOW_BITS) & !difference & HIGH_BITS;
+        if zero_bytes != 0 {
+            return index + (zero_bytes.trailing_zeros() / 8) as usize;
+        }
+        index += 8;
+    }
+
+    bytes[index..]
+        .windows(2)
+        .position(|pair| pair[0] == pair[1])
+        .map_or(bytes.len(), |position| index + position)
+}
+
+/// Reads the first eight bytes of `bytes` as a little-endian word, so that
+/// lower bits belong to earlier bytes.
+fn read_word(bytes: &[u8]) -> u64 {
+    let mut word = [0; 8];
+    word.copy_from_slice(&bytes[..8]);
+
+    u64::from_le_bytes(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn reference_mismatch(a: &[u8], b: &[u8]) -> usize {
+        a.iter().zip(b).take_while(|(a, b)| a == b).count()
+    }
+
+    /// Pixels of `bytes_per_pixel` bytes that all differ from their
+    /// neighbors, in every byte.
+    fn distinct_pixels(count: usize, bytes_per_pixel: usize) -> Vec<u8> {
+        (0..count * bytes_per_pixel)
+            .map(|index| (index / bytes_per_pixel * 3 + index % bytes_per_pixel) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn mismatch_finds_every_difference() {
+        for length in 0..=70 {
+            let a: Vec<u8> = (0..length).map(|index| index as u8).collect();
+            assert_eq!(mismatch(&a, &a), length);
+            assert_eq!(scalar_mismatch(&a, &a), length);
+            for position in 0..length {
+                let mut b = a.clone();
+                b[position] ^= 0x80;
+                assert_eq!(mismatch(&a, &b), reference_mismatch(&a, &b));
+                assert_eq!(scalar_mismatch(&a, &b), reference_mismatch(&a, &b));
+            }
+        }
+    }
+
+    #[test]
+    fn run_length_stops_at_the_first_different_pixel_or_packet_limit() {
+        for bytes_per_pixel in 1..=4 {
+            for run in (1..=70).chain(120..=140).chain([255, 256, 257]) {
+                // A run followed by a pixel differing in only its last byte,
+                // and then by more pixels.
+                let mut pixels = vec![9; run * bytes_per_pixel];
+                let mut different = vec![9; bytes_per_pixel];
+                different[bytes_per_pixel - 1] = 10;
+                pixels.extend_from_slice(&different);
+                pixels.extend(distinct_pixels(20, bytes_per_pixel));
+
+                assert_eq!(
+                    run_length(&pixels, bytes_per_pixel),
+                    run.min(MAX_PACKET_LENGTH),
+                    "run of {run} {bytes_per_pixel}-byte pixels"
+                );
+                assert_eq!(
+                    run_length(&pixels[..run * bytes_per_pixel], bytes_per_pixel),
+                    run.min(MAX_PACKET_LENGTH),
+                    "run of {run} {bytes_per_pixel}-byte pixels ending the row"
+                );
+            }
+        }
+    }
+
+    fn reference_first_repeat(pixels: &[u8], bytes_per_pixel: usize) -> usize {
+        let pixels: Vec<&[u8]> = pixels.chunks_exact(bytes_per_pixel).collect();
+        pixels
+            .windows(2)
+            .position(|pair| pair[0] == pair[1])
+            .unwrap_or(pixels.len())
+    }
+
+    /// Encodes a row the straightforward way, a pixel at a time.
+    fn reference_encode_row(row: &[u8], bytes_per_pixel: usize, out: &mut Vec<u8>) {
+        let pixels: Vec<&[u8]> = row.chunks_exact(bytes_per_pixel).collect();
+        let mut start = 0;
+        while start < pixels.len() {
+            let mut end = start + 1;
+            while end < pixels.len()
+                && end - start < MAX_PACKET_LENGTH
+                && pixels[end] == pixels[start]
+            {
+                end += 1;
+            }
+            if end - start > 1 {
+                out.push(RUN_BITMASK | (end - start - 1) as u8);
+                out.extend_from_slice(pixels[start]);
+                start = end;
+                continue;
+            }
+
+            while end < pixels.len()
+                && end - start < MAX_PACKET_LENGTH
+                && (end + 1 == pixels.len() || pixels[end] != pixels[end + 1])
+            {
+                end += 1;
+            }
+            out.push((end - start - 1) as u8);
+            for pixel in &pixels[start..end] {
+                out.extend_from_slice(pixel);
+            }
+            start = end;
+        }
+    }
+
+    #[test]
+    fn first_repeat_finds_every_repeat() {
+        for bytes_per_pixel in 1..=4 {
+            for count in 0..=70 {
+                let pixels = distinct_pixels(count, bytes_per_pixel);
+                assert_eq!(first_repeat(&pixels, bytes_per_pixel), count);
+                for position in 1..count {
+                    let start = position * bytes_per_pixel;
+                    let previous = start - bytes_per_pixel;
+                    // A pixel repeating the one before it, and one that
+                    // repeats all but its last byte.
+                    let mut repeated = pixels.clone();
+                    repeated.copy_within(previous..start, start);
+                    let mut almost = repeated.clone();
+                    almost[start + bytes_per_pixel - 1] ^= 0x80;
+
+                    for pixels in [repeated, almost] {
+                        assert_eq!(
+                            first_repeat(&pixels, bytes_per_pixel),
+                            reference_first_repeat(&pixels, bytes_per_pixel),
+                            "{count} {bytes_per_pixel}-byte pixels repeating at {position}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn first_repeated_byte_matches_the_reference() {
+        // Neighbouring bytes differing only in their lowest or highest bit,
+        // which borrows between bytes of the difference could mistake for
+        // repeats.
+        let patterns: [fn(usize) -> u8; 3] = [
+            |index| index as u8,
+            |index| (index % 2) as u8,
+            |index| (index % 2) as u8 * 0x80 + 1,
+        ];
+        for pattern in patterns {
+            for count in 0..=40 {
+                let bytes: Vec<u8> = (0..count).map(pattern).collect();
+                assert_eq!(first_repeated_byte(&bytes), count);
+                for position in 1..count {
+                    let mut repeated = bytes.clone();
+                    repeated[position] = repeated[position - 1];
+                    assert_eq!(
+                        first_repeated_byte(&repeated),
+                        reference_first_repeat(&repeated, 1),
+                        "{count} bytes repeating at {position}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn encode_row_matches_the_reference_and_decodes() {
+        // Runs and stretches of distinct pixels of lengths around the vector
+        // widths and the packet limit, picked by a fixed pseudo-random
+        // sequence.
+        let lengths = [1, 2, 3, 5, 15, 16, 17, 70, 127, 128, 129, 255, 256, 257];
+        let mut state = 1u32;
+        let mut next = |bound: usize| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as usize % bound
+        };
+        for bytes_per_pixel in [2, 3, 4] {
+            for _ in 0..200 {
+                let mut row = Vec::new();
+                let mut value = 0u8;
+                for _ in 0..next(6) + 1 {
+                    let length = lengths[next(lengths.len())];
+                    let is_run = next(2) == 0;
+                    for _ in 0..length {
+                        if !is_run || row.is_empty() {
+                            value = value.wrapping_add(1);
+                        }
+                        row.extend((0..bytes_per_pixel).map(|byte| value ^ byte as u8));
+                    }
+                }
+
+                let mut actual = Vec::new();
+                encode_row(&row, bytes_per_pixel, &mut actual);
+                let mut expected = Vec::new();
+                reference_encode_row(&row, bytes_per_pixel, &mut expected);
+                assert_eq!(actual, expected);
+                assert!(
+                    actual.len()
+                        <= max_encoded_row_size(row.len() / bytes_per_pixel, bytes_per_pixel)
+                            .unwrap()
+                );
+
+                let mut decoded = vec![0; row.len()];
+                let mut decoder = RleDecoder::default();
+                decoder
+                    .read_row(&mut &actual[..], &mut decoded, bytes_per_pixel)
+                    .unwrap();
+                decoder.finish().unwrap();
+                assert_eq!(decoded, row);
+            }
+        }
+    }
+}
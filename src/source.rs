@@ -0,0 +1,200 @@
+//! Decoding from sources that support different ways of reading them.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::decoder::{self, convert_row, expand_pixels};
+use crate::error::CountingReader;
+use crate::io::{Cursor, Read, Seek};
+use crate::rle::RleDecoder;
+use crate::{
+    Bgra8, Context, DecodeOptions, Decoded, Decoder, Error, HorizontalOrdering, Image, Result,
+    VerticalOrdering,
+};
+
+/// Something a file can be decoded from, which picks the fastest way of
+/// reading it that it supports.
+///
+/// - A `&[u8]` holding the whole file is decoded like with a [`Decoder`], and
+///   uncompressed pixels are converted straight from the slice.
+/// - [`Seekable`] wraps a reader that can seek, which a [`Decoder`] reads the
+///   footer from before the pixel data.
+/// - [`Streaming`] wraps a reader that can only be read from start to end,
+///   such as a pipe or a socket.
+pub trait Source {
+    /// Decodes the image, handling spec violations as set by `options`.
+    fn decode(self, options: DecodeOptions) -> Result<Decoded>;
+}
+
+/// A reader that can seek, decoded by a [`Decoder`].
+#[derive(Debug)]
+pub struct Seekable<R>(pub R);
+
+/// A reader that can only be read from start to end.
+///
+/// The header, image ID and pixels are read in one pass, and reading stops at
+/// the end of the pixel data. The header is checked as with the other sources,
+/// applying the same policies of [`DecodeOptions`]. The footer and the areas it
+/// points at come after the pixels, so they are left unread, and the footer,
+/// area offset, color map length and postage stamp policies, which need them,
+/// are not applied. The memory allocated grows with the rows read rather than
+/// being set aside up front, so it stays bounded by the length of the input.
+#[derive(Debug)]
+pub struct Streaming<R>(pub R);
+
+impl Source for &[u8] {
+    fn decode(self, options: DecodeOptions) -> Result<Decoded> {
+        Decoder::with_options(Cursor::new(self), options)?.read_slice_with_diagnostics()
+    }
+}
+
+impl<R: Read + Seek> Source for Seekable<R> {
+    fn decode(self, options: DecodeOptions) -> Result<Decoded> {
+        Decoder::with_options(self.0, options)?.read_image_with_diagnostics()
+    }
+}
+
+impl<R: Read> Source for Streaming<R> {
+    fn decode(self, options: DecodeOptions) -> Result<Decoded> {
+        let mut reader = self.0;
+        let mut r = CountingReader::new(&mut reader);
+        let header = decoder::read_header(&mut r, &options)?;
+        let mut diagnostics = Vec::new();
+        decoder::check_header(&header, &options, &mut diagnostics)?;
+
+        let mut id = vec![0; header.id_length as usize];
+        r.read_exact(&mut id)
+            .map_err(|error| Error::from(error).at(r.count(), Context::ImageId))?;
+
+        let mut buf = [0; 256];
+        let mut color_map_size = header.color_map_specification.data_size();
+        while color_map_size > 0 {
+            let length = color_map_size.min(buf.len() as u64) as usize;
+            r.read_exact(&mut buf[..length])
+                .map_err(|error| Error::from(error).at(r.count(), Context::PixelData))?;
+            color_map_size -= length as u64;
+        }
+
+        let specification = header.image_specification;
+        let descriptor = specification.descriptor;
+        let bytes_per_pixel = specification.pixel_depth.bytes_per_pixel();
+        let is_rle = header.image_type.is_rle();
+        let is_reversed = descriptor.horizontal_ordering() == HorizontalOrdering::RightToLeft;
+        let row_size = Image::effective_size(specification.width, 1);
+        let mut data = Vec::new();
+        let mut source = vec![0; specification.width as usize * bytes_per_pixel];
+        let mut rle = RleDecoder::default();
+        for file_row in 0..specification.height {
+            let start = data.len();
+            data.resize(start + row_size, 0);
+            let row = &mut data[start..];
+            if is_rle {
+                let expand = |source: &[u8], row: &mut [u8]| {
+                    expand_pixels(source, row, bytes_per_pixel, descriptor);
+                };
+                rle.read_row_expanded(&mut r, row, bytes_per_pixel, expand)
+                    .map_err(|error| error.at(r.count(), Context::Packet(rle.packet_index())))?;
+                if is_reversed {
+                    bytemuck::cast_slice_mut::<u8, Bgra8>(row).reverse();
+                }
+            } else {
+                r.read_exact(&mut source)
+                    .map_err(|error| Error::from(error).at(r.count(), Context::Row(file_row)))?;
+                convert_row(&source, row, bytes_per_pixel, descriptor);
+            }
+        }
+        if is_rle {
+            rle.finish()
+                .map_err(|error| error.at(r.count(), Context::Packet(rle.packet_index())))?;
+        }
+
+        let mut image = Image::new(specification.width, specification.height, data)?;
+        image.id = id;
+        if descriptor.vertical_ordering() == VerticalOrdering::BottomToTop {
+            image.flip_vertical();
+        }
+
+        Ok(Decoded { image, diagnostics })
+    }
+}
+
+/// Decodes an image from `source`, reading it as described by [`Source`].
+pub fn decode<S: Source>(source: S) -> Result<Image> {
+    decode_with_options(source, DecodeOptions::default()).map(|decoded| decoded.image)
+}
+
+/// Like [`decode`], but handles spec violations as set by `options` and
+/// returns the ones tolerated.
+pub fn decode_with_options<S: Source>(source: S, options: DecodeOptions) -> Result<Decoded> {
+    source.decode(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DiagnosticCode, ViolationPolicy};
+
+    /// Decodes `file` through every source.
+    fn decode_all(file: &[u8], options: DecodeOptions) -> [Result<Decoded>; 3] {
+        [
+            file.decode(options),
+            Seekable(Cursor::new(file)).decode(options),
+            Streaming(file).decode(options),
+        ]
+    }
+
+    fn codes(decoded: &Decoded) -> Vec<DiagnosticCode> {
+        decoded
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.code)
+            .collect()
+    }
+
+    #[test]
+    fn every_source_checks_the_header_alike() {
+        let image = Image::new(2, 2, (0..16).collect()).unwrap();
+        let mut file = Vec::new();
+        image.write_to(&mut file).unwrap();
+
+        // A declared color map, set reserved bits, a 4-bit alpha depth and a
+        // nonzero origin, which is only noted.
+        let violations: [(usize, u8, Option<DiagnosticCode>); 4] = [
+            (1, 1, Some(DiagnosticCode::ColorMap)),
+            (17, 0xe8, Some(DiagnosticCode::ReservedBits)),
+            (17, 0x24, Some(DiagnosticCode::AlphaDepth)),
+            (8, 1, None),
+        ];
+        for (position, value, code) in violations {
+            let mut file = file.clone();
+            file[position] = value;
+
+            let strict = decode_all(&file, DecodeOptions::strict());
+            if code.is_some() {
+                for result in strict {
+                    let error = result.unwrap_err();
+                    assert_eq!(error.offset(), Some(position as u64));
+                }
+            } else {
+                assert!(strict.iter().all(Result::is_ok));
+            }
+
+            let decoded = decode_all(&file, DecodeOptions::default()).map(Result::unwrap);
+            let expected = code.unwrap_or(DiagnosticCode::NonzeroOrigin);
+            for decoded in &decoded {
+                assert_eq!(codes(decoded), [expected]);
+                assert_eq!(decoded.image.data, image.data);
+            }
+
+            let options = DecodeOptions {
+                color_map: ViolationPolicy::Ignore,
+                reserved_bits: ViolationPolicy::Ignore,
+                alpha_depth: ViolationPolicy::Ignore,
+                ..DecodeOptions::strict()
+            };
+            for decoded in decode_all(&file, options).map(Result::unwrap) {
+                assert_eq!(codes(&decoded).len(), code.is_none() as usize);
+            }
+        }
+    }
+}
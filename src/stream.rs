@@ -0,0 +1,352 @@
+//! Decoding rows asynchronously as they arrive, behind the `futures-core`
+//! feature.
+
+use std::future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context as TaskContext, Poll};
+
+use futures_core::Stream;
+use futures_io::AsyncRead;
+
+use crate::decoder::{self, convert_row};
+use crate::rle::RleDecoder;
+use crate::{Context, DecodeOptions, Error, Header, Result, VerticalOrdering, HEADER_SIZE};
+
+/// The number of bytes read at a time, unless a row needs more.
+const CHUNK_SIZE: usize = 32 * 1024;
+
+/// A row decoded by a [`RowStream`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Row {
+    /// The index of the row, counting from the top of the image.
+    pub y: u16,
+    /// The 32-bit pixels of the row from left to right.
+    pub pixels: Vec<u8>,
+}
+
+/// A stream of the rows of a file read from an asynchronous reader, decoded
+/// one at a time as the bytes they are stored in arrive.
+///
+/// Rows come in the order they are stored, which is from the bottom up for
+/// most files; [`Row::y`] tells where each belongs. Only as much of the file
+/// is held in memory as the next row can take up, and nothing is read until
+/// the stream is polled, so a slow consumer holds back the reader.
+///
+/// Reading stops at the end of the pixel data. The footer and the areas it
+/// points at come after the pixels, so they are not read, and neither are
+/// the spec violations [`DecodeOptions`] tolerate checked.
+#[derive(Debug)]
+pub struct RowStream<R> {
+    reader: R,
+    header: Header,
+    /// Bytes read but not decoded yet, starting at `start`.
+    buffer: Vec<u8>,
+    start: usize,
+    /// The position of `buffer[start]` in the file.
+    position: u64,
+    is_eof: bool,
+    rle: RleDecoder,
+    /// The index of the next row, counting in the order they are stored.
+    next_row: u16,
+    /// The pixels of the row being decoded, as stored.
+    source: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> RowStream<R> {
+    /// Reads the header from `reader` and skips the image ID and the color
+    /// map, leaving the reader at the start of the pixel data.
+    pub async fn new(reader: R) -> Result<Self> {
+        let mut stream = RowStream {
+            reader,
+            header: Header::default(),
+            buffer: Vec::new(),
+            start: 0,
+            position: 0,
+            is_eof: false,
+            rle: RleDecoder::default(),
+            next_row: 0,
+            source: Vec::new(),
+        };
+
+        stream.fill(HEADER_SIZE).await?;
+        let header = decoder::read_header(&mut stream.pending(), &DecodeOptions::default())?;
+        stream.consume(HEADER_SIZE);
+
+        stream.fill(header.id_length as usize).await?;
+        let id_length = stream.available().min(header.id_length as usize);
+        stream.consume(id_length);
+        if id_length < header.id_length as usize {
+            let error = io::Error::from(io::ErrorKind::UnexpectedEof);
+            return Err(Error::from(error).at(stream.position, Context::ImageId));
+        }
+
+        let mut color_map_size = header.color_map_specification.data_size();
+        while color_map_size > 0 {
+            let length = color_map_size.min(CHUNK_SIZE as u64) as usize;
+            stream.fill(length).await?;
+            let length = stream.available().min(length);
+            if length == 0 {
+                // The pixel data is then missing, which decoding the first
+                // row reports.
+                break;
+            }
+            stream.consume(length);
+            color_map_size -= length as u64;
+        }
+
+        let specification = header.image_specification;
+        stream.source =
+            vec![0; specification.width as usize * specification.pixel_depth.bytes_per_pixel()];
+        stream.header = header;
+
+        Ok(stream)
+    }
+
+    pub fn width(&self) -> u16 {
+        self.header.image_specification.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.header.image_specification.height
+    }
+
+    /// Reads until at least `length` bytes are buffered or the reader ends.
+    async fn fill(&mut self, length: usize) -> io::Result<()> {
+        future::poll_fn(|cx| self.poll_fill(cx, length)).await
+    }
+
+    fn poll_fill(&mut self, cx: &mut TaskContext<'_>, length: usize) -> Poll<io::Result<()>> {
+        while self.available() < length && !self.is_eof {
+            if self.start > 0 {
+                self.buffer.drain(..self.start);
+                self.start = 0;
+            }
+
+            let filled = self.buffer.len();
+            self.buffer.resize(filled.max(length).max(CHUNK_SIZE), 0);
+            let read = match Pin::new(&mut self.reader).poll_read(cx, &mut self.buffer[filled..]) {
+                Poll::Ready(Ok(read)) => read,
+                Poll::Ready(Err(error)) if error.kind() == io::ErrorKind::Interrupted => {
+                    self.buffer.truncate(filled);
+                    continue;
+                }
+                Poll::Ready(Err(error)) => {
+                    self.buffer.truncate(filled);
+                    return Poll::Ready(Err(error));
+                }
+                Poll::Pending => {
+                    self.buffer.truncate(filled);
+                    return Poll::Pending;
+                }
+            };
+            self.buffer.truncate(filled + read);
+            self.is_eof = read == 0;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    /// Decodes the next row from the buffered bytes, which must hold all of
+    /// it unless the reader has ended.
+    fn decode_row(&mut self) -> Result<Row> {
+        let specification = self.header.image_specification;
+        let bytes_per_pixel = specification.pixel_depth.bytes_per_pixel();
+        let file_row = self.next_row;
+        let mut r = &self.buffer[self.start..];
+        let available = r.len();
+        let result = if self.header.image_type.is_rle() {
+            self.rle
+                .read_row(&mut r, &mut self.source, bytes_per_pixel)
+                .and_then(|()| {
+                    if file_row + 1 == specification.height {
+                        self.rle.finish()?;
+                    }
+                    Ok(())
+                })
+                .map_err(|error| (error, Context::Packet(self.rle.packet_index())))
+        } else {
+            io::Read::read_exact(&mut r, &mut self.source)
+                .map_err(|error| (error.into(), Context::Row(file_row)))
+        };
+        let used = available - r.len();
+        if let Err((error, context)) = result {
+            return Err(error.at(self.position + used as u64, context));
+        }
+        self.consume(used);
+
+        let mut pixels = vec![0; specification.width as usize * 4];
+        convert_row(
+            &self.source,
+            &mut pixels,
+            bytes_per_pixel,
+            specification.descriptor,
+        );
+        let y = if specification.descriptor.vertical_ordering() == VerticalOrdering::BottomToTop {
+            specification.height - 1 - file_row
+        } else {
+            file_row
+        };
+        self.next_row += 1;
+
+        Ok(Row { y, pixels })
+    }
+
+    /// The most bytes a row can be stored in: a run-length encoded row of
+    /// runs of one pixel each.
+    fn max_row_size(&self) -> usize {
+        let specification = self.header.image_specification;
+        let bytes_per_pixel = specification.pixel_depth.bytes_per_pixel();
+        if self.header.image_type.is_rle() {
+            specification.width as usize * (bytes_per_pixel + 1)
+        } else {
+            specification.width as usize * bytes_per_pixel
+        }
+    }
+
+    fn pending(&self) -> &[u8] {
+        &self.buffer[self.start..]
+    }
+
+    fn available(&self) -> usize {
+        self.buffer.len() - self.start
+    }
+
+    fn consume(&mut self, length: usize) {
+        self.start += length;
+        self.position += length as u64;
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for RowStream<R> {
+    type Item = Result<Row>;
+
+    /// Decodes the next row once enough of it has been read, ending the stream
+    /// after the last row or the first error.
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        if self.next_row >= self.height() {
+            return Poll::Ready(None);
+        }
+
+        let length = self.max_row_size();
+        if let Err(error) = std::task::ready!(self.poll_fill(cx, length)) {
+            self.next_row = self.height();
+            return Poll::Ready(Some(Err(error.into())));
+        }
+
+        let row = self.decode_row();
+        if row.is_err() {
+            self.next_row = self.height();
+        }
+
+        Poll::Ready(Some(row))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.height() - self.next_row.min(self.height())) as usize;
+
+        (0, Some(remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interop::block_on;
+    use crate::{EncodeOptions, Image};
+
+    /// A reader handing out at most 3 bytes at a time.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl AsyncRead for Trickle<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _: &mut TaskContext<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            let length = self.0.len().min(buf.len()).min(3);
+            buf[..length].copy_from_slice(&self.0[..length]);
+            self.0 = &self.0[length..];
+
+            Poll::Ready(Ok(length))
+        }
+    }
+
+    /// Every item of `stream`, in order.
+    fn collect<R: AsyncRead + Unpin>(mut stream: RowStream<R>) -> Vec<Result<Row>> {
+        let mut items = Vec::new();
+        while let Some(item) = block_on(future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx))) {
+            items.push(item);
+        }
+
+        items
+    }
+
+    fn image() -> Image {
+        let data = (0..5 * 4 * 4).map(|index| (index / 12) as u8).collect();
+        let mut image = Image::new(5, 4, data).unwrap();
+        image.set_id(b"rows".to_vec()).unwrap();
+
+        image
+    }
+
+    #[test]
+    fn rows_arrive_in_the_order_they_are_stored() {
+        let image = image();
+        for rle in [false, true] {
+            let options = EncodeOptions {
+                rle,
+                ..EncodeOptions::default()
+            };
+            let mut file = Vec::new();
+            image.write_with_options(&mut file, &options).unwrap();
+            let stream = block_on(RowStream::new(Trickle(&file))).unwrap();
+            assert_eq!((stream.width(), stream.height()), (5, 4));
+            assert_eq!(stream.size_hint(), (0, Some(4)));
+
+            let rows: Vec<Row> = collect(stream).into_iter().map(Result::unwrap).collect();
+            for (index, row) in rows.iter().enumerate() {
+                assert_eq!(row.y as usize, index);
+                assert_eq!(row.pixels, image.row(index), "row {index}");
+            }
+        }
+
+        let mut header = Header::new(5, 4, &EncodeOptions::default());
+        header.set_descriptor(header.descriptor() & !0x20);
+        let mut file = Vec::new();
+        header.write_to(&mut file).unwrap();
+        for y in (0..4).rev() {
+            file.extend_from_slice(image.row(y));
+        }
+        let stream = block_on(RowStream::new(&file[..])).unwrap();
+        let ys: Vec<u16> = collect(stream)
+            .into_iter()
+            .map(|row| row.unwrap().y)
+            .collect();
+        assert_eq!(ys, [3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn streams_end_after_the_first_error() {
+        let mut file = Vec::new();
+        image().write_to(&mut file).unwrap();
+        file.truncate(HEADER_SIZE + 4 + 2 * 20 + 7);
+        let items = collect(block_on(RowStream::new(Trickle(&file))).unwrap());
+        assert_eq!(items.len(), 3);
+        assert!(items[..2].iter().all(Result::is_ok));
+        let error = items[2].as_ref().unwrap_err();
+        assert!(
+            matches!(
+                error,
+                Error::Parse {
+                    context: Context::Row(2),
+                    ..
+                }
+            ),
+            "{error:?}"
+        );
+
+        assert!(block_on(RowStream::new(&file[..10])).is_err());
+        assert!(block_on(RowStream::new(&file[..HEADER_SIZE + 2])).is_err());
+    }
+}
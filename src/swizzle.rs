@@ -0,0 +1,171 @@
+//! Conversion between the BGRA pixels held by [`Image`] and the RGBA order
+//! most other libraries use.
+//!
+//! Swapping the red and blue channels is vectorized with SSSE3 where the CPU
+//! supports it and with NEON on AArch64, and falls back to a scalar loop
+//! everywhere else.
+
+use alloc::vec::Vec;
+
+use crate::{parallel, Image, Result};
+
+const BYTES_PER_PIXEL: usize = 4;
+
+impl Image {
+    /// Creates an image from 32-bit pixels in red, green, blue, alpha order,
+    /// converting them in place.
+    ///
+    /// Fails with [`Error::LengthMismatch`](crate::Error::LengthMismatch) if `data` is too short to hold
+    /// every pixel, like [`Image::new`].
+    pub fn from_rgba(width: u16, height: u16, data: Vec<u8>) -> Result<Self> {
+        Ok(Image::new(width, height, data)?.into_bgra())
+    }
+
+    /// Like [`Image::from_rgba`], for `data` known to hold every pixel.
+    #[cfg(any(feature = "image", feature = "palette", feature = "rgb"))]
+    pub(crate) fn from_rgba_parts(width: u16, height: u16, data: Vec<u8>) -> Self {
+        Image::from_parts(width, height, data).into_bgra()
+    }
+
+    /// Converts pixels just taken over in RGBA order into BGRA.
+    fn into_bgra(mut self) -> Self {
+        parallel::for_each_block(&mut self.data, swap_red_blue);
+
+        self
+    }
+
+    /// Copies the pixels into 32-bit red, green, blue, alpha order, without
+    /// any row padding.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let row_size = Image::effective_size(self.width, 1);
+        let mut data = if self.stride == row_size {
+            self.data[..row_size * self.height as usize].to_vec()
+        } else {
+            let mut data = Vec::with_capacity(row_size * self.height as usize);
+            for y in 0..self.height as usize {
+                data.extend_from_slice(self.row(y));
+            }
+
+            data
+        };
+        parallel::for_each_block(&mut data, swap_red_blue);
+
+        data
+    }
+}
+
+/// Swaps the first and third byte of every 4-byte pixel, which converts
+/// between BGRA and RGBA in either direction. Trailing bytes that do not make
+/// up a whole pixel are left untouched.
+pub(crate) fn swap_red_blue(pixels: &mut [u8]) {
+    let done = swap_red_blue_simd(pixels);
+    for pixel in pixels[done..].chunks_exact_mut(BYTES_PER_PIXEL) {
+        pixel.swap(0, 2);
+    }
+}
+
+/// Swaps the channels of as many leading pixels as the vector unit handles,
+/// returning the number of bytes done.
+#[cfg(target_arch = "x86_64")]
+fn swap_red_blue_simd(pixels: &mut [u8]) -> usize {
+    #[cfg(feature = "std")]
+    let has_ssse3 = is_x86_feature_detected!("ssse3");
+    #[cfg(not(feature = "std"))]
+    let has_ssse3 = cfg!(target_feature = "ssse3");
+    if has_ssse3 {
+        // SAFETY: SSSE3 support was just detected, or is enabled at compile
+        // time.
+        unsafe { swap_red_blue_ssse3(pixels) }
+    } else {
+        0
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "ssse3")]
+unsafe fn swap_red_blue_ssse3(pixels: &mut [u8]) -> usize {
+    use core::arch::x86_64::{
+        __m128i, _mm_loadu_si128, _mm_setr_epi8, _mm_shuffle_epi8, _mm_storeu_si128,
+    };
+
+    let shuffle = _mm_setr_epi8(2, 1, 0, 3, 6, 5, 4, 7, 10, 9, 8, 11, 14, 13, 12, 15);
+    let length = pixels.len();
+    let mut chunks = pixels.chunks_exact_mut(16);
+    for chunk in &mut chunks {
+        let chunk = chunk.as_mut_ptr().cast::<__m128i>();
+        // SAFETY: The chunk is 16 bytes long, and unaligned loads and stores
+        // are used.
+        unsafe { _mm_storeu_si128(chunk, _mm_shuffle_epi8(_mm_loadu_si128(chunk), shuffle)) };
+    }
+
+    length - chunks.into_remainder().len()
+}
+
+#[cfg(target_arch = "aarch64")]
+fn swap_red_blue_simd(pixels: &mut [u8]) -> usize {
+    use core::arch::aarch64::{uint8x16x4_t, vld4q_u8, vst4q_u8};
+
+    let length = pixels.len();
+    let mut chunks = pixels.chunks_exact_mut(64);
+    for chunk in &mut chunks {
+        let chunk = chunk.as_mut_ptr();
+        // SAFETY: NEON is always available on AArch64, and the chunk is
+        // 64 bytes long, which is what the interleaved load and store of four
+        // 16-byte registers access.
+        unsafe {
+            let pixels = vld4q_u8(chunk);
+            vst4q_u8(chunk, uint8x16x4_t(pixels.2, pixels.1, pixels.0, pixels.3));
+        }
+    }
+
+    length - chunks.into_remainder().len()
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn swap_red_blue_simd(_pixels: &mut [u8]) -> usize {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scalar_swap_red_blue(pixels: &mut [u8]) {
+        for pixel in pixels.chunks_exact_mut(BYTES_PER_PIXEL) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    /// Bytes that differ from each other, so that any byte moved to the
+    /// wrong place shows.
+    fn distinct_bytes(length: usize) -> Vec<u8> {
+        (0..length).map(|index| (index * 7 + 3) as u8).collect()
+    }
+
+    #[test]
+    fn swap_red_blue_matches_the_scalar_loop() {
+        // Every pixel count across several vector widths, with and without
+        // trailing bytes, starting at every alignment within a vector.
+        for pixels in 0..=70 {
+            for trailing in 0..BYTES_PER_PIXEL {
+                for offset in 0..16 {
+                    let length = pixels * BYTES_PER_PIXEL + trailing;
+                    let mut actual = distinct_bytes(offset + length);
+                    let mut expected = actual.clone();
+                    swap_red_blue(&mut actual[offset..]);
+                    scalar_swap_red_blue(&mut expected[offset..]);
+
+                    assert_eq!(actual, expected, "{pixels} pixels at offset {offset}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rgba_round_trips_through_padded_images() {
+        let rgba = distinct_bytes(Image::effective_size(21, 3));
+        let image = Image::from_rgba(21, 3, rgba.clone()).unwrap();
+        assert_eq!(image.row(0)[..4], [rgba[2], rgba[1], rgba[0], rgba[3]]);
+        assert_eq!(image.to_aligned(64).to_rgba(), rgba);
+    }
+}
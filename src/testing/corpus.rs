@@ -0,0 +1,896 @@
+use std::io::Cursor;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use crate::{
+    ColorCorrectionTable, Comments, EncodeOptions, ExtensionArea, Image, JobTime, Ratio,
+    SoftwareVersion, Timestamp, SIGNATURE,
+};
+
+/// A file of the conformance corpus together with what it decodes to.
+#[derive(Clone, Debug)]
+pub struct TestVector {
+    /// A short name that is unique within the corpus and can be used as a
+    /// file name.
+    pub name: String,
+    /// What the file exercises.
+    pub description: String,
+    /// The contents of the file.
+    pub data: Vec<u8>,
+    /// What a conforming decoder reads from the file: the pixels expanded to
+    /// 32-bit BGRA and ordered top-to-bottom and left-to-right, along with the
+    /// image ID and any metadata.
+    pub expected: Image,
+}
+
+/// Generates a corpus of files covering the edge cases of the format, for
+/// testing readers against.
+///
+/// The corpus holds every image type at every pixel depth the spec allows, in
+/// all four orientations, and both uncompressed and run-length encoded. It
+/// also covers run-length encoding at packet boundaries, the largest possible
+/// color maps, image IDs, and files with extension and developer areas.
+///
+/// Most of the files are written without the encoder of this crate, since
+/// they use formats it cannot write. Colors with fewer than 8 bits per channel
+/// are expected to be expanded by repeating their high bits in the low bits.
+///
+/// The decoder of this crate only reads true-color and 8-bit grayscale images,
+/// and rejects the color-mapped files and grayscale files with alpha with
+/// [`Error::UnsupportedImageType`]. Those are there for testing other readers.
+///
+/// [`Error::UnsupportedImageType`]: crate::Error::UnsupportedImageType
+pub fn conformance_corpus() -> Vec<TestVector> {
+    let mut corpus = Vec::new();
+    pixel_formats(&mut corpus);
+    color_maps(&mut corpus);
+    rle_boundaries(&mut corpus);
+    dimensions(&mut corpus);
+    areas(&mut corpus);
+
+    corpus
+}
+
+/// Every true-color and grayscale pixel format in every orientation.
+fn pixel_formats(corpus: &mut Vec<TestVector>) {
+    let encodings = [
+        ColorEncoding::Rgb15,
+        ColorEncoding::Rgba16,
+        ColorEncoding::Bgr24,
+        ColorEncoding::Bgra32,
+        ColorEncoding::Gray8,
+        ColorEncoding::GrayAlpha16,
+    ];
+    for encoding in encodings {
+        for packets in [None, Some(Packets::Greedy)] {
+            for orientation in Orientation::ALL {
+                let file = RawFile {
+                    orientation,
+                    packets,
+                    ..RawFile::new(7, 5, Pixels::Colors(encoding, mixed_pattern(7, 5)))
+                };
+                let name = format!(
+                    "{}{}-{}",
+                    encoding.name(),
+                    rle_suffix(packets),
+                    orientation.name()
+                );
+                let description = format!(
+                    "pixels of {}{}, stored {}",
+                    encoding.description(),
+                    rle_description(packets),
+                    orientation.description()
+                );
+                corpus.push(file.into_vector(name, description));
+            }
+        }
+    }
+}
+
+/// Color-mapped images with every entry size, and the largest color maps.
+fn color_maps(corpus: &mut Vec<TestVector>) {
+    let encodings = [
+        ColorEncoding::Rgb15,
+        ColorEncoding::Rgba16,
+        ColorEncoding::Bgr24,
+        ColorEncoding::Bgra32,
+    ];
+    for encoding in encodings {
+        for packets in [None, Some(Packets::Greedy)] {
+            for orientation in [Orientation::BOTTOM_LEFT, Orientation::TOP_LEFT] {
+                let indices = (0..35).map(|i| [0, 1, 1, 2, 3, 5, 8, 13][i % 8]).collect();
+                let pixels = Pixels::Indices {
+                    map: ColorMap::new(0, encoding, palette(16)),
+                    index_depth: 8,
+                    indices,
+                };
+                let file = RawFile {
+                    orientation,
+                    packets,
+                    ..RawFile::new(7, 5, pixels)
+                };
+                let name = format!(
+                    "color-mapped-{}{}-{}",
+                    encoding.depth(),
+                    rle_suffix(packets),
+                    orientation.name()
+                );
+                let description = format!(
+                    "8-bit indices into color map entries of {}{}, stored {}",
+                    encoding.description(),
+                    rle_description(packets),
+                    orientation.description()
+                );
+                corpus.push(file.into_vector(name, description));
+            }
+        }
+    }
+
+    let pixels = Pixels::Indices {
+        map: ColorMap::new(0, ColorEncoding::Bgr24, palette(256)),
+        index_depth: 8,
+        indices: (0..256).collect(),
+    };
+    corpus.push(RawFile::new(16, 16, pixels).into_vector(
+        "color-mapped-256-entries",
+        "every entry of the largest color map 8-bit indices can address",
+    ));
+
+    let pixels = Pixels::Indices {
+        map: ColorMap::new(0, ColorEncoding::Bgr24, palette(u16::MAX as usize)),
+        index_depth: 16,
+        indices: (0..=u16::MAX).map(|i| i.min(u16::MAX - 1)).collect(),
+    };
+    corpus.push(RawFile::new(256, 256, pixels).into_vector(
+        "color-mapped-65535-entries",
+        "every entry of the largest color map the header can declare, with 16-bit indices",
+    ));
+
+    let pixels = Pixels::Indices {
+        map: ColorMap::new(100, ColorEncoding::Bgr24, palette(8)),
+        index_depth: 8,
+        indices: (100..108).rev().collect(),
+    };
+    corpus.push(RawFile::new(4, 2, pixels).into_vector(
+        "color-mapped-first-entry-index",
+        "a color map starting at entry 100, so that index 100 is its first entry",
+    ));
+
+    let pixels = Pixels::Indices {
+        map: ColorMap::new(0, ColorEncoding::Bgra32, palette(300)),
+        index_depth: 16,
+        indices: (0..60).map(|i| [0, 299, 299, 299, 150, 7][i % 6]).collect(),
+    };
+    let file = RawFile {
+        packets: Some(Packets::Greedy),
+        ..RawFile::new(10, 6, pixels)
+    };
+    corpus.push(file.into_vector(
+        "color-mapped-16-bit-index-rle",
+        "run-length encoded 16-bit indices into a map of 32-bit colors",
+    ));
+}
+
+/// Run-length encoded pixel data at the limits of packet lengths.
+fn rle_boundaries(corpus: &mut Vec<TestVector>) {
+    let cases = [
+        (128, "rle-run-128", "a run packet of the largest length"),
+        (
+            129,
+            "rle-run-129",
+            "a run one pixel longer than a packet can hold",
+        ),
+    ];
+    for (width, name, description) in cases {
+        let colors = (0..2 * width).map(|i| color(i / width)).collect();
+        let file = RawFile {
+            packets: Some(Packets::Greedy),
+            ..RawFile::new(
+                width as u16,
+                2,
+                Pixels::Colors(ColorEncoding::Bgr24, colors),
+            )
+        };
+        corpus.push(file.into_vector(name, description));
+    }
+
+    let cases = [
+        (128, "rle-raw-128", "a raw packet of the largest length"),
+        (
+            129,
+            "rle-raw-129",
+            "raw pixels one more than a packet can hold",
+        ),
+    ];
+    for (width, name, description) in cases {
+        let colors = (0..2 * width).map(color).collect();
+        let file = RawFile {
+            packets: Some(Packets::Greedy),
+            ..RawFile::new(
+                width as u16,
+                2,
+                Pixels::Colors(ColorEncoding::Bgr24, colors),
+            )
+        };
+        corpus.push(file.into_vector(name, description));
+    }
+
+    let cases = [
+        (
+            Packets::SingleRuns,
+            "rle-single-pixel-runs",
+            "every pixel as a run packet of length one",
+        ),
+        (
+            Packets::SingleRaws,
+            "rle-single-pixel-raw-packets",
+            "every pixel as a raw packet of length one",
+        ),
+        (
+            Packets::AcrossRows,
+            "rle-packets-across-rows",
+            "run and raw packets that continue from one row into the next",
+        ),
+    ];
+    for (packets, name, description) in cases {
+        let file = RawFile {
+            packets: Some(packets),
+            ..RawFile::new(
+                7,
+                5,
+                Pixels::Colors(ColorEncoding::Bgra32, mixed_pattern(7, 5)),
+            )
+        };
+        corpus.push(file.into_vector(name, description));
+    }
+
+    let file = RawFile {
+        packets: Some(Packets::AcrossRows),
+        ..RawFile::new(
+            5,
+            4,
+            Pixels::Colors(ColorEncoding::Bgr24, vec![color(3); 20]),
+        )
+    };
+    corpus.push(file.into_vector(
+        "rle-single-run-across-rows",
+        "a single run packet covering every row of the image",
+    ));
+}
+
+/// Extreme dimensions, image IDs and a bare TGA 2.0 footer.
+fn dimensions(corpus: &mut Vec<TestVector>) {
+    let cases = [
+        (1, 1, "1x1", "an image of a single pixel"),
+        (1024, 1, "wide", "an image of a single long row"),
+        (1, 1024, "tall", "an image of a single long column"),
+    ];
+    for (width, height, name, description) in cases {
+        let colors = mixed_pattern(width, height);
+        let file = RawFile::new(width, height, Pixels::Colors(ColorEncoding::Bgra32, colors));
+        corpus.push(file.into_vector(name, description));
+    }
+
+    let file = RawFile {
+        id: (0..=u8::MAX - 1).collect(),
+        ..RawFile::new(
+            3,
+            2,
+            Pixels::Colors(ColorEncoding::Bgr24, mixed_pattern(3, 2)),
+        )
+    };
+    corpus.push(file.into_vector("image-id-255", "an image ID of the largest length"));
+
+    let file = RawFile {
+        footer: true,
+        ..RawFile::new(
+            3,
+            2,
+            Pixels::Colors(ColorEncoding::Bgr24, mixed_pattern(3, 2)),
+        )
+    };
+    corpus.push(file.into_vector(
+        "footer-without-areas",
+        "a TGA 2.0 footer pointing at neither an extension nor a developer area",
+    ));
+}
+
+/// Extension and developer areas, written by the encoder of this crate.
+fn areas(corpus: &mut Vec<TestVector>) {
+    let extension_area = ExtensionArea {
+        author_name: "Conformance Corpus".to_owned(),
+        comments: Comments::from_text("First line\nSecond line\n\nFourth line").0,
+        timestamp: Timestamp {
+            month: 2,
+            day: 29,
+            year: 2024,
+            hour: 23,
+            minute: 59,
+            second: 58,
+        },
+        job_name: "corpus".to_owned(),
+        job_time: JobTime {
+            hours: 1,
+            minutes: 2,
+            seconds: 3,
+        },
+        software_id: "tga".to_owned(),
+        software_version: SoftwareVersion {
+            major: 1,
+            minor: 23,
+            letter: Some('b'),
+        },
+        pixel_aspect_ratio: Ratio {
+            numerator: 4,
+            denominator: 3,
+        },
+        gamma: Ratio {
+            numerator: 22,
+            denominator: 10,
+        },
+        ..Default::default()
+    };
+    let base = Image::from_parts(7, 5, flatten(&mixed_pattern(7, 5)));
+    let options = EncodeOptions::default();
+
+    let mut image = base.clone();
+    image.set_extension_area(Some(extension_area.clone()));
+    corpus.push(encoded(
+        "extension-area",
+        "an extension area with every field set",
+        image,
+        options,
+    ));
+
+    let mut image = base.clone();
+    image.set_extension_area(Some(ExtensionArea {
+        color_correction_table: Some(ColorCorrectionTable::default()),
+        ..Default::default()
+    }));
+    corpus.push(encoded(
+        "color-correction-table",
+        "an extension area pointing at a color correction table",
+        image,
+        options,
+    ));
+
+    let mut image = base.clone();
+    let stamp = Image::from_parts(2, 2, flatten(&mixed_pattern(2, 2)));
+    image.set_postage_stamp(Some(stamp)).unwrap();
+    corpus.push(encoded(
+        "postage-stamp",
+        "an extension area pointing at a postage stamp",
+        image,
+        options,
+    ));
+
+    let options = EncodeOptions {
+        rle: true,
+        scan_line_table: true,
+        ..Default::default()
+    };
+    corpus.push(encoded(
+        "scan-line-table",
+        "run-length encoded pixels with a scan line table",
+        base.clone(),
+        options,
+    ));
+
+    let mut image = base.clone();
+    image.add_developer_tag(1, b"first".to_vec());
+    image.add_developer_tag(65535, Vec::new());
+    image.add_developer_tag(300, vec![0xff; 1000]);
+    corpus.push(encoded(
+        "developer-area",
+        "a developer area with an empty tag and the largest tag number",
+        image,
+        EncodeOptions::default(),
+    ));
+
+    let mut image = base;
+    image.set_id(b"everything".to_vec()).unwrap();
+    image.set_extension_area(Some(ExtensionArea {
+        color_correction_table: Some(ColorCorrectionTable::default()),
+        ..extension_area
+    }));
+    let stamp = Image::from_parts(2, 2, flatten(&mixed_pattern(2, 2)));
+    image.set_postage_stamp(Some(stamp)).unwrap();
+    image.add_developer_tag(7, b"developer data".to_vec());
+    let options = EncodeOptions {
+        rle: true,
+        scan_line_table: true,
+        ..Default::default()
+    };
+    corpus.push(encoded(
+        "extension-and-developer-area",
+        "every optional part of a TGA 2.0 file at once",
+        image,
+        options,
+    ));
+}
+
+/// A vector written by the encoder. The expected image is the decoded one, so
+/// that it holds the offsets the encoder filled in.
+fn encoded(name: &str, description: &str, image: Image, options: EncodeOptions) -> TestVector {
+    let mut data = Vec::new();
+    image.write_with_options(&mut data, &options).unwrap();
+    let expected = Image::read_from(&mut Cursor::new(&data)).unwrap();
+
+    TestVector {
+        name: name.to_owned(),
+        description: description.to_owned(),
+        data,
+        expected,
+    }
+}
+
+/// How a color is stored, either as a pixel or as a color map entry.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum ColorEncoding {
+    Gray8,
+    GrayAlpha16,
+    Rgb15,
+    Rgba16,
+    Bgr24,
+    Bgra32,
+}
+
+impl ColorEncoding {
+    fn name(self) -> &'static str {
+        match self {
+            ColorEncoding::Gray8 => "grayscale-8",
+            ColorEncoding::GrayAlpha16 => "grayscale-16",
+            ColorEncoding::Rgb15 => "true-color-15",
+            ColorEncoding::Rgba16 => "true-color-16",
+            ColorEncoding::Bgr24 => "true-color-24",
+            ColorEncoding::Bgra32 => "true-color-32",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            ColorEncoding::Gray8 => "8-bit grayscale",
+            ColorEncoding::GrayAlpha16 => "8-bit grayscale with 8 alpha bits",
+            ColorEncoding::Rgb15 => "15-bit color",
+            ColorEncoding::Rgba16 => "16-bit color with an alpha bit",
+            ColorEncoding::Bgr24 => "24-bit color",
+            ColorEncoding::Bgra32 => "32-bit color with 8 alpha bits",
+        }
+    }
+
+    fn is_grayscale(self) -> bool {
+        matches!(self, ColorEncoding::Gray8 | ColorEncoding::GrayAlpha16)
+    }
+
+    fn depth(self) -> u8 {
+        match self {
+            ColorEncoding::Gray8 => 8,
+            ColorEncoding::GrayAlpha16 | ColorEncoding::Rgba16 => 16,
+            ColorEncoding::Rgb15 => 15,
+            ColorEncoding::Bgr24 => 24,
+            ColorEncoding::Bgra32 => 32,
+        }
+    }
+
+    fn alpha_depth(self) -> u8 {
+        match self {
+            ColorEncoding::GrayAlpha16 | ColorEncoding::Bgra32 => 8,
+            ColorEncoding::Rgba16 => 1,
+            _ => 0,
+        }
+    }
+
+    /// The color as it reads back after being stored.
+    fn quantize(self, [b, g, r, a]: [u8; 4]) -> [u8; 4] {
+        let expand = |value: u8| (value >> 3) << 3 | value >> 5;
+        match self {
+            ColorEncoding::Gray8 => [g, g, g, u8::MAX],
+            ColorEncoding::GrayAlpha16 => [g, g, g, a],
+            ColorEncoding::Rgb15 => [expand(b), expand(g), expand(r), u8::MAX],
+            ColorEncoding::Rgba16 => {
+                let a = if a >= 0x80 { u8::MAX } else { 0 };
+                [expand(b), expand(g), expand(r), a]
+            }
+            ColorEncoding::Bgr24 => [b, g, r, u8::MAX],
+            ColorEncoding::Bgra32 => [b, g, r, a],
+        }
+    }
+
+    fn encode(self, [b, g, r, a]: [u8; 4], out: &mut Vec<u8>) {
+        let packed = (r as u16 >> 3) << 10 | (g as u16 >> 3) << 5 | b as u16 >> 3;
+        match self {
+            ColorEncoding::Gray8 => out.push(g),
+            ColorEncoding::GrayAlpha16 => out.extend_from_slice(&[g, a]),
+            ColorEncoding::Rgb15 => out.extend_from_slice(&packed.to_le_bytes()),
+            ColorEncoding::Rgba16 => {
+                let packed = packed | ((a >= 0x80) as u16) << 15;
+                out.extend_from_slice(&packed.to_le_bytes());
+            }
+            ColorEncoding::Bgr24 => out.extend_from_slice(&[b, g, r]),
+            ColorEncoding::Bgra32 => out.extend_from_slice(&[b, g, r, a]),
+        }
+    }
+}
+
+/// The order pixels are stored in, as the corner the first stored pixel is
+/// displayed at.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Orientation {
+    right_to_left: bool,
+    top_to_bottom: bool,
+}
+
+impl Orientation {
+    const BOTTOM_LEFT: Orientation = Orientation {
+        right_to_left: false,
+        top_to_bottom: false,
+    };
+    const TOP_LEFT: Orientation = Orientation {
+        right_to_left: false,
+        top_to_bottom: true,
+    };
+    const ALL: [Orientation; 4] = [
+        Orientation::BOTTOM_LEFT,
+        Orientation::TOP_LEFT,
+        Orientation {
+            right_to_left: true,
+            top_to_bottom: false,
+        },
+        Orientation {
+            right_to_left: true,
+            top_to_bottom: true,
+        },
+    ];
+
+    fn name(self) -> &'static str {
+        match (self.top_to_bottom, self.right_to_left) {
+            (false, false) => "bottom-left",
+            (false, true) => "bottom-right",
+            (true, false) => "top-left",
+            (true, true) => "top-right",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match (self.top_to_bottom, self.right_to_left) {
+            (false, false) => "bottom-to-top and left-to-right",
+            (false, true) => "bottom-to-top and right-to-left",
+            (true, false) => "top-to-bottom and left-to-right",
+            (true, true) => "top-to-bottom and right-to-left",
+        }
+    }
+}
+
+/// How run-length encoded pixels are split into packets.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Packets {
+    /// Repeated pixels as run packets and everything else as raw packets,
+    /// starting new packets at every row.
+    Greedy,
+    /// Like [`Packets::Greedy`], but continuing packets across rows.
+    AcrossRows,
+    /// Every pixel as a run packet of its own.
+    SingleRuns,
+    /// Every pixel as a raw packet of its own.
+    SingleRaws,
+}
+
+#[derive(Clone, Debug)]
+struct ColorMap {
+    first_entry: u16,
+    encoding: ColorEncoding,
+    entries: Vec<[u8; 4]>,
+}
+
+impl ColorMap {
+    fn new(first_entry: u16, encoding: ColorEncoding, entries: Vec<[u8; 4]>) -> Self {
+        ColorMap {
+            first_entry,
+            encoding,
+            entries,
+        }
+    }
+}
+
+/// Pixels in display order, top-to-bottom and left-to-right.
+#[derive(Clone, Debug)]
+enum Pixels {
+    Colors(ColorEncoding, Vec<[u8; 4]>),
+    Indices {
+        map: ColorMap,
+        index_depth: u8,
+        indices: Vec<u16>,
+    },
+}
+
+/// A file written byte by byte, so that it can use formats the encoder does
+/// not support.
+#[derive(Clone, Debug)]
+struct RawFile {
+    width: u16,
+    height: u16,
+    pixels: Pixels,
+    orientation: Orientation,
+    packets: Option<Packets>,
+    id: Vec<u8>,
+    footer: bool,
+}
+
+impl RawFile {
+    fn new(width: u16, height: u16, pixels: Pixels) -> Self {
+        RawFile {
+            width,
+            height,
+            pixels,
+            orientation: Orientation::BOTTOM_LEFT,
+            packets: None,
+            id: Vec::new(),
+            footer: false,
+        }
+    }
+
+    fn into_vector(self, name: impl Into<String>, description: impl Into<String>) -> TestVector {
+        let (image_type, pixel_depth, alpha_depth, color_map) = match &self.pixels {
+            Pixels::Colors(encoding, _) => {
+                let image_type = if encoding.is_grayscale() { 3 } else { 2 };
+                (image_type, encoding.depth(), encoding.alpha_depth(), None)
+            }
+            Pixels::Indices {
+                map, index_depth, ..
+            } => (1, *index_depth, map.encoding.alpha_depth(), Some(map)),
+        };
+
+        // The pixels as stored, and as they are expected to read back.
+        let mut stored = Vec::new();
+        let mut expected = Vec::new();
+        match &self.pixels {
+            Pixels::Colors(encoding, colors) => {
+                for &color in colors {
+                    let mut bytes = Vec::new();
+                    encoding.encode(color, &mut bytes);
+                    stored.push(bytes);
+                    expected.extend_from_slice(&encoding.quantize(color));
+                }
+            }
+            Pixels::Indices {
+                map,
+                index_depth,
+                indices,
+            } => {
+                for &index in indices {
+                    let bytes = index.to_le_bytes();
+                    stored.push(bytes[..*index_depth as usize / 8].to_vec());
+                    let entry = map.entries[(index - map.first_entry) as usize];
+                    expected.extend_from_slice(&map.encoding.quantize(entry));
+                }
+            }
+        }
+
+        let mut data = Vec::new();
+        data.push(self.id.len() as u8);
+        data.push(color_map.is_some() as u8);
+        data.push(image_type + if self.packets.is_some() { 8 } else { 0 });
+        match color_map {
+            Some(map) => {
+                data.write_u16::<LittleEndian>(map.first_entry).unwrap();
+                data.write_u16::<LittleEndian>(map.entries.len() as u16)
+                    .unwrap();
+                data.push(map.encoding.depth());
+            }
+            None => data.extend_from_slice(&[0; 5]),
+        }
+        data.extend_from_slice(&[0; 4]);
+        data.write_u16::<LittleEndian>(self.width).unwrap();
+        data.write_u16::<LittleEndian>(self.height).unwrap();
+        data.push(pixel_depth);
+        data.push(
+            alpha_depth
+                | (self.orientation.right_to_left as u8) << 4
+                | (self.orientation.top_to_bottom as u8) << 5,
+        );
+        data.extend_from_slice(&self.id);
+        if let Some(map) = color_map {
+            for &entry in &map.entries {
+                map.encoding.encode(entry, &mut data);
+            }
+        }
+
+        let width = self.width as usize;
+        let mut rows: Vec<Vec<&[u8]>> = stored
+            .chunks(width)
+            .map(|row| {
+                let mut row: Vec<&[u8]> = row.iter().map(Vec::as_slice).collect();
+                if self.orientation.right_to_left {
+                    row.reverse();
+                }
+                row
+            })
+            .collect();
+        if !self.orientation.top_to_bottom {
+            rows.reverse();
+        }
+
+        match self.packets {
+            None => {
+                for pixel in rows.iter().flatten() {
+                    data.extend_from_slice(pixel);
+                }
+            }
+            Some(Packets::Greedy) => {
+                for row in &rows {
+                    encode_greedy(row, &mut data);
+                }
+            }
+            Some(Packets::AcrossRows) => encode_greedy(&rows.concat(), &mut data),
+            Some(Packets::SingleRuns) => {
+                for pixel in rows.iter().flatten() {
+                    data.push(0b10000000);
+                    data.extend_from_slice(pixel);
+                }
+            }
+            Some(Packets::SingleRaws) => {
+                for pixel in rows.iter().flatten() {
+                    data.push(0);
+                    data.extend_from_slice(pixel);
+                }
+            }
+        }
+
+        if self.footer {
+            data.extend_from_slice(&[0; 8]);
+            data.extend_from_slice(&SIGNATURE);
+            data.extend_from_slice(b".\0");
+        }
+
+        let mut image = Image::from_parts(self.width, self.height, expected);
+        image.set_id(self.id).unwrap();
+
+        TestVector {
+            name: name.into(),
+            description: description.into(),
+            data,
+            expected: image,
+        }
+    }
+}
+
+/// Encodes repeated pixels as run packets and everything else as raw packets.
+fn encode_greedy(pixels: &[&[u8]], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < pixels.len() {
+        let run = pixels[i..]
+            .iter()
+            .take(128)
+            .take_while(|&&pixel| pixel == pixels[i])
+            .count();
+        if run > 1 {
+            out.push(0b10000000 | (run - 1) as u8);
+            out.extend_from_slice(pixels[i]);
+            i += run;
+            continue;
+        }
+
+        let start = i;
+        while i < pixels.len()
+            && i - start < 128
+            && pixels.get(i + 1).is_none_or(|&next| next != pixels[i])
+        {
+            i += 1;
+        }
+        out.push((i - start - 1) as u8);
+        for pixel in &pixels[start..i] {
+            out.extend_from_slice(pixel);
+        }
+    }
+}
+
+fn rle_suffix(packets: Option<Packets>) -> &'static str {
+    if packets.is_some() {
+        "-rle"
+    } else {
+        ""
+    }
+}
+
+fn rle_description(packets: Option<Packets>) -> &'static str {
+    if packets.is_some() {
+        ", run-length encoded"
+    } else {
+        ""
+    }
+}
+
+/// A color that differs from those of nearby values of `i` in every channel.
+fn color(i: usize) -> [u8; 4] {
+    [
+        (i * 67) as u8,
+        (i * 131 + 50) as u8,
+        (i * 29 + 200) as u8,
+        (i * 97 + 128) as u8,
+    ]
+}
+
+/// `count` distinct colors, for up to 65536 colors.
+fn palette(count: usize) -> Vec<[u8; 4]> {
+    (0..count)
+        .map(|i| {
+            let [b, g, r, a] = color(i);
+            [b, g ^ (i >> 8) as u8, r, a]
+        })
+        .collect()
+}
+
+/// A pattern alternating between blocks of a single color, which compress
+/// into run packets, and blocks of distinct colors, which do not.
+fn mixed_pattern(width: u16, height: u16) -> Vec<[u8; 4]> {
+    let mut colors = Vec::new();
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            let i = if (x / 3) % 2 == 0 {
+                y
+            } else {
+                x * height as usize + y
+            };
+            colors.push(color(i));
+        }
+    }
+
+    colors
+}
+
+fn flatten(colors: &[[u8; 4]]) -> Vec<u8> {
+    colors.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_untrusted, Error, ImageType};
+
+    /// Asserts that `actual` holds the same pixels, ID and areas as
+    /// `expected`.
+    fn assert_same(name: &str, actual: &Image, expected: &Image) {
+        assert_eq!(
+            (actual.width(), actual.height()),
+            (expected.width(), expected.height()),
+            "{name}"
+        );
+        for y in 0..expected.height() as usize {
+            assert_eq!(actual.row(y), expected.row(y), "{name}: row {y}");
+        }
+        assert_eq!(actual.id(), expected.id(), "{name}");
+        assert_eq!(actual.extension_area(), expected.extension_area(), "{name}");
+        assert_eq!(actual.developer_tags(), expected.developer_tags(), "{name}");
+        match (actual.postage_stamp(), expected.postage_stamp()) {
+            (Some(actual), Some(expected)) => assert_same(name, actual, expected),
+            (actual, expected) => assert_eq!(actual.is_some(), expected.is_some(), "{name}"),
+        }
+    }
+
+    #[test]
+    fn supported_vectors_decode_to_what_they_expect() {
+        let mut decoded = 0;
+        let mut grayscale = 0;
+        for vector in conformance_corpus() {
+            let image_type = ImageType::from(vector.data[2]);
+            let is_gray_8 = image_type.is_grayscale() && vector.data[16] == 8;
+            let result = decode_untrusted(&vector.data);
+            if !image_type.is_true_color() && !is_gray_8 {
+                let error = result.expect_err(&vector.name);
+                assert!(
+                    matches!(error.root(), Error::UnsupportedImageType { .. }),
+                    "{}: {error}",
+                    vector.name
+                );
+                continue;
+            }
+
+            let image = result.unwrap_or_else(|error| panic!("{}: {error}", vector.name));
+            assert_same(&vector.name, &image, &vector.expected);
+            decoded += 1;
+            grayscale += is_gray_8 as usize;
+        }
+
+        assert!(decoded > grayscale && grayscale > 0);
+    }
+}
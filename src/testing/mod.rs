@@ -0,0 +1,7 @@
+//! Helpers for testing code that produces or consumes images.
+
+mod corpus;
+mod roundtrip;
+
+pub use corpus::{conformance_corpus, TestVector};
+pub use roundtrip::assert_roundtrip;
@@ -0,0 +1,137 @@
+use std::fmt::Write as _;
+use std::io::Cursor;
+
+use crate::{EncodeOptions, Image, PixelFormat};
+
+/// The number of differing pixels listed when a round trip fails.
+const MAX_LISTED_DIFFERENCES: usize = 10;
+
+/// Encodes `image` with `options`, decodes the result and asserts that the
+/// decoded pixels are exactly those of `image`.
+///
+/// Encoding as [`PixelFormat::Bgr24`] drops the alpha channel, so the decoded
+/// pixels are then expected to be opaque. Encoding as [`PixelFormat::Gray8`]
+/// also keeps only the luma of each pixel, which is expected in every color
+/// channel. Only the dimensions and pixels are compared, not the metadata.
+///
+/// # Panics
+///
+/// Panics if encoding or decoding fails, or if the decoded image differs from
+/// `image`, listing the first pixels that differ.
+#[track_caller]
+pub fn assert_roundtrip(image: &Image, options: EncodeOptions) {
+    let mut encoded = Vec::new();
+    if let Err(error) = image.write_with_options(&mut encoded, &options) {
+        panic!("encoding with {options:?} failed: {error}");
+    }
+
+    let decoded = match Image::read_from(&mut Cursor::new(&encoded)) {
+        Ok(decoded) => decoded,
+        Err(error) => panic!("decoding the image encoded with {options:?} failed: {error}"),
+    };
+
+    if (decoded.width(), decoded.height()) != (image.width(), image.height()) {
+        panic!(
+            "round trip with {options:?} changed the dimensions from {}x{} to {}x{}",
+            image.width(),
+            image.height(),
+            decoded.width(),
+            decoded.height(),
+        );
+    }
+
+    let mut count = 0;
+    let mut listing = String::new();
+    for y in 0..image.height() as usize {
+        let expected = image.row(y).chunks_exact(4);
+        let actual = decoded.row(y).chunks_exact(4);
+        for (x, (expected, actual)) in expected.zip(actual).enumerate() {
+            let mut expected = <[u8; 4]>::try_from(expected).unwrap();
+            match options.pixel_format {
+                PixelFormat::Bgr24 => expected[3] = u8::MAX,
+                PixelFormat::Bgra32 => {}
+                PixelFormat::Gray8 => {
+                    let mut luma = [0];
+                    PixelFormat::Gray8.convert_row(&expected, &mut luma);
+                    expected = [luma[0], luma[0], luma[0], u8::MAX];
+                }
+            }
+            if expected == actual {
+                continue;
+            }
+
+            if count < MAX_LISTED_DIFFERENCES {
+                let _ = writeln!(
+                    listing,
+                    "  ({x}, {y}): expected BGRA {expected:?}, got {actual:?}"
+                );
+            }
+            count += 1;
+        }
+    }
+
+    if count > 0 {
+        if count > MAX_LISTED_DIFFERENCES {
+            let _ = writeln!(listing, "  ...");
+        }
+        panic!(
+            "round trip with {options:?} changed {count} of {} pixels:\n{listing}",
+            image.width() as usize * image.height() as usize,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An image of runs of every length up to and past the 128 pixels an RLE
+    /// packet holds, between pixels with varying alpha.
+    fn runs(width: u16, height: u16) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut length = 1;
+        while data.len() < Image::effective_size(width, height) {
+            let value = (length * 37) as u8;
+            for _ in 0..length {
+                data.extend_from_slice(&[value, value ^ 0x55, 255 - value, value / 2]);
+            }
+            data.extend_from_slice(&[1, 2, 3, length as u8]);
+            length = length % 200 + 1;
+        }
+        data.truncate(Image::effective_size(width, height));
+
+        data
+    }
+
+    #[test]
+    fn every_option_combination_round_trips() {
+        let packed = Image::new(300, 7, runs(300, 7)).unwrap();
+        let padded = packed.to_aligned(256);
+        for image in [&packed, &padded] {
+            for pixel_format in [PixelFormat::Bgr24, PixelFormat::Bgra32, PixelFormat::Gray8] {
+                for rle in [false, true] {
+                    for scan_line_table in [false, true] {
+                        for chunk_size in [1, EncodeOptions::default().chunk_size] {
+                            let options = EncodeOptions {
+                                pixel_format,
+                                rle,
+                                scan_line_table,
+                                chunk_size,
+                            };
+                            assert_roundtrip(image, options);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "encoding with")]
+    fn encoding_failures_panic() {
+        assert_roundtrip(
+            &Image::new(0, 0, Vec::new()).unwrap(),
+            EncodeOptions::default(),
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Export of images whose dimensions exceed what a single TGA file can hold.
+//!
+//! TGA stores its width and height as `u16`, so anything wider or taller than
+//! 65535 pixels has to be split across several files. [`write_tiles`] does the
+//! split and describes the result in a [`TileManifest`].
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::{Error, Image, Result};
+
+const BYTES_PER_PIXEL: usize = 4;
+
+/// A single tile produced by [`write_tiles`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tile {
+    /// File name of the tile, relative to the output directory.
+    pub file_name: String,
+    /// Horizontal offset of the tile within the full image.
+    pub x: u32,
+    /// Vertical offset of the tile within the full image.
+    pub y: u32,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// Describes how an oversized image was split into tiles.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileManifest {
+    /// Width of the full image.
+    pub width: u32,
+    /// Height of the full image.
+    pub height: u32,
+    /// Tiles in row-major order.
+    pub tiles: Vec<Tile>,
+}
+
+impl TileManifest {
+    /// Writes the manifest as plain text.
+    ///
+    /// The first line holds the full image dimensions, and each following line
+    /// describes one tile as `file_name x y width height`.
+    pub fn write_to<T: Write>(&self, w: &mut T) -> io::Result<()> {
+        writeln!(w, "{} {}", self.width, self.height)?;
+        for tile in &self.tiles {
+            writeln!(
+                w,
+                "{} {} {} {} {}",
+                tile.file_name, tile.x, tile.y, tile.width, tile.height
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits a 32-bit, top-to-bottom image of arbitrary size into tiles of at most
+/// `tile_width` by `tile_height` pixels.
+///
+/// Each tile is written to `dir` as `{name}_{row}_{column}.tga`, and the
+/// manifest is written alongside them as `{name}.manifest`.
+pub fn write_tiles<P: AsRef<Path>>(
+    dir: P,
+    name: &str,
+    width: u32,
+    height: u32,
+    data: &[u8],
+    tile_width: u16,
+    tile_height: u16,
+) -> Result<TileManifest> {
+    if tile_width == 0 || tile_height == 0 {
+        return Err(Error::InvalidDimensions("tile dimensions must be nonzero"));
+    }
+
+    let row_size = (width as usize)
+        .checked_mul(BYTES_PER_PIXEL)
+        .ok_or(Error::InvalidDimensions("image is too wide"))?;
+    let expected = row_size
+        .checked_mul(height as usize)
+        .ok_or(Error::InvalidDimensions("image is too large"))?;
+    if expected != data.len() {
+        return Err(Error::LengthMismatch {
+            expected,
+            actual: data.len(),
+        });
+    }
+
+    let dir = dir.as_ref();
+    let mut manifest = TileManifest {
+        width,
+        height,
+        tiles: Vec::new(),
+    };
+
+    for (row, y) in (0..height).step_by(tile_height as usize).enumerate() {
+        let tile_height = (height - y).min(tile_height as u32) as u16;
+
+        for (column, x) in (0..width).step_by(tile_width as usize).enumerate() {
+            let tile_width = (width - x).min(tile_width as u32) as u16;
+
+            let tile_row_size = tile_width as usize * BYTES_PER_PIXEL;
+            let mut tile_data = Vec::with_capacity(tile_row_size * tile_height as usize);
+            for source_row in data
+                .chunks_exact(row_size)
+                .skip(y as usize)
+                .take(tile_height as usize)
+            {
+                let start = x as usize * BYTES_PER_PIXEL;
+                tile_data.extend_from_slice(&source_row[start..start + tile_row_size]);
+            }
+
+            let file_name = format!("{}_{}_{}.tga", name, row, column);
+            let mut file = BufWriter::new(File::create(dir.join(&file_name))?);
+            Image::new(tile_width, tile_height, tile_data)?.write_to(&mut file)?;
+            file.flush()?;
+
+            manifest.tiles.push(Tile {
+                file_name,
+                x,
+                y,
+                width: tile_width,
+                height: tile_height,
+            });
+        }
+    }
+
+    let mut file = BufWriter::new(File::create(dir.join(format!("{}.manifest", name)))?);
+    manifest.write_to(&mut file)?;
+    file.flush()?;
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn tiles_hold_the_pixels_they_cover() {
+        let dir = std::env::temp_dir().join(format!("tga-tiles-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (width, height) = (5, 3);
+        let data: Vec<u8> = (0..width * height * 4).map(|index| index as u8).collect();
+        let manifest = write_tiles(&dir, "big", width, height, &data, 2, 2).unwrap();
+
+        assert_eq!((manifest.width, manifest.height), (5, 3));
+        let placements: Vec<_> = manifest
+            .tiles
+            .iter()
+            .map(|tile| (tile.x, tile.y, tile.width, tile.height))
+            .collect();
+        assert_eq!(
+            placements,
+            [
+                (0, 0, 2, 2),
+                (2, 0, 2, 2),
+                (4, 0, 1, 2),
+                (0, 2, 2, 1),
+                (2, 2, 2, 1),
+                (4, 2, 1, 1)
+            ]
+        );
+        assert_eq!(manifest.tiles[5].file_name, "big_1_2.tga");
+
+        for tile in &manifest.tiles {
+            let mut file = File::open(dir.join(&tile.file_name)).unwrap();
+            let image = Image::read_from(&mut file).unwrap();
+            assert_eq!((image.width(), image.height()), (tile.width, tile.height));
+            for row in 0..tile.height as usize {
+                let start = ((tile.y as usize + row) * width as usize + tile.x as usize) * 4;
+                let expected = &data[start..start + tile.width as usize * 4];
+                assert_eq!(image.row(row), expected, "{}", tile.file_name);
+            }
+        }
+
+        let text = fs::read_to_string(dir.join("big.manifest")).unwrap();
+        assert!(text.starts_with("5 3\nbig_0_0.tga 0 0 2 2\n"));
+        assert_eq!(text.lines().count(), 7);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_tiles_are_rejected() {
+        let dir = std::env::temp_dir();
+        assert!(matches!(
+            write_tiles(&dir, "none", 1, 1, &[0; 4], 0, 1),
+            Err(Error::InvalidDimensions(_))
+        ));
+        assert!(matches!(
+            write_tiles(&dir, "none", 2, 2, &[0; 12], 1, 1),
+            Err(Error::LengthMismatch {
+                expected: 16,
+                actual: 12
+            })
+        ));
+    }
+}
@@ -0,0 +1,123 @@
+//! Spans marking the phases of decoding, behind the `tracing` feature.
+//!
+//! Each phase is a `DEBUG` span named after the part of the file it reads,
+//! such as `header` or `pixel_data`, with a `bytes` field holding the number
+//! of bytes of the file that part takes up. Without the feature the spans
+//! compile to nothing.
+
+/// Enters a span for the phase of decoding named `$name`, which lasts until
+/// the returned [`Phase`] is dropped.
+#[cfg(feature = "tracing")]
+macro_rules! phase {
+    ($name:literal) => {
+        $crate::trace::Phase(tracing::debug_span!($name, bytes = tracing::field::Empty).entered())
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! phase {
+    ($name:literal) => {
+        $crate::trace::Phase
+    };
+}
+
+pub(crate) use phase;
+
+/// A phase of decoding in progress.
+#[cfg(feature = "tracing")]
+pub(crate) struct Phase(pub(crate) tracing::span::EnteredSpan);
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct Phase;
+
+impl Phase {
+    /// Records the number of bytes of the file the phase covers.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn bytes(&self, count: u64) {
+        self.0.record("bytes", count);
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn bytes(&self, _count: u64) {}
+
+    /// Ends the phase before the end of the scope it was entered in.
+    pub(crate) fn end(self) {}
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata};
+
+    use crate::{Decoder, EncodeOptions, Image};
+
+    /// The spans entered, in order, with the `bytes` recorded for each.
+    type Spans = Arc<Mutex<Vec<(&'static str, Option<u64>)>>>;
+
+    struct Recorder(Spans);
+
+    struct Bytes(Option<u64>);
+
+    impl Visit for Bytes {
+        fn record_u64(&mut self, field: &Field, value: u64) {
+            if field.name() == "bytes" {
+                self.0 = Some(value);
+            }
+        }
+
+        fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+    }
+
+    impl tracing::Subscriber for Recorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut spans = self.0.lock().unwrap();
+            spans.push((span.metadata().name(), None));
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut bytes = Bytes(None);
+            values.record(&mut bytes);
+            self.0.lock().unwrap()[span.into_u64() as usize - 1].1 = bytes.0;
+        }
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, _event: &Event<'_>) {}
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn decoding_enters_a_span_for_each_phase() {
+        let mut image = Image::new(4, 3, vec![7; 4 * 3 * 4]).unwrap();
+        image.set_id(b"phases".to_vec()).unwrap();
+        let mut file = Vec::new();
+        image
+            .write_with_options(&mut file, &EncodeOptions::default())
+            .unwrap();
+
+        let spans = Spans::default();
+        tracing::subscriber::with_default(Recorder(spans.clone()), || {
+            let decoder = Decoder::new(std::io::Cursor::new(&file)).unwrap();
+            decoder.read_image().unwrap();
+        });
+
+        let spans = spans.lock().unwrap();
+        assert_eq!(spans[0], ("header", Some(crate::HEADER_SIZE as u64)));
+        assert_eq!(spans[1], ("image_id", Some(6)));
+        assert!(
+            spans.contains(&("pixel_data", Some(4 * 3 * 4))),
+            "{spans:?}"
+        );
+    }
+}
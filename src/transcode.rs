@@ -0,0 +1,336 @@
+#[cfg(feature = "std")]
+use alloc::vec;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::panic;
+#[cfg(feature = "std")]
+use std::sync::mpsc;
+#[cfg(feature = "std")]
+use std::thread;
+
+use crate::io::{Read, Seek, Write};
+#[cfg(feature = "std")]
+use crate::{rle, Image};
+use crate::{Decoder, EncodeOptions, PixelFormat, Result};
+
+/// The number of blocks of rows each stage of [`transcode_pipelined`] can get
+/// ahead of the next.
+#[cfg(feature = "std")]
+const CHANNEL_CAPACITY: usize = 2;
+
+/// Changes to make to the pixel data while transcoding. Settings left as `None`
+/// keep the encoding of the source file.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct TranscodeOptions {
+    pub pixel_format: Option<PixelFormat>,
+    pub rle: Option<bool>,
+}
+
+/// Re-encodes the pixel data of the file read from `reader` while keeping the
+/// rest of the file.
+///
+/// The image ID, developer tags and extension area are copied over. The
+/// extension area is copied byte for byte, including anything stored after the
+/// fields defined by the spec, with only the offsets it holds updated. The
+/// postage stamp is re-encoded in the new pixel format and the scan line table
+/// is rebuilt if the source file had one.
+///
+/// The written pixels are always ordered top-to-bottom and left-to-right.
+pub fn transcode<R: Read + Seek, W: Write>(
+    reader: R,
+    writer: &mut W,
+    options: TranscodeOptions,
+) -> Result<()> {
+    let mut decoder = Decoder::new(reader)?;
+    let options = encode_options(&decoder, options);
+    let raw_extension_area = decoder.read_raw_extension_area()?;
+    let image = decoder.read_image()?;
+
+    image.encode(writer, &options, raw_extension_area.as_deref())
+}
+
+/// Like [`transcode`], but decodes the pixels, converts them and writes them
+/// out on separate threads, passing blocks of rows from one to the next
+/// through bounded channels.
+///
+/// Reading the source then overlaps with converting and compressing the
+/// pixels and with writing the result, which speeds up batch conversions that
+/// are bound by both I/O and CPU. The file written is the same as the one
+/// [`transcode`] writes. Files storing their rows from bottom to top are
+/// still decoded and converted in parallel, but nothing is written until
+/// their last row, which comes first in the new file, has been converted.
+///
+/// Only available with the `std` feature, which provides the threads.
+#[cfg(feature = "std")]
+pub fn transcode_pipelined<R: Read + Seek + Send, W: Write>(
+    reader: R,
+    writer: &mut W,
+    options: TranscodeOptions,
+) -> Result<()> {
+    let mut decoder = Decoder::new(reader)?;
+    let options = encode_options(&decoder, options);
+    let raw_extension_area = decoder.read_raw_extension_area()?;
+    let image = decoder.take_metadata();
+    let convert = decoder.row_converter();
+    let is_flipped = decoder.is_bottom_to_top();
+    let width = image.width();
+
+    thread::scope(|scope| {
+        let (stored_sender, stored) =
+            mpsc::sync_channel::<(Range<usize>, Vec<u8>)>(CHANNEL_CAPACITY);
+        let (encoded_sender, encoded) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        let decoding = scope.spawn(move || {
+            decoder.read_stored_rows(|rows, block| {
+                // The next stage only stops early if writing failed, which is
+                // the error reported then.
+                let _ = stored_sender.send((rows, block.to_vec()));
+            })
+        });
+        scope.spawn(move || {
+            for (rows, block) in stored {
+                let block = encode_block(&block, rows.len(), width, convert, is_flipped, &options);
+                if encoded_sender.send(block).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let raw_extension_area = raw_extension_area.as_deref();
+        let result = if is_flipped {
+            let blocks: Vec<_> = encoded.into_iter().collect();
+            image.encode_blocks(
+                writer,
+                &options,
+                raw_extension_area,
+                blocks.into_iter().rev(),
+            )
+        } else {
+            image.encode_blocks(writer, &options, raw_extension_area, encoded)
+        };
+
+        // Decoding failing cuts the pixel data short, so its error takes
+        // precedence over the result of writing what was decoded.
+        decoding
+            .join()
+            .unwrap_or_else(|payload| panic::resume_unwind(payload))?;
+        result
+    })
+}
+
+/// The options to re-encode the file read by `decoder` with.
+fn encode_options<R: Read + Seek>(
+    decoder: &Decoder<R>,
+    options: TranscodeOptions,
+) -> EncodeOptions {
+    let source = decoder.encode_options();
+
+    EncodeOptions {
+        pixel_format: options.pixel_format.unwrap_or(source.pixel_format),
+        rle: options.rle.unwrap_or(source.rle),
+        ..source
+    }
+}
+
+/// Encodes a `block` of `rows` rows of `width` pixels as stored in the source
+/// file, returning the encoded rows in the order they are written together
+/// with the length of each.
+#[cfg(feature = "std")]
+fn encode_block(
+    block: &[u8],
+    rows: usize,
+    width: u16,
+    convert: impl Fn(&[u8], &mut [u8]),
+    is_flipped: bool,
+    options: &EncodeOptions,
+) -> (Vec<u8>, Vec<usize>) {
+    let format = options.pixel_format;
+    let source_size = block.len() / rows;
+    let mut pixels = vec![0; Image::effective_size(width, 1)];
+    let mut row = vec![0; width as usize * format.bytes_per_pixel()];
+    let mut data = Vec::with_capacity(rows * row.len());
+    let mut row_lengths = Vec::with_capacity(rows);
+    for index in 0..rows {
+        let index = if is_flipped { rows - 1 - index } else { index };
+        convert(&block[index * source_size..][..source_size], &mut pixels);
+        format.convert_row(&pixels, &mut row);
+
+        let start = data.len();
+        if options.rle {
+            rle::encode_row(&row, format.bytes_per_pixel(), &mut data);
+        } else {
+            data.extend_from_slice(&row);
+        }
+        row_lengths.push(data.len() - start);
+    }
+
+    (data, row_lengths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::Cursor;
+    use crate::{ExtensionArea, Image};
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// A 6 by 5 image with runs for run-length encoding to find, an image ID,
+    /// a developer tag, an extension area with data after its standard fields
+    /// and a postage stamp.
+    fn image() -> Image {
+        let data = (0..30u8)
+            .flat_map(|index| [index / 4 * 9, index / 2, 0x80, 0xff])
+            .collect();
+        let mut image = Image::new(6, 5, data).unwrap();
+        image.set_id(b"transcoded".to_vec()).unwrap();
+        image.add_developer_tag(7, vec![1, 2, 3]);
+        image.set_extension_area(Some(ExtensionArea {
+            author_name: "Ada".into(),
+            extra: vec![9; 5],
+            ..ExtensionArea::default()
+        }));
+        let stamp = Image::new(1, 2, vec![0x10, 0x20, 0x30, 0xff, 0x40, 0x50, 0x60, 0xff]);
+        image.set_postage_stamp(Some(stamp.unwrap())).unwrap();
+
+        image
+    }
+
+    fn encode(image: &Image, options: &EncodeOptions) -> Vec<u8> {
+        let mut file = Vec::new();
+        image.write_with_options(&mut file, options).unwrap();
+
+        file
+    }
+
+    fn run(file: &[u8], options: TranscodeOptions) -> Result<Vec<u8>> {
+        let mut transcoded = Vec::new();
+        transcode(Cursor::new(file), &mut transcoded, options)?;
+
+        Ok(transcoded)
+    }
+
+    #[test]
+    fn transcoding_keeps_the_pixels_and_metadata() {
+        let image = image();
+        let file = encode(&image, &EncodeOptions::default());
+        for pixel_format in [PixelFormat::Bgra32, PixelFormat::Bgr24] {
+            let options = TranscodeOptions {
+                pixel_format: Some(pixel_format),
+                rle: Some(true),
+            };
+            let transcoded = run(&file, options).unwrap();
+            let decoder = Decoder::new(Cursor::new(&transcoded[..])).unwrap();
+            let encoded_with = decoder.encode_options();
+            assert_eq!(encoded_with.pixel_format, pixel_format);
+            assert!(encoded_with.rle);
+
+            let decoded = decoder.read_image().unwrap();
+            assert_eq!(decoded.data(), image.data());
+            assert_eq!(decoded.id(), image.id());
+            assert_eq!(decoded.developer_tags(), image.developer_tags());
+            let extension_area = decoded.extension_area().unwrap();
+            assert_eq!(extension_area.author_name, "Ada");
+            assert_eq!(extension_area.extra, [9; 5]);
+            let stamp = decoded.postage_stamp().unwrap();
+            assert_eq!(stamp.data(), image.postage_stamp().unwrap().data());
+        }
+    }
+
+    #[test]
+    fn transcoding_without_changes_writes_the_same_file() {
+        let image = image();
+        for rle in [false, true] {
+            let options = EncodeOptions {
+                rle,
+                scan_line_table: true,
+                ..EncodeOptions::default()
+            };
+            let file = encode(&image, &options);
+            assert_eq!(run(&file, TranscodeOptions::default()).unwrap(), file);
+        }
+    }
+
+    #[test]
+    fn transcoding_truncated_files_fails() {
+        let file = encode(&image(), &EncodeOptions::default());
+        for length in [0, 10, 60] {
+            let result = run(&file[..length], TranscodeOptions::default());
+            assert!(result.is_err(), "{length} bytes");
+        }
+    }
+
+    /// `image` stored uncompressed from bottom to top, without its metadata.
+    #[cfg(feature = "std")]
+    fn bottom_to_top_file(image: &Image) -> Vec<u8> {
+        let mut header =
+            crate::Header::new(image.width(), image.height(), &EncodeOptions::default());
+        header.set_descriptor(header.descriptor() & !0x20);
+        let mut file = Vec::new();
+        header.write_to(&mut file).unwrap();
+        for y in (0..image.height() as usize).rev() {
+            file.extend_from_slice(image.row(y));
+        }
+
+        file
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn pipelined_transcoding_writes_the_same_file() {
+        let image = image();
+        let sources = [
+            encode(&image, &EncodeOptions::default()),
+            encode(
+                &image,
+                &EncodeOptions {
+                    rle: true,
+                    scan_line_table: true,
+                    ..EncodeOptions::default()
+                },
+            ),
+            bottom_to_top_file(&image),
+        ];
+        for (index, file) in sources.iter().enumerate() {
+            for pixel_format in [None, Some(PixelFormat::Bgr24), Some(PixelFormat::Gray8)] {
+                for rle in [None, Some(false), Some(true)] {
+                    let options = TranscodeOptions { pixel_format, rle };
+                    let mut pipelined = Vec::new();
+                    transcode_pipelined(Cursor::new(&file[..]), &mut pipelined, options).unwrap();
+                    assert_eq!(
+                        pipelined,
+                        run(file, options).unwrap(),
+                        "file {index}, {options:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn pipelined_transcoding_reports_decoding_errors() {
+        let image = Image::new(6, 5, vec![7; 120]).unwrap();
+        let options = EncodeOptions {
+            rle: true,
+            ..EncodeOptions::default()
+        };
+        let mut truncated = encode(&image, &options);
+        // Raw packets of 128 pixels each run past the end of the file.
+        truncated[crate::HEADER_SIZE..].fill(0x7f);
+        assert!(Decoder::new(Cursor::new(&truncated[..])).is_ok());
+        let complete = encode(&image, &EncodeOptions::default());
+        for file in [&truncated[..], &complete[..10]] {
+            let mut pipelined = Vec::new();
+            let result = transcode_pipelined(
+                Cursor::new(file),
+                &mut pipelined,
+                TranscodeOptions::default(),
+            );
+            assert!(result.is_err(), "{} bytes", file.len());
+        }
+    }
+}
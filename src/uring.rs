@@ -0,0 +1,277 @@
+//! Writing files through io_uring, behind the `uring` feature on Linux.
+
+use std::fs::File;
+use std::io::{self, Seek, Write};
+use std::mem;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+
+use io_uring::{opcode, types, IoUring};
+
+/// The number of bytes collected before they are queued as one write.
+const BUFFER_SIZE: usize = 1 << 20;
+
+/// The number of writes [`UringWriter::new`] keeps in flight at once.
+const DEFAULT_QUEUE_DEPTH: u32 = 8;
+
+/// A writer that queues writes to a file through io_uring instead of waiting
+/// for each of them to complete.
+///
+/// Bytes are collected into buffers of 1 MiB, each of which is handed to the
+/// kernel as one write at the position in the file it belongs at. Writing only
+/// blocks once as many writes are in flight as the queue is deep, so a thread
+/// encoding images, such as one capturing frames, carries on while earlier
+/// parts of the file go to disk. [`Write::flush`] waits for all queued writes
+/// to complete, and so does dropping the writer.
+///
+/// A queued write that fails is reported by the next call to write or flush.
+pub struct UringWriter {
+    ring: IoUring,
+    file: File,
+    /// The position in the file the next buffer is written at.
+    offset: u64,
+    buffer: Vec<u8>,
+    /// The writes in flight, indexed by the user data of their entries.
+    writes: Vec<Option<QueuedWrite>>,
+    in_flight: usize,
+    /// The buffers of completed writes, kept for reuse.
+    spare: Vec<Vec<u8>>,
+    error: Option<io::Error>,
+}
+
+/// A buffer handed to the kernel, which owns it until the write completes.
+struct QueuedWrite {
+    buffer: Vec<u8>,
+    offset: u64,
+    written: usize,
+}
+
+impl UringWriter {
+    /// Creates the file at `path`, truncating it if it exists, and writes to
+    /// it from the start.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        UringWriter::new(File::create(path)?)
+    }
+
+    /// Writes to `file` from its current position, with up to eight writes
+    /// in flight.
+    pub fn new(file: File) -> io::Result<Self> {
+        UringWriter::with_queue_depth(file, DEFAULT_QUEUE_DEPTH)
+    }
+
+    /// Writes to `file` from its current position, with up to `queue_depth`
+    /// writes in flight, but at least one.
+    pub fn with_queue_depth(mut file: File, queue_depth: u32) -> io::Result<Self> {
+        let queue_depth = queue_depth.max(1);
+        let offset = file.stream_position()?;
+
+        Ok(UringWriter {
+            ring: IoUring::new(queue_depth)?,
+            file,
+            offset,
+            buffer: Vec::with_capacity(BUFFER_SIZE),
+            writes: (0..queue_depth).map(|_| None).collect(),
+            in_flight: 0,
+            spare: Vec::new(),
+            error: None,
+        })
+    }
+
+    /// Waits for all queued writes like [`Write::flush`], then waits for the
+    /// file to reach the disk with [`File::sync_all`].
+    pub fn sync_all(&mut self) -> io::Result<()> {
+        self.flush()?;
+
+        self.file.sync_all()
+    }
+
+    /// Hands the collected bytes to the kernel, first waiting for a write to
+    /// complete if the queue is full.
+    fn queue(&mut self) -> io::Result<()> {
+        while self.in_flight == self.writes.len() {
+            self.wait()?;
+        }
+
+        let spare = self
+            .spare
+            .pop()
+            .unwrap_or_else(|| Vec::with_capacity(BUFFER_SIZE));
+        let buffer = mem::replace(&mut self.buffer, spare);
+        let offset = self.offset;
+        self.offset += buffer.len() as u64;
+
+        let slot = self
+            .writes
+            .iter()
+            .position(Option::is_none)
+            .expect("a slot is free while the queue is not full");
+        self.writes[slot] = Some(QueuedWrite {
+            buffer,
+            offset,
+            written: 0,
+        });
+        self.in_flight += 1;
+        self.submit(slot)?;
+        self.reap()
+    }
+
+    /// Submits the part of the write in `slot` that is still to be written.
+    fn submit(&mut self, slot: usize) -> io::Result<()> {
+        let write = self.writes[slot].as_ref().expect("slot holds a write");
+        let remaining = &write.buffer[write.written..];
+        let entry = opcode::Write::new(
+            types::Fd(self.file.as_raw_fd()),
+            remaining.as_ptr(),
+            remaining.len() as u32,
+        )
+        .offset(write.offset + write.written as u64)
+        .build()
+        .user_data(slot as u64);
+
+        // SAFETY: The buffer is owned by the writer, which neither moves,
+        // changes nor frees it until the kernel reports the write complete.
+        unsafe { self.ring.submission().push(&entry) }
+            .expect("the submission queue has room for an entry per slot");
+        self.ring.submit()?;
+
+        Ok(())
+    }
+
+    /// Blocks until at least one write completes.
+    fn wait(&mut self) -> io::Result<()> {
+        match self.ring.submit_and_wait(1) {
+            Ok(_) => {}
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+
+        self.reap()
+    }
+
+    /// Handles the writes that have completed, submitting what is left of
+    /// short ones again.
+    fn reap(&mut self) -> io::Result<()> {
+        loop {
+            let Some(entry) = self.ring.completion().next() else {
+                return Ok(());
+            };
+            let slot = entry.user_data() as usize;
+            let write = self.writes[slot].as_mut().expect("slot holds a write");
+            let result = match entry.result() {
+                0 => Err(io::ErrorKind::WriteZero.into()),
+                length if length > 0 => {
+                    write.written += length as usize;
+                    Ok(())
+                }
+                error => Err(io::Error::from_raw_os_error(-error)),
+            };
+
+            let is_short = write.written < write.buffer.len();
+            match result {
+                Ok(()) if is_short => self.submit(slot)?,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => self.submit(slot)?,
+                result => {
+                    if let Err(error) = result {
+                        self.error.get_or_insert(error);
+                    }
+
+                    let mut buffer = self.writes[slot].take().expect("slot holds a write").buffer;
+                    buffer.clear();
+                    self.spare.push(buffer);
+                    self.in_flight -= 1;
+                }
+            }
+        }
+    }
+
+    /// Reports the first write that failed since the last call.
+    fn take_error(&mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Write for UringWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.take_error()?;
+
+        let length = data.len().min(BUFFER_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&data[..length]);
+        if self.buffer.len() == BUFFER_SIZE {
+            self.queue()?;
+        }
+
+        Ok(length)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            self.queue()?;
+        }
+        while self.in_flight > 0 {
+            self.wait()?;
+        }
+
+        self.take_error()
+    }
+}
+
+impl Drop for UringWriter {
+    fn drop(&mut self) {
+        if self.flush().is_err() && self.in_flight > 0 {
+            // The kernel may still be using the buffers of writes that did not
+            // complete, so they must not be freed.
+            for write in self.writes.drain(..).flatten() {
+                mem::forget(write.buffer);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::SeekFrom;
+
+    #[test]
+    fn queued_writes_land_at_their_positions() {
+        let path = std::env::temp_dir().join(format!("tga-uring-{}.bin", std::process::id()));
+        let mut file = File::create(&path).unwrap();
+        file.write_all(b"prefix").unwrap();
+        file.seek(SeekFrom::Start(3)).unwrap();
+
+        let data: Vec<u8> = (0..3 * BUFFER_SIZE + 1234)
+            .map(|index| (index % 251) as u8)
+            .collect();
+        let mut writer = UringWriter::with_queue_depth(file, 2).unwrap();
+        for chunk in data.chunks(70_000) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.sync_all().unwrap();
+        writer.write_all(b"end").unwrap();
+        drop(writer);
+
+        let written = fs::read(&path).unwrap();
+        assert_eq!(written[..3], *b"pre");
+        assert_eq!(written[3..3 + data.len()], data[..]);
+        assert_eq!(written[3 + data.len()..], *b"end");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failed_writes_are_reported() {
+        let path = std::env::temp_dir().join(format!("tga-uring-{}.ro", std::process::id()));
+        fs::write(&path, []).unwrap();
+        let file = File::open(&path).unwrap();
+        let mut writer = UringWriter::new(file).unwrap();
+        writer.write_all(&[1; 10]).unwrap();
+        assert!(writer.flush().is_err());
+        // The error is only reported once.
+        assert!(writer.flush().is_ok());
+        drop(writer);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,368 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::decoder::{
+    COLOR_MAP_ENTRY_SIZE_POSITION, COLOR_MAP_LENGTH_POSITION, COLOR_MAP_SPECIFICATION_POSITION,
+    COLOR_MAP_TYPE_POSITION, DESCRIPTOR_POSITION, IMAGE_TYPE_POSITION, PIXEL_DEPTH_POSITION,
+};
+use crate::io::Read;
+use crate::{ColorMapSpecification, ColorMapType, Header, ImageType, PixelDepth, Result};
+
+/// A header field that is invalid on its own or contradicts another field.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum HeaderViolation {
+    /// The image type is neither defined by the spec nor reserved for
+    /// developers, so the other fields cannot be checked against it.
+    UnknownImageType(u8),
+    /// The color map type is neither defined by the spec nor reserved for
+    /// developers.
+    UnknownColorMapType(u8),
+    /// The image is color-mapped but declares no color map.
+    MissingColorMap { image_type: u8 },
+    /// A color map is declared for an image type that does not use one.
+    UnexpectedColorMap { image_type: u8 },
+    /// The color map specification is not all zeros although there is no
+    /// color map.
+    UnexpectedColorMapSpecification,
+    /// A color map is declared with no entries.
+    EmptyColorMap,
+    /// The color map entries are not 15, 16, 24 or 32 bits.
+    InvalidColorMapDepth(u8),
+    /// The pixel depth is not one the image type allows.
+    InvalidPixelDepth { image_type: u8, pixel_depth: u8 },
+    /// The alpha depth in the image descriptor does not fit the depth of the
+    /// stored colors, which is the color map entry size for color-mapped
+    /// images and the pixel depth otherwise.
+    InvalidAlphaDepth { alpha_depth: u8, color_depth: u8 },
+}
+
+impl HeaderViolation {
+    /// The offset from the start of the file of the header field the
+    /// violation is found in.
+    pub fn offset(&self) -> u64 {
+        match self {
+            HeaderViolation::UnknownImageType(_) => IMAGE_TYPE_POSITION,
+            HeaderViolation::UnknownColorMapType(_)
+            | HeaderViolation::MissingColorMap { .. }
+            | HeaderViolation::UnexpectedColorMap { .. } => COLOR_MAP_TYPE_POSITION,
+            HeaderViolation::UnexpectedColorMapSpecification => COLOR_MAP_SPECIFICATION_POSITION,
+            HeaderViolation::EmptyColorMap => COLOR_MAP_LENGTH_POSITION,
+            HeaderViolation::InvalidColorMapDepth(_) => COLOR_MAP_ENTRY_SIZE_POSITION,
+            HeaderViolation::InvalidPixelDepth { .. } => PIXEL_DEPTH_POSITION,
+            HeaderViolation::InvalidAlphaDepth { .. } => DESCRIPTOR_POSITION,
+        }
+    }
+}
+
+impl fmt::Display for HeaderViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderViolation::UnknownImageType(image_type) => {
+                write!(f, "unknown image type {image_type}")
+            }
+            HeaderViolation::UnknownColorMapType(color_map_type) => {
+                write!(f, "unknown color map type {color_map_type}")
+            }
+            HeaderViolation::MissingColorMap { image_type } => {
+                write!(f, "image type {image_type} requires a color map")
+            }
+            HeaderViolation::UnexpectedColorMap { image_type } => {
+                write!(f, "image type {image_type} does not use a color map")
+            }
+            HeaderViolation::UnexpectedColorMapSpecification => {
+                f.write_str("color map specification is set without a color map")
+            }
+            HeaderViolation::EmptyColorMap => f.write_str("color map has no entries"),
+            HeaderViolation::InvalidColorMapDepth(color_depth) => {
+                write!(f, "invalid color map entry size of {color_depth} bits")
+            }
+            HeaderViolation::InvalidPixelDepth {
+                image_type,
+                pixel_depth,
+            } => write!(
+                f,
+                "image type {image_type} does not allow {pixel_depth}-bit pixels"
+            ),
+            HeaderViolation::InvalidAlphaDepth {
+                alpha_depth,
+                color_depth,
+            } => write!(
+                f,
+                "{alpha_depth} alpha bits do not fit {color_depth}-bit colors"
+            ),
+        }
+    }
+}
+
+impl Header {
+    /// Cross-checks the fields of the header against each other, returning
+    /// every violation found.
+    pub fn validate(&self) -> Vec<HeaderViolation> {
+        let mut violations = Vec::new();
+        let image_type = self.image_type;
+        let color_map = self.color_map_specification;
+        let pixel_depth = self.image_specification.pixel_depth;
+
+        let is_color_mapped = image_type.is_color_mapped();
+        let is_true_color = image_type.is_true_color();
+        let is_grayscale = image_type.is_grayscale();
+        let is_known = !matches!(image_type, ImageType::Unknown(_));
+        if let ImageType::Unknown(value) = image_type {
+            if !is_developer_defined(value) {
+                violations.push(HeaderViolation::UnknownImageType(value));
+            }
+        }
+
+        match self.color_map_type {
+            ColorMapType::ABSENT => {
+                if is_color_mapped {
+                    violations.push(HeaderViolation::MissingColorMap {
+                        image_type: image_type.into(),
+                    });
+                }
+                if color_map != ColorMapSpecification::default() {
+                    violations.push(HeaderViolation::UnexpectedColorMapSpecification);
+                }
+            }
+            ColorMapType::PRESENT => {
+                if is_true_color || is_grayscale {
+                    violations.push(HeaderViolation::UnexpectedColorMap {
+                        image_type: image_type.into(),
+                    });
+                }
+                if color_map.entry_count == 0 {
+                    violations.push(HeaderViolation::EmptyColorMap);
+                }
+                let depth = PixelDepth::from(color_map.color_depth);
+                let depths = [
+                    PixelDepth::B15,
+                    PixelDepth::B16,
+                    PixelDepth::B24,
+                    PixelDepth::B32,
+                ];
+                if !depths.contains(&depth) {
+                    violations.push(HeaderViolation::InvalidColorMapDepth(depth.bits()));
+                }
+            }
+            ColorMapType(color_map_type) => {
+                if !is_developer_defined(color_map_type) {
+                    violations.push(HeaderViolation::UnknownColorMapType(color_map_type));
+                }
+            }
+        }
+
+        let allowed_pixel_depths: &[PixelDepth] = if is_color_mapped {
+            &[PixelDepth::B8, PixelDepth::B16]
+        } else if is_true_color {
+            &[
+                PixelDepth::B15,
+                PixelDepth::B16,
+                PixelDepth::B24,
+                PixelDepth::B32,
+            ]
+        } else if is_grayscale {
+            &[PixelDepth::B8, PixelDepth::B16]
+        } else {
+            &[]
+        };
+        if !allowed_pixel_depths.is_empty() && !allowed_pixel_depths.contains(&pixel_depth) {
+            violations.push(HeaderViolation::InvalidPixelDepth {
+                image_type: image_type.into(),
+                pixel_depth: pixel_depth.bits(),
+            });
+        }
+
+        let color_depth = if is_color_mapped {
+            color_map.color_depth
+        } else {
+            pixel_depth.bits()
+        };
+        let alpha_depth = self.image_specification.descriptor.alpha_depth();
+        if is_known
+            && image_type != ImageType::NoImage
+            && !alpha_depth_fits(is_grayscale, color_depth, alpha_depth)
+        {
+            violations.push(HeaderViolation::InvalidAlphaDepth {
+                alpha_depth,
+                color_depth,
+            });
+        }
+
+        violations
+    }
+}
+
+/// Reads a header and cross-checks its fields, returning every violation
+/// found.
+///
+/// Only the 18 bytes of the header are read, so this works on files and image
+/// types the decoder does not support.
+pub fn validate_header<T: Read>(r: &mut T) -> Result<Vec<HeaderViolation>> {
+    Ok(Header::read_from(r)?.validate())
+}
+
+/// Whether `alpha_depth` alpha bits are plausible for colors of
+/// `color_depth` bits.
+pub(crate) fn alpha_depth_fits(is_grayscale: bool, color_depth: u8, alpha_depth: u8) -> bool {
+    let allowed: &[u8] = match (is_grayscale, color_depth) {
+        (_, 32) => &[0, 8],
+        (false, 24) => &[0],
+        (false, 16) => &[0, 1],
+        (false, 15) => &[0],
+        (true, 16) => &[0, 8],
+        (true, 8) => &[0],
+        _ => return alpha_depth <= color_depth,
+    };
+
+    allowed.contains(&alpha_depth)
+}
+
+/// Whether a type value lies in the range the spec reserves for developers.
+fn is_developer_defined(value: u8) -> bool {
+    value >= 128
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::Cursor;
+    use crate::HEADER_SIZE;
+
+    /// The header of a 4x2 image with the given color map type, image type,
+    /// color map entry count and size, pixel depth and descriptor.
+    fn header(
+        color_map_type: u8,
+        image_type: u8,
+        entry_count: u16,
+        entry_size: u8,
+        pixel_depth: u8,
+        descriptor: u8,
+    ) -> [u8; HEADER_SIZE] {
+        let [count_low, count_high] = entry_count.to_le_bytes();
+        [
+            0,
+            color_map_type,
+            image_type,
+            0,
+            0,
+            count_low,
+            count_high,
+            entry_size,
+            0,
+            0,
+            0,
+            0,
+            4,
+            0,
+            2,
+            0,
+            pixel_depth,
+            descriptor,
+        ]
+    }
+
+    fn violations(bytes: [u8; HEADER_SIZE]) -> Vec<HeaderViolation> {
+        validate_header(&mut Cursor::new(&bytes[..])).unwrap()
+    }
+
+    #[test]
+    fn valid_headers_have_no_violations() {
+        assert_eq!(violations(header(0, 2, 0, 0, 24, 0)), []);
+        assert_eq!(violations(header(0, 10, 0, 0, 32, 8)), []);
+        assert_eq!(violations(header(0, 2, 0, 0, 16, 1)), []);
+        assert_eq!(violations(header(1, 1, 256, 24, 8, 0)), []);
+        assert_eq!(violations(header(1, 9, 300, 32, 16, 8)), []);
+        assert_eq!(violations(header(0, 3, 0, 0, 16, 8)), []);
+        assert_eq!(violations(header(0, 0, 0, 0, 0, 0)), []);
+        // Developer-defined values are not checked.
+        assert_eq!(violations(header(128, 200, 5, 7, 13, 0)), []);
+    }
+
+    #[test]
+    fn color_map_violations_are_found() {
+        assert_eq!(
+            violations(header(0, 1, 0, 0, 8, 0)),
+            [HeaderViolation::MissingColorMap { image_type: 1 }]
+        );
+        assert_eq!(
+            violations(header(0, 2, 16, 24, 24, 0)),
+            [HeaderViolation::UnexpectedColorMapSpecification]
+        );
+        assert_eq!(
+            violations(header(1, 2, 16, 24, 24, 0)),
+            [HeaderViolation::UnexpectedColorMap { image_type: 2 }]
+        );
+        assert_eq!(
+            violations(header(1, 1, 0, 16, 8, 0)),
+            [HeaderViolation::EmptyColorMap]
+        );
+        assert_eq!(
+            violations(header(1, 1, 16, 20, 8, 0)),
+            [HeaderViolation::InvalidColorMapDepth(20)]
+        );
+        assert_eq!(
+            violations(header(7, 2, 0, 0, 24, 0)),
+            [HeaderViolation::UnknownColorMapType(7)]
+        );
+    }
+
+    #[test]
+    fn depth_violations_are_found() {
+        assert_eq!(
+            violations(header(0, 2, 0, 0, 8, 0)),
+            [HeaderViolation::InvalidPixelDepth {
+                image_type: 2,
+                pixel_depth: 8
+            }]
+        );
+        assert_eq!(
+            violations(header(0, 2, 0, 0, 24, 8)),
+            [HeaderViolation::InvalidAlphaDepth {
+                alpha_depth: 8,
+                color_depth: 24
+            }]
+        );
+        // The alpha depth of color-mapped images is checked against the
+        // color map entries.
+        assert_eq!(
+            violations(header(1, 1, 16, 24, 8, 8)),
+            [HeaderViolation::InvalidAlphaDepth {
+                alpha_depth: 8,
+                color_depth: 24
+            }]
+        );
+        assert_eq!(
+            violations(header(0, 50, 0, 0, 8, 0)),
+            [HeaderViolation::UnknownImageType(50)]
+        );
+    }
+
+    #[test]
+    fn violations_point_at_their_fields() {
+        let offsets: Vec<_> = violations(header(1, 2, 0, 20, 8, 8))
+            .iter()
+            .map(HeaderViolation::offset)
+            .collect();
+        assert_eq!(
+            offsets,
+            [
+                COLOR_MAP_TYPE_POSITION,
+                COLOR_MAP_LENGTH_POSITION,
+                COLOR_MAP_ENTRY_SIZE_POSITION,
+                PIXEL_DEPTH_POSITION,
+            ]
+        );
+        assert_eq!(
+            violations(header(0, 2, 0, 0, 24, 8))[0].offset(),
+            DESCRIPTOR_POSITION
+        );
+    }
+
+    #[test]
+    fn truncated_headers_are_rejected() {
+        let bytes = header(0, 2, 0, 0, 24, 0);
+        assert!(validate_header(&mut Cursor::new(&bytes[..HEADER_SIZE - 1])).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! JavaScript bindings, behind the `wasm` feature.
+//!
+//! Build the crate for the web with wasm-pack, which generates the JavaScript
+//! glue and TypeScript types:
+//!
+//! ```text
+//! wasm-pack build --target web --features wasm
+//! ```
+//!
+//! Pixels are 8-bit red, green, blue, alpha values from the top row to the
+//! bottom one, as canvas `ImageData` holds them:
+//!
+//! ```text
+//! const image = decode(new Uint8Array(await file.arrayBuffer()));
+//! context.putImageData(new ImageData(image.pixels, image.width, image.height), 0, 0);
+//!
+//! const { data, width, height } = context.getImageData(0, 0, canvas.width, canvas.height);
+//! const bytes = encode(data, width, height, true);
+//! ```
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+
+use crate::{EncodeOptions, Error, Image};
+
+/// A decoded image.
+#[wasm_bindgen]
+pub struct DecodedImage {
+    width: u16,
+    height: u16,
+    pixels: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl DecodedImage {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// A copy of the pixels as a `Uint8ClampedArray`, which the `ImageData`
+    /// constructor takes.
+    #[wasm_bindgen(getter)]
+    pub fn pixels(&self) -> Clamped<Vec<u8>> {
+        Clamped(self.pixels.clone())
+    }
+}
+
+/// Decodes an image from the bytes of a file, which may come from an
+/// untrusted source, throwing an `Error` if it is malformed.
+#[wasm_bindgen]
+pub fn decode(data: &[u8]) -> Result<DecodedImage, JsError> {
+    let image = crate::decode_untrusted(data)?;
+
+    Ok(DecodedImage {
+        width: image.width,
+        height: image.height,
+        pixels: image.to_rgba(),
+    })
+}
+
+/// Encodes `width` by `height` pixels, such as the `data` of canvas
+/// `ImageData`, as a 32-bit file, optionally run-length encoding them.
+///
+/// Throws an `Error` if there are not exactly `width * height * 4` values.
+#[wasm_bindgen]
+pub fn encode(
+    pixels: Clamped<Vec<u8>>,
+    width: u16,
+    height: u16,
+    rle: bool,
+) -> Result<Vec<u8>, JsError> {
+    Ok(encode_rgba(pixels.0, width, height, rle)?)
+}
+
+/// Encodes the pixels for [`encode`], failing with [`Error::LengthMismatch`]
+/// unless there are exactly `width * height * 4` values.
+fn encode_rgba(pixels: Vec<u8>, width: u16, height: u16, rle: bool) -> crate::Result<Vec<u8>> {
+    let expected = Image::checked_effective_size(width, height).ok_or(Error::TooLarge("image"))?;
+    if pixels.len() != expected {
+        return Err(Error::LengthMismatch {
+            expected,
+            actual: pixels.len(),
+        });
+    }
+
+    let image = Image::from_rgba(width, height, pixels)?;
+    let options = EncodeOptions {
+        rle,
+        ..EncodeOptions::default()
+    };
+    let mut data = Vec::new();
+    image.write_with_options(&mut data, &options)?;
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Errors become `JsError`s, which can only be created on wasm targets, so
+    // only the successful calls go through the bindings here.
+
+    #[test]
+    fn encoded_pixels_decode_unchanged() {
+        let pixels = crate::tests::pseudo_random_bytes(3 * 5 * 4);
+        for rle in [false, true] {
+            let data = encode(Clamped(pixels.clone()), 3, 5, rle).unwrap();
+            let image = decode(&data).unwrap();
+            assert_eq!((image.width(), image.height()), (3, 5));
+            assert_eq!(image.pixels().0, pixels);
+        }
+    }
+
+    #[test]
+    fn pixels_of_the_wrong_length_are_rejected() {
+        for len in [3 * 5 * 4 - 1, 3 * 5 * 4 + 4] {
+            assert!(matches!(
+                encode_rgba(vec![0; len], 3, 5, false),
+                Err(Error::LengthMismatch { expected: 60, actual }) if actual == len
+            ));
+        }
+        assert!(encode_rgba(Vec::new(), 0, 5, false).is_err());
+    }
+}